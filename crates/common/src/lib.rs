@@ -10,6 +10,15 @@ pub mod sql {
     pub fn quote_literal(value: &str) -> String {
         format!("'{}'", value.replace('\'', "''"))
     }
+
+    /// Lowercase unquoted identifier that fits in `NAMEDATALEN` and reads the same quoted or not.
+    #[must_use]
+    pub fn is_simple_identifier(ident: &str) -> bool {
+        let mut chars = ident.chars();
+        matches!(chars.next(), Some('a'..='z' | '_'))
+            && chars.all(|ch| matches!(ch, 'a'..='z' | '0'..='9' | '_'))
+            && ident.len() < 64
+    }
 }
 
 pub mod settings {
@@ -24,6 +33,281 @@ pub mod settings {
     }
 }
 
+pub mod diff {
+    const CONTEXT_LINES: usize = 3;
+    const MAX_LCS_CELLS: usize = 4_000_000;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum LineOp {
+        Equal,
+        Delete,
+        Insert,
+    }
+
+    #[must_use]
+    pub fn unified_diff(from_label: &str, to_label: &str, from: &str, to: &str) -> String {
+        let from_lines = from.lines().collect::<Vec<_>>();
+        let to_lines = to.lines().collect::<Vec<_>>();
+        let ops = line_ops(&from_lines, &to_lines);
+        if ops.iter().all(|(op, _, _)| *op == LineOp::Equal) {
+            return String::new();
+        }
+
+        let mut out = format!("--- {from_label}\n+++ {to_label}\n");
+        for (start, stop) in hunk_ranges(&ops) {
+            let hunk = &ops[start..stop];
+            let from_count = hunk.iter().filter(|(op, _, _)| *op != LineOp::Insert).count();
+            let to_count = hunk.iter().filter(|(op, _, _)| *op != LineOp::Delete).count();
+            let (_, from_index, to_index) = hunk[0];
+            out.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                if from_count == 0 { from_index } else { from_index + 1 },
+                from_count,
+                if to_count == 0 { to_index } else { to_index + 1 },
+                to_count
+            ));
+            for (op, from_index, to_index) in hunk {
+                let (prefix, line) = match op {
+                    LineOp::Equal => (' ', from_lines[*from_index]),
+                    LineOp::Delete => ('-', from_lines[*from_index]),
+                    LineOp::Insert => ('+', to_lines[*to_index]),
+                };
+                out.push(prefix);
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    fn line_ops(from: &[&str], to: &[&str]) -> Vec<(LineOp, usize, usize)> {
+        let prefix = from.iter().zip(to).take_while(|(a, b)| a == b).count();
+        let suffix = from[prefix..]
+            .iter()
+            .rev()
+            .zip(to[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let from_mid = &from[prefix..from.len() - suffix];
+        let to_mid = &to[prefix..to.len() - suffix];
+
+        let mut ops = (0..prefix).map(|i| (LineOp::Equal, i, i)).collect::<Vec<_>>();
+        let (rows, cols) = (from_mid.len(), to_mid.len());
+        let (mut i, mut j) = (0, 0);
+
+        if (rows + 1).saturating_mul(cols + 1) <= MAX_LCS_CELLS {
+            let width = cols + 1;
+            let mut lcs = vec![0_u32; (rows + 1) * width];
+            for r in (0..rows).rev() {
+                for c in (0..cols).rev() {
+                    lcs[r * width + c] = if from_mid[r] == to_mid[c] {
+                        lcs[(r + 1) * width + c + 1] + 1
+                    } else {
+                        lcs[(r + 1) * width + c].max(lcs[r * width + c + 1])
+                    };
+                }
+            }
+
+            while i < rows && j < cols {
+                if from_mid[i] == to_mid[j] {
+                    ops.push((LineOp::Equal, prefix + i, prefix + j));
+                    i += 1;
+                    j += 1;
+                } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+                    ops.push((LineOp::Delete, prefix + i, prefix + j));
+                    i += 1;
+                } else {
+                    ops.push((LineOp::Insert, prefix + i, prefix + j));
+                    j += 1;
+                }
+            }
+        }
+
+        for r in i..rows {
+            ops.push((LineOp::Delete, prefix + r, prefix + j));
+        }
+        for c in j..cols {
+            ops.push((LineOp::Insert, prefix + rows, prefix + c));
+        }
+        for k in 0..suffix {
+            ops.push((LineOp::Equal, prefix + rows + k, prefix + cols + k));
+        }
+        ops
+    }
+
+    fn hunk_ranges(ops: &[(LineOp, usize, usize)]) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+        let mut k = 0;
+
+        while k < ops.len() {
+            if ops[k].0 == LineOp::Equal {
+                k += 1;
+                continue;
+            }
+
+            let start = k.saturating_sub(CONTEXT_LINES);
+            let mut last_change = k;
+            let mut j = k + 1;
+            while j < ops.len() {
+                if ops[j].0 != LineOp::Equal {
+                    last_change = j;
+                    j += 1;
+                    continue;
+                }
+                let run_end =
+                    ops[j..].iter().position(|(op, _, _)| *op != LineOp::Equal).map(|p| j + p);
+                match run_end {
+                    Some(run_end) if run_end - j <= 2 * CONTEXT_LINES => j = run_end,
+                    _ => break,
+                }
+            }
+
+            let stop = (last_change + 1 + CONTEXT_LINES).min(ops.len());
+            ranges.push((start, stop));
+            k = stop;
+        }
+
+        ranges
+    }
+}
+
+pub mod import_map {
+    use std::collections::HashMap;
+
+    pub const INLINE_IMPORT_MAP_MARKER: &str = "plts-import-map:";
+
+    #[must_use]
+    pub fn parse_inline_import_map(source: &str) -> HashMap<String, String> {
+        let Some(marker_start) = source.find(INLINE_IMPORT_MAP_MARKER) else {
+            return HashMap::new();
+        };
+
+        let mut cursor = marker_start + INLINE_IMPORT_MAP_MARKER.len();
+        while source[cursor..].chars().next().is_some_and(char::is_whitespace) {
+            cursor += source[cursor..].chars().next().map(char::len_utf8).unwrap_or(0);
+        }
+
+        if !source[cursor..].starts_with('{') {
+            return HashMap::new();
+        }
+
+        let mut depth = 0_i32;
+        let mut in_string = false;
+        let mut escaped = false;
+        let mut end = None;
+        for (offset, ch) in source[cursor..].char_indices() {
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if ch == '\\' {
+                    escaped = true;
+                } else if ch == '"' {
+                    in_string = false;
+                }
+                continue;
+            }
+
+            match ch {
+                '"' => in_string = true,
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(cursor + offset + ch.len_utf8());
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let Some(end) = end else {
+            return HashMap::new();
+        };
+
+        serde_json::from_str::<HashMap<String, String>>(&source[cursor..end]).unwrap_or_default()
+    }
+
+    /// Import map values may name an artifact by bare `sha256:` hash.
+    #[must_use]
+    pub fn import_map_target_specifier(target: &str) -> String {
+        if target.starts_with("sha256:") {
+            format!("plts+artifact:{target}")
+        } else {
+            target.to_string()
+        }
+    }
+
+    /// Artifact hashes an inline module can load: `sha256:` targets of its inline import map plus
+    /// any `plts+artifact:sha256:` specifier in the source. Sorted and deduplicated.
+    #[must_use]
+    pub fn referenced_artifact_hashes(source: &str) -> Vec<String> {
+        let mut hashes = parse_inline_import_map(source)
+            .into_values()
+            .filter_map(|target| {
+                import_map_target_specifier(&target)
+                    .strip_prefix("plts+artifact:")
+                    .filter(|hash| hash.starts_with("sha256:"))
+                    .map(str::to_string)
+            })
+            .collect::<Vec<_>>();
+
+        for (start, marker) in source.match_indices("plts+artifact:") {
+            let rest = &source[start + marker.len()..];
+            let end = rest
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, ':' | '-' | '_')))
+                .unwrap_or(rest.len());
+            if rest[..end].starts_with("sha256:") {
+                hashes.push(rest[..end].to_string());
+            }
+        }
+
+        hashes.sort_unstable();
+        hashes.dedup();
+        hashes
+    }
+
+    /// Checks every import map target up front: each must be an absolute module specifier, and
+    /// artifact targets must name a hash `artifact_exists` knows about. Entries are checked in key
+    /// order so the reported entry is stable.
+    pub fn validate_import_map<'a>(
+        entries: impl IntoIterator<Item = (&'a str, &'a str)>,
+        mut artifact_exists: impl FnMut(&str) -> Result<bool, String>,
+    ) -> Result<(), String> {
+        let mut entries = entries.into_iter().collect::<Vec<_>>();
+        entries.sort_unstable();
+
+        for (key, target) in entries {
+            let specifier = import_map_target_specifier(target);
+            if !is_absolute_specifier(&specifier) {
+                return Err(format!(
+                    "import map entry `{key}` has invalid target `{target}`; expected absolute module specifier or artifact hash"
+                ));
+            }
+
+            if let Some(artifact_hash) = specifier.strip_prefix("plts+artifact:")
+                && !artifact_exists(artifact_hash)?
+            {
+                return Err(format!(
+                    "import map entry `{key}` points at unknown artifact `{artifact_hash}`; no such row in plts.artifact"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_absolute_specifier(specifier: &str) -> bool {
+        let Some((scheme, rest)) = specifier.split_once(':') else {
+            return false;
+        };
+
+        scheme.chars().next().is_some_and(|ch| ch.is_ascii_alphabetic())
+            && scheme.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+            && !rest.is_empty()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -36,6 +320,17 @@ mod tests {
         assert_eq!(crate::sql::quote_literal("it's ok"), "'it''s ok'");
     }
 
+    #[test]
+    fn is_simple_identifier_accepts_only_plain_lowercase_names() {
+        assert!(crate::sql::is_simple_identifier("args"));
+        assert!(crate::sql::is_simple_identifier("_payload2"));
+        assert!(!crate::sql::is_simple_identifier(""));
+        assert!(!crate::sql::is_simple_identifier("2args"));
+        assert!(!crate::sql::is_simple_identifier("Payload"));
+        assert!(!crate::sql::is_simple_identifier("args; DROP"));
+        assert!(!crate::sql::is_simple_identifier(&"a".repeat(64)));
+    }
+
     #[test]
     fn parse_bool_setting_supports_common_postgres_forms() {
         assert_eq!(crate::settings::parse_bool_setting("true"), Some(true));
@@ -44,4 +339,107 @@ mod tests {
         assert_eq!(crate::settings::parse_bool_setting("no"), Some(false));
         assert_eq!(crate::settings::parse_bool_setting("maybe"), None);
     }
+
+    #[test]
+    fn unified_diff_reports_changed_lines_with_context() {
+        let from = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let to = "a\nb\nc\nD\ne\nf\ng\nh\ni\n";
+        assert_eq!(
+            crate::diff::unified_diff("from", "to", from, to),
+            "--- from\n+++ to\n@@ -1,8 +1,9 @@\n a\n b\n c\n-d\n+D\n e\n f\n g\n h\n+i\n"
+        );
+        assert_eq!(crate::diff::unified_diff("from", "to", from, from), "");
+    }
+
+    #[test]
+    fn unified_diff_splits_distant_changes_into_hunks() {
+        let from = (1..=20).map(|n| n.to_string()).collect::<Vec<_>>().join("\n");
+        let to = from
+            .lines()
+            .map(|line| match line {
+                "2" => "two",
+                "19" => "nineteen",
+                other => other,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let diff = crate::diff::unified_diff("a", "b", from.as_str(), to.as_str());
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n"), "{diff}");
+        assert!(diff.contains("@@ -16,5 +16,5 @@\n 16\n 17\n 18\n-19\n+nineteen\n 20\n"), "{diff}");
+    }
+
+    #[test]
+    fn parse_inline_import_map_extracts_json_object_after_marker() {
+        let source = r#"
+            // plts-import-map: {"@app/math":"sha256:abc","@app/time":"data:text/javascript,export const now=1;"}
+            import { now } from "@app/time";
+            export default () => now;
+        "#;
+
+        let import_map = crate::import_map::parse_inline_import_map(source);
+        assert_eq!(import_map.get("@app/math").map(String::as_str), Some("sha256:abc"));
+        assert_eq!(
+            import_map.get("@app/time").map(String::as_str),
+            Some("data:text/javascript,export const now=1;")
+        );
+    }
+
+    #[test]
+    fn parse_inline_import_map_returns_empty_when_marker_payload_is_invalid_json() {
+        let source = r#"
+            // plts-import-map: {"@app/math":
+            import { now } from "@app/math";
+            export default () => now;
+        "#;
+
+        assert!(crate::import_map::parse_inline_import_map(source).is_empty());
+    }
+
+    #[test]
+    fn referenced_artifact_hashes_reads_specifiers_and_import_map_targets() {
+        let source = r#"
+            // plts-import-map: {"@app/math":"sha256:abc","@app/util":"plts+artifact:sha256:def","@app/time":"data:text/javascript,export const now=1;"}
+            import { add } from "@app/math";
+            import helper from "plts+artifact:sha256:0123abcd";
+            const lazy = () => import('plts+artifact:sha256:lazy-1');
+            export default () => add(helper, lazy);
+        "#;
+
+        assert_eq!(
+            crate::import_map::referenced_artifact_hashes(source),
+            vec!["sha256:0123abcd", "sha256:abc", "sha256:def", "sha256:lazy-1"]
+        );
+        assert!(
+            crate::import_map::referenced_artifact_hashes("export default () => 1;").is_empty()
+        );
+    }
+
+    #[test]
+    fn validate_import_map_names_the_offending_entry() {
+        let known = |hash: &str| Ok(hash == "sha256:abc");
+
+        assert_eq!(
+            crate::import_map::validate_import_map(
+                [
+                    ("@app/math", "sha256:abc"),
+                    ("@app/time", "data:text/javascript,export const now=1;"),
+                    ("@app/util", "plts+artifact:sha256:abc"),
+                ],
+                known,
+            ),
+            Ok(())
+        );
+
+        let missing = crate::import_map::validate_import_map(
+            [("@app/math", "sha256:abc"), ("@app/typo", "sha256:abd")],
+            known,
+        )
+        .expect_err("unknown artifact hash should be rejected");
+        assert!(missing.contains("`@app/typo`"), "{missing}");
+        assert!(missing.contains("`sha256:abd`"), "{missing}");
+
+        let relative = crate::import_map::validate_import_map([("@app/rel", "./math.js")], known)
+            .expect_err("relative target should be rejected");
+        assert!(relative.contains("`@app/rel` has invalid target `./math.js`"), "{relative}");
+    }
 }
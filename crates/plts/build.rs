@@ -33,4 +33,35 @@ fn main() {
             runtime_dir.display()
         );
     }
+
+    generate_timeout_bindings();
+}
+
+/// `utils/timeout.h` is not part of pgrx's generated `pg_sys` bindings, so generate the few
+/// timer functions the per-op statement timeout needs from the same Postgres headers pgrx uses.
+fn generate_timeout_bindings() {
+    let (_, pg_config) = pgrx_bindgen::detect_pg_config()
+        .unwrap_or_else(|err| panic!("failed to locate pg_config for timeout bindings: {err}"))
+        .into_iter()
+        .next()
+        .expect("pgrx selects a pg_config for the enabled pg feature");
+    let includedir_server = pg_config
+        .includedir_server()
+        .unwrap_or_else(|err| panic!("pg_config did not report includedir-server: {err}"));
+
+    let bindings = bindgen::Builder::default()
+        .header_contents("plts_timeout.h", "#include \"postgres.h\"\n#include \"utils/timeout.h\"\n")
+        .clang_arg(format!("-I{}", includedir_server.display()))
+        .allowlist_type("TimeoutId")
+        .blocklist_type("TimestampTz")
+        .allowlist_function(
+            "enable_timeout_after|enable_timeout_at|disable_timeout|get_timeout_active|get_timeout_finish_time",
+        )
+        .generate()
+        .unwrap_or_else(|err| panic!("failed to generate utils/timeout.h bindings: {err}"));
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    bindings
+        .write_to_file(out_dir.join("timeout_bindings.rs"))
+        .unwrap_or_else(|err| panic!("failed to write utils/timeout.h bindings: {err}"));
 }
@@ -1,15 +1,111 @@
 use crate::compiler::{
-    compile_source_ts, compile_source_ts_checked, compiler_fingerprint, compute_artifact_hash,
-    contains_error_diagnostics, maybe_extract_source_map, semantic_typecheck_typescript,
+    compile_source_ts, compile_source_ts_checked, compiler_fingerprint_for_opts,
+    compute_artifact_hash, contains_error_diagnostics, diagnostics_report,
+    external_source_map_requested, extract_doc_comment, maybe_extract_source_map,
+    push_import_scheme_diagnostics, semantic_typecheck_typescript, strip_inline_source_map,
+    transpile_typescript,
 };
+use crate::function_program::{ProgramLimits, load_function_program, parse_artifact_ptr};
 use crate::observability::{
-    classify_compile_error, log_info, log_warn, metrics_json, record_compile_error,
-    record_compile_start, record_compile_success, should_log_info,
+    classify_compile_error, classify_execute_error, log_info, log_warn, metrics_json,
+    record_compile_error, record_compile_start, record_compile_success, record_execute_error,
+    record_execute_start, record_execute_success, should_log_info,
 };
+use crate::runtime::{
+    batch_result_json, build_runtime_context, describe_program, execute_program,
+    execute_program_batch, isolate_metrics_json, pool_metrics_json, raise_runtime_error_for_sql,
+    resolve_imports_json, runtime_available,
+};
+use crate::settings::RECOGNIZED_SETTINGS;
+use crate::source_map::lookup_original_position;
+use common::diff::unified_diff;
 use common::sql::quote_literal;
 use pgrx::JsonB;
 use pgrx::iter::TableIterator;
 use pgrx::prelude::*;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Upper bound for `plts.bench_compile` so one call cannot pin a backend for minutes.
+const BENCH_COMPILE_MAX_ITERATIONS: i32 = 1000;
+
+struct InlineFunction {
+    oid: pg_sys::Oid,
+    schema: String,
+    name: String,
+    source: String,
+}
+
+/// `LANGUAGE plts` functions in `schema` whose body is raw source rather than an artifact pointer.
+fn inline_plts_functions(schema: &str) -> Result<Vec<InlineFunction>, pgrx::spi::Error> {
+    Spi::connect(|client| {
+        let rows = client.select(
+            "
+            SELECT p.oid, n.nspname::text AS fn_schema, p.proname::text AS fn_name, p.prosrc
+            FROM pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE n.nspname = $1
+              AND l.lanname = 'plts'
+            ORDER BY p.proname, p.oid
+            ",
+            None,
+            &[schema.into()],
+        )?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            let prosrc = row.get_by_name::<String, _>("prosrc")?.unwrap_or_default();
+            if parse_artifact_ptr(&prosrc).is_some() {
+                continue;
+            }
+
+            let Some(oid) = row.get_by_name::<pg_sys::Oid, _>("oid")? else {
+                continue;
+            };
+            let schema = row.get_by_name::<String, _>("fn_schema")?.unwrap_or_default();
+            let name = row.get_by_name::<String, _>("fn_name")?.unwrap_or_default();
+            out.push(InlineFunction { oid, schema, name, source: prosrc });
+        }
+
+        Ok(out)
+    })
+}
+
+/// Artifact hashes reachable from live `LANGUAGE plts` functions: pointer targets and their
+/// import maps, plus the artifact specifiers and inline import map targets of inline bodies.
+fn live_function_artifact_hashes() -> Vec<String> {
+    Spi::connect(|client| {
+        let rows = client.select(
+            "
+            SELECT p.prosrc
+            FROM pg_proc p
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE l.lanname = 'plts'
+            ",
+            None,
+            &[],
+        )?;
+
+        let mut hashes = Vec::new();
+        for row in rows {
+            let prosrc = row.get_by_name::<String, _>("prosrc")?.unwrap_or_default();
+            let Some(ptr) = parse_artifact_ptr(&prosrc) else {
+                hashes.extend(common::import_map::referenced_artifact_hashes(&prosrc));
+                continue;
+            };
+            hashes.extend(ptr.import_map.into_values().filter_map(|target| {
+                let hash = target.strip_prefix("plts+artifact:").unwrap_or(target.as_str());
+                hash.starts_with("sha256:").then(|| hash.to_string())
+            }));
+            hashes.push(ptr.artifact_hash);
+        }
+
+        Ok::<Vec<String>, pgrx::spi::Error>(hashes)
+    })
+    .unwrap_or_else(|e| error!("plts failed to scan plts functions for artifact pointers: {e}"))
+}
 
 #[pg_schema]
 mod plts {
@@ -25,6 +121,76 @@ mod plts {
         JsonB(metrics_json())
     }
 
+    #[pg_extern]
+    fn pool_metrics() -> JsonB {
+        JsonB(pool_metrics_json())
+    }
+
+    #[pg_extern]
+    fn isolate_metrics() -> JsonB {
+        JsonB(isolate_metrics_json())
+    }
+
+    #[pg_extern]
+    fn settings() -> TableIterator<
+        'static,
+        (
+            name!(name, String),
+            name!(current_value, Option<String>),
+            name!(default_value, Option<String>),
+            name!(description, String),
+        ),
+    > {
+        let rows = RECOGNIZED_SETTINGS
+            .iter()
+            .map(|setting| {
+                let current_value = Spi::get_one_with_args::<String>(
+                    "SELECT current_setting($1, true)",
+                    &[setting.name.into()],
+                )
+                .ok()
+                .flatten();
+                (
+                    setting.name.to_string(),
+                    current_value,
+                    setting.default_value.map(str::to_string),
+                    setting.description.to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        TableIterator::new(rows)
+    }
+
+    #[pg_extern]
+    fn runtime_self_test() -> JsonB {
+        if !runtime_available() {
+            return JsonB(json!({ "available": false }));
+        }
+
+        let outcome = execute_program(
+            "export default () => ({ ok: true, sum: 1 + 1 });",
+            "default",
+            &HashMap::new(),
+            &ProgramLimits::default(),
+            &json!({}),
+        );
+        JsonB(match outcome {
+            Ok(result) => json!({ "available": true, "result": result }),
+            Err(err) => json!({
+                "available": true,
+                "error": { "stage": err.stage, "message": err.message }
+            }),
+        })
+    }
+
+    #[pg_extern]
+    fn resolve_imports(fn_oid: pg_sys::Oid) -> JsonB {
+        let Some(program) = load_function_program(fn_oid) else {
+            error!("plts.resolve_imports failed to load function oid={}", fn_oid);
+        };
+        JsonB(resolve_imports_json(&program))
+    }
+
     #[pg_extern]
     fn compile_ts(
         source_ts: &str,
@@ -41,10 +207,48 @@ mod plts {
         TableIterator::once((
             compiled.compiled_js,
             JsonB(compiled.diagnostics),
-            compiler_fingerprint().to_string(),
+            compiler_fingerprint_for_opts(&compiler_opts.0),
         ))
     }
 
+    #[pg_extern]
+    fn compile_report(source_ts: &str, compiler_opts: default!(JsonB, "'{}'::jsonb")) -> JsonB {
+        let compiled = compile_source_ts(source_ts, &compiler_opts.0);
+        JsonB(diagnostics_report(&compiled.diagnostics))
+    }
+
+    /// Transpiles `source_ts` `iterations` times without storing anything and reports wall-clock
+    /// timings in milliseconds, for tracking compiler throughput in CI.
+    #[pg_extern]
+    fn bench_compile(source_ts: &str, iterations: i32) -> JsonB {
+        if !(1..=BENCH_COMPILE_MAX_ITERATIONS).contains(&iterations) {
+            error!(
+                "plts.bench_compile iterations must be between 1 and {BENCH_COMPILE_MAX_ITERATIONS}; got {iterations}"
+            );
+        }
+
+        let mut samples_ms = Vec::with_capacity(iterations as usize);
+        for _ in 0..iterations {
+            pgrx::check_for_interrupts!();
+            let started_at = Instant::now();
+            let (_, diagnostics) = transpile_typescript(source_ts, &json!({}));
+            samples_ms.push(started_at.elapsed().as_secs_f64() * 1000.0);
+
+            if contains_error_diagnostics(&diagnostics) {
+                error!("plts.bench_compile source failed to transpile: {diagnostics}");
+            }
+        }
+
+        let total_ms = samples_ms.iter().sum::<f64>();
+        JsonB(json!({
+            "iterations": iterations,
+            "total_ms": total_ms,
+            "avg_ms": total_ms / samples_ms.len() as f64,
+            "min_ms": samples_ms.iter().copied().fold(f64::INFINITY, f64::min),
+            "max_ms": samples_ms.iter().copied().fold(0.0, f64::max),
+        }))
+    }
+
     #[pg_extern]
     fn typecheck_ts(source_ts: &str, compiler_opts: default!(JsonB, "'{}'::jsonb")) -> JsonB {
         JsonB(semantic_typecheck_typescript(source_ts, &compiler_opts.0))
@@ -66,7 +270,7 @@ mod plts {
         TableIterator::once((
             compiled.compiled_js,
             JsonB(compiled.diagnostics),
-            compiler_fingerprint().to_string(),
+            compiler_fingerprint_for_opts(&compiler_opts.0),
         ))
     }
 
@@ -75,13 +279,26 @@ mod plts {
         source_ts: &str,
         compiled_js: &str,
         compiler_opts: default!(JsonB, "'{}'::jsonb"),
+        diagnostics: default!(Option<JsonB>, "NULL"),
     ) -> String {
-        let fingerprint = compiler_fingerprint();
-        let hash = compute_artifact_hash(source_ts, compiled_js, &compiler_opts.0, fingerprint);
-        let source_map_sql = maybe_extract_source_map(compiled_js, &compiler_opts.0)
+        let fingerprint = compiler_fingerprint_for_opts(&compiler_opts.0);
+        let source_map = maybe_extract_source_map(compiled_js, &compiler_opts.0);
+        let compiled_js = if source_map.is_some() && external_source_map_requested(&compiler_opts.0)
+        {
+            strip_inline_source_map(compiled_js)
+        } else {
+            compiled_js.to_string()
+        };
+        let hash = compute_artifact_hash(source_ts, &compiled_js, &compiler_opts.0, &fingerprint);
+        let source_map_sql =
+            source_map.as_deref().map(quote_literal).unwrap_or_else(|| "NULL".to_string());
+        let doc_sql = extract_doc_comment(source_ts)
             .as_deref()
             .map(quote_literal)
             .unwrap_or_else(|| "NULL".to_string());
+        let diagnostics_sql = diagnostics
+            .map(|value| format!("{}::jsonb", quote_literal(&value.0.to_string())))
+            .unwrap_or_else(|| "NULL".to_string());
 
         let sql = format!(
             "
@@ -91,22 +308,28 @@ mod plts {
                 compiled_js,
                 compiler_opts,
                 compiler_fingerprint,
-                source_map
+                source_map,
+                diagnostics,
+                doc
             )
-            VALUES ({}, {}, {}, {}::jsonb, {}, {})
+            VALUES ({}, {}, {}, {}::jsonb, {}, {}, {}, {})
             ON CONFLICT (artifact_hash) DO UPDATE
             SET source_ts = EXCLUDED.source_ts,
                 compiled_js = EXCLUDED.compiled_js,
                 compiler_opts = EXCLUDED.compiler_opts,
                 compiler_fingerprint = EXCLUDED.compiler_fingerprint,
-                source_map = EXCLUDED.source_map
+                source_map = EXCLUDED.source_map,
+                diagnostics = COALESCE(EXCLUDED.diagnostics, plts.artifact.diagnostics),
+                doc = EXCLUDED.doc
             ",
             quote_literal(&hash),
             quote_literal(source_ts),
-            quote_literal(compiled_js),
+            quote_literal(&compiled_js),
             quote_literal(&compiler_opts.0.to_string()),
-            quote_literal(fingerprint),
-            source_map_sql
+            quote_literal(&fingerprint),
+            source_map_sql,
+            diagnostics_sql,
+            doc_sql
         );
 
         let _ = Spi::run(&sql);
@@ -119,7 +342,8 @@ mod plts {
         let started_at = record_compile_start();
         log_info("plts.compile_and_store start");
         let opts = compiler_opts.0;
-        let compiled = compile_source_ts(source_ts, &opts);
+        let mut compiled = compile_source_ts(source_ts, &opts);
+        push_import_scheme_diagnostics(&mut compiled);
 
         if contains_error_diagnostics(&compiled.diagnostics) {
             let error_message = format!(
@@ -131,7 +355,12 @@ mod plts {
             error!("{error_message}");
         }
 
-        let artifact_hash = upsert_artifact(source_ts, &compiled.compiled_js, JsonB(opts));
+        let artifact_hash = upsert_artifact(
+            source_ts,
+            &compiled.compiled_js,
+            JsonB(opts),
+            Some(JsonB(compiled.diagnostics)),
+        );
         record_compile_success(started_at);
         if should_log_info() {
             log_info(&format!("plts.compile_and_store success artifact_hash={artifact_hash}"));
@@ -139,6 +368,176 @@ mod plts {
         artifact_hash
     }
 
+    #[pg_extern]
+    fn compile_batch(
+        sources: JsonB,
+        compiler_opts: default!(JsonB, "'{}'::jsonb"),
+    ) -> TableIterator<
+        'static,
+        (name!(name, String), name!(artifact_hash, Option<String>), name!(diagnostics, JsonB)),
+    > {
+        let Some(items) = sources.0.as_array() else {
+            error!("plts.compile_batch expects sources to be a JSON array");
+        };
+
+        let mut opts = compiler_opts.0;
+        let fail_fast = opts
+            .as_object_mut()
+            .and_then(|obj| obj.remove("fail_fast"))
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+
+        let mut rows = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let Some(name) = item.get("name").and_then(Value::as_str) else {
+                error!("plts.compile_batch source #{} is missing a string name", index);
+            };
+            let Some(source_ts) = item.get("source_ts").and_then(Value::as_str) else {
+                error!("plts.compile_batch source {} is missing a string source_ts", name);
+            };
+
+            let started_at = record_compile_start();
+            let mut compiled = compile_source_ts(source_ts, &opts);
+            push_import_scheme_diagnostics(&mut compiled);
+            if contains_error_diagnostics(&compiled.diagnostics) {
+                let error_message = format!(
+                    "plts.compile_batch source {} failed due to TypeScript diagnostics: {}",
+                    name, compiled.diagnostics
+                );
+                record_compile_error(started_at, classify_compile_error(error_message.as_str()));
+                if fail_fast {
+                    log_warn("plts.compile_batch aborted due to diagnostics");
+                    error!("{error_message}");
+                }
+                rows.push((name.to_string(), None, JsonB(compiled.diagnostics)));
+                continue;
+            }
+
+            let artifact_hash = upsert_artifact(
+                source_ts,
+                &compiled.compiled_js,
+                JsonB(opts.clone()),
+                Some(JsonB(compiled.diagnostics.clone())),
+            );
+            record_compile_success(started_at);
+            rows.push((name.to_string(), Some(artifact_hash), JsonB(compiled.diagnostics)));
+        }
+
+        if should_log_info() {
+            log_info(&format!("plts.compile_batch compiled {} sources", rows.len()));
+        }
+        TableIterator::new(rows)
+    }
+
+    #[pg_extern]
+    fn invoke_batch(
+        fn_oid: pg_sys::Oid,
+        args_array: JsonB,
+        stop_on_error: default!(bool, "false"),
+    ) -> JsonB {
+        let Some(items) = args_array.0.as_array() else {
+            error!("plts.invoke_batch expects args_array to be a JSON array");
+        };
+
+        let is_plts_function = Spi::get_one_with_args::<bool>(
+            "
+            SELECT l.lanname = 'plts'
+            FROM pg_proc p
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE p.oid = $1
+            ",
+            &[fn_oid.into()],
+        )
+        .ok()
+        .flatten()
+        .unwrap_or(false);
+        if !is_plts_function {
+            error!("plts.invoke_batch target oid={} is not a LANGUAGE plts function", fn_oid);
+        }
+
+        let can_execute = Spi::get_one_with_args::<bool>(
+            "SELECT has_function_privilege($1, 'EXECUTE')",
+            &[fn_oid.into()],
+        )
+        .ok()
+        .flatten()
+        .unwrap_or(false);
+        if !can_execute {
+            error!("permission denied for plts.invoke_batch on function oid={}", fn_oid);
+        }
+
+        if !runtime_available() {
+            error!("plts.invoke_batch requires the v8_runtime feature");
+        }
+
+        let Some(program) = load_function_program(fn_oid) else {
+            error!("plts.invoke_batch failed to load function oid={}", fn_oid);
+        };
+
+        let contexts =
+            items.iter().map(|args| build_runtime_context(&program, args)).collect::<Vec<_>>();
+        let started_at = record_execute_start();
+        match execute_program_batch(
+            &program.source,
+            &program.entrypoint_export,
+            &program.bare_specifier_map,
+            &program.limits,
+            &contexts,
+            stop_on_error,
+            None,
+        ) {
+            Ok(results) => {
+                record_execute_success(started_at);
+                JsonB(Value::Array(results.iter().map(batch_result_json).collect()))
+            }
+            Err(err) => {
+                let error_text = err.to_string();
+                record_execute_error(started_at, classify_execute_error(error_text.as_str()));
+                raise_runtime_error_for_sql(&program, &err);
+            }
+        }
+    }
+
+    #[pg_extern]
+    fn inline_functions(
+        schema: &str,
+    ) -> TableIterator<'static, (name!(schema, String), name!(name, String), name!(oid, pg_sys::Oid))>
+    {
+        let rows = inline_plts_functions(schema)
+            .unwrap_or_else(|e| {
+                error!("plts.inline_functions failed to scan schema {}: {e}", schema)
+            })
+            .into_iter()
+            .map(|function| (function.schema, function.name, function.oid));
+
+        TableIterator::new(rows)
+    }
+
+    /// Compiles every inline `LANGUAGE plts` function in `schema` without storing artifacts and
+    /// reports `{ schema, ok, functions: [{ fn_name, ok, error_count }] }`. Artifact pointers are
+    /// skipped because their artifacts were compiled when stored.
+    #[pg_extern]
+    fn validate_schema(schema: &str) -> JsonB {
+        let functions = inline_plts_functions(schema)
+            .unwrap_or_else(|e| {
+                error!("plts.validate_schema failed to scan schema {}: {e}", schema)
+            })
+            .into_iter()
+            .map(|function| {
+                let mut compiled = compile_source_ts(&function.source, &json!({}));
+                push_import_scheme_diagnostics(&mut compiled);
+                let error_count = diagnostics_report(&compiled.diagnostics)
+                    .pointer("/counts/errors")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0);
+                json!({ "fn_name": function.name, "ok": error_count == 0, "error_count": error_count })
+            })
+            .collect::<Vec<_>>();
+        let ok = functions.iter().all(|function| function["ok"] == true);
+
+        JsonB(json!({ "schema": schema, "ok": ok, "functions": functions }))
+    }
+
     #[pg_extern]
     fn get_artifact(artifact_hash: &str) -> Option<JsonB> {
         let sql = format!(
@@ -149,6 +548,8 @@ mod plts {
                 'compiler_opts', compiler_opts,
                 'compiler_fingerprint', compiler_fingerprint,
                 'source_map', source_map,
+                'diagnostics', diagnostics,
+                'doc', doc,
                 'created_at', created_at
             )
             FROM plts.artifact
@@ -159,4 +560,222 @@ mod plts {
 
         Spi::get_one::<JsonB>(&sql).ok().flatten()
     }
+
+    /// Leading JSDoc block of a `LANGUAGE plts` function: the stored `plts.artifact.doc` for
+    /// artifact pointers, otherwise the comment extracted from the inline source.
+    #[pg_extern]
+    fn doc(fn_oid: pg_sys::Oid) -> Option<String> {
+        let prosrc = Spi::get_one_with_args::<String>(
+            "
+            SELECT p.prosrc
+            FROM pg_proc p
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE p.oid = $1 AND l.lanname = 'plts'
+            ",
+            &[fn_oid.into()],
+        )
+        .unwrap_or_else(|e| error!("plts.doc failed to load function oid={}: {e}", fn_oid))?;
+
+        match parse_artifact_ptr(&prosrc) {
+            Some(ptr) => Spi::get_one_with_args::<String>(
+                "SELECT doc FROM plts.artifact WHERE artifact_hash = $1",
+                &[ptr.artifact_hash.as_str().into()],
+            )
+            .ok()
+            .flatten(),
+            None => extract_doc_comment(&prosrc),
+        }
+    }
+
+    /// Handler metadata (`kind`, `args_schema`) of a `LANGUAGE plts` function, read by evaluating
+    /// its module without invoking the handler. `NULL` when the module cannot be evaluated.
+    #[pg_extern]
+    fn describe(fn_oid: pg_sys::Oid) -> Option<JsonB> {
+        let is_plts_function = Spi::get_one_with_args::<bool>(
+            "
+            SELECT l.lanname = 'plts'
+            FROM pg_proc p
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE p.oid = $1
+            ",
+            &[fn_oid.into()],
+        )
+        .ok()
+        .flatten()
+        .unwrap_or(false);
+        if !is_plts_function || !runtime_available() {
+            return None;
+        }
+
+        let program = load_function_program(fn_oid)?;
+        let metadata = describe_program(&program).ok()?;
+        serde_json::to_value(metadata).ok().map(JsonB)
+    }
+
+    #[pg_extern]
+    fn decode_source_map(artifact_hash: &str, line: i32, column: i32) -> Option<JsonB> {
+        let source_map = Spi::get_one_with_args::<String>(
+            "SELECT source_map FROM plts.artifact WHERE artifact_hash = $1",
+            &[artifact_hash.into()],
+        )
+        .ok()
+        .flatten()?;
+
+        let line = u32::try_from(line).ok()?;
+        let column = u32::try_from(column).ok()?;
+        let position = lookup_original_position(&source_map, line, column)?;
+        Some(JsonB(json!({
+            "source": position.source,
+            "original_line": position.line,
+            "original_column": position.column,
+            "name": position.name,
+        })))
+    }
+
+    #[pg_extern]
+    fn artifact_js_diff(hash_a: &str, hash_b: &str) -> String {
+        let load_compiled_js = |artifact_hash: &str| {
+            Spi::get_one_with_args::<String>(
+                "SELECT compiled_js FROM plts.artifact WHERE artifact_hash = $1",
+                &[artifact_hash.into()],
+            )
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| error!("plts.artifact_js_diff artifact {} not found", artifact_hash))
+        };
+
+        let compiled_a = load_compiled_js(hash_a);
+        let compiled_b = load_compiled_js(hash_b);
+        unified_diff(hash_a, hash_b, compiled_a.as_str(), compiled_b.as_str())
+    }
+
+    /// Raises an error naming the first entry whose target is neither an absolute module
+    /// specifier nor an artifact hash present in `plts.artifact`.
+    #[pg_extern]
+    fn validate_import_map(import_map: JsonB) {
+        let Value::Object(entries) = &import_map.0 else {
+            error!("plts.validate_import_map expects a JSON object of specifier to target");
+        };
+
+        let mut targets = Vec::with_capacity(entries.len());
+        for (key, target) in entries {
+            let Some(target) = target.as_str() else {
+                error!("plts.validate_import_map entry `{}` must map to a string target", key);
+            };
+            targets.push((key.as_str(), target));
+        }
+
+        common::import_map::validate_import_map(targets, |artifact_hash| {
+            Spi::get_one_with_args::<bool>(
+                "SELECT EXISTS (SELECT 1 FROM plts.artifact WHERE artifact_hash = $1)",
+                &[artifact_hash.into()],
+            )
+            .map(|exists| exists.unwrap_or(false))
+            .map_err(|e| format!("failed to look up artifact {artifact_hash}: {e}"))
+        })
+        .unwrap_or_else(|err| error!("plts.validate_import_map: {err}"));
+    }
+
+    /// Deletes artifacts created before `now() - older_than` that nothing can still load: no
+    /// live `plts` function (pointer, import map, or inline import), no `stopgap.fn_version` row,
+    /// and no import from another stored artifact references them.
+    #[pg_extern]
+    fn purge_artifacts(older_than: Interval) -> i64 {
+        let referenced = live_function_artifact_hashes();
+        let stopgap_installed =
+            Spi::get_one::<bool>("SELECT to_regclass('stopgap.fn_version') IS NOT NULL")
+                .ok()
+                .flatten()
+                .unwrap_or(false);
+        let stopgap_filter = if stopgap_installed {
+            "AND NOT EXISTS (SELECT 1 FROM stopgap.fn_version v WHERE v.artifact_hash = a.artifact_hash)"
+        } else {
+            ""
+        };
+        let sql = format!(
+            "
+            WITH purged AS (
+                DELETE FROM plts.artifact a
+                WHERE a.created_at < now() - $1
+                  AND a.artifact_hash <> ALL($2)
+                  {stopgap_filter}
+                  AND NOT EXISTS (
+                      SELECT 1
+                      FROM plts.artifact importer
+                      WHERE importer.artifact_hash <> a.artifact_hash
+                        AND POSITION('plts+artifact:' || a.artifact_hash IN importer.compiled_js) > 0
+                  )
+                RETURNING 1
+            )
+            SELECT count(*) FROM purged
+            "
+        );
+
+        Spi::get_one_with_args::<i64>(&sql, &[older_than.into(), referenced.into()])
+            .unwrap_or_else(|e| error!("plts.purge_artifacts failed: {e}"))
+            .unwrap_or(0)
+    }
+
+    /// Counts, per stored artifact, the live `plts` functions (pointers, import maps, and inline
+    /// imports) and `stopgap.fn_version` rows referencing it. `last_referenced_at` is the creation time of the
+    /// most recent deployment using the artifact, or NULL when no deployment references it.
+    #[pg_extern]
+    fn artifact_usage() -> TableIterator<
+        'static,
+        (
+            name!(artifact_hash, String),
+            name!(ref_count, i64),
+            name!(last_referenced_at, Option<TimestampWithTimeZone>),
+        ),
+    > {
+        let referenced = live_function_artifact_hashes();
+        let stopgap_installed =
+            Spi::get_one::<bool>("SELECT to_regclass('stopgap.fn_version') IS NOT NULL")
+                .ok()
+                .flatten()
+                .unwrap_or(false);
+        let version_refs = if stopgap_installed {
+            "
+            SELECT v.artifact_hash, count(*) AS refs, max(d.created_at) AS last_at
+            FROM stopgap.fn_version v
+            JOIN stopgap.deployment d ON d.id = v.deployment_id
+            GROUP BY v.artifact_hash
+            "
+        } else {
+            "SELECT NULL::text AS artifact_hash, 0::bigint AS refs, NULL::timestamptz AS last_at WHERE false"
+        };
+        let sql = format!(
+            "
+            WITH pointer_refs AS (
+                SELECT hash, count(*) AS refs
+                FROM unnest($1::text[]) AS hash
+                GROUP BY hash
+            ),
+            version_refs AS ({version_refs})
+            SELECT a.artifact_hash,
+                   COALESCE(p.refs, 0) + COALESCE(v.refs, 0) AS ref_count,
+                   v.last_at AS last_referenced_at
+            FROM plts.artifact a
+            LEFT JOIN pointer_refs p ON p.hash = a.artifact_hash
+            LEFT JOIN version_refs v ON v.artifact_hash = a.artifact_hash
+            ORDER BY a.artifact_hash
+            "
+        );
+
+        let rows = Spi::connect(|client| {
+            let rows = client.select(&sql, None, &[referenced.into()])?;
+            let mut out = Vec::new();
+            for row in rows {
+                out.push((
+                    row.get_by_name::<String, _>("artifact_hash")?.unwrap_or_default(),
+                    row.get_by_name::<i64, _>("ref_count")?.unwrap_or(0),
+                    row.get_by_name::<TimestampWithTimeZone, _>("last_referenced_at")?,
+                ));
+            }
+            Ok::<_, pgrx::spi::Error>(out)
+        })
+        .unwrap_or_else(|e| error!("plts.artifact_usage failed: {e}"));
+
+        TableIterator::new(rows)
+    }
 }
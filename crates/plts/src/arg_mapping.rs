@@ -1,5 +1,6 @@
 use pgrx::JsonB;
-use pgrx::pg_catalog::pg_proc::PgProc;
+use pgrx::datum::UnboxDatum;
+use pgrx::pg_catalog::pg_proc::{PgProc, ProArgMode};
 use pgrx::pg_getarg_type;
 use pgrx::prelude::*;
 use serde_json::Value;
@@ -17,6 +18,7 @@ pub(crate) unsafe fn build_args_payload(
     fn_oid: pg_sys::Oid,
 ) -> Value {
     let arg_oids = get_arg_type_oids(fcinfo, fn_oid);
+    let arg_names = get_input_arg_names(fn_oid);
     let nargs = unsafe { (*fcinfo).nargs as usize };
     let mut positional = Vec::with_capacity(nargs);
     let mut named = serde_json::Map::with_capacity(nargs);
@@ -28,7 +30,8 @@ pub(crate) unsafe fn build_args_payload(
             if arg.isnull { Value::Null } else { unsafe { datum_to_json_value(arg.value, oid) } };
 
         positional.push(value.clone());
-        named.insert(i.to_string(), value);
+        let key = arg_names.get(i).cloned().flatten().unwrap_or_else(|| i.to_string());
+        named.insert(key, value);
     }
 
     let mut payload = serde_json::Map::with_capacity(2);
@@ -37,24 +40,95 @@ pub(crate) unsafe fn build_args_payload(
     Value::Object(payload)
 }
 
-unsafe fn datum_to_json_value(datum: pg_sys::Datum, oid: pg_sys::Oid) -> Value {
+pub(crate) unsafe fn datum_to_json_value(datum: pg_sys::Datum, oid: pg_sys::Oid) -> Value {
     match oid {
-        pg_sys::TEXTOID => unsafe {
+        pg_sys::TEXTOID | pg_sys::VARCHAROID | pg_sys::BPCHAROID => unsafe {
             String::from_datum(datum, false).map(Value::String).unwrap_or(Value::Null)
         },
+        pg_sys::INT2OID => unsafe { i16::from_datum(datum, false) }
+            .map(|v| Value::Number(serde_json::Number::from(v)))
+            .unwrap_or(Value::Null),
         pg_sys::INT4OID => unsafe { i32::from_datum(datum, false) }
             .map(|v| Value::Number(serde_json::Number::from(v)))
             .unwrap_or(Value::Null),
+        pg_sys::INT8OID => {
+            unsafe { i64::from_datum(datum, false) }.map(int8_json_value).unwrap_or(Value::Null)
+        }
+        pg_sys::FLOAT4OID => unsafe { f32::from_datum(datum, false) }
+            .and_then(|v| serde_json::Number::from_f64(f64::from(v)))
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        pg_sys::FLOAT8OID => unsafe { f64::from_datum(datum, false) }
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        pg_sys::NUMERICOID => unsafe { AnyNumeric::from_datum(datum, false) }
+            .map(|v| Value::String(v.to_string()))
+            .unwrap_or(Value::Null),
+        pg_sys::TIMESTAMPTZOID => unsafe { TimestampWithTimeZone::from_datum(datum, false) }
+            .map(|v| Value::String(v.to_iso_string()))
+            .unwrap_or(Value::Null),
+        pg_sys::TIMESTAMPOID => unsafe { Timestamp::from_datum(datum, false) }
+            .map(|v| Value::String(v.to_iso_string()))
+            .unwrap_or(Value::Null),
+        pg_sys::DATEOID => unsafe { Date::from_datum(datum, false) }
+            .map(|v| Value::String(v.to_iso_string()))
+            .unwrap_or(Value::Null),
+        pg_sys::UUIDOID => unsafe { pgrx::Uuid::from_datum(datum, false) }
+            .map(|v| Value::String(v.to_string()))
+            .unwrap_or(Value::Null),
         pg_sys::BOOLOID => {
             unsafe { bool::from_datum(datum, false) }.map(Value::Bool).unwrap_or(Value::Null)
         }
         pg_sys::JSONBOID => {
             unsafe { JsonB::from_datum(datum, false) }.map(|v| v.0).unwrap_or(Value::Null)
         }
+        pg_sys::JSONOID => {
+            unsafe { pgrx::Json::from_datum(datum, false) }.map(|v| v.0).unwrap_or(Value::Null)
+        }
+        pg_sys::TEXTARRAYOID => unsafe { array_to_json_value::<String>(datum, Value::String) },
+        pg_sys::INT4ARRAYOID => unsafe { array_to_json_value::<i32>(datum, Value::from) },
+        pg_sys::INT8ARRAYOID => unsafe { array_to_json_value::<i64>(datum, int8_json_value) },
+        pg_sys::FLOAT8ARRAYOID => unsafe {
+            array_to_json_value::<f64>(datum, |v| {
+                serde_json::Number::from_f64(v).map(Value::Number).unwrap_or(Value::Null)
+            })
+        },
+        pg_sys::BOOLARRAYOID => unsafe { array_to_json_value::<bool>(datum, Value::Bool) },
+        pg_sys::NUMERICARRAYOID => unsafe {
+            array_to_json_value::<AnyNumeric>(datum, |v| Value::String(v.to_string()))
+        },
+        pg_sys::JSONBARRAYOID => unsafe { array_to_json_value::<JsonB>(datum, |v| v.0) },
         _ => Value::Null,
     }
 }
 
+/// Largest integer a JS number holds exactly (`Number.MAX_SAFE_INTEGER`, 2^53 - 1).
+const JS_MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// `int8` arrives as a number when JS can hold it exactly and as a decimal string otherwise, the
+/// way `numeric` always does, so `JSON.parse` in the isolate never rounds it.
+fn int8_json_value(value: i64) -> Value {
+    if (-JS_MAX_SAFE_INTEGER..=JS_MAX_SAFE_INTEGER).contains(&value) {
+        Value::from(value)
+    } else {
+        Value::String(value.to_string())
+    }
+}
+
+unsafe fn array_to_json_value<T>(datum: pg_sys::Datum, convert: impl Fn(T) -> Value) -> Value
+where
+    for<'arr> T: UnboxDatum<As<'arr> = T> + 'arr,
+{
+    unsafe { Vec::<Option<T>>::from_datum(datum, false) }
+        .map(|items| {
+            Value::Array(
+                items.into_iter().map(|item| item.map(&convert).unwrap_or(Value::Null)).collect(),
+            )
+        })
+        .unwrap_or(Value::Null)
+}
+
 fn get_arg_type_oids(fcinfo: pg_sys::FunctionCallInfo, fn_oid: pg_sys::Oid) -> Vec<pg_sys::Oid> {
     let nargs = unsafe { (*fcinfo).nargs.max(0) as usize };
     let inferred: Vec<pg_sys::Oid> =
@@ -66,3 +140,89 @@ fn get_arg_type_oids(fcinfo: pg_sys::FunctionCallInfo, fn_oid: pg_sys::Oid) -> V
 
     PgProc::new(fn_oid).map(|proc| proc.proargtypes()).unwrap_or_default()
 }
+
+fn get_input_arg_names(fn_oid: pg_sys::Oid) -> Vec<Option<String>> {
+    let Some(proc) = PgProc::new(fn_oid) else {
+        return Vec::new();
+    };
+
+    let names = proc.proargnames();
+    let modes = proc.proargmodes();
+    let names = if modes.is_empty() {
+        names
+    } else {
+        names
+            .into_iter()
+            .zip(modes)
+            .filter(|(_, mode)| {
+                matches!(mode, ProArgMode::In | ProArgMode::InOut | ProArgMode::Variadic)
+            })
+            .map(|(name, _)| name)
+            .collect()
+    };
+
+    names.into_iter().map(|name| name.filter(|name| !name.is_empty())).collect()
+}
+
+pub(crate) fn function_return_type_oid(fn_oid: pg_sys::Oid) -> pg_sys::Oid {
+    PgProc::new(fn_oid).map(|proc| proc.prorettype()).unwrap_or(pg_sys::JSONBOID)
+}
+
+pub(crate) fn function_returns_set(fn_oid: pg_sys::Oid) -> bool {
+    PgProc::new(fn_oid).is_some_and(|proc| proc.proretset())
+}
+
+/// Declared return types the call handler can produce a datum for. Anything else (numeric,
+/// varchar, arrays, composites, ...) is rejected by the validator and at call time.
+pub(crate) fn is_supported_return_type(rettype: pg_sys::Oid) -> bool {
+    matches!(
+        rettype,
+        pg_sys::INT4OID
+            | pg_sys::INT8OID
+            | pg_sys::FLOAT8OID
+            | pg_sys::BOOLOID
+            | pg_sys::TEXTOID
+            | pg_sys::JSONOID
+            | pg_sys::JSONBOID
+            | pg_sys::VOIDOID
+    )
+}
+
+pub(crate) fn type_display_name(type_oid: pg_sys::Oid) -> String {
+    let name = unsafe { pg_sys::format_type_be(type_oid) };
+    unsafe { std::ffi::CStr::from_ptr(name) }.to_string_lossy().into_owned()
+}
+
+pub(crate) fn json_value_to_return_datum(
+    value: Value,
+    rettype: pg_sys::Oid,
+) -> Result<Option<pg_sys::Datum>, String> {
+    let mismatch = |expected: &str, value: &Value| {
+        format!("cannot coerce return value {value} to declared return type {expected}")
+    };
+
+    match rettype {
+        pg_sys::INT4OID => value
+            .as_i64()
+            .and_then(|v| i32::try_from(v).ok())
+            .map(|v| v.into_datum())
+            .ok_or_else(|| mismatch("int4", &value)),
+        pg_sys::INT8OID => {
+            value.as_i64().map(|v| v.into_datum()).ok_or_else(|| mismatch("int8", &value))
+        }
+        pg_sys::FLOAT8OID => {
+            value.as_f64().map(|v| v.into_datum()).ok_or_else(|| mismatch("float8", &value))
+        }
+        pg_sys::BOOLOID => {
+            value.as_bool().map(|v| v.into_datum()).ok_or_else(|| mismatch("bool", &value))
+        }
+        pg_sys::TEXTOID => match value {
+            Value::String(v) => Ok(v.into_datum()),
+            other => Err(mismatch("text", &other)),
+        },
+        pg_sys::JSONOID => Ok(pgrx::Json(value).into_datum()),
+        pg_sys::JSONBOID => Ok(JsonB(value).into_datum()),
+        pg_sys::VOIDOID => Ok(Some(pg_sys::Datum::from(0))),
+        other => Err(mismatch(&type_display_name(other), &value)),
+    }
+}
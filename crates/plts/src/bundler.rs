@@ -0,0 +1,697 @@
+use base64::Engine;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const MAX_BUNDLED_MODULE_BYTES: usize = 64 * 1024;
+const MAX_BUNDLE_DEPTH: usize = 16;
+const SOURCE_MAP_PREFIX: &str = "//# sourceMappingURL=data:application/json;base64,";
+const DEFAULT_EXPORT_LOCAL: &str = "__plts_default";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ImportStatement {
+    end: usize,
+    clause: Option<ImportClause>,
+    specifier: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ImportClause {
+    default_local: Option<String>,
+    namespace_local: Option<String>,
+    named: Vec<(String, String)>,
+}
+
+#[derive(Debug, Default)]
+struct RewrittenModule {
+    bindings: Vec<String>,
+    body: String,
+    exports: Vec<(String, String)>,
+}
+
+struct Bundler<'a> {
+    resolve_artifact: &'a mut dyn FnMut(&str) -> Option<String>,
+    modules: Vec<String>,
+    exports_by_id: Vec<Vec<String>>,
+    ids: HashMap<String, usize>,
+    visiting: Vec<String>,
+}
+
+pub(crate) fn bundle_enabled(compiler_opts: &Value) -> bool {
+    compiler_opts.get("bundle").and_then(Value::as_bool).unwrap_or(false)
+}
+
+pub(crate) fn bundle_static_imports(
+    entry_js: &str,
+    resolve_artifact: &mut dyn FnMut(&str) -> Option<String>,
+) -> String {
+    let mut bundler = Bundler {
+        resolve_artifact,
+        modules: Vec::new(),
+        exports_by_id: Vec::new(),
+        ids: HashMap::new(),
+        visiting: Vec::new(),
+    };
+
+    let Some(entry) = bundler.rewrite_module(entry_js, true) else {
+        return entry_js.to_string();
+    };
+    if bundler.modules.is_empty() {
+        return entry_js.to_string();
+    }
+
+    let mut prelude = bundler.modules;
+    prelude.extend(entry.bindings);
+    let prelude = prelude.join("\n");
+    let prelude_lines = prelude.matches('\n').count() + 1;
+
+    shift_inline_source_map(&format!("{prelude}\n{}", entry.body), prelude_lines)
+}
+
+impl Bundler<'_> {
+    fn rewrite_module(&mut self, source: &str, is_entry: bool) -> Option<RewrittenModule> {
+        let mut out = RewrittenModule::default();
+        let mask = code_mask(source);
+        let mut cursor = 0;
+
+        while cursor < source.len() {
+            let line_end = source[cursor..].find('\n').map_or(source.len(), |i| cursor + i + 1);
+            let line = &source[cursor..line_end];
+            if continues_literal(&mask, cursor) {
+                out.body.push_str(line);
+                cursor = line_end;
+                continue;
+            }
+
+            if is_static_import_start(line) {
+                let statement = parse_import_statement(source, cursor);
+                let inlinable = statement.as_ref().map_or_else(
+                    || line.contains("data:") || line.contains("plts+artifact:"),
+                    |statement| is_inlinable_specifier(statement.specifier.as_str()),
+                );
+
+                match statement {
+                    Some(statement) if inlinable => {
+                        let id = self.bundle_module(statement.specifier.as_str())?;
+                        if let Some(clause) = statement.clause.as_ref() {
+                            out.bindings.push(self.binding_declaration(id, clause)?);
+                        }
+                        let newlines = source[cursor..statement.end].matches('\n').count();
+                        out.body.push_str(&"\n".repeat(newlines));
+                        cursor = statement.end;
+                        continue;
+                    }
+                    _ if inlinable || !is_entry => return None,
+                    _ => {}
+                }
+            }
+
+            if !is_entry {
+                if line.starts_with("//# sourceMappingURL=") {
+                    if line.ends_with('\n') {
+                        out.body.push('\n');
+                    }
+                    cursor = line_end;
+                    continue;
+                }
+
+                if line.match_indices("import.meta").any(|(index, _)| {
+                    mask[cursor + index] && (index == 0 || line[..index].ends_with(' '))
+                }) {
+                    return None;
+                }
+
+                if line.starts_with("export") {
+                    let rewritten = rewrite_export_line(line, source, &mask, &mut out.exports)?;
+                    out.body.push_str(rewritten.as_str());
+                    cursor = line_end;
+                    continue;
+                }
+            }
+
+            out.body.push_str(line);
+            cursor = line_end;
+        }
+
+        Some(out)
+    }
+
+    fn bundle_module(&mut self, specifier: &str) -> Option<usize> {
+        if let Some(id) = self.ids.get(specifier) {
+            return Some(*id);
+        }
+        if self.visiting.len() >= MAX_BUNDLE_DEPTH || self.visiting.iter().any(|s| s == specifier) {
+            return None;
+        }
+
+        let source = self.load_module_source(specifier)?;
+        if source.len() > MAX_BUNDLED_MODULE_BYTES {
+            return None;
+        }
+
+        self.visiting.push(specifier.to_string());
+        let rewritten = self.rewrite_module(source.as_str(), false);
+        self.visiting.pop();
+        let rewritten = rewritten?;
+
+        let id = self.modules.len();
+        let namespace = rewritten
+            .exports
+            .iter()
+            .map(|(exported, local)| format!("{exported}: {local}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut lines = vec![format!("const __plts_bundle_{id} = await (async () => {{")];
+        lines.extend(rewritten.bindings);
+        lines.push(rewritten.body.trim_end_matches('\n').to_string());
+        lines.push(format!("return Object.freeze({{ __proto__: null, {namespace} }});"));
+        lines.push("})();".to_string());
+
+        self.modules.push(lines.join("\n"));
+        self.exports_by_id
+            .push(rewritten.exports.into_iter().map(|(exported, _)| exported).collect());
+        self.ids.insert(specifier.to_string(), id);
+        Some(id)
+    }
+
+    fn load_module_source(&mut self, specifier: &str) -> Option<String> {
+        if let Some(raw_hash) = specifier.strip_prefix("plts+artifact:") {
+            let artifact_hash = raw_hash.trim_start_matches('/').trim();
+            if artifact_hash.is_empty() {
+                return None;
+            }
+            return (self.resolve_artifact)(artifact_hash);
+        }
+
+        let payload = specifier.strip_prefix("data:")?;
+        let (metadata, encoded) = payload.split_once(',')?;
+        if metadata.contains(";base64") {
+            let decoded = base64::engine::general_purpose::STANDARD.decode(encoded).ok()?;
+            String::from_utf8(decoded).ok()
+        } else if encoded.contains('%') {
+            None
+        } else {
+            Some(encoded.to_string())
+        }
+    }
+
+    fn binding_declaration(&self, id: usize, clause: &ImportClause) -> Option<String> {
+        let exports = &self.exports_by_id[id];
+        let has_export = |name: &str| exports.iter().any(|exported| exported == name);
+        let mut declarations = Vec::new();
+
+        if let Some(local) = clause.default_local.as_ref() {
+            if !has_export("default") {
+                return None;
+            }
+            declarations.push(format!("const {local} = __plts_bundle_{id}.default;"));
+        }
+        if let Some(local) = clause.namespace_local.as_ref() {
+            declarations.push(format!("const {local} = __plts_bundle_{id};"));
+        }
+        if !clause.named.is_empty() {
+            if clause.named.iter().any(|(imported, _)| !has_export(imported)) {
+                return None;
+            }
+            let named =
+                clause
+                    .named
+                    .iter()
+                    .map(|(imported, local)| {
+                        if imported == local {
+                            local.clone()
+                        } else {
+                            format!("{imported}: {local}")
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+            declarations.push(format!("const {{ {named} }} = __plts_bundle_{id};"));
+        }
+
+        Some(declarations.join(" "))
+    }
+}
+
+/// Lists the specifiers of every top-level static `import` statement in `js`, in source order.
+pub(crate) fn static_import_specifiers(js: &str) -> Vec<String> {
+    let mut specifiers = Vec::new();
+    let mask = code_mask(js);
+    let mut cursor = 0;
+    for line in js.split_inclusive('\n') {
+        if !continues_literal(&mask, cursor) && is_static_import_start(line) {
+            if let Some(specifier) = import_statement_specifier(&js[cursor..]) {
+                specifiers.push(specifier.to_string());
+            }
+        }
+        cursor += line.len();
+    }
+    specifiers
+}
+
+fn import_statement_specifier(source: &str) -> Option<&str> {
+    let rest = source.strip_prefix("import")?;
+    let quote_offset = rest.find(['"', '\''])?;
+    let quote = rest[quote_offset..].chars().next()?;
+    let literal = &rest[quote_offset + 1..];
+    Some(&literal[..literal.find(quote)?])
+}
+
+fn is_inlinable_specifier(specifier: &str) -> bool {
+    specifier.starts_with("data:") || specifier.starts_with("plts+artifact:")
+}
+
+fn is_static_import_start(line: &str) -> bool {
+    let Some(rest) = line.strip_prefix("import") else {
+        return false;
+    };
+    let Some(first) = rest.chars().next() else {
+        return false;
+    };
+    if !(first.is_whitespace() || matches!(first, '{' | '*' | '"' | '\'')) {
+        return false;
+    }
+    !matches!(rest.trim_start().chars().next(), Some('(') | Some('.') | None)
+}
+
+fn parse_import_statement(source: &str, start: usize) -> Option<ImportStatement> {
+    let rest = source[start..].strip_prefix("import")?;
+    let body_start = start + "import".len();
+    let quote_offset = rest.find(['"', '\''])?;
+    let quote = rest[quote_offset..].chars().next()?;
+    let head = rest[..quote_offset].trim();
+
+    let clause = if head.is_empty() {
+        None
+    } else {
+        let clause = head.strip_suffix("from")?;
+        if !clause.ends_with(|ch: char| ch.is_whitespace() || ch == '}') {
+            return None;
+        }
+        Some(parse_import_clause(clause.trim())?)
+    };
+
+    let literal_start = body_start + quote_offset + 1;
+    let literal_len = source[literal_start..].find(quote)?;
+    let specifier = &source[literal_start..literal_start + literal_len];
+    if specifier.contains('\\') || specifier.contains('\n') {
+        return None;
+    }
+
+    let mut end = literal_start + literal_len + 1;
+    let trailing = &source[end..];
+    let spaces = trailing.len() - trailing.trim_start_matches([' ', '\t']).len();
+    end += spaces;
+    if source[end..].starts_with(';') {
+        end += 1;
+    }
+
+    Some(ImportStatement { end, clause, specifier: specifier.to_string() })
+}
+
+fn parse_import_clause(clause: &str) -> Option<ImportClause> {
+    let mut parsed = ImportClause::default();
+    let mut rest = clause;
+
+    if !rest.starts_with('{') && !rest.starts_with('*') {
+        let (default_local, tail) = match rest.split_once(',') {
+            Some((default_local, tail)) => (default_local.trim(), tail.trim()),
+            None => (rest.trim(), ""),
+        };
+        if !is_identifier(default_local) || default_local == "type" {
+            return None;
+        }
+        parsed.default_local = Some(default_local.to_string());
+        rest = tail;
+    }
+
+    if let Some(namespace) = rest.strip_prefix('*') {
+        let local = namespace.trim().strip_prefix("as")?.trim();
+        if !is_identifier(local) {
+            return None;
+        }
+        parsed.namespace_local = Some(local.to_string());
+    } else if let Some(named) = rest.strip_prefix('{') {
+        parsed.named = parse_specifier_list(named.strip_suffix('}')?)?;
+    } else if !rest.is_empty() {
+        return None;
+    }
+
+    Some(parsed)
+}
+
+fn parse_specifier_list(list: &str) -> Option<Vec<(String, String)>> {
+    let mut out = Vec::new();
+    for item in list.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let mut parts = item.split_whitespace();
+        let imported = parts.next()?;
+        let local = match (parts.next(), parts.next(), parts.next()) {
+            (None, None, None) => imported,
+            (Some("as"), Some(local), None) => local,
+            _ => return None,
+        };
+        if imported == "type" || !is_identifier(imported) || !is_identifier(local) {
+            return None;
+        }
+        out.push((imported.to_string(), local.to_string()));
+    }
+    Some(out)
+}
+
+fn rewrite_export_line(
+    line: &str,
+    source: &str,
+    mask: &[bool],
+    exports: &mut Vec<(String, String)>,
+) -> Option<String> {
+    let rest = line.strip_prefix("export")?;
+    if !rest.starts_with(|ch: char| ch.is_whitespace() || ch == '{') {
+        return Some(line.to_string());
+    }
+    let rest = rest.trim_start();
+
+    if let Some(default_rest) = rest.strip_prefix("default ") {
+        let declaration = default_rest.trim_start();
+        if let Some(name) = declared_function_or_class_name(declaration) {
+            exports.push(("default".to_string(), name));
+            return Some(declaration.to_string());
+        }
+        exports.push(("default".to_string(), DEFAULT_EXPORT_LOCAL.to_string()));
+        return Some(format!("const {DEFAULT_EXPORT_LOCAL} = {declaration}"));
+    }
+
+    if let Some(declaration) = rest.strip_prefix("const ") {
+        let name = leading_identifier(declaration.trim_start())?;
+        exports.push((name.to_string(), name.to_string()));
+        return Some(rest.to_string());
+    }
+
+    if let Some(name) = declared_function_or_class_name(rest) {
+        exports.push((name.clone(), name));
+        return Some(rest.to_string());
+    }
+
+    if let Some(list) = rest.strip_prefix('{') {
+        let (list, tail) = list.split_once('}')?;
+        let tail = tail.trim();
+        if !(tail.is_empty() || tail == ";") {
+            return None;
+        }
+        for (local, exported) in parse_specifier_list(list)? {
+            if !declares_immutable_binding(source, mask, local.as_str()) {
+                return None;
+            }
+            exports.push((exported, local));
+        }
+        return Some(if line.ends_with('\n') { "\n".to_string() } else { String::new() });
+    }
+
+    None
+}
+
+fn declared_function_or_class_name(declaration: &str) -> Option<String> {
+    let declaration = declaration.strip_prefix("async ").map_or(declaration, str::trim_start);
+    let rest = declaration
+        .strip_prefix("function")
+        .map(|rest| rest.trim_start().trim_start_matches('*'))
+        .or_else(|| declaration.strip_prefix("class "))?;
+    leading_identifier(rest.trim_start()).filter(|name| *name != "extends").map(str::to_string)
+}
+
+fn declares_immutable_binding(source: &str, mask: &[bool], name: &str) -> bool {
+    ["const ", "function ", "function* ", "class "].iter().any(|keyword| {
+        source.match_indices(keyword).any(|(index, _)| {
+            let at_token_start = source[..index]
+                .chars()
+                .next_back()
+                .is_none_or(|ch| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '$'));
+            let after = &source[index + keyword.len()..];
+            mask[index] && at_token_start && leading_identifier(after) == Some(name)
+        })
+    })
+}
+
+/// Marks which bytes of `source` are code rather than comment, string, or template literal text,
+/// so the line-based rewrite never takes quoted or commented-out text for a statement. Regex
+/// literals are not recognized.
+fn code_mask(source: &str) -> Vec<bool> {
+    let bytes = source.as_bytes();
+    let mut mask = vec![true; bytes.len()];
+    // Brace depth at which each open `${` substitution closes, innermost last.
+    let mut substitutions = Vec::new();
+    let mut depth = 0usize;
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let start = index;
+        match bytes[index] {
+            b'/' if bytes.get(index + 1) == Some(&b'/') => {
+                index = source[index..].find('\n').map_or(bytes.len(), |end| index + end);
+            }
+            b'/' if bytes.get(index + 1) == Some(&b'*') => {
+                index = source[index + 2..].find("*/").map_or(bytes.len(), |end| index + end + 4);
+            }
+            quote @ (b'"' | b'\'') => {
+                index += 1;
+                while index < bytes.len() && bytes[index] != quote && bytes[index] != b'\n' {
+                    index += if bytes[index] == b'\\' { 2 } else { 1 };
+                }
+                index = (index + 1).min(bytes.len());
+            }
+            b'`' => {
+                index = skip_template_text(bytes, index + 1, &mut depth, &mut substitutions);
+            }
+            b'}' if substitutions.last() == Some(&depth) => {
+                substitutions.pop();
+                depth -= 1;
+                index = skip_template_text(bytes, index + 1, &mut depth, &mut substitutions);
+            }
+            b'{' => {
+                depth += 1;
+                index += 1;
+                continue;
+            }
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                index += 1;
+                continue;
+            }
+            _ => {
+                index += 1;
+                continue;
+            }
+        }
+        index = index.min(bytes.len());
+        mask[start..index].fill(false);
+    }
+
+    mask
+}
+
+/// Whether the line starting at `line_start` continues a block comment, string, or template
+/// literal opened on an earlier line.
+fn continues_literal(mask: &[bool], line_start: usize) -> bool {
+    line_start > 0 && !mask[line_start - 1]
+}
+
+/// Skips template literal text from `index` up to and including the closing backtick or the
+/// `${` that opens a substitution, returning the index after it.
+fn skip_template_text(
+    bytes: &[u8],
+    mut index: usize,
+    depth: &mut usize,
+    substitutions: &mut Vec<usize>,
+) -> usize {
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            b'`' => return index + 1,
+            b'$' if bytes.get(index + 1) == Some(&b'{') => {
+                *depth += 1;
+                substitutions.push(*depth);
+                return index + 2;
+            }
+            _ => index += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn leading_identifier(text: &str) -> Option<&str> {
+    let end = text
+        .char_indices()
+        .find(|(_, ch)| !(ch.is_ascii_alphanumeric() || *ch == '_' || *ch == '$'))
+        .map_or(text.len(), |(index, _)| index);
+    let identifier = &text[..end];
+    is_identifier(identifier).then_some(identifier)
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(ch) if ch.is_ascii_alphabetic() || ch == '_' || ch == '$')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '$')
+}
+
+fn shift_inline_source_map(bundled_js: &str, prelude_lines: usize) -> String {
+    let Some(marker) = bundled_js.rfind(SOURCE_MAP_PREFIX) else {
+        return bundled_js.to_string();
+    };
+    let encoded_start = marker + SOURCE_MAP_PREFIX.len();
+    let encoded_end = bundled_js[encoded_start..]
+        .find(['\n', '\r'])
+        .map_or(bundled_js.len(), |offset| encoded_start + offset);
+
+    let shifted = base64::engine::general_purpose::STANDARD
+        .decode(bundled_js[encoded_start..encoded_end].trim())
+        .ok()
+        .and_then(|decoded| serde_json::from_slice::<Value>(&decoded).ok())
+        .and_then(|mut map| {
+            let mappings = map.get("mappings")?.as_str()?;
+            map["mappings"] = Value::String(format!("{}{mappings}", ";".repeat(prelude_lines)));
+            Some(base64::engine::general_purpose::STANDARD.encode(map.to_string()))
+        });
+
+    match shifted {
+        Some(encoded) => {
+            format!("{}{encoded}{}", &bundled_js[..encoded_start], &bundled_js[encoded_end..])
+        }
+        None => bundled_js.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bundle_static_imports, static_import_specifiers};
+    use base64::Engine;
+
+    fn data_url(source: &str) -> String {
+        format!(
+            "data:text/javascript;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(source)
+        )
+    }
+
+    fn has_static_import(js: &str) -> bool {
+        js.lines().any(super::is_static_import_start)
+    }
+
+    #[test]
+    fn bundled_artifact_has_no_remaining_import_statements() {
+        let helper =
+            data_url("export const factor = 4;\nexport function scale(n) { return n * factor; }\n");
+        let entry = format!(
+            "import {{ scale, factor as f }} from \"{helper}\";\nimport * as math from \"plts+artifact:sha256:abc\";\nexport default (ctx) => scale(ctx.args.n) + f + math.default;\n"
+        );
+        let mut resolve = |hash: &str| {
+            assert_eq!(hash, "sha256:abc");
+            Some("const base = 40;\nexport default base;\n".to_string())
+        };
+
+        let bundled = bundle_static_imports(entry.as_str(), &mut resolve);
+
+        assert!(!has_static_import(bundled.as_str()), "bundled output kept imports: {bundled}");
+        assert!(bundled.contains("const { scale, factor: f } = __plts_bundle_0;"));
+        assert!(bundled.contains("const math = __plts_bundle_1;"));
+        assert!(bundled.contains("export default (ctx) => scale(ctx.args.n) + f + math.default;"));
+    }
+
+    #[test]
+    fn bundling_keeps_dynamic_imports_and_unresolvable_modules() {
+        let entry = "const lazy = () => import(\"data:text/javascript,export const x = 1;\");\nexport default lazy;\n";
+        assert_eq!(bundle_static_imports(entry, &mut |_| None), entry);
+
+        let missing = "import { x } from \"plts+artifact:sha256:missing\";\nexport default x;\n";
+        assert_eq!(bundle_static_imports(missing, &mut |_| None), missing);
+
+        let runtime =
+            "import { query } from \"@stopgap/runtime\";\nexport default query(() => 1);\n";
+        assert_eq!(bundle_static_imports(runtime, &mut |_| None), runtime);
+    }
+
+    #[test]
+    fn bundling_declines_modules_with_mutable_exports() {
+        let helper = data_url("export let counter = 0;\n");
+        let entry =
+            format!("import {{ counter }} from \"{helper}\";\nexport default () => counter;\n");
+        assert_eq!(bundle_static_imports(entry.as_str(), &mut |_| None), entry);
+    }
+
+    #[test]
+    fn bundling_ignores_export_lines_inside_template_literals_and_block_comments() {
+        let helper = data_url(
+            "export const banner = `\nexport let hidden = 1;\n${\"x\"}\nexport { nope };\n`;\n/*\nexport let commented = 2;\n*/\nexport const one = 1;\n",
+        );
+        let entry = format!(
+            "import {{ banner, one }} from \"{helper}\";\nexport default () => banner + one;\n"
+        );
+
+        let bundled = bundle_static_imports(entry.as_str(), &mut |_| None);
+
+        assert!(!has_static_import(bundled.as_str()), "bundled output kept imports: {bundled}");
+        assert!(bundled.contains("\nexport let hidden = 1;\n"), "template text changed: {bundled}");
+        assert!(bundled.contains("\nexport let commented = 2;\n"), "comment changed: {bundled}");
+        assert!(
+            bundled
+                .contains("return Object.freeze({ __proto__: null, banner: banner, one: one });")
+        );
+    }
+
+    #[test]
+    fn export_lists_need_a_real_immutable_declaration() {
+        let in_string =
+            data_url("let value = 1;\nconst note = \"const value = 2\";\nexport { value };\n");
+        let entry =
+            format!("import {{ value }} from \"{in_string}\";\nexport default () => value;\n");
+        assert_eq!(bundle_static_imports(entry.as_str(), &mut |_| None), entry);
+
+        let in_comment = data_url("let value = 1;\n// const value = 2;\nexport { value };\n");
+        let entry =
+            format!("import {{ value }} from \"{in_comment}\";\nexport default () => value;\n");
+        assert_eq!(bundle_static_imports(entry.as_str(), &mut |_| None), entry);
+
+        let declared = data_url("const value = 1;\nexport { value };\n");
+        let entry =
+            format!("import {{ value }} from \"{declared}\";\nexport default () => value;\n");
+        assert_ne!(bundle_static_imports(entry.as_str(), &mut |_| None), entry);
+    }
+
+    #[test]
+    fn bundling_shifts_inline_source_map_by_prelude_lines() {
+        let helper = data_url("export const one = 1;\n");
+        let map = base64::engine::general_purpose::STANDARD
+            .encode(r#"{"version":3,"sources":["main.ts"],"mappings":"AAAA"}"#);
+        let entry = format!(
+            "import {{ one }} from \"{helper}\";\nexport default () => one;\n//# sourceMappingURL=data:application/json;base64,{map}\n"
+        );
+
+        let bundled = bundle_static_imports(entry.as_str(), &mut |_| None);
+        let encoded = bundled
+            .rsplit_once("base64,")
+            .map(|(_, encoded)| encoded.trim())
+            .expect("bundled output should keep inline source map");
+        let shifted = String::from_utf8(
+            base64::engine::general_purpose::STANDARD.decode(encoded).expect("valid base64"),
+        )
+        .expect("valid utf-8");
+        let prelude_lines = bundled.lines().take_while(|line| !line.starts_with("export")).count()
+            - entry.lines().take_while(|line| !line.starts_with("export")).count();
+        assert!(shifted.contains(&format!("\"mappings\":\"{}AAAA\"", ";".repeat(prelude_lines))));
+    }
+
+    #[test]
+    fn static_import_specifiers_cover_every_import_form() {
+        let js = "import \"data:text/javascript,\";\n\
+                  import def, { a as b } from 'https://example.com/mod.js';\n\
+                  import * as ns from\n  \"plts+artifact:sha256:abc\";\n\
+                  const lazy = import(\"./lazy.js\");\n\
+                  const doc = `\nimport \"data:text/javascript,\";\n`;\n\
+                  /*\nimport * as hidden from \"plts+artifact:sha256:def\";\n*/\n";
+        assert_eq!(
+            static_import_specifiers(js),
+            vec![
+                "data:text/javascript,".to_string(),
+                "https://example.com/mod.js".to_string(),
+                "plts+artifact:sha256:abc".to_string(),
+            ]
+        );
+    }
+}
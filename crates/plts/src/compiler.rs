@@ -1,8 +1,14 @@
+use crate::bundler::{bundle_enabled, bundle_static_imports, static_import_specifiers};
 use crate::compiler_service::{self, CompilerRequestKind};
+use crate::function_program::load_compiled_artifact_source;
 use crate::observability::{
     log_info, log_warn, record_tsgo_wasm_cache_event, record_tsgo_wasm_init_start,
     record_tsgo_wasm_init_success, should_log_info, should_log_warn,
 };
+use crate::preamble::{
+    prepend_compile_preamble, remap_preamble_diagnostics, validate_compile_preamble,
+};
+use crate::runtime::{allowed_imports_hint, is_loadable_import_specifier};
 use base64::Engine as Base64Engine;
 use directories_next::ProjectDirs;
 #[cfg(not(test))]
@@ -30,6 +36,7 @@ const CARGO_LOCK_CONTENT: &str = include_str!("../../../Cargo.lock");
 const STOPGAP_TSGO_API_WASM: &[u8] =
     include_bytes!("../../../third_party/stopgap-tsgo-api/dist/stopgap-tsgo-api.wasm");
 const STOPGAP_TSGO_RUNTIME_DECLARATIONS: &str = include_str!("tsgo_runtime.d.ts");
+const INLINE_SOURCE_MAP_PREFIX: &str = "//# sourceMappingURL=data:application/json;base64,";
 static TS_COMPILER_FINGERPRINT: OnceLock<String> = OnceLock::new();
 static TSGO_WASM_RUNTIME: OnceLock<Result<TsgoWasmRuntime, String>> = OnceLock::new();
 static TSGO_WASM_TEMPFILE_COUNTER: AtomicU64 = AtomicU64::new(0);
@@ -58,6 +65,8 @@ pub(crate) struct TsgoServiceRequest<'a> {
     source_ts: &'a str,
     #[serde(default, skip_serializing_if = "is_false")]
     source_map: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<&'a str>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     declarations: Vec<TsgoVirtualDeclaration>,
 }
@@ -122,13 +131,17 @@ fn is_false(value: &bool) -> bool {
 pub(crate) fn encode_tsgo_service_request<'a>(
     request_kind: CompilerRequestKind,
     source_ts: &'a str,
-    compiler_opts: &Value,
+    compiler_opts: &'a Value,
 ) -> Result<Vec<u8>, String> {
     let request = TsgoServiceRequest {
         operation: request_kind.operation_name(),
         source_ts,
-        source_map: request_kind.includes_source_map()
-            && compiler_opts.get("source_map").and_then(Value::as_bool).unwrap_or(false),
+        source_map: request_kind.includes_source_map() && source_map_requested(compiler_opts),
+        target: if request_kind.includes_target() {
+            compiler_opts.get("target").and_then(Value::as_str)
+        } else {
+            None
+        },
         declarations: tsgo_virtual_declarations(compiler_opts),
     };
     serde_json::to_vec(&request)
@@ -234,6 +247,18 @@ pub(crate) fn compiler_fingerprint() -> &'static str {
         .as_str()
 }
 
+pub(crate) fn compiler_fingerprint_for_opts(compiler_opts: &Value) -> String {
+    let mut fingerprint = compiler_fingerprint().to_string();
+    if bundle_enabled(compiler_opts) {
+        fingerprint.push_str(";bundle@1");
+    }
+    if let Some(preamble) = crate::compile_preamble() {
+        let preamble_hash = hex::encode(Sha256::digest(preamble.as_bytes()));
+        fingerprint.push_str(&format!(";preamble_sha256@{preamble_hash}"));
+    }
+    fingerprint
+}
+
 pub(crate) fn tsgo_api_wasm_bytes() -> &'static [u8] {
     STOPGAP_TSGO_API_WASM
 }
@@ -685,7 +710,7 @@ fn set_owner_only_file_permissions(_path: &Path) {}
 
 pub(crate) fn compile_source_ts(source_ts: &str, compiler_opts: &Value) -> CompileOutput {
     let (compiled_js, diagnostics) = transpile_typescript(source_ts, compiler_opts);
-    CompileOutput { compiled_js, diagnostics }
+    maybe_bundle_output(CompileOutput { compiled_js, diagnostics }, compiler_opts)
 }
 
 pub(crate) fn compile_source_ts_checked(source_ts: &str, compiler_opts: &Value) -> CompileOutput {
@@ -693,10 +718,13 @@ pub(crate) fn compile_source_ts_checked(source_ts: &str, compiler_opts: &Value)
         Ok(response) => {
             let diagnostics =
                 response.diagnostics.iter().cloned().map(tsgo_diagnostic_to_json).collect();
-            CompileOutput {
-                compiled_js: response.compiled_js,
-                diagnostics: Value::Array(diagnostics),
-            }
+            maybe_bundle_output(
+                CompileOutput {
+                    compiled_js: response.compiled_js,
+                    diagnostics: Value::Array(diagnostics),
+                },
+                compiler_opts,
+            )
         }
         Err(err) => CompileOutput {
             compiled_js: String::new(),
@@ -708,6 +736,34 @@ pub(crate) fn compile_source_ts_checked(source_ts: &str, compiler_opts: &Value)
     }
 }
 
+/// Adds an error diagnostic for each static import the runtime module loader would reject.
+pub(crate) fn push_import_scheme_diagnostics(output: &mut CompileOutput) {
+    let rejected = static_import_specifiers(output.compiled_js.as_str())
+        .into_iter()
+        .filter(|specifier| !is_loadable_import_specifier(specifier))
+        .map(|specifier| {
+            diagnostic_from_message(
+                "error",
+                &format!("unsupported module import `{specifier}`; {}", allowed_imports_hint()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(entries) = output.diagnostics.as_array_mut() {
+        entries.extend(rejected);
+    }
+}
+
+fn maybe_bundle_output(output: CompileOutput, compiler_opts: &Value) -> CompileOutput {
+    if !bundle_enabled(compiler_opts) || contains_error_diagnostics(&output.diagnostics) {
+        return output;
+    }
+
+    let compiled_js =
+        bundle_static_imports(output.compiled_js.as_str(), &mut load_compiled_artifact_source);
+    CompileOutput { compiled_js, diagnostics: output.diagnostics }
+}
+
 pub(crate) fn transpile_typescript(source_ts: &str, compiler_opts: &Value) -> (String, Value) {
     match service_typescript(source_ts, compiler_opts, CompilerRequestKind::Transpile) {
         Ok(response) => {
@@ -747,9 +803,18 @@ fn service_typescript(
     compiler_opts: &Value,
     request_kind: CompilerRequestKind,
 ) -> Result<TsgoServiceResponse, String> {
-    let request_json = encode_tsgo_service_request(request_kind, source_ts, compiler_opts)?;
+    let (source_ts, line_offset) = match crate::compile_preamble() {
+        Some(preamble) => {
+            validate_compile_preamble(&preamble)?;
+            prepend_compile_preamble(&preamble, source_ts)
+        }
+        None => (source_ts.to_string(), 0),
+    };
+    let request_json = encode_tsgo_service_request(request_kind, &source_ts, compiler_opts)?;
     let response_json = compiler_service::compiler_service_request(request_kind, request_json)?;
-    decode_tsgo_service_response(request_kind, &response_json)
+    let mut response = decode_tsgo_service_response(request_kind, &response_json)?;
+    remap_preamble_diagnostics(&mut response.diagnostics, line_offset);
+    Ok(response)
 }
 
 fn tsgo_diagnostic_to_json(diag: TsgoDiagnostic) -> Value {
@@ -974,6 +1039,29 @@ pub(crate) fn contains_error_diagnostics(diagnostics: &Value) -> bool {
         .unwrap_or(false)
 }
 
+/// Groups diagnostics by severity for editor integrations. Anything that is not a `warning` or
+/// `info` counts as an error, matching how the compiler service classifies unknown categories.
+pub(crate) fn diagnostics_report(diagnostics: &Value) -> Value {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut infos = Vec::new();
+    for entry in diagnostics.as_array().into_iter().flatten() {
+        match entry.get("severity").and_then(Value::as_str) {
+            Some("warning") => warnings.push(entry.clone()),
+            Some("info") => infos.push(entry.clone()),
+            _ => errors.push(entry.clone()),
+        }
+    }
+
+    json!({
+        "ok": errors.is_empty(),
+        "counts": { "errors": errors.len(), "warnings": warnings.len(), "infos": infos.len() },
+        "errors": errors,
+        "warnings": warnings,
+        "infos": infos,
+    })
+}
+
 fn diagnostic_from_message(severity: &str, message: &str) -> Value {
     let mut line = Value::Null;
     let mut column = Value::Null;
@@ -1001,21 +1089,39 @@ fn extract_line_column(message: &str) -> Option<(u32, u32)> {
     Some((line, col))
 }
 
+pub(crate) fn source_map_requested(compiler_opts: &Value) -> bool {
+    match compiler_opts.get("source_map") {
+        Some(Value::Bool(enabled)) => *enabled,
+        Some(Value::String(mode)) => matches!(mode.as_str(), "inline" | "external"),
+        _ => false,
+    }
+}
+
+pub(crate) fn external_source_map_requested(compiler_opts: &Value) -> bool {
+    compiler_opts.get("source_map").and_then(Value::as_str) == Some("external")
+}
+
 pub(crate) fn maybe_extract_source_map(compiled_js: &str, compiler_opts: &Value) -> Option<String> {
-    let source_map_enabled =
-        compiler_opts.get("source_map").and_then(Value::as_bool).unwrap_or(false);
-    if !source_map_enabled {
+    if !source_map_requested(compiler_opts) {
         return None;
     }
 
     extract_inline_source_map(compiled_js)
 }
 
-pub(crate) fn extract_inline_source_map(compiled_js: &str) -> Option<String> {
-    const SOURCE_MAP_PREFIX: &str = "//# sourceMappingURL=data:application/json;base64,";
+pub(crate) fn strip_inline_source_map(compiled_js: &str) -> String {
+    let Some(marker) = compiled_js.rfind(INLINE_SOURCE_MAP_PREFIX) else {
+        return compiled_js.to_string();
+    };
+
+    let line_end =
+        compiled_js[marker..].find('\n').map_or(compiled_js.len(), |idx| marker + idx + 1);
+    format!("{}{}", &compiled_js[..marker], &compiled_js[line_end..])
+}
 
-    let marker = compiled_js.rfind(SOURCE_MAP_PREFIX)?;
-    let encoded = compiled_js[(marker + SOURCE_MAP_PREFIX.len())..].lines().next()?.trim();
+pub(crate) fn extract_inline_source_map(compiled_js: &str) -> Option<String> {
+    let marker = compiled_js.rfind(INLINE_SOURCE_MAP_PREFIX)?;
+    let encoded = compiled_js[(marker + INLINE_SOURCE_MAP_PREFIX.len())..].lines().next()?.trim();
     if encoded.is_empty() {
         return None;
     }
@@ -1023,3 +1129,26 @@ pub(crate) fn extract_inline_source_map(compiled_js: &str) -> Option<String> {
     let decoded = base64::engine::general_purpose::STANDARD.decode(encoded).ok()?;
     String::from_utf8(decoded).ok()
 }
+
+/// Returns the text of a leading `/** ... */` JSDoc block in `source_ts`, with the comment
+/// delimiters and per-line `*` gutters removed. Only a block at the very top of the module counts.
+pub(crate) fn extract_doc_comment(source_ts: &str) -> Option<String> {
+    let body = source_ts.trim_start().strip_prefix("/**")?;
+    if body.starts_with('/') {
+        return None;
+    }
+
+    let end = body.find("*/")?;
+    let doc = body[..end]
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line).trim_end()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let doc = doc.trim();
+
+    (!doc.is_empty()).then(|| doc.to_string())
+}
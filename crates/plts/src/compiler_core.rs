@@ -1,5 +1,6 @@
 use base64::Engine as Base64Engine;
 use serde_json::Value;
+use serde_json::json;
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::io::ErrorKind;
@@ -16,6 +17,8 @@ const STOPGAP_TSGO_API_WASM: &[u8] =
     include_bytes!("../../../third_party/stopgap-tsgo-api/dist/stopgap-tsgo-api.wasm");
 const STOPGAP_TSGO_RUNTIME_DECLARATIONS: &str = include_str!("tsgo_runtime.d.ts");
 
+const INLINE_SOURCE_MAP_PREFIX: &str = "//# sourceMappingURL=data:application/json;base64,";
+
 static TS_COMPILER_FINGERPRINT: OnceLock<String> = OnceLock::new();
 static TSGO_WASM_TEMPFILE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
@@ -283,21 +286,62 @@ pub(crate) fn contains_error_diagnostics(diagnostics: &Value) -> bool {
         .unwrap_or(false)
 }
 
+/// Groups diagnostics by severity for editor integrations. Anything that is not a `warning` or
+/// `info` counts as an error, matching how the compiler service classifies unknown categories.
+pub(crate) fn diagnostics_report(diagnostics: &Value) -> Value {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut infos = Vec::new();
+    for entry in diagnostics.as_array().into_iter().flatten() {
+        match entry.get("severity").and_then(Value::as_str) {
+            Some("warning") => warnings.push(entry.clone()),
+            Some("info") => infos.push(entry.clone()),
+            _ => errors.push(entry.clone()),
+        }
+    }
+
+    json!({
+        "ok": errors.is_empty(),
+        "counts": { "errors": errors.len(), "warnings": warnings.len(), "infos": infos.len() },
+        "errors": errors,
+        "warnings": warnings,
+        "infos": infos,
+    })
+}
+
+pub(crate) fn source_map_requested(compiler_opts: &Value) -> bool {
+    match compiler_opts.get("source_map") {
+        Some(Value::Bool(enabled)) => *enabled,
+        Some(Value::String(mode)) => matches!(mode.as_str(), "inline" | "external"),
+        _ => false,
+    }
+}
+
+pub(crate) fn external_source_map_requested(compiler_opts: &Value) -> bool {
+    compiler_opts.get("source_map").and_then(Value::as_str) == Some("external")
+}
+
 pub(crate) fn maybe_extract_source_map(compiled_js: &str, compiler_opts: &Value) -> Option<String> {
-    let source_map_enabled =
-        compiler_opts.get("source_map").and_then(Value::as_bool).unwrap_or(false);
-    if !source_map_enabled {
+    if !source_map_requested(compiler_opts) {
         return None;
     }
 
     extract_inline_source_map(compiled_js)
 }
 
-pub(crate) fn extract_inline_source_map(compiled_js: &str) -> Option<String> {
-    const SOURCE_MAP_PREFIX: &str = "//# sourceMappingURL=data:application/json;base64,";
+pub(crate) fn strip_inline_source_map(compiled_js: &str) -> String {
+    let Some(marker) = compiled_js.rfind(INLINE_SOURCE_MAP_PREFIX) else {
+        return compiled_js.to_string();
+    };
 
-    let marker = compiled_js.rfind(SOURCE_MAP_PREFIX)?;
-    let encoded = compiled_js[(marker + SOURCE_MAP_PREFIX.len())..].lines().next()?.trim();
+    let line_end =
+        compiled_js[marker..].find('\n').map_or(compiled_js.len(), |idx| marker + idx + 1);
+    format!("{}{}", &compiled_js[..marker], &compiled_js[line_end..])
+}
+
+pub(crate) fn extract_inline_source_map(compiled_js: &str) -> Option<String> {
+    let marker = compiled_js.rfind(INLINE_SOURCE_MAP_PREFIX)?;
+    let encoded = compiled_js[(marker + INLINE_SOURCE_MAP_PREFIX.len())..].lines().next()?.trim();
     if encoded.is_empty() {
         return None;
     }
@@ -306,6 +350,29 @@ pub(crate) fn extract_inline_source_map(compiled_js: &str) -> Option<String> {
     String::from_utf8(decoded).ok()
 }
 
+/// Returns the text of a leading `/** ... */` JSDoc block in `source_ts`, with the comment
+/// delimiters and per-line `*` gutters removed. Only a block at the very top of the module counts.
+pub(crate) fn extract_doc_comment(source_ts: &str) -> Option<String> {
+    let body = source_ts.trim_start().strip_prefix("/**")?;
+    if body.starts_with('/') {
+        return None;
+    }
+
+    let end = body.find("*/")?;
+    let doc = body[..end]
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line).trim_end()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let doc = doc.trim();
+
+    (!doc.is_empty()).then(|| doc.to_string())
+}
+
 pub(crate) fn tsgo_virtual_declarations(compiler_opts: &Value) -> Vec<TsgoVirtualDeclaration> {
     let mut declarations = vec![TsgoVirtualDeclaration {
         file_name: "/stopgap/runtime/index.d.ts".to_string(),
@@ -534,13 +601,15 @@ fn set_owner_only_file_permissions(_path: &Path) {}
 #[cfg(test)]
 mod tests {
     use super::{
-        TsgoWasmCacheMode, TsgoWasmEngineProfile, TsgoWasmInitOutcome,
-        bootstrap_tsgo_wasm_cache_paths, build_tsgo_wasm_engine, compiler_fingerprint,
-        compute_artifact_hash, contains_error_diagnostics, dependency_version_from_lock,
-        ensure_wasmtime_cache_config, extract_inline_source_map, load_tsgo_wasm_module_from_bytes,
-        parse_tsgo_wasm_cache_mode, resolve_tsgo_wasm_cache_root, toml_string, tsgo_api_wasm_bytes,
-        tsgo_virtual_declarations, tsgo_wasm_engine_profile, tsgo_wasm_manual_artifact_path,
-        tsgo_wasm_manual_fingerprint,
+        STOPGAP_TSGO_RUNTIME_DECLARATIONS, TsgoWasmCacheMode, TsgoWasmEngineProfile,
+        TsgoWasmInitOutcome, bootstrap_tsgo_wasm_cache_paths, build_tsgo_wasm_engine,
+        compiler_fingerprint, compute_artifact_hash, contains_error_diagnostics,
+        dependency_version_from_lock, diagnostics_report, ensure_wasmtime_cache_config,
+        external_source_map_requested, extract_doc_comment, extract_inline_source_map,
+        load_tsgo_wasm_module_from_bytes, maybe_extract_source_map, parse_tsgo_wasm_cache_mode,
+        resolve_tsgo_wasm_cache_root, source_map_requested, strip_inline_source_map, toml_string,
+        tsgo_api_wasm_bytes, tsgo_virtual_declarations, tsgo_wasm_engine_profile,
+        tsgo_wasm_manual_artifact_path, tsgo_wasm_manual_fingerprint,
     };
     use serde_json::json;
     use std::fs;
@@ -762,6 +831,40 @@ mod tests {
         assert!(source_map.contains("\"version\":3"));
     }
 
+    #[test]
+    fn extract_doc_comment_reads_leading_jsdoc_block() {
+        let source = "\n/**\n * Greets the caller.\n *\n * Returns `{ hello }`.\n */\nexport default (ctx: any) => ({ hello: ctx.args.name });\n";
+        assert_eq!(
+            extract_doc_comment(source).as_deref(),
+            Some("Greets the caller.\n\nReturns `{ hello }`.")
+        );
+        assert_eq!(
+            extract_doc_comment("/** One-liner. */ export const x = 1;").as_deref(),
+            Some("One-liner.")
+        );
+        assert_eq!(extract_doc_comment("/* plain */ export const x = 1;"), None);
+        assert_eq!(extract_doc_comment("export const x = 1;\n/** late */"), None);
+        assert_eq!(extract_doc_comment("/**/ export const x = 1;"), None);
+        assert_eq!(extract_doc_comment("/** \n * \n */"), None);
+    }
+
+    #[test]
+    fn external_source_map_moves_payload_out_of_compiled_js() {
+        let opts = json!({ "source_map": "external" });
+        let compiled = "export const x = 1;\n//# sourceMappingURL=data:application/json;base64,eyJ2ZXJzaW9uIjozfQ==\n";
+        assert!(source_map_requested(&opts));
+        assert!(external_source_map_requested(&opts));
+        assert!(!external_source_map_requested(&json!({ "source_map": true })));
+
+        let source_map = maybe_extract_source_map(compiled, &opts)
+            .expect("external source map should be extracted from the emitted payload");
+        assert!(source_map.contains("\"version\":3"));
+
+        let stripped = strip_inline_source_map(compiled);
+        assert_eq!(stripped, "export const x = 1;\n");
+        assert!(!stripped.contains("sourceMappingURL"));
+    }
+
     #[test]
     fn compiler_fingerprint_includes_dependency_versions() {
         let fingerprint = compiler_fingerprint();
@@ -811,9 +914,37 @@ mod tests {
         assert!(declarations[0].content.contains("declare module \"@stopgap/runtime\""));
     }
 
+    #[test]
+    fn tsgo_runtime_declarations_describe_the_runtime_context_surface() {
+        for member in ["queryRow:", "queryColumnar:", "batch:", "memo:", "describe:"] {
+            assert!(
+                STOPGAP_TSGO_RUNTIME_DECLARATIONS.contains(member),
+                "runtime declarations should declare `{member}`"
+            );
+        }
+        assert!(STOPGAP_TSGO_RUNTIME_DECLARATIONS.contains("type PltsContext<"));
+    }
+
     #[test]
     fn contains_error_diagnostics_detects_error_entries() {
         assert!(contains_error_diagnostics(&json!([{ "severity": "error" }])));
         assert!(!contains_error_diagnostics(&json!([{ "severity": "warning" }])));
     }
+
+    #[test]
+    fn diagnostics_report_groups_entries_by_severity() {
+        let report = diagnostics_report(&json!([
+            { "severity": "warning", "message": "w" },
+            { "severity": "error", "message": "e" },
+            { "severity": "info", "message": "i" },
+            { "severity": "warning", "message": "w2" },
+        ]));
+
+        assert_eq!(report["ok"], json!(false));
+        assert_eq!(report["counts"], json!({ "errors": 1, "warnings": 2, "infos": 1 }));
+        assert_eq!(report["errors"], json!([{ "severity": "error", "message": "e" }]));
+        assert_eq!(report["infos"], json!([{ "severity": "info", "message": "i" }]));
+        assert_eq!(report["warnings"][1]["message"], json!("w2"));
+        assert_eq!(diagnostics_report(&json!([]))["ok"], json!(true));
+    }
 }
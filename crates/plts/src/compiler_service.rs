@@ -71,6 +71,10 @@ impl CompilerRequestKind {
         matches!(self, Self::Transpile | Self::CompileChecked)
     }
 
+    pub(crate) fn includes_target(self) -> bool {
+        matches!(self, Self::Transpile | Self::CompileChecked)
+    }
+
     fn code(self) -> u8 {
         match self {
             Self::Typecheck => 1,
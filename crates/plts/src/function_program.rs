@@ -1,8 +1,11 @@
 use crate::compiler::{contains_error_diagnostics, transpile_typescript};
 use common::sql::quote_literal;
+use pgrx::JsonB;
 use pgrx::prelude::*;
 use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
@@ -21,14 +24,18 @@ pub(crate) struct FunctionProgram {
     pub(crate) source: String,
     pub(crate) entrypoint_export: String,
     pub(crate) bare_specifier_map: HashMap<String, String>,
+    pub(crate) artifact_hash: Option<String>,
+    pub(crate) limits: ProgramLimits,
+    pub(crate) args: Value,
 }
 
 pub(crate) fn load_function_program(fn_oid: pg_sys::Oid) -> Option<FunctionProgram> {
     let program_cache_mutex =
         FUNCTION_PROGRAM_CACHE.get_or_init(|| Mutex::new(FunctionProgramCache::default()));
+    let preamble_hash = compile_preamble_hash();
 
     if let Ok(mut cache) = program_cache_mutex.lock() {
-        if let Some(cached) = cache.get(fn_oid) {
+        if let Some(cached) = cache.get(fn_oid, preamble_hash) {
             return Some(cached);
         }
     }
@@ -37,7 +44,20 @@ pub(crate) fn load_function_program(fn_oid: pg_sys::Oid) -> Option<FunctionProgr
         "
         SELECT n.nspname::text AS fn_schema,
                p.proname::text AS fn_name,
-               p.prosrc::text AS prosrc
+               p.prosrc::text AS prosrc,
+               COALESCE((
+                   SELECT jsonb_agg(
+                              jsonb_build_object(
+                                  'name', NULLIF(p.proargnames[arg.ord], ''),
+                                  'type_oid', arg.type_oid::bigint,
+                                  'type_name', arg.type_oid::regtype::text
+                              )
+                              ORDER BY arg.ord
+                          )
+                   FROM unnest(COALESCE(p.proallargtypes, p.proargtypes::oid[]))
+                        WITH ORDINALITY AS arg(type_oid, ord)
+                   WHERE p.proargmodes IS NULL OR p.proargmodes[arg.ord] IN ('i', 'b', 'v')
+               ), '[]'::jsonb) AS fn_args
         FROM pg_proc p
         JOIN pg_namespace n ON n.oid = p.pronamespace
         WHERE p.oid = {}
@@ -51,16 +71,26 @@ pub(crate) fn load_function_program(fn_oid: pg_sys::Oid) -> Option<FunctionProgr
             let schema = row.get_by_name::<String, _>("fn_schema")?.unwrap_or_default();
             let name = row.get_by_name::<String, _>("fn_name")?.unwrap_or_default();
             let prosrc = row.get_by_name::<String, _>("prosrc")?.unwrap_or_default();
-            Ok::<Option<(String, String, String)>, pgrx::spi::Error>(Some((schema, name, prosrc)))
+            let args = row
+                .get_by_name::<JsonB, _>("fn_args")?
+                .map(|args| args.0)
+                .unwrap_or_else(|| Value::Array(Vec::new()));
+            Ok::<Option<(String, String, String, Value)>, pgrx::spi::Error>(Some((
+                schema, name, prosrc, args,
+            )))
         } else {
-            Ok::<Option<(String, String, String)>, pgrx::spi::Error>(None)
+            Ok::<Option<(String, String, String, Value)>, pgrx::spi::Error>(None)
         }
     })
     .ok()
     .flatten()?;
 
-    let (source, entrypoint_export, bare_specifier_map, cacheable) =
-        resolve_program_source(&row.2)?;
+    let (source, ptr) = resolve_program_source(&row.2)?;
+    let cacheable = ptr.is_none();
+    let (entrypoint_export, bare_specifier_map, artifact_hash, limits) = match ptr {
+        Some(ptr) => (ptr.export_name, ptr.import_map, Some(ptr.artifact_hash), ptr.limits),
+        None => ("default".to_string(), HashMap::new(), None, ProgramLimits::default()),
+    };
     let program = FunctionProgram {
         oid: fn_oid,
         schema: row.0,
@@ -68,21 +98,70 @@ pub(crate) fn load_function_program(fn_oid: pg_sys::Oid) -> Option<FunctionProgr
         source,
         entrypoint_export,
         bare_specifier_map,
+        artifact_hash,
+        limits,
+        args: row.3,
     };
 
     if cacheable {
         if let Ok(mut cache) = program_cache_mutex.lock() {
-            cache.insert(program.clone());
+            cache.insert(program.clone(), preamble_hash);
         }
     }
 
     Some(program)
 }
 
-fn resolve_program_source(prosrc: &str) -> Option<(String, String, HashMap<String, String>, bool)> {
+/// Hash of the `plts.compile_preamble` inline bodies are transpiled with; the setting is per
+/// session, so a cached program only serves calls made under the same preamble.
+fn compile_preamble_hash() -> u64 {
+    let mut hasher = DefaultHasher::new();
+    crate::compile_preamble().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Resolves `schema.name` to the oid of a `LANGUAGE plts` function the current role may execute.
+#[cfg_attr(not(feature = "v8_runtime"), allow(dead_code))]
+pub(crate) fn resolve_plts_function_oid(schema: &str, name: &str) -> Result<pg_sys::Oid, String> {
+    let oids = Spi::connect(|client| {
+        let rows = client.select(
+            "
+            SELECT p.oid
+            FROM pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE n.nspname = $1
+              AND p.proname = $2
+              AND l.lanname = 'plts'
+              AND has_function_privilege(p.oid, 'EXECUTE')
+            ORDER BY p.oid
+            ",
+            None,
+            &[schema.into(), name.into()],
+        )?;
+
+        let mut oids = Vec::new();
+        for row in rows {
+            if let Some(oid) = row.get_by_name::<pg_sys::Oid, _>("oid")? {
+                oids.push(oid);
+            }
+        }
+
+        Ok::<Vec<pg_sys::Oid>, pgrx::spi::Error>(oids)
+    })
+    .map_err(|err| format!("failed to look up plts function {schema}.{name}: {err}"))?;
+
+    match oids.as_slice() {
+        [oid] => Ok(*oid),
+        [] => Err(format!("plts function {schema}.{name} does not exist")),
+        _ => Err(format!("plts function {schema}.{name} is overloaded")),
+    }
+}
+
+fn resolve_program_source(prosrc: &str) -> Option<(String, Option<ArtifactPtr>)> {
     if let Some(ptr) = parse_artifact_ptr(prosrc) {
         return load_compiled_artifact_from_cache_or_db(&ptr.artifact_hash)
-            .map(|source| (source, ptr.export_name, ptr.import_map, false));
+            .map(|source| (source, Some(ptr)));
     }
 
     let (compiled_js, diagnostics) = transpile_typescript(prosrc, &serde_json::json!({}));
@@ -90,7 +169,7 @@ fn resolve_program_source(prosrc: &str) -> Option<(String, String, HashMap<Strin
         return None;
     }
 
-    Some((compiled_js, "default".to_string(), HashMap::new(), true))
+    Some((compiled_js, None))
 }
 
 fn load_compiled_artifact_from_cache_or_db(artifact_hash: &str) -> Option<String> {
@@ -116,7 +195,6 @@ fn load_compiled_artifact_from_cache_or_db(artifact_hash: &str) -> Option<String
     Some(source)
 }
 
-#[cfg(feature = "v8_runtime")]
 pub(crate) fn load_compiled_artifact_source(artifact_hash: &str) -> Option<String> {
     if artifact_hash.is_empty() {
         return None;
@@ -130,6 +208,16 @@ pub(crate) struct ArtifactPtr {
     pub(crate) artifact_hash: String,
     pub(crate) export_name: String,
     pub(crate) import_map: HashMap<String, String>,
+    pub(crate) limits: ProgramLimits,
+}
+
+/// Per-function runtime caps carried by a deployed artifact pointer; unset fields fall back
+/// to the session GUCs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ProgramLimits {
+    pub(crate) max_runtime_ms: Option<u64>,
+    pub(crate) max_heap_mb: Option<u64>,
+    pub(crate) max_query_rows: Option<usize>,
 }
 
 #[derive(Debug, Default)]
@@ -151,6 +239,7 @@ struct FunctionProgramCache {
 #[derive(Debug, Clone)]
 struct CachedFunctionProgram {
     program: FunctionProgram,
+    preamble_hash: u64,
     estimated_source_bytes: usize,
     expires_at: Instant,
 }
@@ -169,11 +258,11 @@ impl Default for FunctionProgramCache {
 }
 
 impl FunctionProgramCache {
-    fn get(&mut self, fn_oid: pg_sys::Oid) -> Option<FunctionProgram> {
+    fn get(&mut self, fn_oid: pg_sys::Oid, preamble_hash: u64) -> Option<FunctionProgram> {
         let key = fn_oid.to_u32();
         let now = Instant::now();
         let cached = self.by_oid.get(&key)?.clone();
-        if cached.expires_at <= now {
+        if cached.expires_at <= now || cached.preamble_hash != preamble_hash {
             self.remove_key(key);
             return None;
         }
@@ -182,7 +271,7 @@ impl FunctionProgramCache {
         Some(cached.program)
     }
 
-    fn insert(&mut self, program: FunctionProgram) {
+    fn insert(&mut self, program: FunctionProgram, preamble_hash: u64) {
         let key = program.oid.to_u32();
         let estimated_source_bytes = estimate_program_size_bytes(&program);
         if estimated_source_bytes > self.max_source_bytes {
@@ -192,6 +281,7 @@ impl FunctionProgramCache {
 
         let cached = CachedFunctionProgram {
             program,
+            preamble_hash,
             estimated_source_bytes,
             expires_at: Instant::now() + self.ttl,
         };
@@ -322,5 +412,15 @@ pub(crate) fn parse_artifact_ptr(prosrc: &str) -> Option<ArtifactPtr> {
         .unwrap_or("default")
         .to_string();
 
-    Some(ArtifactPtr { artifact_hash, export_name, import_map })
+    let limits = parsed.get("limits");
+    let limit = |key: &str| {
+        limits.and_then(|limits| limits.get(key)).and_then(Value::as_u64).filter(|v| *v > 0)
+    };
+    let limits = ProgramLimits {
+        max_runtime_ms: limit("max_runtime_ms"),
+        max_heap_mb: limit("max_heap_mb"),
+        max_query_rows: limit("max_query_rows").and_then(|v| usize::try_from(v).ok()),
+    };
+
+    Some(ArtifactPtr { artifact_hash, export_name, import_map, limits })
 }
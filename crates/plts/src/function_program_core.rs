@@ -9,6 +9,16 @@ pub(crate) struct ArtifactPtr {
     pub(crate) artifact_hash: String,
     pub(crate) export_name: String,
     pub(crate) import_map: HashMap<String, String>,
+    pub(crate) limits: ProgramLimits,
+}
+
+/// Per-function runtime caps carried by a deployed artifact pointer; unset fields fall back
+/// to the session GUCs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ProgramLimits {
+    pub(crate) max_runtime_ms: Option<u64>,
+    pub(crate) max_heap_mb: Option<u64>,
+    pub(crate) max_query_rows: Option<usize>,
 }
 
 #[derive(Debug, Default)]
@@ -189,13 +199,24 @@ pub(crate) fn parse_artifact_ptr(prosrc: &str) -> Option<ArtifactPtr> {
         .unwrap_or("default")
         .to_string();
 
-    Some(ArtifactPtr { artifact_hash, export_name, import_map })
+    let limits = parsed.get("limits");
+    let limit = |key: &str| {
+        limits.and_then(|limits| limits.get(key)).and_then(Value::as_u64).filter(|v| *v > 0)
+    };
+    let limits = ProgramLimits {
+        max_runtime_ms: limit("max_runtime_ms"),
+        max_heap_mb: limit("max_heap_mb"),
+        max_query_rows: limit("max_query_rows").and_then(|v| usize::try_from(v).ok()),
+    };
+
+    Some(ArtifactPtr { artifact_hash, export_name, import_map, limits })
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        ARTIFACT_SOURCE_CACHE_CAPACITY, ArtifactSourceCache, ProgramCache, parse_artifact_ptr,
+        ARTIFACT_SOURCE_CACHE_CAPACITY, ArtifactSourceCache, ProgramCache, ProgramLimits,
+        parse_artifact_ptr,
     };
     use std::time::Duration;
 
@@ -214,6 +235,28 @@ mod tests {
         assert_eq!(ptr.export_name, "named");
     }
 
+    #[test]
+    fn parse_artifact_ptr_reads_positive_limits() {
+        let ptr = parse_artifact_ptr(
+            r#"{"kind":"artifact_ptr","artifact_hash":"sha256:abc","limits":{"max_runtime_ms":5000,"max_heap_mb":0,"max_query_rows":10}}"#,
+        )
+        .expect("expected pointer metadata");
+        assert_eq!(
+            ptr.limits,
+            ProgramLimits {
+                max_runtime_ms: Some(5000),
+                max_heap_mb: None,
+                max_query_rows: Some(10)
+            }
+        );
+        assert_eq!(
+            parse_artifact_ptr(r#"{"kind":"artifact_ptr","artifact_hash":"sha256:abc"}"#)
+                .expect("expected pointer metadata")
+                .limits,
+            ProgramLimits::default()
+        );
+    }
+
     #[test]
     fn program_cache_promotes_recent_entries() {
         let mut cache = ProgramCache::new(8, 1_024, Duration::from_secs(30));
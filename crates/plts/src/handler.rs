@@ -1,17 +1,26 @@
-use crate::arg_mapping::{build_args_payload, is_single_jsonb_arg_function};
+use crate::arg_mapping::{
+    build_args_payload, function_return_type_oid, function_returns_set,
+    is_single_jsonb_arg_function, is_supported_return_type, json_value_to_return_datum,
+    type_display_name,
+};
 use crate::compiler::{contains_error_diagnostics, semantic_typecheck_typescript};
 use crate::function_program::load_function_program;
 use crate::function_program::parse_artifact_ptr;
+use crate::include_timing_enabled;
 use crate::observability::{
     classify_execute_error, log_info, log_warn, record_execute_error, record_execute_start,
     record_execute_success, should_log_info, should_log_warn,
 };
 use crate::runtime::{
-    build_runtime_context, execute_program, format_runtime_error_for_sql, runtime_available,
+    RowSink, build_runtime_context, execute_program_timed, raise_runtime_error_for_sql,
+    runtime_available,
 };
+use crate::trigger::TriggerCall;
 use pgrx::JsonB;
 use pgrx::prelude::*;
 use serde_json::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 #[pg_guard]
 #[unsafe(no_mangle)]
@@ -29,6 +38,7 @@ pub unsafe extern "C-unwind" fn plts_call_handler(
     }
 
     let fn_oid = unsafe { (*flinfo).fn_oid };
+    let trigger_call = unsafe { TriggerCall::from_fcinfo(fcinfo) };
     let args_payload = unsafe { build_args_payload(fcinfo, fn_oid) };
     let is_jsonb_single_arg = is_single_jsonb_arg_function(fcinfo, fn_oid);
 
@@ -54,13 +64,55 @@ pub unsafe extern "C-unwind" fn plts_call_handler(
                     program.schema, program.name, program.oid
                 ));
             }
-            let context = build_runtime_context(&program, &runtime_args_payload);
-            match execute_program(
+            let mut context = build_runtime_context(&program, &runtime_args_payload);
+            if let Some(trigger_call) = trigger_call.as_ref() {
+                context["trigger"] = trigger_call.context_json();
+            }
+            let returns_set = function_returns_set(fn_oid);
+            let set_writer = returns_set.then(|| {
+                let rettype = function_return_type_oid(fn_oid);
+                let writer =
+                    unsafe { SetResultWriter::begin(fcinfo, rettype) }.unwrap_or_else(|err| {
+                        error!(
+                            "plts function {}.{} (oid={}) {}",
+                            program.schema, program.name, program.oid, err
+                        )
+                    });
+                Rc::new(RefCell::new(writer))
+            });
+            let row_sink = set_writer.as_ref().map(|writer| {
+                let writer = Rc::clone(writer);
+                Box::new(move |row| writer.borrow_mut().push(row)) as RowSink
+            });
+            let (result, timing) = execute_program_timed(
                 &program.source,
                 &program.entrypoint_export,
                 &program.bare_specifier_map,
+                &program.limits,
                 &context,
-            ) {
+                row_sink,
+            );
+            // The envelope is only representable for json/jsonb scalar returns.
+            let result = if include_timing_enabled()
+                && matches!(function_return_type_oid(fn_oid), pg_sys::JSONBOID | pg_sys::JSONOID)
+                && !returns_set
+            {
+                result.map(|value| Some(timing.envelope(value)))
+            } else {
+                result
+            };
+            if let Some(trigger_call) = trigger_call.as_ref()
+                && let Ok(value) = &result
+            {
+                record_execute_success(started_at);
+                return trigger_call.result_datum(value.clone()).unwrap_or_else(|err| {
+                    error!(
+                        "plts trigger function {}.{} (oid={}) {}",
+                        program.schema, program.name, program.oid, err
+                    )
+                });
+            }
+            match result {
                 Ok(Some(value)) => {
                     record_execute_success(started_at);
                     if should_log_info() {
@@ -69,8 +121,34 @@ pub unsafe extern "C-unwind" fn plts_call_handler(
                             program.schema, program.name, program.oid
                         ));
                     }
-                    if let Some(datum) = JsonB(value).into_datum() {
-                        return datum;
+                    if let Some(writer) = set_writer.as_ref() {
+                        let rows = match value {
+                            Value::Array(rows) => rows,
+                            other => error!(
+                                "plts set-returning function {}.{} (oid={}) must return an array or generator, got {}",
+                                program.schema, program.name, program.oid, other
+                            ),
+                        };
+                        let mut writer = writer.borrow_mut();
+                        rows.into_iter().try_for_each(|row| writer.push(row)).unwrap_or_else(
+                            |err| {
+                                error!(
+                                    "plts function {}.{} (oid={}) {}",
+                                    program.schema, program.name, program.oid, err
+                                )
+                            },
+                        );
+                        unsafe { writer.finish(fcinfo) };
+                        return pg_sys::Datum::from(0);
+                    }
+                    let rettype = function_return_type_oid(fn_oid);
+                    match json_value_to_return_datum(value, rettype) {
+                        Ok(Some(datum)) => return datum,
+                        Ok(None) => {}
+                        Err(err) => error!(
+                            "plts function {}.{} (oid={}) {}",
+                            program.schema, program.name, program.oid, err
+                        ),
                     }
                 }
                 Ok(None) => {
@@ -81,6 +159,9 @@ pub unsafe extern "C-unwind" fn plts_call_handler(
                             program.schema, program.name, program.oid
                         ));
                     }
+                    if let Some(writer) = set_writer.as_ref() {
+                        unsafe { writer.borrow().finish(fcinfo) };
+                    }
                     unsafe { (*fcinfo).isnull = true };
                     return pg_sys::Datum::from(0);
                 }
@@ -94,12 +175,19 @@ pub unsafe extern "C-unwind" fn plts_call_handler(
                             program.schema, program.name, program.oid, err
                         ));
                     }
-                    error!("{}", format_runtime_error_for_sql(&program, &err));
+                    raise_runtime_error_for_sql(&program, &err);
                 }
             }
         }
     }
 
+    if trigger_call.is_some() {
+        error!(
+            "plts trigger function (oid={}) requires the v8 runtime and a compiled program",
+            fn_oid
+        );
+    }
+
     if is_jsonb_single_arg && unsafe { (*fcinfo).nargs == 1 } {
         let arg0 = unsafe { (*fcinfo).args.as_ptr() };
         if !arg0.is_null() && unsafe { !(*arg0).isnull } {
@@ -115,6 +203,75 @@ pub unsafe extern "C-unwind" fn plts_call_handler(
     pg_sys::Datum::from(0)
 }
 
+/// Tuplestore-backed result of a set-returning call. Rows are converted and stored as they
+/// arrive, so a generator handler's rows never have to be held in one array.
+struct SetResultWriter {
+    rsinfo: *mut pg_sys::ReturnSetInfo,
+    rettype: pg_sys::Oid,
+    tupdesc: pg_sys::TupleDesc,
+    tupstore: *mut pg_sys::Tuplestorestate,
+}
+
+impl SetResultWriter {
+    unsafe fn begin(
+        fcinfo: pg_sys::FunctionCallInfo,
+        rettype: pg_sys::Oid,
+    ) -> Result<Self, String> {
+        let rsinfo = unsafe { (*fcinfo).resultinfo } as *mut pg_sys::ReturnSetInfo;
+        let materialize_allowed = !rsinfo.is_null()
+            && unsafe { (*rsinfo).type_ } == pg_sys::NodeTag::T_ReturnSetInfo
+            && unsafe { (*rsinfo).allowedModes }
+                & pg_sys::SetFunctionReturnMode::SFRM_Materialize as i32
+                != 0;
+        if !materialize_allowed {
+            return Err("returns a set but was called in a context that cannot accept a set".into());
+        }
+
+        let expected_desc = unsafe { (*rsinfo).expectedDesc };
+        if !expected_desc.is_null() && unsafe { (*expected_desc).natts } != 1 {
+            return Err("returns a set with more than one column, which is not supported".into());
+        }
+
+        let per_query_memory = unsafe { (*(*rsinfo).econtext).ecxt_per_query_memory };
+        let (tupdesc, tupstore) = unsafe {
+            PgMemoryContexts::For(per_query_memory).switch_to(|_| {
+                let tupdesc = pg_sys::CreateTemplateTupleDesc(1);
+                pg_sys::TupleDescInitEntry(tupdesc, 1, c"value".as_ptr(), rettype, -1, 0);
+                (tupdesc, pg_sys::tuplestore_begin_heap(true, false, pg_sys::work_mem))
+            })
+        };
+
+        Ok(Self { rsinfo, rettype, tupdesc, tupstore })
+    }
+
+    fn push(&mut self, row: Value) -> Result<(), String> {
+        let datum = match row {
+            Value::Null => None,
+            value => json_value_to_return_datum(value, self.rettype)?,
+        };
+        let values = [datum.unwrap_or(pg_sys::Datum::from(0))];
+        let nulls = [datum.is_none()];
+        unsafe {
+            pg_sys::tuplestore_putvalues(
+                self.tupstore,
+                self.tupdesc,
+                values.as_ptr(),
+                nulls.as_ptr(),
+            )
+        };
+        Ok(())
+    }
+
+    unsafe fn finish(&self, fcinfo: pg_sys::FunctionCallInfo) {
+        unsafe {
+            (*self.rsinfo).returnMode = pg_sys::SetFunctionReturnMode::SFRM_Materialize;
+            (*self.rsinfo).setResult = self.tupstore;
+            (*self.rsinfo).setDesc = self.tupdesc;
+            (*fcinfo).isnull = true;
+        }
+    }
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn pg_finfo_plts_call_handler() -> &'static pg_sys::Pg_finfo_record {
     const V1_API: pg_sys::Pg_finfo_record = pg_sys::Pg_finfo_record { api_version: 1 };
@@ -132,6 +289,15 @@ pub unsafe extern "C-unwind" fn plts_validator(fcinfo: pg_sys::FunctionCallInfo)
         return pg_sys::Datum::from(0);
     };
 
+    let rettype = function_return_type_oid(fn_oid);
+    if rettype != pg_sys::TRIGGEROID && !is_supported_return_type(rettype) {
+        error!(
+            "plts validator rejected function oid={} because return type {} is not supported; use int4, int8, float8, boolean, text, json, jsonb, void, or trigger",
+            fn_oid,
+            type_display_name(rettype)
+        );
+    }
+
     let Some(prosrc) = load_prosrc(fn_oid) else {
         return pg_sys::Datum::from(0);
     };
@@ -45,6 +45,27 @@ impl Default for IsolatePoolConfig {
     }
 }
 
+impl IsolatePoolConfig {
+    /// Builds a config from raw `plts.isolate_*` GUC values. A value the pool cannot honor
+    /// (negative, or zero invocations) keeps its default instead of being clamped.
+    pub fn from_guc_values(
+        enable_reuse: bool,
+        max_pool_size: i32,
+        max_age_seconds: i32,
+        max_invocations: i32,
+    ) -> Self {
+        Self {
+            max_age_seconds: u64::try_from(max_age_seconds).unwrap_or(DEFAULT_MAX_AGE_SECONDS),
+            max_invocations: u64::try_from(max_invocations)
+                .ok()
+                .filter(|value| *value > 0)
+                .unwrap_or(DEFAULT_MAX_INVOCATIONS),
+            max_pool_size: usize::try_from(max_pool_size).unwrap_or(DEFAULT_MAX_POOL_SIZE),
+            enable_reuse,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct PoolEntry<T> {
     value: T,
@@ -95,6 +116,12 @@ impl<T> CheckedOut<T> {
         self.was_warm
     }
 
+    /// Records a near-heap-limit event so check-in retires the shell as `HeapPressure`, even
+    /// though hitting the limit also terminates execution.
+    pub fn mark_heap_pressure(&mut self) {
+        self.entry.heap_pressure_events += 1;
+    }
+
     fn into_entry(self) -> PoolEntry<T> {
         self.entry
     }
@@ -205,7 +232,6 @@ impl<T> IsolatePool<T> {
         CheckinOutcome { returned: true, retire_reason: None }
     }
 
-    #[cfg(test)]
     pub fn available_count(&self) -> usize {
         self.available.len()
     }
@@ -251,14 +277,14 @@ fn retire_reason_for_health<T>(
         return Some(RetireReason::ConfigChanged);
     }
 
-    if health.terminated {
-        return Some(RetireReason::Termination);
-    }
-
     if health.heap_pressure || entry.heap_pressure_events > 0 {
         return Some(RetireReason::HeapPressure);
     }
 
+    if health.terminated {
+        return Some(RetireReason::Termination);
+    }
+
     if !health.cleanup_ok {
         return Some(RetireReason::CleanupFailure);
     }
@@ -280,6 +306,31 @@ mod tests {
     use std::thread;
     use std::time::Duration;
 
+    #[test]
+    fn config_from_guc_values_keeps_in_range_settings() {
+        let config = IsolatePoolConfig::from_guc_values(false, 8, 0, 1);
+        assert_eq!(
+            config,
+            IsolatePoolConfig {
+                max_age_seconds: 0,
+                max_invocations: 1,
+                max_pool_size: 8,
+                enable_reuse: false,
+            }
+        );
+    }
+
+    #[test]
+    fn config_from_guc_values_falls_back_to_defaults_for_invalid_settings() {
+        let config = IsolatePoolConfig::from_guc_values(true, -1, -30, 0);
+        assert_eq!(config, IsolatePoolConfig::default());
+
+        let config = IsolatePoolConfig::from_guc_values(true, 3, 60, -5);
+        assert_eq!(config.max_pool_size, 3);
+        assert_eq!(config.max_age_seconds, 60);
+        assert_eq!(config.max_invocations, DEFAULT_MAX_INVOCATIONS);
+    }
+
     #[test]
     fn checkout_miss_when_empty() {
         let mut pool = IsolatePool::<u32>::new();
@@ -344,6 +395,24 @@ mod tests {
         assert_eq!(pool.available_count(), 0);
     }
 
+    #[test]
+    fn heap_pressure_mark_takes_precedence_over_termination() {
+        let mut pool = IsolatePool::new();
+        let config = IsolatePoolConfig::default();
+        assert!(pool.insert_fresh(4_u32, &config).returned);
+
+        let mut checked_out = pool.checkout(&config).checked_out.expect("checkout should succeed");
+        checked_out.mark_heap_pressure();
+        let outcome = pool.checkin(
+            checked_out,
+            &config,
+            ShellHealth { terminated: true, ..Default::default() },
+        );
+        assert!(!outcome.returned);
+        assert_eq!(outcome.retire_reason, Some(RetireReason::HeapPressure));
+        assert_eq!(pool.available_count(), 0);
+    }
+
     #[test]
     fn cleanup_failure_retires_shell() {
         let mut pool = IsolatePool::new();
@@ -3,9 +3,11 @@ use pgrx::pg_sys;
 use pgrx::prelude::*;
 #[cfg(not(test))]
 use pgrx::{GucContext, GucFlags, GucRegistry};
+use std::ffi::CString;
 
 mod api;
 mod arg_mapping;
+mod bundler;
 mod compiler;
 #[cfg(test)]
 mod compiler_core;
@@ -15,11 +17,16 @@ mod function_program;
 mod function_program_core;
 mod handler;
 mod isolate_pool;
+mod module_cache;
 mod observability;
+mod preamble;
 mod runtime;
 #[cfg(test)]
 mod runtime_core;
 mod runtime_spi;
+mod settings;
+mod source_map;
+mod trigger;
 
 ::pgrx::pg_module_magic!(name, version);
 
@@ -27,9 +34,17 @@ pub(crate) static ISOLATE_REUSE_GUC: GucSetting<bool> = GucSetting::<bool>::new(
 pub(crate) static ISOLATE_POOL_SIZE_GUC: GucSetting<i32> = GucSetting::<i32>::new(2);
 pub(crate) static ISOLATE_MAX_AGE_S_GUC: GucSetting<i32> = GucSetting::<i32>::new(120);
 pub(crate) static ISOLATE_MAX_INVOCATIONS_GUC: GucSetting<i32> = GucSetting::<i32>::new(250);
+pub(crate) static ENABLE_CONSOLE_GUC: GucSetting<bool> = GucSetting::<bool>::new(false);
+pub(crate) static INCLUDE_TIMING_GUC: GucSetting<bool> = GucSetting::<bool>::new(false);
+pub(crate) static MODULE_CACHE_BYTES_GUC: GucSetting<i32> =
+    GucSetting::<i32>::new(16 * 1024 * 1024);
+pub(crate) static MODULE_CACHE_ENTRIES_GUC: GucSetting<i32> = GucSetting::<i32>::new(128);
+pub(crate) static INTERRUPT_POLL_MS_GUC: GucSetting<i32> = GucSetting::<i32>::new(5);
 pub(crate) static COMPILER_REACTOR_MAX_REQUESTS_GUC: GucSetting<i32> = GucSetting::<i32>::new(1000);
 pub(crate) static COMPILER_REACTOR_MAX_AGE_S_GUC: GucSetting<i32> = GucSetting::<i32>::new(300);
 pub(crate) static COMPILER_REQUEST_TIMEOUT_MS_GUC: GucSetting<i32> = GucSetting::<i32>::new(30_000);
+pub(crate) static COMPILE_PREAMBLE_GUC: GucSetting<Option<CString>> =
+    GucSetting::<Option<CString>>::new(None);
 
 #[cfg(not(test))]
 #[allow(non_snake_case)]
@@ -74,6 +89,60 @@ pub extern "C-unwind" fn _PG_init() {
         GucContext::Userset,
         GucFlags::default(),
     );
+    GucRegistry::define_bool_guc(
+        c"plts.enable_console",
+        c"Install a console object in plts handlers that writes to the Postgres log.",
+        c"When off, handlers keep the default runtime console and console output is not routed to Postgres.",
+        &ENABLE_CONSOLE_GUC,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_bool_guc(
+        c"plts.include_timing",
+        c"Wrap jsonb handler results in an envelope with execution timings.",
+        c"When on, jsonb-returning plts functions return { \"@plts\": 1, result, timing } with total and ctx.db timings.",
+        &INCLUDE_TIMING_GUC,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_int_guc(
+        c"plts.module_cache_bytes",
+        c"Maximum total bytes of V8 code cache kept for imported artifact modules per backend.",
+        c"Least recently used entries are evicted past this budget; 0 disables the module code cache.",
+        &MODULE_CACHE_BYTES_GUC,
+        0,
+        i32::MAX,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_int_guc(
+        c"plts.module_cache_entries",
+        c"Maximum number of artifact modules with cached V8 code per backend.",
+        c"Least recently used entries are evicted past this count; 0 disables the module code cache.",
+        &MODULE_CACHE_ENTRIES_GUC,
+        0,
+        100_000,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_int_guc(
+        c"plts.interrupt_poll_ms",
+        c"Interval in milliseconds at which a running handler checks for cancel and timeout.",
+        c"Lower values stop cancelled or timed out handlers sooner at the cost of more watchdog wakeups.",
+        &INTERRUPT_POLL_MS_GUC,
+        1,
+        100,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_string_guc(
+        c"plts.compile_preamble",
+        c"TypeScript prepended to every source before transpilation.",
+        c"Shared polyfills and helpers placed here are in scope for every handler; import and export declarations are rejected.",
+        &COMPILE_PREAMBLE_GUC,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
     if preloading {
         GucRegistry::define_int_guc(
             c"plts.compiler_reactor_max_requests",
@@ -117,16 +186,43 @@ pub(crate) fn isolate_reuse_enabled() -> bool {
     ISOLATE_REUSE_GUC.get()
 }
 
-pub(crate) fn isolate_pool_size() -> usize {
-    ISOLATE_POOL_SIZE_GUC.get().max(0) as usize
+pub(crate) fn console_enabled() -> bool {
+    ENABLE_CONSOLE_GUC.get()
+}
+
+pub(crate) fn include_timing_enabled() -> bool {
+    INCLUDE_TIMING_GUC.get()
+}
+
+pub(crate) fn module_cache_max_bytes() -> usize {
+    MODULE_CACHE_BYTES_GUC.get().max(0) as usize
 }
 
-pub(crate) fn isolate_max_age_seconds() -> u64 {
-    ISOLATE_MAX_AGE_S_GUC.get().max(0) as u64
+pub(crate) fn module_cache_max_entries() -> usize {
+    MODULE_CACHE_ENTRIES_GUC.get().max(0) as usize
 }
 
-pub(crate) fn isolate_max_invocations() -> u64 {
-    ISOLATE_MAX_INVOCATIONS_GUC.get().max(1) as u64
+pub(crate) fn interrupt_poll_ms() -> i32 {
+    INTERRUPT_POLL_MS_GUC.get()
+}
+
+pub(crate) fn isolate_pool_size() -> i32 {
+    ISOLATE_POOL_SIZE_GUC.get()
+}
+
+pub(crate) fn compile_preamble() -> Option<String> {
+    COMPILE_PREAMBLE_GUC
+        .get()
+        .and_then(|raw| raw.into_string().ok())
+        .filter(|preamble| !preamble.trim().is_empty())
+}
+
+pub(crate) fn isolate_max_age_seconds() -> i32 {
+    ISOLATE_MAX_AGE_S_GUC.get()
+}
+
+pub(crate) fn isolate_max_invocations() -> i32 {
+    ISOLATE_MAX_INVOCATIONS_GUC.get()
 }
 
 pub(crate) fn compiler_reactor_max_requests() -> u64 {
@@ -153,12 +249,16 @@ extension_sql!(
         compiler_fingerprint text NOT NULL,
         created_at timestamptz NOT NULL DEFAULT now(),
         source_map text,
-        diagnostics jsonb
+        diagnostics jsonb,
+        doc text
     );
 
     ALTER TABLE plts.artifact
     ADD COLUMN IF NOT EXISTS source_map text;
 
+    ALTER TABLE plts.artifact
+    ADD COLUMN IF NOT EXISTS doc text;
+
     CREATE FUNCTION plts_call_handler()
     RETURNS language_handler
     AS 'MODULE_PATHNAME', 'plts_call_handler'
@@ -0,0 +1,190 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Entry-count and byte caps for `ModuleCodeCache`, read from `plts.module_cache_entries` and
+/// `plts.module_cache_bytes` on each insert so setting changes apply without a restart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleCacheBudget {
+    pub max_entries: usize,
+    pub max_bytes: usize,
+}
+
+impl ModuleCacheBudget {
+    pub fn is_disabled(&self) -> bool {
+        self.max_entries == 0 || self.max_bytes == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModuleCacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+/// V8 code cache bytes keyed by artifact hash. Each entry remembers the hash of the module
+/// source it was produced for, so a differently versioned source counts as a miss.
+#[derive(Debug, Default)]
+pub struct ModuleCodeCache {
+    by_hash: HashMap<String, CachedModuleCode>,
+    lru: VecDeque<String>,
+    total_bytes: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+#[derive(Debug)]
+struct CachedModuleCode {
+    source_hash: u64,
+    data: Vec<u8>,
+}
+
+impl ModuleCodeCache {
+    pub fn get(&mut self, artifact_hash: &str, source_hash: u64) -> Option<Vec<u8>> {
+        match self.by_hash.get(artifact_hash) {
+            Some(cached) if cached.source_hash == source_hash => {
+                let data = cached.data.clone();
+                self.hits += 1;
+                self.promote(artifact_hash);
+                Some(data)
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        artifact_hash: String,
+        source_hash: u64,
+        data: Vec<u8>,
+        budget: ModuleCacheBudget,
+    ) {
+        self.remove_key(&artifact_hash);
+        if budget.is_disabled() || data.len() > budget.max_bytes {
+            return;
+        }
+
+        while self.by_hash.len() >= budget.max_entries
+            || self.total_bytes + data.len() > budget.max_bytes
+        {
+            let Some(evicted) = self.lru.pop_front() else {
+                break;
+            };
+
+            if let Some(previous) = self.by_hash.remove(&evicted) {
+                self.total_bytes = self.total_bytes.saturating_sub(previous.data.len());
+                self.evictions += 1;
+            }
+        }
+
+        self.lru.push_back(artifact_hash.clone());
+        self.total_bytes += data.len();
+        self.by_hash.insert(artifact_hash, CachedModuleCode { source_hash, data });
+    }
+
+    pub fn stats(&self) -> ModuleCacheStats {
+        ModuleCacheStats {
+            entries: self.by_hash.len(),
+            bytes: self.total_bytes,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+
+    fn promote(&mut self, artifact_hash: &str) {
+        if let Some(position) = self.lru.iter().position(|entry| entry == artifact_hash) {
+            let key = self.lru.remove(position).expect("position came from lru index");
+            self.lru.push_back(key);
+        }
+    }
+
+    fn remove_key(&mut self, artifact_hash: &str) {
+        if let Some(previous) = self.by_hash.remove(artifact_hash) {
+            self.total_bytes = self.total_bytes.saturating_sub(previous.data.len());
+        }
+
+        if let Some(position) = self.lru.iter().position(|entry| entry == artifact_hash) {
+            let _ = self.lru.remove(position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: ModuleCacheBudget = ModuleCacheBudget { max_entries: 8, max_bytes: 10 };
+
+    #[test]
+    fn get_hits_only_for_matching_source_hash() {
+        let mut cache = ModuleCodeCache::default();
+        cache.insert("sha256:a".to_string(), 1, vec![1, 2, 3], BUDGET);
+
+        assert_eq!(cache.get("sha256:a", 1), Some(vec![1, 2, 3]));
+        assert_eq!(cache.get("sha256:a", 2), None);
+        assert_eq!(cache.get("sha256:b", 1), None);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 2));
+    }
+
+    #[test]
+    fn insert_evicts_least_recently_used_entries_to_fit_byte_budget() {
+        let mut cache = ModuleCodeCache::default();
+        cache.insert("sha256:a".to_string(), 1, vec![0; 4], BUDGET);
+        cache.insert("sha256:b".to_string(), 1, vec![0; 4], BUDGET);
+        assert!(cache.get("sha256:a", 1).is_some());
+
+        cache.insert("sha256:c".to_string(), 1, vec![0; 4], BUDGET);
+
+        assert!(cache.get("sha256:b", 1).is_none());
+        assert!(cache.get("sha256:a", 1).is_some());
+        assert!(cache.get("sha256:c", 1).is_some());
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.bytes, stats.evictions), (2, 8, 1));
+    }
+
+    #[test]
+    fn insert_respects_entry_budget() {
+        let mut cache = ModuleCodeCache::default();
+        let budget = ModuleCacheBudget { max_entries: 1, max_bytes: 1024 };
+        cache.insert("sha256:a".to_string(), 1, vec![0; 2], budget);
+        cache.insert("sha256:b".to_string(), 1, vec![0; 2], budget);
+
+        assert!(cache.get("sha256:a", 1).is_none());
+        assert_eq!(cache.stats().entries, 1);
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn replacing_an_entry_updates_bytes_without_counting_an_eviction() {
+        let mut cache = ModuleCodeCache::default();
+        cache.insert("sha256:a".to_string(), 1, vec![0; 6], BUDGET);
+        cache.insert("sha256:a".to_string(), 2, vec![0; 3], BUDGET);
+
+        assert_eq!(cache.get("sha256:a", 2), Some(vec![0; 3]));
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.bytes, stats.evictions), (1, 3, 0));
+    }
+
+    #[test]
+    fn oversized_or_disabled_inserts_are_not_stored() {
+        let mut cache = ModuleCodeCache::default();
+        cache.insert("sha256:big".to_string(), 1, vec![0; 11], BUDGET);
+        cache.insert(
+            "sha256:off".to_string(),
+            1,
+            vec![0; 1],
+            ModuleCacheBudget { max_entries: 8, max_bytes: 0 },
+        );
+
+        assert_eq!(cache.stats().entries, 0);
+        assert_eq!(cache.stats().bytes, 0);
+    }
+}
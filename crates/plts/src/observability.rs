@@ -238,6 +238,24 @@ pub(crate) fn record_runtime_warm_shell_reuse() {
     RUNTIME_READINESS_WARM_SHELL_REUSES.fetch_add(1, Ordering::Relaxed);
 }
 
+/// Pool-level counters for `plts.pool_metrics()`; the live pool sizes are added by the runtime.
+pub(crate) fn runtime_pool_counters_json() -> Value {
+    json!({
+        "hits": RUNTIME_READINESS_CHECKOUT_HITS.load(Ordering::Relaxed),
+        "misses": RUNTIME_READINESS_CHECKOUT_MISSES.load(Ordering::Relaxed),
+        "cold_invocations": RUNTIME_READINESS_COLD_SHELL_CREATES.load(Ordering::Relaxed),
+        "warm_invocations": RUNTIME_READINESS_WARM_SHELL_REUSES.load(Ordering::Relaxed),
+        "retired": RUNTIME_READINESS_RETIRED.load(Ordering::Relaxed),
+        "retire_reasons": {
+            "max_age": RUNTIME_READINESS_RETIRE_MAX_AGE.load(Ordering::Relaxed),
+            "max_invocations": RUNTIME_READINESS_RETIRE_MAX_INVOCATIONS.load(Ordering::Relaxed),
+            "termination": RUNTIME_READINESS_RETIRE_TERMINATION.load(Ordering::Relaxed),
+            "heap_pressure": RUNTIME_READINESS_RETIRE_HEAP_PRESSURE.load(Ordering::Relaxed),
+            "other": RUNTIME_READINESS_RETIRE_OTHER.load(Ordering::Relaxed)
+        }
+    })
+}
+
 pub(crate) fn record_runtime_retire(reason: &str) {
     RUNTIME_READINESS_RETIRED.fetch_add(1, Ordering::Relaxed);
     match reason {
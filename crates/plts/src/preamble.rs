@@ -0,0 +1,99 @@
+use crate::compiler::TsgoDiagnostic;
+
+/// Rejects a `plts.compile_preamble` that would change the module it is prepended to: a static
+/// `import` or `export` would add dependencies or exports to every artifact compiled with it.
+/// Dynamic `import()` and `import.meta` are fine.
+pub(crate) fn validate_compile_preamble(preamble: &str) -> Result<(), String> {
+    match preamble.lines().map(str::trim).find(|line| is_module_declaration(line)) {
+        Some(line) => Err(format!(
+            "plts.compile_preamble must not contain import or export declarations; found `{line}`"
+        )),
+        None => Ok(()),
+    }
+}
+
+fn is_module_declaration(line: &str) -> bool {
+    ["import", "export"].iter().any(|keyword| {
+        line.strip_prefix(keyword).is_some_and(|rest| {
+            rest.starts_with(|ch: char| ch.is_whitespace() || matches!(ch, '{' | '*' | '"' | '\''))
+        })
+    })
+}
+
+/// Returns the source to hand to the compiler and the number of lines the preamble pushed the
+/// original source down by.
+pub(crate) fn prepend_compile_preamble(preamble: &str, source_ts: &str) -> (String, u32) {
+    let separator = if preamble.ends_with('\n') { "" } else { "\n" };
+    let line_offset = preamble.matches('\n').count() + separator.len();
+    (format!("{preamble}{separator}{source_ts}"), u32::try_from(line_offset).unwrap_or(u32::MAX))
+}
+
+/// Maps diagnostic lines back onto the original source. Diagnostics raised inside the preamble
+/// keep their line and are labelled so they are not mistaken for errors in the handler.
+pub(crate) fn remap_preamble_diagnostics(diagnostics: &mut [TsgoDiagnostic], line_offset: u32) {
+    if line_offset == 0 {
+        return;
+    }
+
+    for diagnostic in diagnostics {
+        match diagnostic.line {
+            Some(line) if line > line_offset => diagnostic.line = Some(line - line_offset),
+            Some(_) => {
+                diagnostic.message = format!("in plts.compile_preamble: {}", diagnostic.message)
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(line: Option<u32>) -> TsgoDiagnostic {
+        TsgoDiagnostic {
+            severity: "error".to_string(),
+            phase: None,
+            message: "boom".to_string(),
+            line,
+            column: Some(1),
+        }
+    }
+
+    #[test]
+    fn validate_rejects_static_imports_and_exports() {
+        assert!(validate_compile_preamble("(globalThis as any).shim = 1;").is_ok());
+        assert!(
+            validate_compile_preamble("const meta = import.meta;\nawait import('data:,');").is_ok()
+        );
+        assert!(validate_compile_preamble("const exported = 1;").is_ok());
+
+        let error = validate_compile_preamble("const a = 1;\n  export const b = 2;")
+            .expect_err("export should be rejected");
+        assert!(error.contains("`export const b = 2;`"));
+        assert!(validate_compile_preamble("import { x } from 'y';").is_err());
+        assert!(validate_compile_preamble("import 'side-effect';").is_err());
+        assert!(validate_compile_preamble("export{}").is_err());
+    }
+
+    #[test]
+    fn prepend_counts_lines_added_before_the_source() {
+        assert_eq!(prepend_compile_preamble("a();", "b();"), ("a();\nb();".to_string(), 1));
+        assert_eq!(
+            prepend_compile_preamble("a();\nc();\n", "b();"),
+            ("a();\nc();\nb();".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn remap_shifts_source_lines_and_labels_preamble_lines() {
+        let mut diagnostics = vec![diagnostic(Some(5)), diagnostic(Some(2)), diagnostic(None)];
+        remap_preamble_diagnostics(&mut diagnostics, 2);
+
+        assert_eq!(diagnostics[0].line, Some(3));
+        assert_eq!(diagnostics[0].message, "boom");
+        assert_eq!(diagnostics[1].line, Some(2));
+        assert_eq!(diagnostics[1].message, "in plts.compile_preamble: boom");
+        assert_eq!(diagnostics[2].message, "boom");
+    }
+}
@@ -1,9 +1,15 @@
-use crate::function_program::FunctionProgram;
+use crate::bundler::static_import_specifiers;
+use crate::function_program::{FunctionProgram, ProgramLimits};
 #[cfg(feature = "v8_runtime")]
-use crate::function_program::load_compiled_artifact_source;
+use crate::function_program::{
+    load_compiled_artifact_source, load_function_program, resolve_plts_function_oid,
+};
 #[cfg(feature = "v8_runtime")]
 use crate::isolate_pool::{CheckedOut, IsolatePool, IsolatePoolConfig, RetireReason, ShellHealth};
 #[cfg(feature = "v8_runtime")]
+use crate::module_cache::{ModuleCacheBudget, ModuleCodeCache};
+use crate::observability::runtime_pool_counters_json;
+#[cfg(feature = "v8_runtime")]
 use crate::observability::{
     record_runtime_checkout_hit, record_runtime_checkout_miss, record_runtime_cleanup,
     record_runtime_cold_shell_create, record_runtime_context_setup, record_runtime_module_evaluate,
@@ -11,29 +17,38 @@ use crate::observability::{
     record_runtime_warm_shell_reuse,
 };
 #[cfg(feature = "v8_runtime")]
-use crate::runtime_spi::{exec_sql_with_params, query_json_rows_with_params};
+use crate::runtime_spi::{
+    DbBatchStatement, QueryRowsOverride, exec_sql_batch, exec_sql_with_params,
+    query_json_columns_with_params, query_json_rows_with_params,
+};
+use crate::source_map::remap_top_stack_frame;
 #[cfg(feature = "v8_runtime")]
 use crate::{
-    isolate_max_age_seconds, isolate_max_invocations, isolate_pool_size, isolate_reuse_enabled,
+    console_enabled, interrupt_poll_ms, isolate_max_age_seconds, isolate_max_invocations,
+    isolate_pool_size, isolate_reuse_enabled, module_cache_max_bytes, module_cache_max_entries,
 };
 #[cfg(feature = "v8_runtime")]
 use base64::Engine;
+#[cfg(feature = "v8_runtime")]
+use common::import_map::INLINE_IMPORT_MAP_MARKER;
+use common::import_map::{import_map_target_specifier, parse_inline_import_map};
 use pgrx::prelude::*;
 use serde_json::Value;
 use serde_json::json;
 #[cfg(feature = "v8_runtime")]
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::ffi::CString;
 use std::fmt;
 #[cfg(feature = "v8_runtime")]
 use std::rc::Rc;
 #[cfg(feature = "v8_runtime")]
 use std::sync::Arc;
 #[cfg(feature = "v8_runtime")]
-use std::sync::OnceLock;
-#[cfg(feature = "v8_runtime")]
 use std::sync::atomic::{AtomicBool, Ordering};
 #[cfg(feature = "v8_runtime")]
+use std::sync::{Mutex, OnceLock};
+#[cfg(feature = "v8_runtime")]
 use std::thread::{self, JoinHandle};
 #[cfg(feature = "v8_runtime")]
 use std::time::{Duration, Instant};
@@ -43,11 +58,41 @@ pub(crate) struct RuntimeExecError {
     stage: &'static str,
     message: String,
     stack: Option<String>,
+    reason: Option<TerminationReason>,
+    stopgap: Option<ThrownStopgapError>,
+}
+
+/// SQLSTATE and bare message of the `StopgapError` that ended an invocation, as recorded by the
+/// invoke wrapper from the error's constructor-set field rather than from its name.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub(crate) struct ThrownStopgapError {
+    sqlstate: String,
+    message: String,
+}
+
+/// Why the runtime stopped a handler before it finished. Reported as `reason=` in the raised
+/// error so monitoring can classify terminations without matching on the message text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(any(test, feature = "v8_runtime")), allow(dead_code))]
+pub(crate) enum TerminationReason {
+    Timeout,
+    Memory,
+    Interrupt,
+}
+
+impl TerminationReason {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::Memory => "memory",
+            Self::Interrupt => "interrupt",
+        }
+    }
 }
 
 impl RuntimeExecError {
     pub(crate) fn new(stage: &'static str, message: impl Into<String>) -> Self {
-        Self { stage, message: message.into(), stack: None }
+        Self { stage, message: message.into(), stack: None, reason: None, stopgap: None }
     }
 
     #[cfg(any(test, feature = "v8_runtime"))]
@@ -56,13 +101,30 @@ impl RuntimeExecError {
         message: impl Into<String>,
         stack: impl Into<Option<String>>,
     ) -> Self {
-        Self { stage, message: message.into(), stack: stack.into() }
+        Self { stage, message: message.into(), stack: stack.into(), reason: None, stopgap: None }
+    }
+
+    #[cfg(any(test, feature = "v8_runtime"))]
+    pub(crate) fn terminated(
+        stage: &'static str,
+        message: impl Into<String>,
+        reason: TerminationReason,
+    ) -> Self {
+        Self { stage, message: message.into(), stack: None, reason: Some(reason), stopgap: None }
+    }
+
+    #[cfg(feature = "v8_runtime")]
+    fn with_stopgap(self, stopgap: Option<ThrownStopgapError>) -> Self {
+        Self { stopgap, ..self }
     }
 }
 
 impl fmt::Display for RuntimeExecError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "stage={}; message={}", self.stage, self.message)?;
+        if let Some(reason) = self.reason {
+            write!(f, "; reason={}", reason.as_str())?;
+        }
         if let Some(stack) = &self.stack {
             write!(f, "; stack={stack}")?;
         }
@@ -70,16 +132,132 @@ impl fmt::Display for RuntimeExecError {
     }
 }
 
-pub(crate) fn format_runtime_error_for_sql(
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SqlRuntimeError {
+    message: String,
+    detail: Option<String>,
+    hint: String,
+}
+
+fn format_runtime_error_for_sql(
     program: &FunctionProgram,
     err: &RuntimeExecError,
-) -> String {
-    format!(
-        "plts runtime error for {}.{} (oid={}): {}; sql_context={{schema={}, name={}, oid={}}}",
-        program.schema, program.name, program.oid, err, program.schema, program.name, program.oid
+) -> SqlRuntimeError {
+    let reason =
+        err.reason.map(|reason| format!(", reason={}", reason.as_str())).unwrap_or_default();
+    let message = format!(
+        "plts runtime error for {}.{} (oid={}): stage={}; message={}; sql_context={{schema={}, name={}, oid={}{}}}",
+        program.schema,
+        program.name,
+        program.oid,
+        err.stage,
+        err.message,
+        program.schema,
+        program.name,
+        program.oid,
+        reason
+    );
+    let message = match original_ts_position(program, err) {
+        Some(position) => format!("{message}; ts_position={position}"),
+        None => message,
+    };
+    SqlRuntimeError {
+        message,
+        detail: err.stack.clone(),
+        hint: format!("raised by plts function {}.{}", program.schema, program.name),
+    }
+}
+
+pub(crate) fn raise_runtime_error_for_sql(program: &FunctionProgram, err: &RuntimeExecError) -> ! {
+    // The cancel/terminate that stopped the isolate is still pending; let Postgres raise its own
+    // `query_canceled`/`admin_shutdown` error so clients see the usual SQLSTATE.
+    if err.reason == Some(TerminationReason::Interrupt) {
+        pgrx::check_for_interrupts!();
+    }
+    let formatted = format_runtime_error_for_sql(program, err);
+    // A failed `assert`/`StopgapError` surfaces as its own message under the SQLSTATE it chose.
+    let (code, message) = match &err.stopgap {
+        Some(thrown) => (stopgap_sqlstate_code(&thrown.sqlstate), thrown.message.clone()),
+        None => (PgSqlErrorCode::ERRCODE_INTERNAL_ERROR as i32, formatted.message),
+    };
+    raise_sqlstate_error(code, &message, formatted.detail.as_deref(), &formatted.hint)
+}
+
+/// Raises an ERROR under the packed SQLSTATE `code` as-is. `ErrorReport` only carries the codes
+/// `PgSqlErrorCode` lists and reports any other (e.g. `U0001`) as `XX000`, so this goes through
+/// `ThrowErrorData`, whose error pgrx rethrows unchanged once the Rust frames have unwound.
+fn raise_sqlstate_error(code: i32, message: &str, detail: Option<&str>, hint: &str) -> ! {
+    let message = sql_error_cstring(message);
+    let detail = detail.map(sql_error_cstring);
+    let hint = sql_error_cstring(hint);
+    let mut edata = pg_sys::ErrorData {
+        elevel: PgLogLevel::ERROR as i32,
+        sqlerrcode: code,
+        message: message.as_ptr().cast_mut(),
+        detail: detail.as_ref().map_or(std::ptr::null_mut(), |detail| detail.as_ptr().cast_mut()),
+        hint: hint.as_ptr().cast_mut(),
+        ..Default::default()
+    };
+    // SAFETY: `ThrowErrorData` copies every string it is given before raising, and the strings
+    // live in this frame, outside the guarded FFI call that unwinds back through it.
+    unsafe { pg_sys::ThrowErrorData(&mut edata) };
+    unreachable!("ERROR-level reports do not return")
+}
+
+fn sql_error_cstring(text: &str) -> CString {
+    CString::new(text.replace('\0', "")).expect("NUL bytes were removed")
+}
+
+/// SQLSTATE classes a handler may not raise under: success, warning, and no-data are not errors,
+/// and transaction rollback, operator intervention, and internal error would make clients retry
+/// or alert on a handler's own failure.
+const RESERVED_STOPGAP_SQLSTATE_CLASSES: [&str; 6] = ["00", "01", "02", "40", "57", "XX"];
+
+/// `MAKE_SQLSTATE('P','0','0','0','1')`, Postgres' `raise_exception`.
+const RAISE_EXCEPTION_SQLSTATE_CODE: i32 = 32 + (1 << 24);
+
+/// Packs the SQLSTATE a `StopgapError` chose, falling back to `P0001` for a malformed code or one
+/// in a reserved class.
+fn stopgap_sqlstate_code(sqlstate: &str) -> i32 {
+    let reserved =
+        RESERVED_STOPGAP_SQLSTATE_CLASSES.iter().any(|class| sqlstate.starts_with(class));
+    match sqlstate_code(sqlstate) {
+        Some(code) if !reserved => code,
+        _ => RAISE_EXCEPTION_SQLSTATE_CODE,
+    }
+}
+
+/// Packs a five-character SQLSTATE the way Postgres' `MAKE_SQLSTATE` does.
+fn sqlstate_code(sqlstate: &str) -> Option<i32> {
+    let bytes = sqlstate.as_bytes();
+    if bytes.len() != 5
+        || !bytes.iter().all(|byte| byte.is_ascii_digit() || byte.is_ascii_uppercase())
+    {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(index, byte)| ((i32::from(*byte) - i32::from(b'0')) & 0x3F) << (6 * index))
+            .sum(),
     )
 }
 
+fn original_ts_position(program: &FunctionProgram, err: &RuntimeExecError) -> Option<String> {
+    let stack = err.stack.as_deref()?;
+    let artifact_hash = program.artifact_hash.as_deref()?;
+    let source_map = Spi::get_one_with_args::<String>(
+        "SELECT source_map FROM plts.artifact WHERE artifact_hash = $1",
+        &[artifact_hash.into()],
+    )
+    .ok()
+    .flatten()?;
+
+    // Only frames in the invoked entry module line up with the artifact's own source map.
+    remap_top_stack_frame(stack, "file:///plts/main-", &source_map)
+}
+
 #[cfg(any(test, feature = "v8_runtime"))]
 pub(crate) fn parse_js_error_details(details: &str) -> (String, Option<String>) {
     let trimmed = details.trim();
@@ -102,7 +280,8 @@ pub(crate) fn build_runtime_context(program: &FunctionProgram, args_payload: &Va
         "fn": {
             "oid": program.oid.to_u32(),
             "name": program.name,
-            "schema": program.schema
+            "schema": program.schema,
+            "args": program.args
         },
         "now": current_timestamp_text()
     })
@@ -112,61 +291,6 @@ fn current_timestamp_text() -> String {
     Spi::get_one::<String>("SELECT now()::text").ok().flatten().unwrap_or_default()
 }
 
-#[cfg(any(test, feature = "v8_runtime"))]
-const INLINE_IMPORT_MAP_MARKER: &str = "plts-import-map:";
-
-#[cfg(any(test, feature = "v8_runtime"))]
-fn parse_inline_import_map(source: &str) -> HashMap<String, String> {
-    let Some(marker_start) = source.find(INLINE_IMPORT_MAP_MARKER) else {
-        return HashMap::new();
-    };
-
-    let mut cursor = marker_start + INLINE_IMPORT_MAP_MARKER.len();
-    while source[cursor..].chars().next().is_some_and(char::is_whitespace) {
-        cursor += source[cursor..].chars().next().map(char::len_utf8).unwrap_or(0);
-    }
-
-    if source[cursor..].chars().next() != Some('{') {
-        return HashMap::new();
-    }
-
-    let mut depth = 0_i32;
-    let mut in_string = false;
-    let mut escaped = false;
-    let mut end = None;
-    for (offset, ch) in source[cursor..].char_indices() {
-        if in_string {
-            if escaped {
-                escaped = false;
-            } else if ch == '\\' {
-                escaped = true;
-            } else if ch == '"' {
-                in_string = false;
-            }
-            continue;
-        }
-
-        match ch {
-            '"' => in_string = true,
-            '{' => depth += 1,
-            '}' => {
-                depth -= 1;
-                if depth == 0 {
-                    end = Some(cursor + offset + ch.len_utf8());
-                    break;
-                }
-            }
-            _ => {}
-        }
-    }
-
-    let Some(end) = end else {
-        return HashMap::new();
-    };
-
-    serde_json::from_str::<HashMap<String, String>>(&source[cursor..end]).unwrap_or_default()
-}
-
 #[cfg(feature = "v8_runtime")]
 #[derive(Default)]
 struct PltsModuleLoaderState {
@@ -179,7 +303,18 @@ struct PltsModuleLoader {
     state: Rc<RefCell<PltsModuleLoaderState>>,
 }
 
-#[cfg(feature = "v8_runtime")]
+pub(crate) const STOPGAP_RUNTIME_SPECIFIER: &str = "@stopgap/runtime";
+pub(crate) const ALLOWED_IMPORT_SCHEMES: [&str; 2] = ["data", "plts+artifact"];
+
+/// Whether the module loader can resolve `specifier` imported from an entry module. Bare
+/// specifiers (including `@stopgap/runtime`) pass because import maps are only known at load.
+pub(crate) fn is_loadable_import_specifier(specifier: &str) -> bool {
+    is_bare_module_specifier(specifier)
+        || specifier
+            .split_once(':')
+            .is_some_and(|(scheme, _)| ALLOWED_IMPORT_SCHEMES.contains(&scheme))
+}
+
 fn is_bare_module_specifier(specifier: &str) -> bool {
     !specifier.starts_with("./")
         && !specifier.starts_with("../")
@@ -191,16 +326,10 @@ fn is_bare_module_specifier(specifier: &str) -> bool {
 fn resolve_inline_import_map_target(
     target: &str,
 ) -> Result<deno_core::ModuleSpecifier, deno_core::error::ModuleLoaderError> {
-    if let Ok(specifier) = deno_core::ModuleSpecifier::parse(target) {
+    if let Ok(specifier) = deno_core::ModuleSpecifier::parse(&import_map_target_specifier(target)) {
         return Ok(specifier);
     }
 
-    if target.starts_with("sha256:") {
-        let specifier = format!("plts+artifact:{target}");
-        return Ok(deno_core::ModuleSpecifier::parse(&specifier)
-            .map_err(deno_error::JsErrorBox::from_err)?);
-    }
-
     Err(deno_error::JsErrorBox::generic(format!(
         "invalid inline import map target `{target}`; expected absolute module specifier or artifact hash"
     ))
@@ -215,7 +344,7 @@ impl deno_core::ModuleLoader for PltsModuleLoader {
         referrer: &str,
         _kind: deno_core::ResolutionKind,
     ) -> Result<deno_core::ModuleSpecifier, deno_core::error::ModuleLoaderError> {
-        if specifier == "@stopgap/runtime" {
+        if specifier == STOPGAP_RUNTIME_SPECIFIER {
             return Ok(deno_core::ModuleSpecifier::parse("file:///plts/__stopgap_runtime__.js")
                 .map_err(deno_error::JsErrorBox::from_err)?);
         }
@@ -231,8 +360,20 @@ impl deno_core::ModuleLoader for PltsModuleLoader {
             .into());
         }
 
-        Ok(deno_core::resolve_import(specifier, referrer)
-            .map_err(deno_error::JsErrorBox::from_err)?)
+        let resolved = deno_core::resolve_import(specifier, referrer)
+            .map_err(deno_error::JsErrorBox::from_err)?;
+        // Artifact and data modules load unversioned so their code cache is reusable; their
+        // imports inherit the importer's invocation nonce here instead.
+        match invocation_nonce_from_specifier(referrer) {
+            Some(nonce) if invocation_nonce_from_specifier(resolved.as_str()).is_none() => {
+                Ok(deno_core::ModuleSpecifier::parse(&versioned_module_target(
+                    resolved.as_str(),
+                    nonce,
+                ))
+                .map_err(deno_error::JsErrorBox::from_err)?)
+            }
+            _ => Ok(resolved),
+        }
     }
 
     fn load(
@@ -244,6 +385,62 @@ impl deno_core::ModuleLoader for PltsModuleLoader {
     ) -> deno_core::ModuleLoadResponse {
         deno_core::ModuleLoadResponse::Sync(load_module_source(module_specifier))
     }
+
+    fn code_cache_ready(
+        &self,
+        module_specifier: deno_core::ModuleSpecifier,
+        hash: u64,
+        code_cache: &[u8],
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()>>> {
+        if let Ok(artifact_hash) = parse_artifact_module_hash(&module_specifier) {
+            let budget = module_cache_budget();
+            if let Ok(mut cache) = module_code_cache().lock() {
+                cache.insert(artifact_hash, hash, code_cache.to_vec(), budget);
+            }
+        }
+        Box::pin(async {})
+    }
+}
+
+#[cfg(feature = "v8_runtime")]
+static MODULE_CODE_CACHE: OnceLock<Mutex<ModuleCodeCache>> = OnceLock::new();
+
+#[cfg(feature = "v8_runtime")]
+fn module_code_cache() -> &'static Mutex<ModuleCodeCache> {
+    MODULE_CODE_CACHE.get_or_init(|| Mutex::new(ModuleCodeCache::default()))
+}
+
+#[cfg(feature = "v8_runtime")]
+fn module_cache_budget() -> ModuleCacheBudget {
+    ModuleCacheBudget {
+        max_entries: module_cache_max_entries(),
+        max_bytes: module_cache_max_bytes(),
+    }
+}
+
+/// Looks up V8 code cache for an artifact module by its unversioned source, so every invocation
+/// of the same artifact shares one entry. Returns `None` when the cache is disabled, which also
+/// keeps V8 from producing cache data for the module.
+#[cfg(feature = "v8_runtime")]
+fn artifact_code_cache_info(
+    artifact_hash: &str,
+    source: &str,
+) -> Option<deno_core::SourceCodeCacheInfo> {
+    use std::hash::{Hash, Hasher};
+
+    if module_cache_budget().is_disabled() {
+        return None;
+    }
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    source.hash(&mut hasher);
+    let hash = hasher.finish();
+    let data = module_code_cache()
+        .lock()
+        .ok()
+        .and_then(|mut cache| cache.get(artifact_hash, hash))
+        .map(std::borrow::Cow::Owned);
+    Some(deno_core::SourceCodeCacheInfo { hash, data })
 }
 
 #[cfg(feature = "v8_runtime")]
@@ -265,21 +462,16 @@ fn load_module_source(
                     module_specifier, artifact_hash
                 ))
             })?;
-            let source = invocation_nonce_from_specifier(module_specifier.as_str())
-                .map(|nonce| version_source_module_literals(source.as_str(), nonce))
-                .unwrap_or(source);
+            let code_cache = artifact_code_cache_info(&artifact_hash, &source);
             Ok(ModuleSource::new(
                 ModuleType::JavaScript,
                 ModuleSourceCode::String(source.into()),
                 module_specifier,
-                None,
+                code_cache,
             ))
         }
         "data" => {
             let source = decode_data_url_module_code(module_specifier)?;
-            let source = invocation_nonce_from_specifier(module_specifier.as_str())
-                .map(|nonce| version_source_module_literals(source.as_str(), nonce))
-                .unwrap_or(source);
             Ok(ModuleSource::new(
                 ModuleType::JavaScript,
                 ModuleSourceCode::String(source.into()),
@@ -287,8 +479,8 @@ fn load_module_source(
                 None,
             ))
         }
-        "file" if stripped_specifier == "file:///plts/__stopgap_runtime__.js" => Ok(
-            ModuleSource::new(
+        "file" if stripped_specifier == "file:///plts/__stopgap_runtime__.js" => {
+            Ok(ModuleSource::new(
                 ModuleType::JavaScript,
                 ModuleSourceCode::String(
                     include_str!("../../../packages/runtime/dist/embedded_runtime.js")
@@ -297,16 +489,74 @@ fn load_module_source(
                 ),
                 module_specifier,
                 None,
-            ),
-        ),
+            ))
+        }
         _ => Err(deno_error::JsErrorBox::generic(format!(
-            "unsupported module import `{}`; allowed imports are `data:`, `plts+artifact:<hash>`, and `@stopgap/runtime`",
-            module_specifier
+            "unsupported module import `{}`; {}",
+            module_specifier,
+            allowed_imports_hint()
         ))
         .into()),
     }
 }
 
+/// Reports how each static import of the function's module would resolve, applying the
+/// pointer and inline import maps the same way the module loader does.
+pub(crate) fn resolve_imports_json(program: &FunctionProgram) -> Value {
+    let mut import_map = program.bare_specifier_map.clone();
+    import_map.extend(parse_inline_import_map(&program.source));
+
+    static_import_specifiers(&program.source)
+        .into_iter()
+        .map(|specifier| match resolve_import_specifier(&specifier, &import_map) {
+            Ok((resolved, kind)) => {
+                json!({ "specifier": specifier, "resolved": resolved, "kind": kind })
+            }
+            Err(message) => json!({
+                "specifier": specifier,
+                "resolved": Value::Null,
+                "kind": "error",
+                "error": message
+            }),
+        })
+        .collect()
+}
+
+fn resolve_import_specifier(
+    specifier: &str,
+    import_map: &HashMap<String, String>,
+) -> Result<(String, &'static str), String> {
+    if specifier == STOPGAP_RUNTIME_SPECIFIER {
+        return Ok((specifier.to_string(), "stopgap_runtime"));
+    }
+
+    let resolved = if is_bare_module_specifier(specifier) {
+        let target = import_map.get(specifier).ok_or_else(|| {
+            format!("unsupported bare module import `{specifier}`; it has no import map entry")
+        })?;
+        import_map_target_specifier(target)
+    } else {
+        specifier.to_string()
+    };
+
+    match resolved.split_once(':').map(|(scheme, _)| scheme) {
+        Some("data") => Ok((resolved, "data")),
+        Some("plts+artifact") => Ok((resolved, "artifact")),
+        _ => Err(format!("unsupported module specifier `{resolved}`; {}", allowed_imports_hint())),
+    }
+}
+
+pub(crate) fn allowed_imports_hint() -> String {
+    let schemes = ALLOWED_IMPORT_SCHEMES
+        .iter()
+        .map(|scheme| format!("`{scheme}:`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "allowed imports are {schemes}, mapped bare specifiers, and `{STOPGAP_RUNTIME_SPECIFIER}`"
+    )
+}
+
 #[cfg(feature = "v8_runtime")]
 fn parse_artifact_module_hash(
     module_specifier: &deno_core::ModuleSpecifier,
@@ -486,13 +736,24 @@ struct RuntimeInterruptGuard {
     worker: Option<JoinHandle<()>>,
 }
 
+const MIN_INTERRUPT_POLL_MS: i32 = 1;
+const MAX_INTERRUPT_POLL_MS: i32 = 100;
+
+/// Clamps `plts.interrupt_poll_ms` to the range the watchdog supports.
+#[cfg_attr(not(any(test, feature = "v8_runtime")), allow(dead_code))]
+pub(crate) fn interrupt_poll_interval_ms(raw: i32) -> u64 {
+    raw.clamp(MIN_INTERRUPT_POLL_MS, MAX_INTERRUPT_POLL_MS) as u64
+}
+
+/// Watches for Postgres cancel/terminate signals and the optional runtime timeout while a
+/// handler runs, terminating the isolate when either fires. The watcher runs even without a
+/// timeout so `pg_cancel_backend` always stops a busy handler within one poll interval
+/// (`plts.interrupt_poll_ms`).
 #[cfg(feature = "v8_runtime")]
 impl RuntimeInterruptGuard {
-    fn with_statement_timeout(
-        runtime: &mut deno_core::JsRuntime,
-        timeout_ms: Option<u64>,
-    ) -> Option<Self> {
-        let timeout_ms = timeout_ms.filter(|value| *value > 0)?;
+    fn start(runtime: &mut deno_core::JsRuntime, timeout_ms: Option<u64>) -> Self {
+        let timeout = timeout_ms.filter(|value| *value > 0).map(Duration::from_millis);
+        let poll_interval = Duration::from_millis(interrupt_poll_interval_ms(interrupt_poll_ms()));
         let cancel = Arc::new(AtomicBool::new(false));
         let timed_out = Arc::new(AtomicBool::new(false));
         let interrupted = Arc::new(AtomicBool::new(false));
@@ -500,7 +761,6 @@ impl RuntimeInterruptGuard {
         let timed_out_worker = Arc::clone(&timed_out);
         let interrupted_worker = Arc::clone(&interrupted);
         let isolate_handle = runtime.v8_isolate().thread_safe_handle();
-        let timeout = Duration::from_millis(timeout_ms);
 
         let worker = thread::spawn(move || {
             let start = Instant::now();
@@ -515,17 +775,17 @@ impl RuntimeInterruptGuard {
                     return;
                 }
 
-                if start.elapsed() >= timeout {
+                if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
                     timed_out_worker.store(true, Ordering::Relaxed);
                     isolate_handle.terminate_execution();
                     return;
                 }
 
-                thread::sleep(Duration::from_millis(5));
+                thread::sleep(poll_interval);
             }
         });
 
-        Some(Self { cancel, timed_out, interrupted, worker: Some(worker) })
+        Self { cancel, timed_out, interrupted, worker: Some(worker) }
     }
 
     fn timed_out(&self) -> bool {
@@ -574,8 +834,23 @@ fn op_plts_db_query(
     #[string] sql: String,
     #[serde] params: Vec<serde_json::Value>,
     read_only: bool,
+    call_read_only: bool,
 ) -> Result<serde_json::Value, deno_error::JsErrorBox> {
-    query_json_rows_with_params(&sql, params, read_only).map_err(deno_error::JsErrorBox::generic)
+    timed_db_op(|| query_json_rows_with_params(&sql, params, read_only, call_read_only))
+        .map_err(deno_error::JsErrorBox::generic)
+}
+
+#[cfg(feature = "v8_runtime")]
+#[deno_core::op2]
+#[serde]
+fn op_plts_db_query_columnar(
+    #[string] sql: String,
+    #[serde] params: Vec<serde_json::Value>,
+    read_only: bool,
+    call_read_only: bool,
+) -> Result<serde_json::Value, deno_error::JsErrorBox> {
+    timed_db_op(|| query_json_columns_with_params(&sql, params, read_only, call_read_only))
+        .map_err(deno_error::JsErrorBox::generic)
 }
 
 #[cfg(feature = "v8_runtime")]
@@ -585,12 +860,89 @@ fn op_plts_db_exec(
     #[string] sql: String,
     #[serde] params: Vec<serde_json::Value>,
     read_only: bool,
+    call_read_only: bool,
+) -> Result<serde_json::Value, deno_error::JsErrorBox> {
+    timed_db_op(|| exec_sql_with_params(&sql, params, read_only, call_read_only))
+        .map_err(deno_error::JsErrorBox::generic)
+}
+
+#[cfg(feature = "v8_runtime")]
+#[deno_core::op2]
+#[serde]
+fn op_plts_db_batch(
+    #[serde] statements: Vec<DbBatchStatement>,
+    read_only: bool,
 ) -> Result<serde_json::Value, deno_error::JsErrorBox> {
-    exec_sql_with_params(&sql, params, read_only).map_err(deno_error::JsErrorBox::generic)
+    timed_db_op(|| exec_sql_batch(statements, read_only)).map_err(deno_error::JsErrorBox::generic)
+}
+
+#[cfg(feature = "v8_runtime")]
+#[deno_core::op2]
+fn op_plts_log(#[string] level: String, #[string] message: String) {
+    match level.as_str() {
+        "debug" => debug1!("{message}"),
+        "log" => log!("{message}"),
+        "warn" | "error" => warning!("{message}"),
+        _ => info!("{message}"),
+    }
 }
 
 #[cfg(feature = "v8_runtime")]
-deno_core::extension!(plts_runtime_ext, ops = [op_plts_db_query, op_plts_db_exec]);
+#[deno_core::op2]
+fn op_plts_record_stopgap_error(#[serde] thrown: Option<ThrownStopgapError>) {
+    THROWN_STOPGAP_ERROR.with(|cell| *cell.borrow_mut() = thrown);
+}
+
+#[cfg(feature = "v8_runtime")]
+#[deno_core::op2(fast)]
+fn op_plts_row_sink_active() -> bool {
+    ROW_SINK.with(|cell| cell.borrow().is_some())
+}
+
+#[cfg(feature = "v8_runtime")]
+#[deno_core::op2]
+fn op_plts_emit_row(#[serde] row: serde_json::Value) -> Result<(), deno_error::JsErrorBox> {
+    ROW_SINK
+        .with(|cell| match cell.borrow_mut().as_mut() {
+            Some(sink) => sink(row),
+            None => Err("no set-returning call is collecting rows".to_string()),
+        })
+        .map_err(deno_error::JsErrorBox::generic)
+}
+
+#[cfg(feature = "v8_runtime")]
+#[deno_core::op2]
+#[serde]
+fn op_plts_describe(
+    #[string] schema: String,
+    #[string] name: String,
+) -> Result<serde_json::Value, deno_error::JsErrorBox> {
+    let fn_oid =
+        resolve_plts_function_oid(&schema, &name).map_err(deno_error::JsErrorBox::generic)?;
+    let program = load_function_program(fn_oid).ok_or_else(|| {
+        deno_error::JsErrorBox::generic(format!("ctx.describe failed to load {schema}.{name}"))
+    })?;
+    let metadata = describe_program(&program).map_err(|err| {
+        deno_error::JsErrorBox::generic(format!("ctx.describe {schema}.{name}: {err}"))
+    })?;
+    serde_json::to_value(metadata).map_err(|err| deno_error::JsErrorBox::generic(err.to_string()))
+}
+
+#[cfg(feature = "v8_runtime")]
+deno_core::extension!(
+    plts_runtime_ext,
+    ops = [
+        op_plts_db_query,
+        op_plts_db_query_columnar,
+        op_plts_db_exec,
+        op_plts_db_batch,
+        op_plts_log,
+        op_plts_record_stopgap_error,
+        op_plts_row_sink_active,
+        op_plts_emit_row,
+        op_plts_describe
+    ]
+);
 
 #[cfg(any(test, feature = "v8_runtime"))]
 const STATIC_BOOTSTRAP_RUNTIME_LOCKDOWN_SCRIPT_NAME: &str = "plts_runtime_lockdown.js";
@@ -608,6 +960,7 @@ fn build_dynamic_context_setup_script(
     context_json: &str,
     db_mode_js: &str,
     db_read_only_js: bool,
+    console_enabled: bool,
 ) -> Result<String, RuntimeExecError> {
     let encoded_context = serde_json::to_string(context_json).map_err(|e| {
         RuntimeExecError::new(
@@ -621,14 +974,59 @@ fn build_dynamic_context_setup_script(
         "globalThis.__plts_ctx = JSON.parse({});\
          globalThis.__plts_ctx.db = {{\
            mode: '{}',\
-           query(input, params) {{\
-             return globalThis.__plts_internal_ops.dbQuery(input, params, {}, arguments.length > 1);\
+           query(input, params, options) {{\
+             return globalThis.__plts_internal_ops.dbQuery(input, params, {}, arguments.length > 1, options);\
+           }},\
+           queryRow(input, params, options) {{\
+             return globalThis.__plts_internal_ops.dbQueryRow(input, params, {}, arguments.length > 1, options);\
+           }},\
+           queryValue(input, params, options) {{\
+             return globalThis.__plts_internal_ops.dbQueryValue(input, params, {}, arguments.length > 1, options);\
            }},\
-           exec(input, params) {{\
-             return globalThis.__plts_internal_ops.dbExec(input, params, {}, arguments.length > 1);\
+           queryColumnar(input, params, options) {{\
+             return globalThis.__plts_internal_ops.dbQueryColumnar(input, params, {}, arguments.length > 1, options);\
+           }},\
+           exec(input, params, options) {{\
+             return globalThis.__plts_internal_ops.dbExec(input, params, {}, arguments.length > 1, options);\
+           }},\
+           batch(statements) {{\
+             return globalThis.__plts_internal_ops.dbBatch(statements, {});\
            }}\
-          }};",
-        encoded_context, db_mode_js, db_read_only_js, db_read_only_js
+          }};\
+         globalThis.__plts_ctx.memo = (() => {{\
+           const cache = new Map();\
+           return (key, factory) => {{\
+             if (typeof factory !== 'function') {{\
+               throw new TypeError('ctx.memo expects a factory function');\
+             }}\
+             if (cache.has(key)) {{\
+               return cache.get(key);\
+             }}\
+             const value = factory();\
+             cache.set(key, value);\
+             return value;\
+           }};\
+         }})();\
+         globalThis.__plts_ctx.log = {{\
+           info(message) {{\
+             globalThis.__plts_internal_ops.log('info', message);\
+           }},\
+           warn(message) {{\
+             globalThis.__plts_internal_ops.log('warn', message);\
+           }}\
+         }};\
+         globalThis.__plts_ctx.describe = (schema, name) =>\
+           globalThis.__plts_internal_ops.describe(schema, name);\
+         globalThis.__plts_internal_ops.installConsole({});",
+        encoded_context,
+        db_mode_js,
+        db_read_only_js,
+        db_read_only_js,
+        db_read_only_js,
+        db_read_only_js,
+        db_read_only_js,
+        db_read_only_js,
+        console_enabled
     ))
 }
 
@@ -694,6 +1092,13 @@ struct RuntimeShell {
     invocation_nonce: u64,
 }
 
+#[cfg(feature = "v8_runtime")]
+impl Drop for RuntimeShell {
+    fn drop(&mut self) {
+        LIVE_RUNTIME_SHELLS.with(|live| live.set(live.get().saturating_sub(1)));
+    }
+}
+
 #[cfg(feature = "v8_runtime")]
 struct RuntimeShellGuard {
     checked_out: Option<CheckedOut<RuntimeShell>>,
@@ -720,6 +1125,9 @@ impl RuntimeShellGuard {
 
     fn set_heap_pressure(&mut self) {
         self.health.heap_pressure = true;
+        if let Some(checked_out) = self.checked_out.as_mut() {
+            checked_out.mark_heap_pressure();
+        }
     }
 
     fn set_cleanup_failed(&mut self) {
@@ -742,16 +1150,73 @@ impl RuntimeShellGuard {
 #[cfg(feature = "v8_runtime")]
 thread_local! {
     static RUNTIME_POOL: RefCell<IsolatePool<RuntimeShell>> = RefCell::new(IsolatePool::new());
+    static LIVE_RUNTIME_SHELLS: Cell<usize> = const { Cell::new(0) };
+}
+
+#[cfg(feature = "v8_runtime")]
+pub(crate) fn pool_metrics_json() -> Value {
+    let config = current_runtime_pool_config();
+    let available_count = RUNTIME_POOL.with(|pool| pool.borrow().available_count());
+    let mut metrics = runtime_pool_counters_json();
+    metrics["active_isolates"] = json!(LIVE_RUNTIME_SHELLS.with(Cell::get));
+    metrics["available_count"] = json!(available_count);
+    metrics["config"] = json!({
+        "enable_reuse": config.enable_reuse,
+        "max_pool_size": config.max_pool_size,
+        "max_age_seconds": config.max_age_seconds,
+        "max_invocations": config.max_invocations
+    });
+    metrics
+}
+
+#[cfg(not(feature = "v8_runtime"))]
+pub(crate) fn pool_metrics_json() -> Value {
+    let mut metrics = runtime_pool_counters_json();
+    metrics["active_isolates"] = json!(0);
+    metrics["available_count"] = json!(0);
+    metrics
+}
+
+#[cfg(feature = "v8_runtime")]
+pub(crate) fn isolate_metrics_json() -> Value {
+    let module_cache = module_code_cache().lock().map(|cache| cache.stats()).unwrap_or_default();
+    let budget = module_cache_budget();
+    json!({
+        "module_cache": {
+            "entries": module_cache.entries,
+            "bytes": module_cache.bytes,
+            "hits": module_cache.hits,
+            "misses": module_cache.misses,
+            "evictions": module_cache.evictions,
+            "max_entries": budget.max_entries,
+            "max_bytes": budget.max_bytes
+        }
+    })
+}
+
+#[cfg(not(feature = "v8_runtime"))]
+pub(crate) fn isolate_metrics_json() -> Value {
+    json!({
+        "module_cache": {
+            "entries": 0,
+            "bytes": 0,
+            "hits": 0,
+            "misses": 0,
+            "evictions": 0,
+            "max_entries": crate::module_cache_max_entries(),
+            "max_bytes": crate::module_cache_max_bytes()
+        }
+    })
 }
 
 #[cfg(feature = "v8_runtime")]
 fn current_runtime_pool_config() -> IsolatePoolConfig {
-    IsolatePoolConfig {
-        enable_reuse: isolate_reuse_enabled(),
-        max_pool_size: isolate_pool_size(),
-        max_age_seconds: isolate_max_age_seconds(),
-        max_invocations: isolate_max_invocations(),
-    }
+    IsolatePoolConfig::from_guc_values(
+        isolate_reuse_enabled(),
+        isolate_pool_size(),
+        isolate_max_age_seconds(),
+        isolate_max_invocations(),
+    )
 }
 
 #[cfg(feature = "v8_runtime")]
@@ -775,7 +1240,9 @@ fn elapsed_us(started_at: Instant) -> u64 {
 }
 
 #[cfg(feature = "v8_runtime")]
-fn checkout_runtime_shell() -> Result<RuntimeShellGuard, RuntimeExecError> {
+fn checkout_runtime_shell(
+    heap_limit_setting: Option<String>,
+) -> Result<RuntimeShellGuard, RuntimeExecError> {
     let config = current_runtime_pool_config();
     let started_at = Instant::now();
 
@@ -792,7 +1259,7 @@ fn checkout_runtime_shell() -> Result<RuntimeShellGuard, RuntimeExecError> {
                 record_runtime_warm_shell_reuse();
             }
             let mut guard = RuntimeShellGuard::from_checked_out(checked_out);
-            if guard.shell_mut().heap_limit_setting != current_plts_max_heap_setting() {
+            if guard.shell_mut().heap_limit_setting != heap_limit_setting {
                 guard.set_config_changed();
                 let health = guard.health();
                 let checked_out = guard.into_checked_out();
@@ -807,7 +1274,7 @@ fn checkout_runtime_shell() -> Result<RuntimeShellGuard, RuntimeExecError> {
         }
 
         record_runtime_cold_shell_create();
-        return build_runtime_shell().map(RuntimeShellGuard::new);
+        return build_runtime_shell(heap_limit_setting).map(RuntimeShellGuard::new);
     }
 }
 
@@ -822,10 +1289,9 @@ fn checkin_runtime_shell(checked_out: CheckedOut<RuntimeShell>, health: ShellHea
 }
 
 #[cfg(feature = "v8_runtime")]
-fn build_runtime_shell() -> Result<RuntimeShell, RuntimeExecError> {
+fn build_runtime_shell(max_heap_setting: Option<String>) -> Result<RuntimeShell, RuntimeExecError> {
     use deno_core::{JsRuntime, RuntimeOptions, v8};
 
-    let max_heap_setting = current_plts_max_heap_setting();
     let max_heap_bytes = max_heap_setting.as_deref().and_then(parse_runtime_heap_limit_bytes);
     let startup_snapshot = runtime_startup_snapshot();
     let loader_state = Rc::new(RefCell::new(PltsModuleLoaderState::default()));
@@ -870,6 +1336,7 @@ fn build_runtime_shell() -> Result<RuntimeShell, RuntimeExecError> {
         )
     })?;
 
+    LIVE_RUNTIME_SHELLS.with(|live| live.set(live.get() + 1));
     Ok(RuntimeShell {
         runtime,
         loader_state,
@@ -1047,13 +1514,281 @@ fn strip_invocation_suffix(specifier: &str) -> &str {
         .unwrap_or(specifier)
 }
 
-#[cfg(feature = "v8_runtime")]
+pub(crate) type BatchInvocationResult = Result<Option<Value>, RuntimeExecError>;
+
+pub(crate) fn batch_result_json(result: &BatchInvocationResult) -> Value {
+    match result {
+        Ok(value) => json!({ "ok": true, "value": value }),
+        Err(err) => json!({
+            "ok": false,
+            "error": { "stage": err.stage, "message": err.message }
+        }),
+    }
+}
+
 pub(crate) fn execute_program(
     source: &str,
     entrypoint_export: &str,
     pointer_import_map: &HashMap<String, String>,
+    limits: &ProgramLimits,
     context: &Value,
 ) -> Result<Option<Value>, RuntimeExecError> {
+    execute_program_batch(
+        source,
+        entrypoint_export,
+        pointer_import_map,
+        limits,
+        std::slice::from_ref(context),
+        true,
+        None,
+    )?
+    .pop()
+    .unwrap_or_else(|| {
+        Err(RuntimeExecError::new("entrypoint invocation", "runtime produced no result"))
+    })
+}
+
+/// Wall-clock and `ctx.db` timings for one handler invocation, reported through the
+/// `plts.include_timing` result envelope.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ExecutionTiming {
+    total_us: u64,
+    db_us: u64,
+    db_calls: u64,
+}
+
+impl ExecutionTiming {
+    pub(crate) fn envelope(self, result: Option<Value>) -> Value {
+        json!({
+            "@plts": 1,
+            "result": result,
+            "timing": {
+                "total_ms": self.total_us as f64 / 1000.0,
+                "db_ms": self.db_us as f64 / 1000.0,
+                "db_calls": self.db_calls
+            }
+        })
+    }
+}
+
+thread_local! {
+    // (calls, elapsed microseconds) of `ctx.db` ops for the innermost timed invocation.
+    static DB_OP_TIMING: std::cell::Cell<(u64, u64)> = const { std::cell::Cell::new((0, 0)) };
+}
+
+/// Handler metadata set by the `@stopgap/runtime` wrappers on the entrypoint; both fields are
+/// `None` for plain `LANGUAGE plts` handlers.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct HandlerMetadata {
+    pub(crate) kind: Option<String>,
+    pub(crate) args_schema: Option<Value>,
+}
+
+#[cfg(feature = "v8_runtime")]
+const MAX_RUNTIME_CALL_DEPTH: usize = 16;
+
+#[cfg(feature = "v8_runtime")]
+thread_local! {
+    // Nesting depth of program runs on this backend: handler calls made through `ctx.db` and
+    // `ctx.describe` each run in their own shell.
+    static RUNTIME_CALL_DEPTH: Cell<usize> = const { Cell::new(0) };
+    // Set while `describe_program` evaluates a module so its top-level code cannot reach the
+    // database; describing may run under `stopgap.deploy`'s definer role.
+    static DESCRIBE_ACTIVE: Cell<bool> = const { Cell::new(false) };
+    // The `StopgapError` that ended the current invocation, if that is what ended it.
+    static THROWN_STOPGAP_ERROR: std::cell::RefCell<Option<ThrownStopgapError>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+#[cfg(feature = "v8_runtime")]
+struct RuntimeCallDepthGuard;
+
+#[cfg(feature = "v8_runtime")]
+impl RuntimeCallDepthGuard {
+    fn enter() -> Result<Self, RuntimeExecError> {
+        let depth = RUNTIME_CALL_DEPTH.with(Cell::get);
+        if depth >= MAX_RUNTIME_CALL_DEPTH {
+            return Err(RuntimeExecError::new(
+                "call depth",
+                format!("nested plts calls exceeded the maximum depth of {MAX_RUNTIME_CALL_DEPTH}"),
+            ));
+        }
+
+        RUNTIME_CALL_DEPTH.with(|cell| cell.set(depth + 1));
+        Ok(Self)
+    }
+}
+
+#[cfg(feature = "v8_runtime")]
+impl Drop for RuntimeCallDepthGuard {
+    fn drop(&mut self) {
+        RUNTIME_CALL_DEPTH.with(|cell| cell.set(cell.get().saturating_sub(1)));
+    }
+}
+
+#[cfg(feature = "v8_runtime")]
+struct DescribeScope {
+    previous: bool,
+}
+
+#[cfg(feature = "v8_runtime")]
+impl DescribeScope {
+    fn enter() -> Self {
+        Self { previous: DESCRIBE_ACTIVE.with(|cell| cell.replace(true)) }
+    }
+}
+
+#[cfg(feature = "v8_runtime")]
+impl Drop for DescribeScope {
+    fn drop(&mut self) {
+        DESCRIBE_ACTIVE.with(|cell| cell.set(self.previous));
+    }
+}
+
+pub(crate) type RowSink = Box<dyn FnMut(Value) -> Result<(), String>>;
+
+thread_local! {
+    // Receives rows yielded by a generator handler of the innermost set-returning call.
+    static ROW_SINK: std::cell::RefCell<Option<RowSink>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Routes rows yielded by a generator handler to `sink` until dropped. `execute_program_batch`
+/// installs one for every invocation (with no sink unless the caller is a set-returning call), so
+/// a nested call never writes into an outer call's result set.
+struct RowSinkScope {
+    previous: Option<RowSink>,
+}
+
+impl RowSinkScope {
+    fn install(sink: Option<RowSink>) -> Self {
+        Self { previous: ROW_SINK.with(|cell| cell.replace(sink)) }
+    }
+}
+
+impl Drop for RowSinkScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        ROW_SINK.with(|cell| *cell.borrow_mut() = previous);
+    }
+}
+
+/// Runs one handler invocation while collecting `ExecutionTiming`, sending generator rows to
+/// `row_sink` for set-returning calls. Nested handler calls made through `ctx.db` get their own
+/// counters; the outer invocation still counts the enclosing db op.
+pub(crate) fn execute_program_timed(
+    source: &str,
+    entrypoint_export: &str,
+    pointer_import_map: &HashMap<String, String>,
+    limits: &ProgramLimits,
+    context: &Value,
+    row_sink: Option<RowSink>,
+) -> (Result<Option<Value>, RuntimeExecError>, ExecutionTiming) {
+    let outer = DB_OP_TIMING.with(|cell| cell.replace((0, 0)));
+    let started_at = std::time::Instant::now();
+    let result = execute_program_batch(
+        source,
+        entrypoint_export,
+        pointer_import_map,
+        limits,
+        std::slice::from_ref(context),
+        true,
+        row_sink,
+    )
+    .and_then(|mut results| {
+        results.pop().unwrap_or_else(|| {
+            Err(RuntimeExecError::new("entrypoint invocation", "runtime produced no result"))
+        })
+    });
+    let total_us = started_at.elapsed().as_micros().min(u128::from(u64::MAX)) as u64;
+    let (db_calls, db_us) = DB_OP_TIMING.with(|cell| cell.replace(outer));
+    (result, ExecutionTiming { total_us, db_us, db_calls })
+}
+
+#[cfg(feature = "v8_runtime")]
+fn timed_db_op(op: impl FnOnce() -> Result<Value, String>) -> Result<Value, String> {
+    if DESCRIBE_ACTIVE.with(Cell::get) {
+        return Err("db access is not allowed while a module is evaluated for describe".to_string());
+    }
+
+    let started_at = Instant::now();
+    let result = op();
+    let elapsed = elapsed_us(started_at);
+    DB_OP_TIMING.with(|cell| {
+        let (calls, micros) = cell.get();
+        cell.set((calls.saturating_add(1), micros.saturating_add(elapsed)));
+    });
+    result
+}
+
+/// Invokes the handler once per context. Generator rows go to `row_sink`; with `None` a nested
+/// generator sees no active sink even when an outer set-returning call is still collecting.
+pub(crate) fn execute_program_batch(
+    source: &str,
+    entrypoint_export: &str,
+    pointer_import_map: &HashMap<String, String>,
+    limits: &ProgramLimits,
+    contexts: &[Value],
+    stop_on_error: bool,
+    row_sink: Option<RowSink>,
+) -> Result<Vec<BatchInvocationResult>, RuntimeExecError> {
+    let _row_sink = RowSinkScope::install(row_sink);
+    run_program_batch(
+        source,
+        entrypoint_export,
+        pointer_import_map,
+        limits,
+        contexts,
+        stop_on_error,
+    )
+}
+
+#[cfg(feature = "v8_runtime")]
+fn run_program_batch(
+    source: &str,
+    entrypoint_export: &str,
+    pointer_import_map: &HashMap<String, String>,
+    limits: &ProgramLimits,
+    contexts: &[Value],
+    stop_on_error: bool,
+) -> Result<Vec<BatchInvocationResult>, RuntimeExecError> {
+    run_program(source, entrypoint_export, pointer_import_map, limits, contexts, stop_on_error)
+        .map(|(_, results)| results)
+}
+
+/// Evaluates the function's module and reads its handler metadata without invoking the handler.
+#[cfg(feature = "v8_runtime")]
+pub(crate) fn describe_program(
+    program: &FunctionProgram,
+) -> Result<HandlerMetadata, RuntimeExecError> {
+    let _describe = DescribeScope::enter();
+    let _row_sink = RowSinkScope::install(None);
+    run_program(
+        &program.source,
+        &program.entrypoint_export,
+        &program.bare_specifier_map,
+        &program.limits,
+        &[],
+        true,
+    )
+    .map(|(metadata, _)| metadata)
+}
+
+#[cfg(not(feature = "v8_runtime"))]
+pub(crate) fn describe_program(
+    _program: &FunctionProgram,
+) -> Result<HandlerMetadata, RuntimeExecError> {
+    Err(RuntimeExecError::new("runtime bootstrap", "v8_runtime feature is disabled"))
+}
+
+#[cfg(feature = "v8_runtime")]
+fn run_program(
+    source: &str,
+    entrypoint_export: &str,
+    pointer_import_map: &HashMap<String, String>,
+    limits: &ProgramLimits,
+    contexts: &[Value],
+    stop_on_error: bool,
+) -> Result<(HandlerMetadata, Vec<BatchInvocationResult>), RuntimeExecError> {
     use deno_core::{ModuleSpecifier, PollEventLoopOptions, serde_v8, v8};
 
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -1075,7 +1810,10 @@ pub(crate) fn execute_program(
         }
     }
 
-    let mut shell_guard = checkout_runtime_shell()?;
+    let _call_depth = RuntimeCallDepthGuard::enter()?;
+    let heap_limit_setting =
+        limits.max_heap_mb.map(|mb| mb.to_string()).or_else(current_plts_max_heap_setting);
+    let mut shell_guard = checkout_runtime_shell(heap_limit_setting)?;
     let shell = shell_guard.shell_mut();
     shell.heap_limit_reached.store(false, Ordering::Relaxed);
     shell.invocation_nonce = shell.invocation_nonce.saturating_add(1);
@@ -1093,10 +1831,10 @@ pub(crate) fn execute_program(
     shell.loader_state.borrow_mut().bare_specifier_map = bare_specifier_map;
 
     let statement_timeout_ms = current_statement_timeout_ms();
-    let max_runtime_ms = current_plts_max_runtime_ms();
+    let max_runtime_ms = limits.max_runtime_ms.or_else(current_plts_max_runtime_ms);
+    let _query_rows_override = QueryRowsOverride::set(limits.max_query_rows);
     let effective_timeout_ms = resolve_runtime_timeout_ms(statement_timeout_ms, max_runtime_ms);
-    let interrupt_guard =
-        RuntimeInterruptGuard::with_statement_timeout(&mut shell.runtime, effective_timeout_ms);
+    let interrupt_guard = RuntimeInterruptGuard::start(&mut shell.runtime, effective_timeout_ms);
     let heap_limit_setting = shell.heap_limit_setting.clone();
     let heap_limit_reached = Arc::clone(&shell.heap_limit_reached);
     let setup_started_at = Instant::now();
@@ -1107,29 +1845,32 @@ pub(crate) fn execute_program(
         let map_runtime_error = |stage: &'static str, details: &str| {
             if heap_limit_reached.load(Ordering::Relaxed) {
                 let configured_limit = heap_limit_setting.as_deref().unwrap_or("unknown");
-                RuntimeExecError::new(
+                RuntimeExecError::terminated(
                     "memory limit",
                     format!(
                         "execution exceeded configured runtime memory limit (plts.max_heap_mb={}) while in stage `{}`",
                         configured_limit, stage
                     ),
+                    TerminationReason::Memory,
                 )
-            } else if interrupt_guard.as_ref().is_some_and(RuntimeInterruptGuard::timed_out) {
+            } else if interrupt_guard.timed_out() {
                 let configured_ms = effective_timeout_ms.unwrap_or_default();
-                RuntimeExecError::new(
+                RuntimeExecError::terminated(
                     "statement timeout",
                     format!(
                         "execution exceeded configured runtime timeout ({}ms) while in stage `{}`",
                         configured_ms, stage
                     ),
+                    TerminationReason::Timeout,
                 )
-            } else if interrupt_guard.as_ref().is_some_and(RuntimeInterruptGuard::interrupted) {
-                RuntimeExecError::new(
+            } else if interrupt_guard.interrupted() {
+                RuntimeExecError::terminated(
                     "postgres interrupt",
                     format!(
                         "execution interrupted by pending PostgreSQL cancel signal while in stage `{}`",
                         stage
                     ),
+                    TerminationReason::Interrupt,
                 )
             } else {
                 format_js_error(stage, details)
@@ -1202,93 +1943,148 @@ pub(crate) fn execute_program(
             }
         }
 
-        let db_mode = {
-            let handler_kind_value = runtime
+        let metadata = {
+            let metadata_value = runtime
                 .execute_script(
-                    "plts_handler_kind.js",
+                    "plts_handler_metadata.js",
                     r#"
                     (() => {
-                        const kind = globalThis.__plts_entrypoint?.__stopgap_kind;
-                        return typeof kind === "string" ? kind : null;
+                        const entrypoint = globalThis.__plts_entrypoint;
+                        const kind = entrypoint?.__stopgap_kind;
+                        return {
+                            kind: typeof kind === "string" ? kind : null,
+                            args_schema: JSON.parse(
+                                JSON.stringify(entrypoint?.__stopgap_args_schema ?? null)
+                            ),
+                        };
                     })();
                     "#,
                 )
                 .map_err(|e| map_runtime_error("handler metadata", &e.to_string()))?;
 
             let scope = &mut runtime.handle_scope();
-            let local = v8::Local::new(scope, handler_kind_value);
-            let handler_kind = serde_v8::from_v8::<Option<String>>(scope, local).map_err(|e| {
+            let local = v8::Local::new(scope, metadata_value);
+            serde_v8::from_v8::<HandlerMetadata>(scope, local).map_err(|e| {
                 RuntimeExecError::new(
                     "handler metadata",
-                    format!("failed to decode stopgap handler kind: {e}"),
+                    format!("failed to decode stopgap handler metadata: {e}"),
                 )
-            })?;
+            })?
+        };
+        let db_mode = match metadata.kind.as_deref() {
+            Some("query") => DbAccessMode::ReadOnly,
+            _ => DbAccessMode::ReadWrite,
+        };
 
-            match handler_kind.as_deref() {
-                Some("query") => DbAccessMode::ReadOnly,
-                _ => DbAccessMode::ReadWrite,
-            }
+        let terminated = || {
+            heap_limit_reached.load(Ordering::Relaxed)
+                || interrupt_guard.timed_out()
+                || interrupt_guard.interrupted()
         };
 
-        let context_setup_started_at = Instant::now();
-        let context_setup_result = (|| {
-            let context_json = serde_json::to_string(context).map_err(|e| {
-                RuntimeExecError::new(
-                    "context serialize",
-                    format!("failed to serialize runtime context: {e}"),
-                )
-            })?;
+        let mut results = Vec::with_capacity(contexts.len());
+        for (index, context) in contexts.iter().enumerate() {
+            let context_setup_started_at = Instant::now();
+            let context_setup_result = (|| {
+                let context_json = serde_json::to_string(context).map_err(|e| {
+                    RuntimeExecError::new(
+                        "context serialize",
+                        format!("failed to serialize runtime context: {e}"),
+                    )
+                })?;
 
-            let set_ctx_script = build_dynamic_context_setup_script(
-                &context_json,
-                db_mode.as_js_mode(),
-                db_mode.is_read_only(),
-            )?;
-
-            runtime
-                .execute_script("plts_ctx.js", set_ctx_script)
-                .map_err(|e| map_runtime_error("context setup", &e.to_string()))?;
-
-            Ok::<(), RuntimeExecError>(())
-        })();
-        record_runtime_context_setup(elapsed_us(context_setup_started_at));
-        record_runtime_setup_realm(elapsed_us(setup_started_at));
-        context_setup_result?;
-
-        let invoke_script = r#"
-            if (typeof globalThis.__plts_entrypoint !== "function") {
-                throw new Error("configured module export must be a function");
+                let set_ctx_script = build_dynamic_context_setup_script(
+                    &context_json,
+                    db_mode.as_js_mode(),
+                    db_mode.is_read_only(),
+                    console_enabled(),
+                )?;
+
+                runtime
+                    .execute_script("plts_ctx.js", set_ctx_script)
+                    .map_err(|e| map_runtime_error("context setup", &e.to_string()))?;
+
+                Ok::<(), RuntimeExecError>(())
+            })();
+            record_runtime_context_setup(elapsed_us(context_setup_started_at));
+            if index == 0 {
+                record_runtime_setup_realm(elapsed_us(setup_started_at));
             }
-            globalThis.__plts_entrypoint(globalThis.__plts_ctx);
-        "#;
 
-        let value = runtime
-            .execute_script("plts_invoke.js", invoke_script)
-            .map_err(|e| map_runtime_error("entrypoint invocation", &e.to_string()))?;
+            let result = context_setup_result.and_then(|()| {
+                let invoke_script = r#"
+                    if (typeof globalThis.__plts_entrypoint !== "function") {
+                        throw new Error("configured module export must be a function");
+                    }
+                    (() => {
+                        const ops = globalThis.__plts_internal_ops;
+                        const fail = (err) => {
+                            ops.recordError(err);
+                            throw err;
+                        };
+                        let result;
+                        try {
+                            result = globalThis.__plts_entrypoint(globalThis.__plts_ctx);
+                            if (ops.isRowStream(result)) {
+                                result = ops.drainRows(result);
+                            } else if (typeof result?.then === "function") {
+                                result = result.then(ops.normalizeResult);
+                            } else {
+                                return ops.normalizeResult(result);
+                            }
+                        } catch (err) {
+                            fail(err);
+                        }
+                        return typeof result?.then === "function"
+                            ? result.then(undefined, fail)
+                            : result;
+                    })();
+                "#;
+
+                THROWN_STOPGAP_ERROR.with(|cell| *cell.borrow_mut() = None);
+                let take_thrown = || THROWN_STOPGAP_ERROR.with(|cell| cell.borrow_mut().take());
+                let value =
+                    runtime.execute_script("plts_invoke.js", invoke_script).map_err(|e| {
+                        map_runtime_error("entrypoint invocation", &e.to_string())
+                            .with_stopgap(take_thrown())
+                    })?;
+
+                #[allow(deprecated)]
+                let value = deno_core::futures::executor::block_on(runtime.resolve_value(value))
+                    .map_err(|e| {
+                        map_runtime_error("entrypoint await", &e.to_string())
+                            .with_stopgap(take_thrown())
+                    })?;
+
+                let scope = &mut runtime.handle_scope();
+                let local = v8::Local::new(scope, value);
+                if local.is_null_or_undefined() {
+                    return Ok(None);
+                }
 
-        #[allow(deprecated)]
-        let value = deno_core::futures::executor::block_on(runtime.resolve_value(value))
-            .map_err(|e| map_runtime_error("entrypoint await", &e.to_string()))?;
+                let value = serde_v8::from_v8::<Value>(scope, local).map_err(|e| {
+                    RuntimeExecError::new(
+                        "result decode",
+                        format!("failed to decode JS result value: {e}"),
+                    )
+                })?;
 
-        let scope = &mut runtime.handle_scope();
-        let local = v8::Local::new(scope, value);
-        if local.is_null_or_undefined() {
-            return Ok(None);
-        }
+                if value.is_null() { Ok(None) } else { Ok(Some(value)) }
+            });
 
-        let value = serde_v8::from_v8::<Value>(scope, local).map_err(|e| {
-            RuntimeExecError::new("result decode", format!("failed to decode JS result value: {e}"))
-        })?;
+            match result {
+                Err(err) if stop_on_error || terminated() => return Err(err),
+                result => results.push(result),
+            }
+        }
 
-        if value.is_null() { Ok(None) } else { Ok(Some(value)) }
+        Ok((metadata, results))
     })();
 
     if shell.heap_limit_reached.load(Ordering::Relaxed) {
         shell_guard.set_heap_pressure();
         shell_guard.set_terminated();
-    } else if interrupt_guard.as_ref().is_some_and(RuntimeInterruptGuard::timed_out)
-        || interrupt_guard.as_ref().is_some_and(RuntimeInterruptGuard::interrupted)
-    {
+    } else if interrupt_guard.timed_out() || interrupt_guard.interrupted() {
         shell_guard.set_terminated();
     }
 
@@ -1308,12 +2104,14 @@ pub(crate) fn execute_program(
 }
 
 #[cfg(not(feature = "v8_runtime"))]
-pub(crate) fn execute_program(
+fn run_program_batch(
     _source: &str,
     _entrypoint_export: &str,
     _pointer_import_map: &HashMap<String, String>,
-    _context: &Value,
-) -> Result<Option<Value>, RuntimeExecError> {
+    _limits: &ProgramLimits,
+    _contexts: &[Value],
+    _stop_on_error: bool,
+) -> Result<Vec<BatchInvocationResult>, RuntimeExecError> {
     Err(RuntimeExecError::new("runtime bootstrap", "v8_runtime feature is disabled"))
 }
 
@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use serde_json::Value;
+use serde_json::json;
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -6,11 +7,29 @@ pub(crate) struct RuntimeExecError {
     stage: &'static str,
     message: String,
     stack: Option<String>,
+    reason: Option<TerminationReason>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TerminationReason {
+    Timeout,
+    Memory,
+    Interrupt,
+}
+
+impl TerminationReason {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::Memory => "memory",
+            Self::Interrupt => "interrupt",
+        }
+    }
 }
 
 impl RuntimeExecError {
     pub(crate) fn new(stage: &'static str, message: impl Into<String>) -> Self {
-        Self { stage, message: message.into(), stack: None }
+        Self { stage, message: message.into(), stack: None, reason: None }
     }
 
     pub(crate) fn with_stack(
@@ -18,13 +37,24 @@ impl RuntimeExecError {
         message: impl Into<String>,
         stack: impl Into<Option<String>>,
     ) -> Self {
-        Self { stage, message: message.into(), stack: stack.into() }
+        Self { stage, message: message.into(), stack: stack.into(), reason: None }
+    }
+
+    pub(crate) fn terminated(
+        stage: &'static str,
+        message: impl Into<String>,
+        reason: TerminationReason,
+    ) -> Self {
+        Self { stage, message: message.into(), stack: None, reason: Some(reason) }
     }
 }
 
 impl fmt::Display for RuntimeExecError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "stage={}; message={}", self.stage, self.message)?;
+        if let Some(reason) = self.reason {
+            write!(f, "; reason={}", reason.as_str())?;
+        }
         if let Some(stack) = &self.stack {
             write!(f, "; stack={stack}")?;
         }
@@ -32,7 +62,18 @@ impl fmt::Display for RuntimeExecError {
     }
 }
 
-pub(crate) const INLINE_IMPORT_MAP_MARKER: &str = "plts-import-map:";
+pub(crate) type BatchInvocationResult = Result<Option<Value>, RuntimeExecError>;
+
+pub(crate) fn batch_result_json(result: &BatchInvocationResult) -> Value {
+    match result {
+        Ok(value) => json!({ "ok": true, "value": value }),
+        Err(err) => json!({
+            "ok": false,
+            "error": { "stage": err.stage, "message": err.message }
+        }),
+    }
+}
+
 pub(crate) const STATIC_BOOTSTRAP_RUNTIME_LOCKDOWN_SCRIPT_NAME: &str = "plts_runtime_lockdown.js";
 pub(crate) const STATIC_BOOTSTRAP_RUNTIME_LOCKDOWN_SCRIPT: &str =
     include_str!("runtime_lockdown.js");
@@ -48,55 +89,40 @@ pub(crate) fn parse_js_error_details(details: &str) -> (String, Option<String>)
     }
 }
 
-pub(crate) fn parse_inline_import_map(source: &str) -> HashMap<String, String> {
-    let Some(marker_start) = source.find(INLINE_IMPORT_MAP_MARKER) else {
-        return HashMap::new();
-    };
-
-    let mut cursor = marker_start + INLINE_IMPORT_MAP_MARKER.len();
-    while source[cursor..].chars().next().is_some_and(char::is_whitespace) {
-        cursor += source[cursor..].chars().next().map(char::len_utf8).unwrap_or(0);
-    }
-
-    if source[cursor..].chars().next() != Some('{') {
-        return HashMap::new();
-    }
-
-    let mut depth = 0_i32;
-    let mut in_string = false;
-    let mut escaped = false;
-    let mut end = None;
-    for (offset, ch) in source[cursor..].char_indices() {
-        if in_string {
-            if escaped {
-                escaped = false;
-            } else if ch == '\\' {
-                escaped = true;
-            } else if ch == '"' {
-                in_string = false;
-            }
-            continue;
-        }
-
-        match ch {
-            '"' => in_string = true,
-            '{' => depth += 1,
-            '}' => {
-                depth -= 1;
-                if depth == 0 {
-                    end = Some(cursor + offset + ch.len_utf8());
-                    break;
-                }
-            }
-            _ => {}
-        }
+/// SQLSTATE classes a handler may not raise under: success, warning, and no-data are not errors,
+/// and transaction rollback, operator intervention, and internal error would make clients retry
+/// or alert on a handler's own failure.
+const RESERVED_STOPGAP_SQLSTATE_CLASSES: [&str; 6] = ["00", "01", "02", "40", "57", "XX"];
+
+/// `MAKE_SQLSTATE('P','0','0','0','1')`, Postgres' `raise_exception`.
+const RAISE_EXCEPTION_SQLSTATE_CODE: i32 = 32 + (1 << 24);
+
+/// Packs the SQLSTATE a `StopgapError` chose, falling back to `P0001` for a malformed code or one
+/// in a reserved class.
+pub(crate) fn stopgap_sqlstate_code(sqlstate: &str) -> i32 {
+    let reserved =
+        RESERVED_STOPGAP_SQLSTATE_CLASSES.iter().any(|class| sqlstate.starts_with(class));
+    match sqlstate_code(sqlstate) {
+        Some(code) if !reserved => code,
+        _ => RAISE_EXCEPTION_SQLSTATE_CODE,
     }
+}
 
-    let Some(end) = end else {
-        return HashMap::new();
-    };
-
-    serde_json::from_str::<HashMap<String, String>>(&source[cursor..end]).unwrap_or_default()
+/// Packs a five-character SQLSTATE the way Postgres' `MAKE_SQLSTATE` does.
+fn sqlstate_code(sqlstate: &str) -> Option<i32> {
+    let bytes = sqlstate.as_bytes();
+    if bytes.len() != 5
+        || !bytes.iter().all(|byte| byte.is_ascii_digit() || byte.is_ascii_uppercase())
+    {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(index, byte)| ((i32::from(*byte) - i32::from(b'0')) & 0x3F) << (6 * index))
+            .sum(),
+    )
 }
 
 pub(crate) fn resolve_runtime_timeout_ms(
@@ -181,6 +207,13 @@ pub(crate) fn parse_runtime_heap_limit_bytes(raw: &str) -> Option<usize> {
     Some(bytes as usize)
 }
 
+const MIN_INTERRUPT_POLL_MS: i32 = 1;
+const MAX_INTERRUPT_POLL_MS: i32 = 100;
+
+pub(crate) fn interrupt_poll_interval_ms(raw: i32) -> u64 {
+    raw.clamp(MIN_INTERRUPT_POLL_MS, MAX_INTERRUPT_POLL_MS) as u64
+}
+
 pub(crate) fn interrupt_pending_from_flags(
     interrupt_pending: i32,
     query_cancel_pending: i32,
@@ -197,6 +230,7 @@ pub(crate) fn build_dynamic_context_setup_script(
     context_json: &str,
     db_mode_js: &str,
     db_read_only_js: bool,
+    console_enabled: bool,
 ) -> Result<String, RuntimeExecError> {
     let encoded_context = serde_json::to_string(context_json).map_err(|e| {
         RuntimeExecError::new(
@@ -210,23 +244,69 @@ pub(crate) fn build_dynamic_context_setup_script(
         "globalThis.__plts_ctx = JSON.parse({});\
          globalThis.__plts_ctx.db = {{\
            mode: '{}',\
-           query(input, params) {{\
-             return globalThis.__plts_internal_ops.dbQuery(input, params, {}, arguments.length > 1);\
+           query(input, params, options) {{\
+             return globalThis.__plts_internal_ops.dbQuery(input, params, {}, arguments.length > 1, options);\
+           }},\
+           queryRow(input, params, options) {{\
+             return globalThis.__plts_internal_ops.dbQueryRow(input, params, {}, arguments.length > 1, options);\
            }},\
-           exec(input, params) {{\
-             return globalThis.__plts_internal_ops.dbExec(input, params, {}, arguments.length > 1);\
+           queryValue(input, params, options) {{\
+             return globalThis.__plts_internal_ops.dbQueryValue(input, params, {}, arguments.length > 1, options);\
+           }},\
+           queryColumnar(input, params, options) {{\
+             return globalThis.__plts_internal_ops.dbQueryColumnar(input, params, {}, arguments.length > 1, options);\
+           }},\
+           exec(input, params, options) {{\
+             return globalThis.__plts_internal_ops.dbExec(input, params, {}, arguments.length > 1, options);\
+           }},\
+           batch(statements) {{\
+             return globalThis.__plts_internal_ops.dbBatch(statements, {});\
            }}\
-          }};",
-        encoded_context, db_mode_js, db_read_only_js, db_read_only_js
+          }};\
+         globalThis.__plts_ctx.memo = (() => {{\
+           const cache = new Map();\
+           return (key, factory) => {{\
+             if (typeof factory !== 'function') {{\
+               throw new TypeError('ctx.memo expects a factory function');\
+             }}\
+             if (cache.has(key)) {{\
+               return cache.get(key);\
+             }}\
+             const value = factory();\
+             cache.set(key, value);\
+             return value;\
+           }};\
+         }})();\
+         globalThis.__plts_ctx.log = {{\
+           info(message) {{\
+             globalThis.__plts_internal_ops.log('info', message);\
+           }},\
+           warn(message) {{\
+             globalThis.__plts_internal_ops.log('warn', message);\
+           }}\
+         }};\
+         globalThis.__plts_ctx.describe = (schema, name) =>\
+           globalThis.__plts_internal_ops.describe(schema, name);\
+         globalThis.__plts_internal_ops.installConsole({});",
+        encoded_context,
+        db_mode_js,
+        db_read_only_js,
+        db_read_only_js,
+        db_read_only_js,
+        db_read_only_js,
+        db_read_only_js,
+        db_read_only_js,
+        console_enabled
     ))
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        RuntimeExecError, build_dynamic_context_setup_script, interrupt_pending_from_flags,
-        parse_inline_import_map, parse_js_error_details, parse_runtime_heap_limit_bytes,
-        parse_statement_timeout_ms, resolve_runtime_timeout_ms, static_bootstrap_scripts,
+        RuntimeExecError, TerminationReason, batch_result_json, build_dynamic_context_setup_script,
+        interrupt_pending_from_flags, interrupt_poll_interval_ms, parse_js_error_details,
+        parse_runtime_heap_limit_bytes, parse_statement_timeout_ms, resolve_runtime_timeout_ms,
+        static_bootstrap_scripts, stopgap_sqlstate_code,
     };
 
     #[test]
@@ -237,6 +317,22 @@ mod tests {
         assert_eq!(stack.as_deref(), Some("at default (plts_module.js:1:1)\n    at foo"));
     }
 
+    #[test]
+    fn stopgap_sqlstate_code_packs_allowed_classes_and_falls_back_to_raise_exception() {
+        // MAKE_SQLSTATE('2','2','0','2','3') / ('P','0','0','0','1') as in errcodes.h.
+        let invalid_parameter_value = 2 + (2 << 6) + (2 << 18) + (3 << 24);
+        let raise_exception = 32 + (1 << 24);
+
+        assert_eq!(stopgap_sqlstate_code("22023"), invalid_parameter_value);
+        assert_eq!(stopgap_sqlstate_code("U0001"), 37 + (1 << 24));
+        assert_eq!(stopgap_sqlstate_code("P0001"), raise_exception);
+        for reserved in ["00000", "01000", "02000", "40001", "40P01", "57014", "XX000"] {
+            assert_eq!(stopgap_sqlstate_code(reserved), raise_exception, "{reserved}");
+        }
+        assert_eq!(stopgap_sqlstate_code("22x23"), raise_exception);
+        assert_eq!(stopgap_sqlstate_code("2202"), raise_exception);
+    }
+
     #[test]
     fn runtime_exec_error_display() {
         let err = RuntimeExecError::with_stack(
@@ -248,33 +344,39 @@ mod tests {
         assert!(rendered.contains("stage=entrypoint invocation"));
         assert!(rendered.contains("message=Uncaught Error: boom"));
         assert!(rendered.contains("stack=at default"));
+        assert!(!rendered.contains("reason="));
     }
 
     #[test]
-    fn parse_inline_import_map_extracts_json_object_after_marker() {
-        let source = r#"
-            // plts-import-map: {"@app/math":"sha256:abc","@app/time":"data:text/javascript,export const now=1;"}
-            import { now } from "@app/time";
-            export default () => now;
-        "#;
-
-        let import_map = parse_inline_import_map(source);
-        assert_eq!(import_map.get("@app/math").map(String::as_str), Some("sha256:abc"));
-        assert_eq!(
-            import_map.get("@app/time").map(String::as_str),
-            Some("data:text/javascript,export const now=1;")
-        );
+    fn runtime_exec_error_display_reports_termination_reason() {
+        let cases = [
+            ("statement timeout", TerminationReason::Timeout, "reason=timeout"),
+            ("memory limit", TerminationReason::Memory, "reason=memory"),
+            ("postgres interrupt", TerminationReason::Interrupt, "reason=interrupt"),
+        ];
+        for (stage, reason, expected) in cases {
+            let rendered = RuntimeExecError::terminated(stage, "stopped", reason).to_string();
+            assert!(rendered.ends_with(expected), "{rendered} should end with {expected}");
+        }
     }
 
     #[test]
-    fn parse_inline_import_map_returns_empty_when_marker_payload_is_invalid_json() {
-        let source = r#"
-            // plts-import-map: {"@app/math":
-            import { now } from "@app/math";
-            export default () => now;
-        "#;
+    fn batch_result_json_reports_values_and_errors_positionally() {
+        let ok = batch_result_json(&Ok(Some(serde_json::json!({ "id": 1 }))));
+        assert_eq!(ok, serde_json::json!({ "ok": true, "value": { "id": 1 } }));
 
-        assert!(parse_inline_import_map(source).is_empty());
+        let null = batch_result_json(&Ok(None));
+        assert_eq!(null, serde_json::json!({ "ok": true, "value": null }));
+
+        let failed =
+            batch_result_json(&Err(RuntimeExecError::new("entrypoint invocation", "boom")));
+        assert_eq!(
+            failed,
+            serde_json::json!({
+                "ok": false,
+                "error": { "stage": "entrypoint invocation", "message": "boom" }
+            })
+        );
     }
 
     #[test]
@@ -290,12 +392,20 @@ mod tests {
 
     #[test]
     fn runtime_dynamic_context_setup_script_encodes_invocation_specific_data() {
-        let script = build_dynamic_context_setup_script(r#"{"id":7}"#, "ro", true)
+        let script = build_dynamic_context_setup_script(r#"{"id":7}"#, "ro", true, false)
             .expect("dynamic context setup script should build");
         assert!(script.contains("__plts_ctx"));
         assert!(script.contains("mode: 'ro'"));
         assert!(script.contains("dbQuery"));
+        assert!(script.contains("dbQueryRow"));
+        assert!(script.contains("dbQueryValue"));
+        assert!(script.contains("dbQueryColumnar"));
         assert!(script.contains("dbExec"));
+        assert!(script.contains("dbBatch"));
+        assert!(script.contains("globalThis.__plts_ctx.memo"));
+        assert!(script.contains("globalThis.__plts_ctx.log"));
+        assert!(script.contains("globalThis.__plts_ctx.describe"));
+        assert!(script.contains("installConsole(false)"));
     }
 
     #[test]
@@ -352,4 +462,14 @@ mod tests {
         assert!(interrupt_pending_from_flags(0, 1, 0));
         assert!(interrupt_pending_from_flags(0, 0, 1));
     }
+
+    #[test]
+    fn interrupt_poll_interval_ms_clamps_to_supported_range() {
+        assert_eq!(interrupt_poll_interval_ms(5), 5);
+        assert_eq!(interrupt_poll_interval_ms(1), 1);
+        assert_eq!(interrupt_poll_interval_ms(100), 100);
+        assert_eq!(interrupt_poll_interval_ms(0), 1);
+        assert_eq!(interrupt_poll_interval_ms(-20), 1);
+        assert_eq!(interrupt_poll_interval_ms(5_000), 100);
+    }
 }
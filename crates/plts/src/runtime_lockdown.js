@@ -38,14 +38,230 @@
         throw new Error("plts runtime bootstrap failed: Deno core ops are unavailable");
     }
 
+    // `options` may only tighten the handler's DB mode: `{ readOnly: true }` makes a
+    // read-write handler's call read-only, and nothing makes a read-only handler writable.
+    const normalizeDbOpCall = (input, params, paramsProvided, options, opName) => {
+        if (options !== undefined && (typeof options !== "object" || options === null)) {
+            throw new TypeError(`${opName} options must be an object`);
+        }
+
+        const explicitParams = paramsProvided && (params !== undefined || options === undefined);
+        const call = normalizeDbCall(input, params, explicitParams, opName);
+        return { ...call, callReadOnly: options?.readOnly === true };
+    };
+
+    // Handler results go through serde_v8, which has no JSON form for Map/Set. Convert them
+    // up front: a Map becomes an object keyed by String(key), a Set becomes an array.
+    const normalizeResultValue = (value, path) => {
+        if (value instanceof Map) {
+            const out = {};
+            for (const [key, entry] of value) {
+                if (typeof key !== "string" && typeof key !== "number") {
+                    throw new TypeError(
+                        `handler result Map at ${path} has a ${typeof key} key; only string and number keys can be returned`
+                    );
+                }
+                out[String(key)] = normalizeResultValue(entry, `${path}.${key}`);
+            }
+            return out;
+        }
+
+        if (value instanceof Set) {
+            return Array.from(value, (entry, index) => normalizeResultValue(entry, `${path}[${index}]`));
+        }
+
+        if (Array.isArray(value)) {
+            let changed = false;
+            const out = value.map((entry, index) => {
+                const normalized = normalizeResultValue(entry, `${path}[${index}]`);
+                changed ||= normalized !== entry;
+                return normalized;
+            });
+            return changed ? out : value;
+        }
+
+        if (typeof value === "object" && value !== null) {
+            const proto = Object.getPrototypeOf(value);
+            if (proto !== Object.prototype && proto !== null) {
+                return value;
+            }
+
+            let changed = false;
+            const out = {};
+            for (const [key, entry] of Object.entries(value)) {
+                const normalized = normalizeResultValue(entry, `${path}.${key}`);
+                changed ||= normalized !== entry;
+                out[key] = normalized;
+            }
+            return changed ? out : value;
+        }
+
+        return value;
+    };
+
+    // ctx.log and console only carry strings to Postgres; anything else is JSON-stringified first.
+    const stringifyLogPart = (part) =>
+        typeof part === "string" ? part : (JSON.stringify(part) ?? String(part));
+
+    const formatLogMessage = (message) => {
+        const text = stringifyLogPart(message);
+        const fn = globalThis.__plts_ctx?.fn;
+        if (!fn) {
+            return text;
+        }
+        return `plts ${fn.schema}.${fn.name} (oid ${fn.oid}): ${text}`;
+    };
+
+    const defaultConsole = globalThis.console;
+    const consoleMethod = (level) => (...parts) => {
+        coreOps.op_plts_log(level, formatLogMessage(parts.map(stringifyLogPart).join(" ")));
+    };
+    const pltsConsole = Object.freeze({
+        log: consoleMethod("log"),
+        info: consoleMethod("info"),
+        debug: consoleMethod("debug"),
+        warn: consoleMethod("warn"),
+        error: consoleMethod("error"),
+    });
+
+    const SQLSTATE_PATTERN = /^[0-9A-Z]{5}$/;
+    let stopgapSqlstateOf;
+
+    // The SQLSTATE lives in a private field that only this constructor sets, so renaming or
+    // reshaping some other error cannot make it raise under a SQLSTATE of the handler's choosing.
+    class StopgapError extends Error {
+        #sqlstate;
+
+        constructor(message, sqlstate = "P0001") {
+            if (typeof sqlstate !== "string" || !SQLSTATE_PATTERN.test(sqlstate)) {
+                throw new TypeError(
+                    `stopgap error sqlstate must be 5 characters of 0-9/A-Z, got ${sqlstate}`
+                );
+            }
+            super(message);
+            this.#sqlstate = sqlstate;
+            this.name = "StopgapError";
+        }
+
+        get sqlstate() {
+            return this.#sqlstate;
+        }
+
+        static {
+            stopgapSqlstateOf = (value) =>
+                typeof value === "object" && value !== null && #sqlstate in value
+                    ? value.#sqlstate
+                    : null;
+        }
+    }
+
     const ops = {
-        dbQuery(input, params, readOnly = false, paramsProvided = false) {
-            const call = normalizeDbCall(input, params, paramsProvided, "db.query");
-            return coreOps.op_plts_db_query(call.sql, call.params, readOnly);
+        StopgapError,
+        // Tells plts which SQLSTATE and bare message a failed invocation raises with; anything
+        // but a `StopgapError` clears them so it raises as an internal error.
+        recordError(err) {
+            const sqlstate = stopgapSqlstateOf(err);
+            coreOps.op_plts_record_stopgap_error(
+                sqlstate === null ? null : { sqlstate, message: String(err.message) }
+            );
+        },
+        normalizeResult(value) {
+            return normalizeResultValue(value, "$");
+        },
+        isRowStream(value) {
+            return (
+                typeof value === "object" &&
+                value !== null &&
+                typeof value.next === "function" &&
+                (typeof value[Symbol.asyncIterator] === "function" ||
+                    typeof value[Symbol.iterator] === "function")
+            );
+        },
+        // A generator handler's rows go straight to the set-returning call's tuplestore one at a
+        // time; any other call collects them into an array result.
+        drainRows(rows) {
+            const streaming = coreOps.op_plts_row_sink_active();
+            const collected = [];
+            let index = 0;
+            const emit = (row) => {
+                const normalized = normalizeResultValue(row, `$[${index++}]`);
+                if (streaming) {
+                    coreOps.op_plts_emit_row(normalized);
+                } else {
+                    collected.push(normalized);
+                }
+            };
+
+            if (typeof rows[Symbol.asyncIterator] === "function") {
+                return (async () => {
+                    for await (const row of rows) {
+                        emit(row);
+                    }
+                    return streaming ? null : collected;
+                })();
+            }
+
+            for (const row of rows) {
+                emit(row);
+            }
+            return streaming ? null : collected;
+        },
+        dbQuery(input, params, readOnly = false, paramsProvided = false, options = undefined) {
+            const call = normalizeDbOpCall(input, params, paramsProvided, options, "db.query");
+            return coreOps.op_plts_db_query(call.sql, call.params, readOnly, call.callReadOnly);
+        },
+        dbQueryRow(input, params, readOnly = false, paramsProvided = false, options = undefined) {
+            const call = normalizeDbOpCall(input, params, paramsProvided, options, "db.queryRow");
+            const rows = coreOps.op_plts_db_query(call.sql, call.params, readOnly, call.callReadOnly);
+            if (rows.length > 1) {
+                throw new Error(`db.queryRow expected at most one row, got ${rows.length}`);
+            }
+            return rows.length === 0 ? null : rows[0];
+        },
+        dbQueryValue(input, params, readOnly = false, paramsProvided = false, options = undefined) {
+            const call = normalizeDbOpCall(input, params, paramsProvided, options, "db.queryValue");
+            const rows = coreOps.op_plts_db_query(call.sql, call.params, readOnly, call.callReadOnly);
+            if (rows.length !== 1) {
+                throw new Error(`db.queryValue expected exactly one row, got ${rows.length}`);
+            }
+            const value = Object.values(rows[0])[0];
+            return value === undefined ? null : value;
+        },
+        dbQueryColumnar(input, params, readOnly = false, paramsProvided = false, options = undefined) {
+            const call = normalizeDbOpCall(input, params, paramsProvided, options, "db.queryColumnar");
+            return coreOps.op_plts_db_query_columnar(
+                call.sql,
+                call.params,
+                readOnly,
+                call.callReadOnly
+            );
+        },
+        dbExec(input, params, readOnly = false, paramsProvided = false, options = undefined) {
+            const call = normalizeDbOpCall(input, params, paramsProvided, options, "db.exec");
+            return coreOps.op_plts_db_exec(call.sql, call.params, readOnly, call.callReadOnly);
+        },
+        dbBatch(statements, readOnly = false) {
+            if (!Array.isArray(statements)) {
+                throw new TypeError("db.batch expects an array of statements");
+            }
+
+            const calls = statements.map((entry, index) =>
+                normalizeDbCall(entry, undefined, false, `db.batch[${index}]`)
+            );
+            return coreOps.op_plts_db_batch(calls, readOnly);
+        },
+        log(level, message) {
+            coreOps.op_plts_log(level, formatLogMessage(message));
+        },
+        describe(schema, name) {
+            if (typeof schema !== "string" || typeof name !== "string") {
+                throw new TypeError("ctx.describe expects a schema and a function name");
+            }
+            return coreOps.op_plts_describe(schema, name);
         },
-        dbExec(input, params, readOnly = false, paramsProvided = false) {
-            const call = normalizeDbCall(input, params, paramsProvided, "db.exec");
-            return coreOps.op_plts_db_exec(call.sql, call.params, readOnly);
+        // Called for every invocation so pooled isolates follow the current plts.enable_console.
+        installConsole(enabled) {
+            globalThis.console = enabled ? pltsConsole : defaultConsole;
         },
     };
 
@@ -3,6 +3,8 @@ use pgrx::JsonB;
 #[cfg(feature = "v8_runtime")]
 use pgrx::datum::DatumWithOid;
 #[cfg(feature = "v8_runtime")]
+use pgrx::pg_sys::panic::CaughtError;
+#[cfg(feature = "v8_runtime")]
 use pgrx::prelude::*;
 #[cfg(feature = "v8_runtime")]
 use serde_json::Value;
@@ -24,13 +26,91 @@ pub(crate) enum BoundParam {
     Float(f64),
     Text(String),
     Json(Value),
+    Array(ArrayParam),
     NullText,
 }
 
+/// A param sent as `{ "@array": [...], "element_type": "int4" }`, bound as a Postgres array
+/// so handlers can write `WHERE id = ANY($1)`.
+#[cfg(feature = "v8_runtime")]
+#[derive(Debug)]
+pub(crate) enum ArrayParam {
+    Int4(Vec<Option<i32>>),
+    Int8(Vec<Option<i64>>),
+    Float8(Vec<Option<f64>>),
+    Text(Vec<Option<String>>),
+    Bool(Vec<Option<bool>>),
+}
+
+#[cfg(feature = "v8_runtime")]
+const ARRAY_PARAM_KEY: &str = "@array";
+#[cfg(feature = "v8_runtime")]
+const ARRAY_PARAM_ELEMENT_TYPES: [&str; 5] = ["int4", "int8", "float8", "text", "bool"];
+
+#[cfg(feature = "v8_runtime")]
+impl ArrayParam {
+    fn from_envelope(envelope: &serde_json::Map<String, Value>) -> Result<Self, String> {
+        let Some(Value::Array(items)) = envelope.get(ARRAY_PARAM_KEY) else {
+            return Err(format!("`{ARRAY_PARAM_KEY}` must be a JSON array"));
+        };
+        let element_type =
+            envelope.get("element_type").and_then(Value::as_str).ok_or_else(|| {
+                format!(
+                    "array params require `element_type` (one of {})",
+                    ARRAY_PARAM_ELEMENT_TYPES.join(", ")
+                )
+            })?;
+
+        fn collect<T>(
+            items: &[Value],
+            element_type: &str,
+            convert: impl Fn(&Value) -> Option<T>,
+        ) -> Result<Vec<Option<T>>, String> {
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| match item {
+                    Value::Null => Ok(None),
+                    other => convert(other).map(Some).ok_or_else(|| {
+                        format!("array element {index} is not a valid {element_type}: {other}")
+                    }),
+                })
+                .collect()
+        }
+
+        match element_type {
+            "int4" => {
+                collect(items, element_type, |v| v.as_i64().and_then(|n| i32::try_from(n).ok()))
+                    .map(Self::Int4)
+            }
+            "int8" => collect(items, element_type, Value::as_i64).map(Self::Int8),
+            "float8" => collect(items, element_type, Value::as_f64).map(Self::Float8),
+            "text" => {
+                collect(items, element_type, |v| v.as_str().map(str::to_string)).map(Self::Text)
+            }
+            "bool" => collect(items, element_type, Value::as_bool).map(Self::Bool),
+            other => Err(format!(
+                "unsupported array element_type '{other}'; expected one of {}",
+                ARRAY_PARAM_ELEMENT_TYPES.join(", ")
+            )),
+        }
+    }
+
+    fn as_datum_with_oid(&self) -> DatumWithOid<'_> {
+        match self {
+            Self::Int4(v) => v.clone().into(),
+            Self::Int8(v) => v.clone().into(),
+            Self::Float8(v) => v.clone().into(),
+            Self::Text(v) => v.clone().into(),
+            Self::Bool(v) => v.clone().into(),
+        }
+    }
+}
+
 #[cfg(feature = "v8_runtime")]
 impl BoundParam {
-    fn from_json(value: Value) -> Self {
-        match value {
+    fn from_json(value: Value) -> Result<Self, String> {
+        Ok(match value {
             Value::Bool(v) => Self::Bool(v),
             Value::Number(n) => {
                 if let Some(v) = n.as_i64() {
@@ -42,9 +122,12 @@ impl BoundParam {
                 }
             }
             Value::String(v) => Self::Text(v),
+            Value::Object(ref envelope) if envelope.contains_key(ARRAY_PARAM_KEY) => {
+                Self::Array(ArrayParam::from_envelope(envelope)?)
+            }
             Value::Array(_) | Value::Object(_) => Self::Json(value),
             Value::Null => Self::NullText,
-        }
+        })
     }
 
     fn as_datum_with_oid(&self) -> DatumWithOid<'_> {
@@ -54,54 +137,110 @@ impl BoundParam {
             Self::Float(v) => (*v).into(),
             Self::Text(v) => v.as_str().into(),
             Self::Json(v) => JsonB(v.clone()).into(),
+            Self::Array(v) => v.as_datum_with_oid(),
             Self::NullText => Option::<&str>::None.into(),
         }
     }
 }
 
 #[cfg(feature = "v8_runtime")]
-pub(crate) fn bind_json_params(params: Vec<Value>) -> Vec<BoundParam> {
-    params.into_iter().map(BoundParam::from_json).collect()
+pub(crate) fn bind_json_params(
+    op_name: &str,
+    params: Vec<Value>,
+) -> Result<Vec<BoundParam>, String> {
+    params
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            BoundParam::from_json(value)
+                .map_err(|e| format!("{op_name} param ${} is invalid: {e}", index + 1))
+        })
+        .collect()
 }
 
 #[cfg(feature = "v8_runtime")]
+/// `read_only` is the handler's mode; `call_read_only` is a per-call `{ readOnly: true }`
+/// opt-in, which can only add restrictions on top of the handler's mode.
 pub(crate) fn query_json_rows_with_params(
     sql: &str,
     params: Vec<Value>,
     read_only: bool,
+    call_read_only: bool,
+) -> Result<Value, String> {
+    query_json_with_params("db.query", sql, params, read_only, call_read_only, false)
+}
+
+#[cfg(feature = "v8_runtime")]
+/// Same checks as `query_json_rows_with_params`, but returns `{ columns, rows }` with each row
+/// as an array of values in column order. `columns` comes from the first row, so it is empty
+/// when the query returns no rows.
+pub(crate) fn query_json_columns_with_params(
+    sql: &str,
+    params: Vec<Value>,
+    read_only: bool,
+    call_read_only: bool,
+) -> Result<Value, String> {
+    query_json_with_params("db.queryColumnar", sql, params, read_only, call_read_only, true)
+}
+
+#[cfg(feature = "v8_runtime")]
+fn query_json_with_params(
+    op_name: &str,
+    sql: &str,
+    params: Vec<Value>,
+    read_only: bool,
+    call_read_only: bool,
+    columnar: bool,
 ) -> Result<Value, String> {
     let limits = RuntimeDbLimits::from_settings();
 
     if read_only && !is_read_only_sql(sql) {
-        return Err(
-            "db.query is read-only for stopgap.query handlers; use a SELECT-only statement"
-                .to_string(),
-        );
+        return Err(format!(
+            "{op_name} is read-only for stopgap.query handlers; use a SELECT-only statement"
+        ));
+    }
+    if call_read_only && !is_read_only_sql(sql) {
+        return Err(format!(
+            "{op_name} was called with {{ readOnly: true }}; use a SELECT-only statement"
+        ));
     }
 
-    validate_sql_and_params("db.query", sql, params.len(), &limits)?;
+    validate_sql_and_params(op_name, sql, params.len(), &limits)?;
 
-    let bound = bind_json_params(params);
+    let bound = bind_json_params(op_name, params)?;
     let args: Vec<DatumWithOid<'_>> = bound.iter().map(BoundParam::as_datum_with_oid).collect();
     let fetch_limit = limits.max_query_rows.saturating_add(1);
-    let wrapped_sql = format!(
-        "SELECT COALESCE(jsonb_agg(row_json), '[]'::jsonb) FROM (SELECT to_jsonb(q) AS row_json FROM ({}) q LIMIT {}) rows",
-        sql, fetch_limit
-    );
-
-    let rows = Spi::get_one_with_args::<JsonB>(&wrapped_sql, &args)
-        .map_err(|e| format!("db.query SPI error: {e}"))?
+    // `json` (unlike `jsonb`) keeps the select-list order, which the columnar shape relies on.
+    let wrapped_sql = if columnar {
+        format!(
+            "WITH fetched AS (SELECT to_json(q) AS row_json, row_number() OVER () AS n FROM ({}) q LIMIT {}) \
+             SELECT jsonb_build_object(\
+               'columns', COALESCE((SELECT jsonb_agg(e.key ORDER BY e.ord) FROM (SELECT row_json FROM fetched ORDER BY n LIMIT 1) f, json_each(f.row_json) WITH ORDINALITY AS e(key, value, ord)), '[]'::jsonb),\
+               'rows', COALESCE((SELECT jsonb_agg((SELECT jsonb_agg(e.value::jsonb ORDER BY e.ord) FROM json_each(fetched.row_json) WITH ORDINALITY AS e(key, value, ord)) ORDER BY n) FROM fetched), '[]'::jsonb))",
+            sql, fetch_limit
+        )
+    } else {
+        format!(
+            "SELECT COALESCE(jsonb_agg(row_json), '[]'::jsonb) FROM (SELECT to_jsonb(q) AS row_json FROM ({}) q LIMIT {}) rows",
+            sql, fetch_limit
+        )
+    };
+
+    let _op_timeout = OpStatementTimeout::arm(limits.max_op_ms);
+    let result = Spi::get_one_with_args::<JsonB>(&wrapped_sql, &args)
+        .map_err(|e| format!("{op_name} SPI error: {e}"))?
         .map(|v| v.0)
-        .unwrap_or_else(|| json!([]));
+        .unwrap_or_else(|| if columnar { json!({ "columns": [], "rows": [] }) } else { json!([]) });
 
+    let rows = if columnar { &result["rows"] } else { &result };
     if rows.as_array().is_some_and(|entries| entries.len() > limits.max_query_rows) {
         return Err(format!(
-            "db.query returned more than {} rows; increase plts.max_query_rows if this result set is expected",
+            "{op_name} returned more than {} rows; increase plts.max_query_rows if this result set is expected",
             limits.max_query_rows
         ));
     }
 
-    Ok(rows)
+    Ok(result)
 }
 
 #[cfg(feature = "v8_runtime")]
@@ -109,6 +248,7 @@ pub(crate) fn exec_sql_with_params(
     sql: &str,
     params: Vec<Value>,
     read_only: bool,
+    call_read_only: bool,
 ) -> Result<Value, String> {
     let limits = RuntimeDbLimits::from_settings();
 
@@ -116,16 +256,141 @@ pub(crate) fn exec_sql_with_params(
         return Err("db.exec is disabled for stopgap.query handlers; switch to stopgap.mutation"
             .to_string());
     }
+    if call_read_only && !is_read_only_sql(sql) {
+        return Err(
+            "db.exec was called with { readOnly: true }; use a SELECT-only statement".to_string()
+        );
+    }
 
     validate_sql_and_params("db.exec", sql, params.len(), &limits)?;
 
-    let bound = bind_json_params(params);
+    let bound = bind_json_params("db.exec", params)?;
     let args: Vec<DatumWithOid<'_>> = bound.iter().map(BoundParam::as_datum_with_oid).collect();
+    let _op_timeout = OpStatementTimeout::arm(limits.max_op_ms);
     Spi::run_with_args(sql, &args).map_err(|e| format!("db.exec SPI error: {e}"))?;
     Ok(json!({ "ok": true }))
 }
 
 #[cfg(feature = "v8_runtime")]
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct DbBatchStatement {
+    sql: String,
+    #[serde(default)]
+    params: Vec<Value>,
+}
+
+/// Runs every statement in order inside the caller's transaction. Limits and read-only checks
+/// cover the whole batch before anything executes; a failing statement aborts the handler's
+/// transaction, so earlier statements in the batch never commit on their own.
+#[cfg(feature = "v8_runtime")]
+pub(crate) fn exec_sql_batch(
+    statements: Vec<DbBatchStatement>,
+    read_only: bool,
+) -> Result<Value, String> {
+    let limits = RuntimeDbLimits::from_settings();
+
+    for (index, statement) in statements.iter().enumerate() {
+        let op_name = format!("db.batch[{index}]");
+        if read_only && !is_read_only_sql(&statement.sql) {
+            return Err(format!(
+                "{op_name} writes; stopgap.query handlers may only batch SELECT-only statements"
+            ));
+        }
+        validate_sql_and_params(&op_name, &statement.sql, statement.params.len(), &limits)?;
+    }
+
+    let count = statements.len();
+    let bound_statements = statements
+        .into_iter()
+        .enumerate()
+        .map(|(index, statement)| {
+            bind_json_params(&format!("db.batch[{index}]"), statement.params)
+                .map(|bound| (statement.sql, bound))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (index, (sql, bound)) in bound_statements.into_iter().enumerate() {
+        let _op_timeout = OpStatementTimeout::arm(limits.max_op_ms);
+        PgTryBuilder::new(|| {
+            let args: Vec<DatumWithOid<'_>> =
+                bound.iter().map(BoundParam::as_datum_with_oid).collect();
+            Spi::run_with_args(&sql, &args)
+        })
+        .catch_others(|caught| {
+            let detail = match caught {
+                CaughtError::PostgresError(report) | CaughtError::ErrorReport(report) => {
+                    report.message().to_string()
+                }
+                CaughtError::RustPanic { ereport, .. } => ereport.message().to_string(),
+            };
+            error!("db.batch[{index}] failed: {detail}")
+        })
+        .execute()
+        .map_err(|e| format!("db.batch[{index}] SPI error: {e}"))?;
+    }
+
+    Ok(json!({ "ok": true, "count": count }))
+}
+
+/// `utils/timeout.h` bindings generated by `build.rs`; pgrx's `pg_sys` does not include them.
+#[cfg(feature = "v8_runtime")]
+#[allow(non_camel_case_types, non_upper_case_globals, dead_code)]
+mod timeout_sys {
+    use pgrx::pg_sys::TimestampTz;
+
+    include!(concat!(env!("OUT_DIR"), "/timeout_bindings.rs"));
+}
+
+#[cfg(feature = "v8_runtime")]
+use timeout_sys::{
+    TimeoutId_STATEMENT_TIMEOUT as STATEMENT_TIMEOUT, disable_timeout, enable_timeout_after,
+    enable_timeout_at, get_timeout_active, get_timeout_finish_time,
+};
+
+/// Arms Postgres' statement timer for a single SPI op and restores the outer statement
+/// deadline on drop, including when the op errors out.
+#[cfg(feature = "v8_runtime")]
+struct OpStatementTimeout {
+    outer_finish: Option<pg_sys::TimestampTz>,
+}
+
+#[cfg(feature = "v8_runtime")]
+impl OpStatementTimeout {
+    fn arm(max_op_ms: Option<usize>) -> Option<Self> {
+        let max_op_ms = max_op_ms?;
+        let delay_ms = std::ffi::c_int::try_from(max_op_ms).unwrap_or(std::ffi::c_int::MAX);
+        let outer_finish = unsafe {
+            if get_timeout_active(STATEMENT_TIMEOUT) {
+                Some(get_timeout_finish_time(STATEMENT_TIMEOUT))
+            } else {
+                None
+            }
+        };
+
+        let op_finish = unsafe { pg_sys::GetCurrentTimestamp() }
+            .saturating_add(i64::from(delay_ms).saturating_mul(1000));
+        if outer_finish.is_some_and(|outer| outer <= op_finish) {
+            return None;
+        }
+
+        unsafe { enable_timeout_after(STATEMENT_TIMEOUT, delay_ms) };
+        Some(Self { outer_finish })
+    }
+}
+
+#[cfg(feature = "v8_runtime")]
+impl Drop for OpStatementTimeout {
+    fn drop(&mut self) {
+        unsafe {
+            match self.outer_finish {
+                Some(finish) => enable_timeout_at(STATEMENT_TIMEOUT, finish),
+                None => disable_timeout(STATEMENT_TIMEOUT, false),
+            }
+        }
+    }
+}
+
+#[cfg(any(test, feature = "v8_runtime"))]
 pub(crate) fn is_read_only_sql(sql: &str) -> bool {
     let normalized = strip_leading_sql_comments(sql).to_ascii_lowercase();
     if !(normalized.starts_with("select") || normalized.starts_with("with")) {
@@ -144,7 +409,7 @@ pub(crate) fn is_read_only_sql(sql: &str) -> bool {
     true
 }
 
-#[cfg(feature = "v8_runtime")]
+#[cfg(any(test, feature = "v8_runtime"))]
 fn contains_forbidden_unquoted_token(sql: &str, forbidden: &[&str]) -> bool {
     let mut token = String::new();
     let mut chars = sql.chars().peekable();
@@ -152,8 +417,11 @@ fn contains_forbidden_unquoted_token(sql: &str, forbidden: &[&str]) -> bool {
     enum ScanState {
         Normal,
         SingleQuoted,
+        EscapeQuoted,
         DoubleQuoted,
         DollarQuoted(String),
+        LineComment,
+        BlockComment(usize),
     }
 
     let mut state = ScanState::Normal;
@@ -170,6 +438,18 @@ fn contains_forbidden_unquoted_token(sql: &str, forbidden: &[&str]) -> bool {
                 }
                 continue;
             }
+            ScanState::EscapeQuoted => {
+                if ch == '\\' {
+                    chars.next();
+                } else if ch == '\'' {
+                    if chars.peek().copied() == Some('\'') {
+                        chars.next();
+                    } else {
+                        state = ScanState::Normal;
+                    }
+                }
+                continue;
+            }
             ScanState::DoubleQuoted => {
                 if ch == '"' {
                     if chars.peek().copied() == Some('"') {
@@ -196,12 +476,47 @@ fn contains_forbidden_unquoted_token(sql: &str, forbidden: &[&str]) -> bool {
                 }
                 continue;
             }
+            ScanState::LineComment => {
+                if ch == '\n' {
+                    state = ScanState::Normal;
+                }
+                continue;
+            }
+            ScanState::BlockComment(depth) => {
+                if ch == '*' && chars.peek().copied() == Some('/') {
+                    chars.next();
+                    if *depth == 1 {
+                        state = ScanState::Normal;
+                    } else {
+                        *depth -= 1;
+                    }
+                } else if ch == '/' && chars.peek().copied() == Some('*') {
+                    chars.next();
+                    *depth += 1;
+                }
+                continue;
+            }
             ScanState::Normal => {}
         }
 
+        if ch == '-' && chars.peek().copied() == Some('-') {
+            chars.next();
+            state = ScanState::LineComment;
+        } else if ch == '/' && chars.peek().copied() == Some('*') {
+            chars.next();
+            state = ScanState::BlockComment(1);
+        }
+        if !matches!(state, ScanState::Normal) {
+            if forbidden.contains(&token.as_str()) {
+                return true;
+            }
+            token.clear();
+            continue;
+        }
+
         if ch == '\'' {
+            state = if token == "e" { ScanState::EscapeQuoted } else { ScanState::SingleQuoted };
             token.clear();
-            state = ScanState::SingleQuoted;
             continue;
         }
 
@@ -257,7 +572,7 @@ fn contains_forbidden_unquoted_token(sql: &str, forbidden: &[&str]) -> bool {
     false
 }
 
-#[cfg(feature = "v8_runtime")]
+#[cfg(any(test, feature = "v8_runtime"))]
 fn strip_leading_sql_comments(sql: &str) -> &str {
     let mut rest = sql.trim_start();
     loop {
@@ -281,12 +596,39 @@ fn strip_leading_sql_comments(sql: &str) -> &str {
     }
 }
 
+#[cfg(feature = "v8_runtime")]
+thread_local! {
+    static QUERY_ROWS_OVERRIDE: std::cell::Cell<Option<usize>> = const { std::cell::Cell::new(None) };
+}
+
+/// Scopes a per-function `max_query_rows` cap (from a deployed artifact pointer) to the
+/// current invocation, taking precedence over `plts.max_query_rows`.
+#[cfg(feature = "v8_runtime")]
+pub(crate) struct QueryRowsOverride {
+    previous: Option<usize>,
+}
+
+#[cfg(feature = "v8_runtime")]
+impl QueryRowsOverride {
+    pub(crate) fn set(max_query_rows: Option<usize>) -> Self {
+        Self { previous: QUERY_ROWS_OVERRIDE.with(|cell| cell.replace(max_query_rows)) }
+    }
+}
+
+#[cfg(feature = "v8_runtime")]
+impl Drop for QueryRowsOverride {
+    fn drop(&mut self) {
+        QUERY_ROWS_OVERRIDE.with(|cell| cell.set(self.previous));
+    }
+}
+
 #[cfg(feature = "v8_runtime")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct RuntimeDbLimits {
     max_sql_bytes: usize,
     max_params: usize,
     max_query_rows: usize,
+    max_op_ms: Option<usize>,
 }
 
 #[cfg(feature = "v8_runtime")]
@@ -295,7 +637,12 @@ impl RuntimeDbLimits {
         Self {
             max_sql_bytes: read_limit_setting("plts.max_sql_bytes", DEFAULT_MAX_SQL_BYTES),
             max_params: read_limit_setting("plts.max_params", DEFAULT_MAX_PARAMS),
-            max_query_rows: read_limit_setting("plts.max_query_rows", DEFAULT_MAX_QUERY_ROWS),
+            max_query_rows: QUERY_ROWS_OVERRIDE.with(|cell| cell.get()).unwrap_or_else(|| {
+                read_limit_setting("plts.max_query_rows", DEFAULT_MAX_QUERY_ROWS)
+            }),
+            max_op_ms: current_setting_text("plts.max_op_ms")
+                .as_deref()
+                .and_then(parse_positive_usize),
         }
     }
 }
@@ -347,3 +694,36 @@ pub(crate) fn parse_positive_usize(raw: &str) -> Option<usize> {
 
     trimmed.parse::<usize>().ok().filter(|value| *value > 0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::is_read_only_sql;
+
+    #[test]
+    fn is_read_only_sql_allows_forbidden_words_inside_identifiers_and_literals() {
+        assert!(is_read_only_sql("SELECT last_update FROM t"));
+        assert!(is_read_only_sql("SELECT 'update' AS note"));
+        assert!(is_read_only_sql("SELECT E'it\\'s a delete' AS note"));
+        assert!(is_read_only_sql("SELECT \"update\" FROM t"));
+        assert!(is_read_only_sql("SELECT $tag$drop table t$tag$ AS note"));
+        assert!(is_read_only_sql("SELECT 1 -- update later\n"));
+        assert!(is_read_only_sql("SELECT /* outer /* delete */ insert */ 1"));
+    }
+
+    #[test]
+    fn is_read_only_sql_rejects_write_statements_in_ctes() {
+        assert!(!is_read_only_sql(
+            "WITH w AS (INSERT INTO t(id) VALUES (1) RETURNING id) SELECT id FROM w"
+        ));
+        assert!(!is_read_only_sql(
+            "WITH a AS (SELECT 1), b AS (DELETE FROM t RETURNING 1) SELECT * FROM b"
+        ));
+        assert!(!is_read_only_sql("UPDATE t SET id = 1"));
+    }
+
+    #[test]
+    fn is_read_only_sql_does_not_hide_writes_behind_quotes_in_comments() {
+        assert!(!is_read_only_sql("SELECT 1 /* ' */; DELETE FROM t; -- '"));
+        assert!(!is_read_only_sql("SELECT E'\\''; DELETE FROM t; SELECT ''"));
+    }
+}
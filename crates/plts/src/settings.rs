@@ -0,0 +1,137 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RecognizedSetting {
+    pub(crate) name: &'static str,
+    pub(crate) default_value: Option<&'static str>,
+    pub(crate) description: &'static str,
+}
+
+// Keep this registry in sync with `_PG_init` GUC definitions and the runtime/compiler
+// `current_setting` readers so `plts.settings()` stays an accurate operator reference.
+pub(crate) const RECOGNIZED_SETTINGS: &[RecognizedSetting] = &[
+    RecognizedSetting {
+        name: "plts.isolate_reuse",
+        default_value: Some("on"),
+        description: "Enable backend-local pooled runtime reuse for the V8 runtime.",
+    },
+    RecognizedSetting {
+        name: "plts.isolate_pool_size",
+        default_value: Some("2"),
+        description: "Maximum number of warm runtime shells kept ready per backend.",
+    },
+    RecognizedSetting {
+        name: "plts.isolate_max_age_s",
+        default_value: Some("120"),
+        description: "Maximum lifetime in seconds for a pooled runtime shell.",
+    },
+    RecognizedSetting {
+        name: "plts.isolate_max_invocations",
+        default_value: Some("250"),
+        description: "Maximum number of invocations allowed on a pooled runtime shell.",
+    },
+    RecognizedSetting {
+        name: "plts.enable_console",
+        default_value: Some("off"),
+        description: "Route console.log/info/debug/warn/error in handlers to the Postgres log.",
+    },
+    RecognizedSetting {
+        name: "plts.include_timing",
+        default_value: Some("off"),
+        description: "Wrap jsonb handler results as { \"@plts\": 1, result, timing } with execution timings.",
+    },
+    RecognizedSetting {
+        name: "plts.module_cache_bytes",
+        default_value: Some("16777216"),
+        description: "Maximum total bytes of V8 code cache kept for imported artifact modules; 0 disables it.",
+    },
+    RecognizedSetting {
+        name: "plts.module_cache_entries",
+        default_value: Some("128"),
+        description: "Maximum number of artifact modules with cached V8 code; 0 disables the cache.",
+    },
+    RecognizedSetting {
+        name: "plts.interrupt_poll_ms",
+        default_value: Some("5"),
+        description: "Interval in milliseconds at which a running handler checks for cancel and timeout (1-100).",
+    },
+    RecognizedSetting {
+        name: "plts.compile_preamble",
+        default_value: None,
+        description: "TypeScript prepended to every source before transpilation; included in the compiler fingerprint.",
+    },
+    RecognizedSetting {
+        name: "plts.compiler_reactor_max_requests",
+        default_value: Some("1000"),
+        description: "Maximum number of requests served by the shared TSGo reactor before recycling (preload only).",
+    },
+    RecognizedSetting {
+        name: "plts.compiler_reactor_max_age_s",
+        default_value: Some("300"),
+        description: "Maximum age in seconds for the shared TSGo reactor before recycling (preload only).",
+    },
+    RecognizedSetting {
+        name: "plts.compiler_request_timeout_ms",
+        default_value: Some("30000"),
+        description: "Timeout in milliseconds for shared compiler-service requests (preload only).",
+    },
+    RecognizedSetting {
+        name: "plts.max_runtime_ms",
+        default_value: None,
+        description: "Runtime execution cap; the stricter of this and statement_timeout applies.",
+    },
+    RecognizedSetting {
+        name: "plts.max_op_ms",
+        default_value: None,
+        description: "Per-statement timeout in milliseconds applied to each ctx.db call.",
+    },
+    RecognizedSetting {
+        name: "plts.max_heap_mb",
+        default_value: None,
+        description: "Optional V8 heap cap in megabytes for runtime invocations.",
+    },
+    RecognizedSetting {
+        name: "plts.max_sql_bytes",
+        default_value: Some("131072"),
+        description: "Maximum SQL text size in bytes accepted by ctx.db calls.",
+    },
+    RecognizedSetting {
+        name: "plts.max_params",
+        default_value: Some("256"),
+        description: "Maximum number of bound parameters accepted by ctx.db calls.",
+    },
+    RecognizedSetting {
+        name: "plts.max_query_rows",
+        default_value: Some("1000"),
+        description: "Maximum number of rows returned by a single ctx.db.query call.",
+    },
+    RecognizedSetting {
+        name: "plts.log_level",
+        default_value: Some("warn"),
+        description: "Log verbosity for plts compile/runtime events (off, error, warn, info, debug).",
+    },
+    RecognizedSetting {
+        name: "plts.tsgo_wasm_cache_mode",
+        default_value: Some("auto"),
+        description: "TSGo WASM module cache behavior (auto, manual-only, off).",
+    },
+    RecognizedSetting {
+        name: "plts.tsgo_wasm_cache_dir",
+        default_value: None,
+        description: "Override directory for the TSGo WASM module cache.",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::RECOGNIZED_SETTINGS;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn recognized_settings_are_unique_and_namespaced() {
+        let mut seen = BTreeSet::new();
+        for setting in RECOGNIZED_SETTINGS {
+            assert!(setting.name.starts_with("plts."), "{} should use plts prefix", setting.name);
+            assert!(seen.insert(setting.name), "{} should be listed once", setting.name);
+            assert!(!setting.description.is_empty(), "{} should be described", setting.name);
+        }
+    }
+}
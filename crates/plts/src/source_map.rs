@@ -0,0 +1,211 @@
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OriginalPosition {
+    pub(crate) source: Option<String>,
+    pub(crate) line: u32,
+    pub(crate) column: u32,
+    pub(crate) name: Option<String>,
+}
+
+/// Resolves a 1-based generated `line`/`column` through a v3 source map. Columns past the
+/// last mapped segment on a line resolve to that segment, matching how stack frames point
+/// into the middle of a mapped expression.
+pub(crate) fn lookup_original_position(
+    source_map: &str,
+    line: u32,
+    column: u32,
+) -> Option<OriginalPosition> {
+    if line == 0 || column == 0 {
+        return None;
+    }
+
+    let parsed = serde_json::from_str::<Value>(source_map).ok()?;
+    let mappings = parsed.get("mappings")?.as_str()?;
+    let source_root = parsed.get("sourceRoot").and_then(Value::as_str).unwrap_or("");
+    let sources = string_list(parsed.get("sources"));
+    let names = string_list(parsed.get("names"));
+
+    let target_line = (line - 1) as usize;
+    let target_column = i64::from(column - 1);
+    let mut state = [0_i64; 4];
+    let mut name_index = 0_i64;
+
+    for (line_index, encoded_line) in mappings.split(';').enumerate() {
+        let mut generated_column = 0_i64;
+        let mut best = None;
+
+        for segment in encoded_line.split(',').filter(|segment| !segment.is_empty()) {
+            let fields = decode_vlq_segment(segment)?;
+            generated_column += fields[0];
+            if fields.len() < 4 {
+                continue;
+            }
+
+            for (slot, delta) in state.iter_mut().zip(&fields[1..4]) {
+                *slot += delta;
+            }
+            let name = fields.get(4).map(|delta| {
+                name_index += delta;
+                name_index
+            });
+
+            if line_index == target_line && generated_column <= target_column {
+                best = Some((state, name));
+            }
+        }
+
+        if line_index == target_line {
+            let ([source_index, original_line, original_column, _], name) = best?;
+            let source = usize::try_from(source_index)
+                .ok()
+                .and_then(|idx| sources.get(idx))
+                .map(|source| format!("{source_root}{source}"));
+            let name = name
+                .and_then(|idx| usize::try_from(idx).ok())
+                .and_then(|idx| names.get(idx))
+                .cloned();
+            return Some(OriginalPosition {
+                source,
+                line: u32::try_from(original_line + 1).ok()?,
+                column: u32::try_from(original_column + 1).ok()?,
+                name,
+            });
+        }
+    }
+
+    None
+}
+
+/// Maps the first stack frame located in `module` through `source_map` and renders it as
+/// `source:line:column`. Frames are expected in V8's `at fn (specifier:line:column)` form.
+pub(crate) fn remap_top_stack_frame(stack: &str, module: &str, source_map: &str) -> Option<String> {
+    let (line, column) =
+        stack.lines().filter(|frame| frame.contains(module)).find_map(stack_frame_position)?;
+    let position = lookup_original_position(source_map, line, column)?;
+    Some(format!(
+        "{}:{}:{}",
+        position.source.as_deref().unwrap_or("<unknown>"),
+        position.line,
+        position.column
+    ))
+}
+
+fn stack_frame_position(frame: &str) -> Option<(u32, u32)> {
+    let location = frame.trim().trim_end_matches(')');
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    parts.next()?;
+    Some((line, column))
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries.iter().map(|entry| entry.as_str().unwrap_or_default().to_string()).collect()
+        })
+        .unwrap_or_default()
+}
+
+fn decode_vlq_segment(segment: &str) -> Option<Vec<i64>> {
+    let mut fields = Vec::with_capacity(5);
+    let mut value = 0_i64;
+    let mut shift = 0_u32;
+
+    for byte in segment.bytes() {
+        let digit = base64_digit(byte)?;
+        value |= i64::from(digit & 0x1f).checked_shl(shift)?;
+        if digit & 0x20 != 0 {
+            shift += 5;
+            if shift > 60 {
+                return None;
+            }
+            continue;
+        }
+
+        let magnitude = value >> 1;
+        fields.push(if value & 1 == 1 { -magnitude } else { magnitude });
+        value = 0;
+        shift = 0;
+    }
+
+    if shift != 0 || fields.is_empty() { None } else { Some(fields) }
+}
+
+fn base64_digit(byte: u8) -> Option<u8> {
+    match byte {
+        b'A'..=b'Z' => Some(byte - b'A'),
+        b'a'..=b'z' => Some(byte - b'a' + 26),
+        b'0'..=b'9' => Some(byte - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        OriginalPosition, decode_vlq_segment, lookup_original_position, remap_top_stack_frame,
+    };
+
+    const MAP: &str = r#"{
+        "version": 3,
+        "sources": ["entry.ts"],
+        "names": ["answer"],
+        "mappings": "AAAA;AACA,OAAOA,MAAM;;AAEZ"
+    }"#;
+
+    #[test]
+    fn vlq_segments_decode_signed_values() {
+        assert_eq!(decode_vlq_segment("AAAA"), Some(vec![0, 0, 0, 0]));
+        assert_eq!(decode_vlq_segment("AACA"), Some(vec![0, 0, 1, 0]));
+        assert_eq!(decode_vlq_segment("D"), Some(vec![-1]));
+        assert_eq!(decode_vlq_segment("gB"), Some(vec![16]));
+        assert_eq!(decode_vlq_segment("g"), None);
+    }
+
+    #[test]
+    fn lookup_resolves_nearest_preceding_segment() {
+        assert_eq!(
+            lookup_original_position(MAP, 2, 10),
+            Some(OriginalPosition {
+                source: Some("entry.ts".to_string()),
+                line: 2,
+                column: 8,
+                name: Some("answer".to_string()),
+            })
+        );
+        assert_eq!(
+            lookup_original_position(MAP, 4, 1),
+            Some(OriginalPosition {
+                source: Some("entry.ts".to_string()),
+                line: 4,
+                column: 2,
+                name: None,
+            })
+        );
+    }
+
+    #[test]
+    fn lookup_returns_none_for_unmapped_positions() {
+        assert_eq!(lookup_original_position(MAP, 3, 1), None);
+        assert_eq!(lookup_original_position(MAP, 9, 1), None);
+        assert_eq!(lookup_original_position("not json", 1, 1), None);
+    }
+
+    #[test]
+    fn remap_top_stack_frame_uses_first_frame_in_module() {
+        let stack = "at helper (file:///plts/__stopgap_runtime__.js:1:1)\n    \
+                     at default (file:///plts/main-1.js:2:10)\n    \
+                     at file:///plts/main-1.js:4:1";
+        assert_eq!(
+            remap_top_stack_frame(stack, "file:///plts/main-", MAP).as_deref(),
+            Some("entry.ts:2:8")
+        );
+        assert_eq!(remap_top_stack_frame(stack, "file:///plts/other-", MAP), None);
+        assert_eq!(remap_top_stack_frame("at default (native)", "native", MAP), None);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::arg_mapping::datum_to_json_value;
+use pgrx::prelude::*;
+use pgrx::{PgTupleDesc, heap_getattr_raw};
+use serde_json::{Map, Value, json};
+use std::ffi::CString;
+use std::num::NonZeroUsize;
+
+/// A `LANGUAGE plts` function invoked by a trigger. The firing event is exposed to the handler as
+/// `ctx.trigger`, and the handler's result is turned back into the row Postgres expects.
+pub(crate) struct TriggerCall {
+    fcinfo: pg_sys::FunctionCallInfo,
+    trigger_data: *mut pg_sys::TriggerData,
+}
+
+impl TriggerCall {
+    pub(crate) unsafe fn from_fcinfo(fcinfo: pg_sys::FunctionCallInfo) -> Option<Self> {
+        if !unsafe { pgrx::called_as_trigger(fcinfo) } {
+            return None;
+        }
+
+        let trigger_data = unsafe { (*fcinfo).context } as *mut pg_sys::TriggerData;
+        (!trigger_data.is_null()).then_some(Self { fcinfo, trigger_data })
+    }
+
+    pub(crate) fn context_json(&self) -> Value {
+        let Ok(trigger) = (unsafe { PgTrigger::from_fcinfo(&*self.fcinfo) }) else {
+            return Value::Null;
+        };
+        let tupdesc = self.tupdesc();
+
+        json!({
+            "name": trigger.name().unwrap_or_default(),
+            "operation": trigger.op().map(|op| op.to_string()).unwrap_or_default(),
+            "when": trigger.when().map(|when| when.to_string()).unwrap_or_default(),
+            "level": trigger.level().to_string(),
+            "table": trigger.table_name().unwrap_or_default(),
+            "schema": trigger.table_schema().unwrap_or_default(),
+            "old": unsafe { tuple_to_json(self.old_tuple(), tupdesc) },
+            "new": unsafe { tuple_to_json(self.new_tuple(), tupdesc) },
+        })
+    }
+
+    /// Converts the handler result into the trigger return value. `null`/`undefined` keeps the
+    /// row Postgres passed in; an object is merged over it column by column.
+    pub(crate) fn result_datum(&self, value: Option<Value>) -> Result<pg_sys::Datum, String> {
+        let event = self.event();
+        if !pgrx::trigger_fired_for_row(event) {
+            return Ok(pg_sys::Datum::from(0));
+        }
+
+        let base =
+            if pgrx::trigger_fired_by_delete(event) { self.old_tuple() } else { self.new_tuple() };
+        if base.is_null() {
+            return Ok(pg_sys::Datum::from(0));
+        }
+
+        match value {
+            None | Some(Value::Null) => Ok(pg_sys::Datum::from(base)),
+            Some(Value::Object(_)) if pgrx::trigger_fired_by_delete(event) => {
+                Ok(pg_sys::Datum::from(base))
+            }
+            Some(Value::Object(fields)) => {
+                let tuple = unsafe { merge_tuple(base, self.tupdesc(), &fields) }?;
+                Ok(pg_sys::Datum::from(tuple))
+            }
+            Some(other) => {
+                Err(format!("trigger handlers must return an object or null, got {other}"))
+            }
+        }
+    }
+
+    fn event(&self) -> u32 {
+        unsafe { (*self.trigger_data).tg_event }
+    }
+
+    fn tupdesc(&self) -> pg_sys::TupleDesc {
+        unsafe { (*(*self.trigger_data).tg_relation).rd_att }
+    }
+
+    fn old_tuple(&self) -> pg_sys::HeapTuple {
+        let event = self.event();
+        if pgrx::trigger_fired_for_row(event)
+            && (pgrx::trigger_fired_by_update(event) || pgrx::trigger_fired_by_delete(event))
+        {
+            unsafe { (*self.trigger_data).tg_trigtuple }
+        } else {
+            std::ptr::null_mut()
+        }
+    }
+
+    fn new_tuple(&self) -> pg_sys::HeapTuple {
+        let event = self.event();
+        if !pgrx::trigger_fired_for_row(event) {
+            std::ptr::null_mut()
+        } else if pgrx::trigger_fired_by_insert(event) {
+            unsafe { (*self.trigger_data).tg_trigtuple }
+        } else if pgrx::trigger_fired_by_update(event) {
+            unsafe { (*self.trigger_data).tg_newtuple }
+        } else {
+            std::ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn tuple_to_json(tuple: pg_sys::HeapTuple, tupdesc: pg_sys::TupleDesc) -> Value {
+    if tuple.is_null() {
+        return Value::Null;
+    }
+
+    let desc = unsafe { PgTupleDesc::from_pg_unchecked(tupdesc) };
+    let mut row = Map::with_capacity(desc.len());
+    for (index, attr) in desc.iter().enumerate() {
+        if attr.is_dropped() {
+            continue;
+        }
+
+        let attno = NonZeroUsize::new(index + 1).expect("attribute numbers start at 1");
+        let value = unsafe { heap_getattr_raw(tuple, attno, tupdesc) }
+            .map(|datum| unsafe { datum_to_json_value(datum, attr.type_oid().value()) })
+            .unwrap_or(Value::Null);
+        row.insert(attr.name().to_string(), value);
+    }
+
+    Value::Object(row)
+}
+
+unsafe fn merge_tuple(
+    base: pg_sys::HeapTuple,
+    tupdesc: pg_sys::TupleDesc,
+    fields: &Map<String, Value>,
+) -> Result<pg_sys::HeapTuple, String> {
+    let desc = unsafe { PgTupleDesc::from_pg_unchecked(tupdesc) };
+    if let Some(unknown) =
+        fields.keys().find(|key| !desc.iter().any(|attr| !attr.is_dropped() && attr.name() == *key))
+    {
+        return Err(format!("trigger result has unknown column `{unknown}`"));
+    }
+
+    let mut values = Vec::with_capacity(desc.len());
+    let mut nulls = Vec::with_capacity(desc.len());
+    for (index, attr) in desc.iter().enumerate() {
+        let attno = NonZeroUsize::new(index + 1).expect("attribute numbers start at 1");
+        let datum = match fields.get(attr.name()) {
+            _ if attr.is_dropped() => None,
+            Some(Value::Null) => None,
+            Some(value) => Some(unsafe { json_to_column_datum(attr, value) }?),
+            None => unsafe { heap_getattr_raw(base, attno, tupdesc) },
+        };
+        nulls.push(datum.is_none());
+        values.push(datum.unwrap_or(pg_sys::Datum::from(0)));
+    }
+
+    Ok(unsafe { pg_sys::heap_form_tuple(tupdesc, values.as_mut_ptr(), nulls.as_mut_ptr()) })
+}
+
+unsafe fn json_to_column_datum(
+    attr: &pg_sys::FormData_pg_attribute,
+    value: &Value,
+) -> Result<pg_sys::Datum, String> {
+    let type_oid = attr.type_oid().value();
+    let text = match value {
+        Value::String(text) if !matches!(type_oid, pg_sys::JSONBOID | pg_sys::JSONOID) => {
+            text.clone()
+        }
+        other => other.to_string(),
+    };
+    let text = CString::new(text)
+        .map_err(|_| format!("trigger result column `{}` contains a NUL byte", attr.name()))?;
+
+    let mut input_fn = pg_sys::InvalidOid;
+    let mut io_param = pg_sys::InvalidOid;
+    unsafe {
+        pg_sys::getTypeInputInfo(type_oid, &mut input_fn, &mut io_param);
+        Ok(pg_sys::OidInputFunctionCall(
+            input_fn,
+            text.as_ptr() as *mut _,
+            io_param,
+            attr.type_mod(),
+        ))
+    }
+}
@@ -3,6 +3,9 @@ declare const fetch: unknown;
 declare const Request: unknown;
 declare const WebSocket: unknown;
 
+/** Context passed to a plain `export default (ctx) => ...` plts handler. */
+type PltsContext<TArgs = unknown> = import("@stopgap/runtime").StopgapContext<TArgs>;
+
 declare module "@stopgap/runtime" {
   export type JsonPrimitive = string | number | boolean | null;
   export type JsonValue = unknown;
@@ -17,17 +20,60 @@ declare module "@stopgap/runtime" {
 
   export type DbMode = "ro" | "rw";
 
+  export type DbCallOptions = {
+    readOnly?: boolean;
+  };
+
+  export type DbInput =
+    | string
+    | { sql: string; params?: unknown[] }
+    | { toSQL(): { sql: string; params?: unknown[] } };
+
   export type DbApi = {
     mode: DbMode;
-    query: (sql: string, params?: unknown[]) => Promise<JsonValue[]>;
-    exec: (sql: string, params?: unknown[]) => Promise<{ ok: true }>;
+    query: (sql: DbInput, params?: unknown[], options?: DbCallOptions) => Promise<JsonValue[]>;
+    queryRow: (sql: DbInput, params?: unknown[], options?: DbCallOptions) => Promise<JsonValue | null>;
+    queryValue: (sql: DbInput, params?: unknown[], options?: DbCallOptions) => Promise<JsonValue | null>;
+    queryColumnar: (
+      sql: DbInput,
+      params?: unknown[],
+      options?: DbCallOptions
+    ) => Promise<{ columns: string[]; rows: JsonValue[][] }>;
+    exec: (sql: DbInput, params?: unknown[], options?: DbCallOptions) => Promise<{ ok: true }>;
+    batch: (statements: DbInput[]) => Promise<{ ok: true; count: number }>;
+  };
+
+  export type TriggerContext = {
+    name: string;
+    operation: "INSERT" | "UPDATE" | "DELETE" | "TRUNCATE";
+    when: "BEFORE" | "AFTER" | "INSTEAD OF";
+    level: "ROW" | "STATEMENT";
+    table: string;
+    schema: string;
+    old: Record<string, JsonValue> | null;
+    new: Record<string, JsonValue> | null;
   };
 
   export type StopgapContext<TArgs> = {
     args: TArgs;
     db: DbApi;
-    fn: { oid: number; schema: string; name: string };
+    fn: {
+      oid: number;
+      schema: string;
+      name: string;
+      args: Array<{ name: string | null; type_oid: number; type_name: string }>;
+    };
     now: string;
+    trigger?: TriggerContext;
+    memo: <T>(key: unknown, factory: () => T) => T;
+    log: {
+      info: (message: unknown) => void;
+      warn: (message: unknown) => void;
+    };
+    describe: (schema: string, name: string) => {
+      kind: "query" | "mutation" | null;
+      args_schema: JsonValue | null;
+    };
   };
 
   export type StopgapWrapped = ((ctx: unknown) => Promise<unknown>) & {
@@ -42,6 +88,13 @@ declare module "@stopgap/runtime" {
 
   export const validateArgs: (schema: JsonSchema | null | undefined, value: unknown, path?: string) => void;
 
+  export class StopgapError extends Error {
+    readonly sqlstate: string;
+    constructor(message: string, sqlstate?: string);
+  }
+
+  export function assert(condition: unknown, message: string, sqlstate?: string): asserts condition;
+
   export const v: {
     object<T extends Record<string, StopgapSchema<unknown>>>(
       shape: T
@@ -80,6 +133,8 @@ declare module "@stopgap/runtime" {
     query: typeof query;
     mutation: typeof mutation;
     validateArgs: typeof validateArgs;
+    assert: typeof assert;
+    StopgapError: typeof StopgapError;
   };
 
   export default runtimeApi;
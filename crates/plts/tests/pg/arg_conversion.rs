@@ -61,22 +61,22 @@ fn test_regular_args_conversion_for_common_types() {
     );
 
     assert_eq!(
-        payload.0.get("named").and_then(|named| named.get("0")).and_then(Value::as_str),
+        payload.0.get("named").and_then(|named| named.get("t")).and_then(Value::as_str),
         Some("hello")
     );
     assert_eq!(
-        payload.0.get("named").and_then(|named| named.get("1")).and_then(Value::as_i64),
+        payload.0.get("named").and_then(|named| named.get("i")).and_then(Value::as_i64),
         Some(42)
     );
     assert_eq!(
-        payload.0.get("named").and_then(|named| named.get("2")).and_then(Value::as_bool),
+        payload.0.get("named").and_then(|named| named.get("b")).and_then(Value::as_bool),
         Some(true)
     );
     assert_eq!(
         payload
             .0
             .get("named")
-            .and_then(|named| named.get("3"))
+            .and_then(|named| named.get("j"))
             .and_then(|entry| entry.get("ok"))
             .and_then(Value::as_bool),
         Some(true)
@@ -84,3 +84,143 @@ fn test_regular_args_conversion_for_common_types() {
 
     Spi::run("DROP SCHEMA IF EXISTS plts_it CASCADE;").expect("test teardown SQL should succeed");
 }
+
+#[pg_test]
+fn test_regular_args_conversion_for_numeric_and_timestamp_types() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_numeric_args_it CASCADE;
+        CREATE SCHEMA plts_numeric_args_it;
+        CREATE OR REPLACE FUNCTION plts_numeric_args_it.arg_echo(
+            id int8,
+            ratio float8,
+            weight float4,
+            amount numeric,
+            at timestamptz
+        )
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => ({ positional: ctx.args.positional, named: ctx.args.named });
+        $$;
+        ",
+    )
+    .expect("numeric args setup SQL should succeed");
+
+    let payload = Spi::get_one::<JsonB>(
+        "
+        SELECT plts_numeric_args_it.arg_echo(
+            9007199254740993,
+            2.5,
+            0.5,
+            12345678901234567890.123456789,
+            '2024-01-02 03:04:05+00'
+        )
+        ",
+    )
+    .expect("numeric arg_echo query should succeed")
+    .expect("numeric arg_echo should return a json payload");
+
+    let positional = payload
+        .0
+        .get("positional")
+        .and_then(Value::as_array)
+        .cloned()
+        .expect("payload should carry positional args");
+
+    assert_eq!(positional.first().and_then(Value::as_str), Some("9007199254740993"));
+    assert_eq!(positional.get(1).and_then(Value::as_f64), Some(2.5));
+    assert_eq!(positional.get(2).and_then(Value::as_f64), Some(0.5));
+    assert_eq!(positional.get(3).and_then(Value::as_str), Some("12345678901234567890.123456789"));
+    assert!(
+        positional.get(4).and_then(Value::as_str).is_some_and(|value| value.contains('T')),
+        "timestamptz args should arrive as ISO strings"
+    );
+    assert_eq!(
+        payload.0.get("named").and_then(|named| named.get("id")).and_then(Value::as_str),
+        Some("9007199254740993")
+    );
+
+    let in_range = Spi::get_one::<JsonB>(
+        "SELECT plts_numeric_args_it.arg_echo(9007199254740991, 0, 0, 0, '2024-01-02 03:04:05+00')",
+    )
+    .expect("in-range int8 arg_echo query should succeed")
+    .expect("in-range int8 arg_echo should return a json payload");
+    assert_eq!(
+        in_range.0.get("named").and_then(|named| named.get("id")).and_then(Value::as_i64),
+        Some(9_007_199_254_740_991)
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_numeric_args_it CASCADE;")
+        .expect("numeric args teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_regular_args_conversion_for_array_types() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_array_args_it CASCADE;
+        CREATE SCHEMA plts_array_args_it;
+        CREATE OR REPLACE FUNCTION plts_array_args_it.arg_echo(tags text[], ids int4[])
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => ({ positional: ctx.args.positional, named: ctx.args.named });
+        $$;
+        ",
+    )
+    .expect("array args setup SQL should succeed");
+
+    let payload = Spi::get_one::<JsonB>(
+        "SELECT plts_array_args_it.arg_echo(ARRAY['a','b']::text[], ARRAY[1, NULL, 3]::int4[])",
+    )
+    .expect("array arg_echo query should succeed")
+    .expect("array arg_echo should return a json payload");
+
+    let positional = payload.0.get("positional").and_then(Value::as_array);
+    assert_eq!(positional.and_then(|items| items.first()), Some(&Value::from(vec!["a", "b"])));
+    assert_eq!(
+        positional.and_then(|items| items.get(1)),
+        Some(&Value::Array(vec![Value::from(1), Value::Null, Value::from(3)]))
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_array_args_it CASCADE;")
+        .expect("array args teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_regular_args_named_map_uses_argument_names() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_named_args_it CASCADE;
+        CREATE SCHEMA plts_named_args_it;
+        CREATE OR REPLACE FUNCTION plts_named_args_it.f(customer_id int4, text)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => ({ positional: ctx.args.positional, named: ctx.args.named });
+        $$;
+        ",
+    )
+    .expect("named args setup SQL should succeed");
+
+    let payload = Spi::get_one::<JsonB>("SELECT plts_named_args_it.f(42, 'note')")
+        .expect("named args query should succeed")
+        .expect("named args function should return a json payload");
+
+    assert_eq!(
+        payload.0.get("positional"),
+        Some(&Value::from(vec![Value::from(42), Value::from("note")]))
+    );
+    assert_eq!(
+        payload.0.get("named").and_then(|named| named.get("customer_id")).and_then(Value::as_i64),
+        Some(42)
+    );
+    assert_eq!(
+        payload.0.get("named").and_then(|named| named.get("1")).and_then(Value::as_str),
+        Some("note")
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_named_args_it CASCADE;")
+        .expect("named args teardown SQL should succeed");
+}
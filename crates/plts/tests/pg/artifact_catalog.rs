@@ -29,3 +29,626 @@ fn test_compile_and_store_round_trip() {
         "stored artifact should include compiled_js"
     );
 }
+
+#[pg_test]
+fn test_compile_and_store_extracts_leading_doc_comment() {
+    let source = "/**\n * Greets the caller by name.\n */\nexport default (ctx: any) => ({ hello: ctx.args.name });";
+    let artifact_hash = Spi::get_one_with_args::<String>(
+        "SELECT plts.compile_and_store($1::text, '{}'::jsonb)",
+        &[source.into()],
+    )
+    .expect("compile_and_store query should succeed")
+    .expect("compile_and_store should return an artifact hash");
+
+    let doc = Spi::get_one_with_args::<String>(
+        "SELECT plts.get_artifact($1)->>'doc'",
+        &[artifact_hash.as_str().into()],
+    )
+    .expect("get_artifact query should succeed");
+    assert_eq!(doc.as_deref(), Some("Greets the caller by name."));
+
+    let pointer = format!(
+        r#"{{"plts":1,"kind":"artifact_ptr","artifact_hash":"{artifact_hash}","export":"default","mode":"stopgap_deployed"}}"#
+    );
+    Spi::run(&format!(
+        "
+        DROP SCHEMA IF EXISTS plts_doc_it CASCADE;
+        CREATE SCHEMA plts_doc_it;
+        CREATE OR REPLACE FUNCTION plts_doc_it.ptr_fn(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$ {pointer} $$;
+        CREATE OR REPLACE FUNCTION plts_doc_it.inline_fn(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        /** Echoes its arguments. */
+        export default (ctx: any) => ctx.args;
+        $$;
+        CREATE OR REPLACE FUNCTION plts_doc_it.bare_fn(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$ export default (ctx: any) => ctx.args; $$;
+        "
+    ))
+    .expect("doc function setup SQL should succeed");
+
+    let doc_for = |signature: &str| {
+        Spi::get_one::<String>(&format!("SELECT plts.doc('{signature}'::regprocedure)"))
+            .expect("plts.doc query should succeed")
+    };
+    assert_eq!(doc_for("plts_doc_it.ptr_fn(jsonb)").as_deref(), Some("Greets the caller by name."));
+    assert_eq!(doc_for("plts_doc_it.inline_fn(jsonb)").as_deref(), Some("Echoes its arguments."));
+    assert_eq!(doc_for("plts_doc_it.bare_fn(jsonb)"), None);
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_doc_it CASCADE;")
+        .expect("doc function teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_upsert_artifact_persists_diagnostics() {
+    let artifact_hash = Spi::get_one::<String>(
+        "SELECT plts.compile_and_store('export const stored: number = 1;', '{}'::jsonb)",
+    )
+    .expect("compile_and_store query should succeed")
+    .expect("compile_and_store should return an artifact hash");
+    let stored = Spi::get_one_with_args::<JsonB>(
+        "SELECT plts.get_artifact($1)->'diagnostics'",
+        &[artifact_hash.as_str().into()],
+    )
+    .expect("get_artifact query should succeed")
+    .expect("compile_and_store should persist diagnostics");
+    assert_eq!(stored.0, Value::Array(Vec::new()));
+
+    let diagnostics =
+        r#"[{"severity":"warning","phase":"transpile","message":"kept","line":1,"column":1}]"#;
+    let upserted_hash = Spi::get_one_with_args::<String>(
+        "SELECT plts.upsert_artifact('export const stored: number = 2;', 'export const stored = 2;', '{}'::jsonb, $1::jsonb)",
+        &[diagnostics.into()],
+    )
+    .expect("upsert_artifact query should succeed")
+    .expect("upsert_artifact should return an artifact hash");
+    let plain_hash = Spi::get_one::<String>(
+        "SELECT plts.upsert_artifact('export const stored: number = 2;', 'export const stored = 2;', '{}'::jsonb)",
+    )
+    .expect("upsert_artifact without diagnostics should succeed")
+    .expect("upsert_artifact should return an artifact hash");
+    assert_eq!(upserted_hash, plain_hash, "diagnostics must not affect the artifact hash");
+
+    let round_trip = Spi::get_one_with_args::<JsonB>(
+        "SELECT plts.get_artifact($1)->'diagnostics'",
+        &[upserted_hash.as_str().into()],
+    )
+    .expect("get_artifact query should succeed")
+    .expect("upserted artifact should keep its diagnostics");
+    assert_eq!(
+        round_trip.0.pointer("/0/message").and_then(Value::as_str),
+        Some("kept"),
+        "diagnostics should round-trip through plts.artifact"
+    );
+}
+
+#[pg_test]
+fn test_compile_ts_bundle_inlines_static_imports() {
+    let helper_hash = Spi::get_one_with_args::<String>(
+        "SELECT plts.compile_and_store($1::text, '{}'::jsonb)",
+        &["export const offset: number = 2;".into()],
+    )
+    .expect("helper compile_and_store should succeed")
+    .expect("helper compile_and_store should return an artifact hash");
+
+    let source = format!(
+        "import {{ factor }} from \"data:text/javascript;base64,ZXhwb3J0IGNvbnN0IGZhY3RvciA9IDQ7\";\n\
+         import {{ offset }} from \"plts+artifact:{helper_hash}\";\n\
+         export default (ctx: any) => ({{ value: ctx.args.n * factor + offset }});\n\
+         export const lazy = () => import(\"data:text/javascript,export default 1;\");\n"
+    );
+
+    let compiled = Spi::get_one_with_args::<JsonB>(
+        "
+        SELECT jsonb_build_object('compiled_js', compiled_js, 'fingerprint', compiler_fingerprint)
+        FROM plts.compile_ts($1::text, '{\"bundle\": true}'::jsonb)
+        ",
+        &[source.as_str().into()],
+    )
+    .expect("bundled compile_ts should succeed")
+    .expect("bundled compile_ts should return a row");
+    let compiled_js = compiled.0.get("compiled_js").and_then(Value::as_str).unwrap_or_default();
+    let fingerprint = compiled.0.get("fingerprint").and_then(Value::as_str).unwrap_or_default();
+
+    assert!(
+        !compiled_js.lines().any(|line| line.starts_with("import ") || line.starts_with("import{")),
+        "bundled artifact should not keep static import statements: {compiled_js}"
+    );
+    assert!(compiled_js.contains("import(\"data:"), "dynamic imports must not be inlined");
+    assert!(fingerprint.ends_with(";bundle@1"), "bundle mode should be part of the fingerprint");
+
+    let unbundled_fingerprint = Spi::get_one_with_args::<String>(
+        "SELECT compiler_fingerprint FROM plts.compile_ts($1::text, '{}'::jsonb)",
+        &[source.as_str().into()],
+    )
+    .expect("unbundled compile_ts should succeed")
+    .expect("unbundled compile_ts should return a fingerprint");
+    assert_ne!(fingerprint, unbundled_fingerprint);
+}
+
+#[pg_test]
+fn test_compile_report_groups_transpile_diagnostics_by_severity() {
+    let source = "export const a: number = ;\nexport const b = 1 +;\n";
+    let flat = Spi::get_one_with_args::<JsonB>(
+        "SELECT diagnostics FROM plts.compile_ts($1::text, '{}'::jsonb)",
+        &[source.into()],
+    )
+    .expect("compile_ts query should succeed")
+    .expect("compile_ts should return diagnostics");
+    let report = Spi::get_one_with_args::<JsonB>(
+        "SELECT plts.compile_report($1::text, '{}'::jsonb)",
+        &[source.into()],
+    )
+    .expect("compile_report query should succeed")
+    .expect("compile_report should return a report");
+
+    let flat = flat.0.as_array().cloned().unwrap_or_default();
+    let with_severity = |severity: &str| {
+        flat.iter()
+            .filter(|entry| entry.get("severity").and_then(Value::as_str) == Some(severity))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let group =
+        |key: &str| report.0.get(key).and_then(Value::as_array).cloned().unwrap_or_default();
+
+    assert!(!with_severity("error").is_empty(), "broken source should report errors: {flat:?}");
+    assert_eq!(group("errors"), with_severity("error"));
+    assert_eq!(group("warnings"), with_severity("warning"));
+    assert_eq!(group("infos"), with_severity("info"));
+    assert_eq!(report.0.get("ok").and_then(Value::as_bool), Some(false));
+    let count = |key: &str| {
+        report.0.get("counts").and_then(|counts| counts.get(key)).and_then(Value::as_u64)
+    };
+    assert_eq!(count("errors"), Some(with_severity("error").len() as u64));
+    assert_eq!(count("warnings"), Some(with_severity("warning").len() as u64));
+    assert_eq!(count("infos"), Some(with_severity("info").len() as u64));
+
+    let clean = Spi::get_one::<JsonB>(
+        "SELECT plts.compile_report('export const value: number = 1;', '{}'::jsonb)",
+    )
+    .expect("clean compile_report query should succeed")
+    .expect("clean compile_report should return a report");
+    assert_eq!(clean.0.get("ok").and_then(Value::as_bool), Some(true));
+}
+
+#[pg_test]
+fn test_bench_compile_reports_timings_without_storing_artifacts() {
+    let source = "export const benched: number = 7;";
+    let artifact_count = || {
+        Spi::get_one::<i64>("SELECT count(*) FROM plts.artifact")
+            .expect("artifact count query should succeed")
+            .expect("artifact count should not be null")
+    };
+    let before = artifact_count();
+
+    let report =
+        Spi::get_one_with_args::<JsonB>("SELECT plts.bench_compile($1::text, 3)", &[source.into()])
+            .expect("bench_compile query should succeed")
+            .expect("bench_compile should return a report");
+    let timing = |key: &str| {
+        report.0.get(key).and_then(Value::as_f64).unwrap_or_else(|| panic!("missing {key}"))
+    };
+
+    assert_eq!(report.0.get("iterations").and_then(Value::as_i64), Some(3));
+    assert!(timing("min_ms") <= timing("avg_ms") && timing("avg_ms") <= timing("max_ms"));
+    assert!(timing("total_ms") >= timing("max_ms"));
+    assert_eq!(artifact_count(), before, "bench_compile must not store artifacts");
+
+    for iterations in [0, 1001] {
+        Spi::run(&format!(
+            "
+            DO $$
+            BEGIN
+                PERFORM plts.bench_compile('export const x = 1;', {iterations});
+                RAISE EXCEPTION 'expected bench_compile to reject iterations={iterations}';
+            EXCEPTION
+                WHEN OTHERS THEN
+                    IF POSITION('iterations must be between 1 and 1000' IN SQLERRM) = 0 THEN
+                        RAISE;
+                    END IF;
+            END;
+            $$;
+            "
+        ))
+        .expect("out-of-range iterations should be rejected");
+    }
+
+    Spi::run(
+        "
+        DO $$
+        BEGIN
+            PERFORM plts.bench_compile('export const broken: number = ;', 1);
+            RAISE EXCEPTION 'expected bench_compile to reject broken source';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('failed to transpile' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("broken source should be rejected");
+}
+
+#[pg_test]
+fn test_artifact_js_diff_reports_emitted_code_changes() {
+    let hash_a = Spi::get_one::<String>(
+        "SELECT plts.compile_and_store('export const answer: number = 41;', '{}'::jsonb)",
+    )
+    .expect("first compile_and_store should succeed")
+    .expect("first compile_and_store should return an artifact hash");
+    let hash_b = Spi::get_one::<String>(
+        "SELECT plts.compile_and_store('export const answer: number = 42;', '{}'::jsonb)",
+    )
+    .expect("second compile_and_store should succeed")
+    .expect("second compile_and_store should return an artifact hash");
+
+    let diff = Spi::get_one_with_args::<String>(
+        "SELECT plts.artifact_js_diff($1, $2)",
+        &[hash_a.as_str().into(), hash_b.as_str().into()],
+    )
+    .expect("artifact_js_diff should succeed")
+    .expect("artifact_js_diff should return text");
+    assert!(diff.starts_with(&format!("--- {hash_a}\n+++ {hash_b}\n")), "{diff}");
+    assert!(diff.contains("-export const answer = 41;\n+export const answer = 42;\n"), "{diff}");
+
+    let same = Spi::get_one_with_args::<String>(
+        "SELECT plts.artifact_js_diff($1, $1)",
+        &[hash_a.as_str().into()],
+    )
+    .expect("artifact_js_diff on identical artifacts should succeed")
+    .expect("artifact_js_diff should return text");
+    assert!(same.is_empty(), "identical artifacts should produce an empty diff");
+}
+
+#[pg_test]
+fn test_inline_functions_excludes_artifact_pointers() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_inline_functions_it CASCADE;
+        CREATE SCHEMA plts_inline_functions_it;
+        CREATE OR REPLACE FUNCTION plts_inline_functions_it.raw_fn(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$ export default (ctx: any) => ctx.args; $$;
+        ",
+    )
+    .expect("inline function setup SQL should succeed");
+
+    let artifact_hash = Spi::get_one::<String>(
+        "SELECT plts.compile_and_store('export default (ctx: any) => ctx.args;', '{}'::jsonb)",
+    )
+    .expect("compile_and_store should succeed")
+    .expect("compile_and_store should return an artifact hash");
+    let pointer = format!(
+        r#"{{"plts":1,"kind":"artifact_ptr","artifact_hash":"{artifact_hash}","export":"default","mode":"stopgap_deployed"}}"#
+    );
+    Spi::run(&format!(
+        "
+        CREATE OR REPLACE FUNCTION plts_inline_functions_it.ptr_fn(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$ {pointer} $$;
+        "
+    ))
+    .expect("pointer function setup SQL should succeed");
+
+    let inline = Spi::get_one::<String>(
+        "
+        SELECT string_agg(schema || '.' || name, ',' ORDER BY name)
+        FROM plts.inline_functions('plts_inline_functions_it')
+        ",
+    )
+    .expect("inline_functions should succeed")
+    .expect("inline_functions should return rows");
+    assert_eq!(inline, "plts_inline_functions_it.raw_fn");
+
+    let oid_matches = Spi::get_one::<bool>(
+        "
+        SELECT oid = 'plts_inline_functions_it.raw_fn(jsonb)'::regprocedure::oid
+        FROM plts.inline_functions('plts_inline_functions_it')
+        ",
+    )
+    .expect("inline_functions oid query should succeed")
+    .expect("inline_functions should return an oid");
+    assert!(oid_matches);
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_inline_functions_it CASCADE;")
+        .expect("inline function teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_validate_schema_reports_per_function_compile_errors() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_validate_schema_it CASCADE;
+        CREATE SCHEMA plts_validate_schema_it;
+        CREATE OR REPLACE FUNCTION plts_validate_schema_it.good_fn(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$ export default (ctx: any) => ctx.args; $$;
+        SELECT set_config('check_function_bodies', 'off', true);
+        CREATE OR REPLACE FUNCTION plts_validate_schema_it.broken_fn(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$ export const value = ; $$;
+        SELECT set_config('check_function_bodies', 'on', true);
+        "#,
+    )
+    .expect("validate_schema setup SQL should succeed");
+
+    let report = Spi::get_one::<JsonB>("SELECT plts.validate_schema('plts_validate_schema_it')")
+        .expect("validate_schema query should succeed")
+        .expect("validate_schema should return a report");
+    assert_eq!(report.0.get("ok").and_then(Value::as_bool), Some(false));
+
+    let functions =
+        report.0.get("functions").and_then(Value::as_array).cloned().unwrap_or_default();
+    let names = functions
+        .iter()
+        .filter_map(|function| function.get("fn_name").and_then(Value::as_str))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["broken_fn", "good_fn"]);
+    assert_eq!(functions[0].get("ok").and_then(Value::as_bool), Some(false));
+    assert!(functions[0].get("error_count").and_then(Value::as_u64).is_some_and(|count| count > 0));
+    assert_eq!(functions[1].get("ok").and_then(Value::as_bool), Some(true));
+    assert_eq!(functions[1].get("error_count").and_then(Value::as_u64), Some(0));
+
+    Spi::run("DROP FUNCTION plts_validate_schema_it.broken_fn(jsonb);")
+        .expect("broken function drop should succeed");
+    let ok = Spi::get_one::<bool>(
+        "SELECT (plts.validate_schema('plts_validate_schema_it')->>'ok')::boolean",
+    )
+    .expect("validate_schema query should succeed");
+    assert_eq!(ok, Some(true));
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_validate_schema_it CASCADE;")
+        .expect("validate_schema teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_compile_batch_stores_each_source() {
+    let hashes = Spi::get_one::<i64>(
+        r#"
+        SELECT count(DISTINCT artifact_hash)
+        FROM plts.compile_batch(
+            '[
+                {"name": "one", "source_ts": "export const value: number = 1;"},
+                {"name": "two", "source_ts": "export const value: number = 2;"},
+                {"name": "three", "source_ts": "export const value: number = 3;"}
+            ]'::jsonb
+        ) AS batch
+        JOIN plts.artifact a USING (artifact_hash)
+        "#,
+    )
+    .expect("compile_batch should succeed")
+    .expect("compile_batch should return a count");
+    assert_eq!(hashes, 3);
+
+    let failed = Spi::get_one::<String>(
+        r#"
+        SELECT string_agg(name, ',' ORDER BY name)
+        FROM plts.compile_batch(
+            '[
+                {"name": "ok", "source_ts": "export const value: number = 1;"},
+                {"name": "broken", "source_ts": "export const value = ;"}
+            ]'::jsonb
+        )
+        WHERE artifact_hash IS NULL
+        "#,
+    )
+    .expect("compile_batch without fail_fast should succeed")
+    .expect("compile_batch should report the failed source");
+    assert_eq!(failed, "broken");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM * FROM plts.compile_batch(
+                '[{"name": "broken", "source_ts": "export const value = ;"}]'::jsonb,
+                '{"fail_fast": true}'::jsonb
+            );
+            RAISE EXCEPTION 'expected compile_batch fail_fast abort';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('plts.compile_batch source broken failed' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("compile_batch fail_fast should abort the batch");
+}
+
+#[pg_test]
+fn test_compile_and_store_rejects_disallowed_import_schemes() {
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts.compile_and_store(
+                'import { remote } from "https://example.com/mod.js"; export default () => remote;',
+                '{}'::jsonb
+            );
+            RAISE EXCEPTION 'expected compile_and_store to reject https import';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('unsupported module import `https://example.com/mod.js`' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("compile_and_store should reject https imports at compile time");
+}
+
+#[pg_test]
+fn test_decode_source_map_resolves_generated_position() {
+    let mapped_hash = Spi::get_one::<String>(
+        "SELECT plts.compile_and_store('export const answer: number = 42;', '{\"source_map\": true}'::jsonb)",
+    )
+    .expect("compile_and_store with source maps should succeed")
+    .expect("compile_and_store should return an artifact hash");
+
+    let decoded = Spi::get_one_with_args::<JsonB>(
+        "SELECT plts.decode_source_map($1, 1, 14)",
+        &[mapped_hash.as_str().into()],
+    )
+    .expect("decode_source_map should succeed")
+    .expect("decode_source_map should resolve a mapped position");
+    assert_eq!(decoded.0.get("original_line").and_then(Value::as_i64), Some(1));
+    assert!(decoded.0.get("original_column").and_then(Value::as_i64).is_some_and(|col| col >= 1));
+    assert!(
+        decoded.0.get("source").and_then(Value::as_str).is_some_and(|source| !source.is_empty())
+    );
+
+    let unmapped_hash = Spi::get_one::<String>(
+        "SELECT plts.compile_and_store('export const answer: number = 42;', '{}'::jsonb)",
+    )
+    .expect("compile_and_store without source maps should succeed")
+    .expect("compile_and_store should return an artifact hash");
+    let missing = Spi::get_one_with_args::<bool>(
+        "SELECT plts.decode_source_map($1, 1, 14) IS NULL",
+        &[unmapped_hash.as_str().into()],
+    )
+    .expect("decode_source_map without a stored map should succeed");
+    assert_eq!(missing, Some(true));
+}
+
+#[pg_test]
+fn test_purge_artifacts_removes_only_old_unreferenced_rows() {
+    Spi::run(
+        r#"
+        INSERT INTO plts.artifact (
+            artifact_hash, source_ts, compiled_js, compiler_opts, compiler_fingerprint, created_at
+        )
+        VALUES
+            ('sha256:purge-orphan', '', 'export default 1;', '{}'::jsonb, 'test', now() - interval '30 days'),
+            ('sha256:purge-live', '', 'export default 2;', '{}'::jsonb, 'test', now() - interval '30 days'),
+            ('sha256:purge-inline', '', 'export default 4;', '{}'::jsonb, 'test', now() - interval '30 days'),
+            ('sha256:purge-mapped', '', 'export default 5;', '{}'::jsonb, 'test', now() - interval '30 days'),
+            ('sha256:purge-recent', '', 'export default 3;', '{}'::jsonb, 'test', now());
+
+        DROP SCHEMA IF EXISTS plts_purge_artifacts_it CASCADE;
+        CREATE SCHEMA plts_purge_artifacts_it;
+        CREATE OR REPLACE FUNCTION plts_purge_artifacts_it.live(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$ {"plts":1,"kind":"artifact_ptr","artifact_hash":"sha256:purge-live","export":"default"} $$;
+        CREATE OR REPLACE FUNCTION plts_purge_artifacts_it.inline(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        // plts-import-map: {"@app/mapped":"sha256:purge-mapped"}
+        import inlined from "plts+artifact:sha256:purge-inline";
+        import mapped from "@app/mapped";
+        export default () => ({ total: inlined + mapped });
+        $$;
+        "#,
+    )
+    .expect("purge_artifacts setup SQL should succeed");
+
+    let purged = Spi::get_one::<i64>("SELECT plts.purge_artifacts(interval '7 days')")
+        .expect("purge_artifacts should succeed");
+    assert_eq!(purged, Some(1));
+
+    let remaining = Spi::get_one::<String>(
+        "
+        SELECT string_agg(artifact_hash, ',' ORDER BY artifact_hash)
+        FROM plts.artifact
+        WHERE artifact_hash LIKE 'sha256:purge-%'
+        ",
+    )
+    .expect("remaining artifact query should succeed");
+    assert_eq!(
+        remaining.as_deref(),
+        Some("sha256:purge-inline,sha256:purge-live,sha256:purge-mapped,sha256:purge-recent")
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_purge_artifacts_it CASCADE;")
+        .expect("purge_artifacts teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_artifact_usage_counts_live_pointer_references() {
+    Spi::run(
+        r#"
+        INSERT INTO plts.artifact (artifact_hash, source_ts, compiled_js, compiler_opts, compiler_fingerprint)
+        VALUES
+            ('sha256:usage-deployed', '', 'export default 1;', '{}'::jsonb, 'test'),
+            ('sha256:usage-unused', '', 'export default 2;', '{}'::jsonb, 'test');
+
+        DROP SCHEMA IF EXISTS plts_artifact_usage_it CASCADE;
+        CREATE SCHEMA plts_artifact_usage_it;
+        CREATE OR REPLACE FUNCTION plts_artifact_usage_it.deployed(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$ {"plts":1,"kind":"artifact_ptr","artifact_hash":"sha256:usage-deployed","export":"default"} $$;
+        "#,
+    )
+    .expect("artifact_usage setup SQL should succeed");
+
+    let deployed_refs = Spi::get_one::<i64>(
+        "SELECT ref_count FROM plts.artifact_usage() WHERE artifact_hash = 'sha256:usage-deployed'",
+    )
+    .expect("artifact_usage query should succeed")
+    .expect("deployed artifact should be listed");
+    assert!(deployed_refs >= 1, "deployed artifact should be referenced, got {deployed_refs}");
+
+    let unused_refs = Spi::get_one::<i64>(
+        "SELECT ref_count FROM plts.artifact_usage() WHERE artifact_hash = 'sha256:usage-unused'",
+    )
+    .expect("artifact_usage query should succeed");
+    assert_eq!(unused_refs, Some(0));
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_artifact_usage_it CASCADE;
+        DELETE FROM plts.artifact WHERE artifact_hash LIKE 'sha256:usage-%';
+        ",
+    )
+    .expect("artifact_usage teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_validate_import_map_checks_targets() {
+    let artifact_hash = Spi::get_one::<String>(
+        "SELECT plts.compile_and_store('export const two = 2;', '{}'::jsonb)",
+    )
+    .expect("compile_and_store query should succeed")
+    .expect("compile_and_store should return an artifact hash");
+
+    Spi::run_with_args(
+        "SELECT plts.validate_import_map(jsonb_build_object('@app/two', $1::text, '@app/data', 'data:text/javascript,export default 1;'))",
+        &[artifact_hash.as_str().into()],
+    )
+    .expect("import map with known targets should validate");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts.validate_import_map('{"@app/typo": "sha256:does-not-exist"}'::jsonb);
+            RAISE EXCEPTION 'expected unknown artifact to be rejected';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('import map entry `@app/typo` points at unknown artifact' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("unknown artifact target should fail validation");
+}
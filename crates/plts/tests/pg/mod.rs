@@ -8,21 +8,39 @@ include!("arg_conversion.rs");
 include!("artifact_catalog.rs");
 include!("metrics.rs");
 include!("runtime_performance_baseline.rs");
+include!("settings.rs");
+include!("typecheck.rs");
 #[cfg(feature = "v8_runtime")]
 include!("runtime_artifact_pointer.rs");
 #[cfg(feature = "v8_runtime")]
 include!("runtime_async.rs");
 #[cfg(feature = "v8_runtime")]
+include!("runtime_cancel.rs");
+#[cfg(feature = "v8_runtime")]
+include!("runtime_compile_preamble.rs");
+#[cfg(feature = "v8_runtime")]
 include!("runtime_contract.rs");
 #[cfg(feature = "v8_runtime")]
 include!("runtime_db_input_forms.rs");
 #[cfg(feature = "v8_runtime")]
+include!("runtime_invoke_batch.rs");
+#[cfg(feature = "v8_runtime")]
 include!("runtime_module_imports.rs");
 #[cfg(feature = "v8_runtime")]
 include!("runtime_nulls.rs");
 #[cfg(feature = "v8_runtime")]
+include!("runtime_pool_metrics.rs");
+#[cfg(feature = "v8_runtime")]
 include!("runtime_readiness_baseline.rs");
 #[cfg(feature = "v8_runtime")]
+include!("runtime_return_types.rs");
+#[cfg(feature = "v8_runtime")]
+include!("runtime_self_test.rs");
+#[cfg(feature = "v8_runtime")]
 include!("runtime_stopgap_wrappers.rs");
 #[cfg(feature = "v8_runtime")]
 include!("runtime_surface_lockdown.rs");
+#[cfg(feature = "v8_runtime")]
+include!("runtime_termination.rs");
+#[cfg(feature = "v8_runtime")]
+include!("runtime_triggers.rs");
@@ -110,3 +110,151 @@ fn test_artifact_pointer_supports_named_export_entrypoint() {
     Spi::run("DROP SCHEMA IF EXISTS plts_runtime_ptr_named_it CASCADE;")
         .expect("named-export artifact-pointer teardown SQL should succeed");
 }
+
+#[pg_test]
+fn test_artifact_pointer_limits_override_max_query_rows() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_runtime_ptr_limits_it CASCADE;
+        CREATE SCHEMA plts_runtime_ptr_limits_it;
+        SET LOCAL plts.max_query_rows = '100';
+        ",
+    )
+    .expect("artifact-pointer limits setup SQL should succeed");
+
+    let source = "export default async (ctx: any) => ({ rows: (await ctx.db.query('SELECT * FROM generate_series(1, 3)')).length });";
+    let artifact_hash = Spi::get_one_with_args::<String>(
+        "SELECT plts.compile_and_store($1::text, '{}'::jsonb)",
+        &[source.into()],
+    )
+    .expect("compile_and_store query should succeed")
+    .expect("compile_and_store should return artifact hash");
+
+    let pointer = json!({
+        "plts": 1,
+        "kind": "artifact_ptr",
+        "artifact_hash": artifact_hash,
+        "export": "default",
+        "mode": "stopgap_deployed",
+        "limits": { "max_query_rows": 2 }
+    })
+    .to_string()
+    .replace('\'', "''");
+
+    Spi::run(&format!(
+        "
+        CREATE OR REPLACE FUNCTION plts_runtime_ptr_limits_it.ptr_fn(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$ {} $$;
+        ",
+        pointer
+    ))
+    .expect("pointer function creation SQL should succeed");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_ptr_limits_it.ptr_fn('{}'::jsonb);
+            RAISE EXCEPTION 'expected pointer max_query_rows limit to apply';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('db.query returned more than 2 rows' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("pointer limits should cap db.query rows");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_ptr_limits_it CASCADE;")
+        .expect("artifact-pointer limits teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_ctx_describe_reads_deployed_query_handler_metadata() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_runtime_describe_it CASCADE;
+        CREATE SCHEMA plts_runtime_describe_it;
+        ",
+    )
+    .expect("describe setup schema SQL should succeed");
+
+    let source = r#"
+        import { query } from "@stopgap/runtime";
+
+        export default query(
+            { type: "object", properties: { id: { type: "integer" } }, required: ["id"] },
+            async (args: any) => ({ id: args.id })
+        );
+    "#;
+    let artifact_hash = Spi::get_one_with_args::<String>(
+        "SELECT plts.compile_and_store($1::text, '{}'::jsonb)",
+        &[source.into()],
+    )
+    .expect("compile_and_store query should succeed")
+    .expect("compile_and_store should return artifact hash");
+
+    let pointer = json!({
+        "plts": 1,
+        "kind": "artifact_ptr",
+        "artifact_hash": artifact_hash,
+        "export": "default",
+        "mode": "stopgap_deployed"
+    })
+    .to_string()
+    .replace('\'', "''");
+
+    let create_sql = format!(
+        "
+        CREATE OR REPLACE FUNCTION plts_runtime_describe_it.get_item(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$ {} $$;
+        CREATE OR REPLACE FUNCTION plts_runtime_describe_it.dispatch(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => ctx.describe('plts_runtime_describe_it', 'get_item');
+        $$;
+        ",
+        pointer
+    );
+    Spi::run(create_sql.as_str()).expect("describe function creation SQL should succeed");
+
+    let payload = Spi::get_one::<JsonB>("SELECT plts_runtime_describe_it.dispatch('{}'::jsonb)")
+        .expect("describing handler invocation should succeed")
+        .expect("describing handler should return jsonb");
+
+    assert_eq!(
+        payload.0,
+        json!({
+            "kind": "query",
+            "args_schema": {
+                "type": "object",
+                "properties": { "id": { "type": "integer" } },
+                "required": ["id"]
+            }
+        })
+    );
+
+    let described = Spi::get_one::<JsonB>(
+        "SELECT plts.describe('plts_runtime_describe_it.get_item(jsonb)'::regprocedure)",
+    )
+    .expect("plts.describe query should succeed")
+    .expect("plts.describe should return jsonb for a deployed handler");
+    assert_eq!(described.0, payload.0);
+
+    let plain = Spi::get_one::<JsonB>(
+        "SELECT plts.describe('plts_runtime_describe_it.dispatch(jsonb)'::regprocedure)",
+    )
+    .expect("plts.describe query should succeed")
+    .expect("plts.describe should return jsonb for a plain handler");
+    assert_eq!(plain.0, json!({ "kind": null, "args_schema": null }));
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_describe_it CASCADE;")
+        .expect("describe teardown SQL should succeed");
+}
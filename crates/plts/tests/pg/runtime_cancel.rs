@@ -0,0 +1,62 @@
+#[pg_test]
+fn test_runtime_cancel_signal_interrupts_busy_loop_handler() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_cancel_it CASCADE;
+        CREATE SCHEMA plts_cancel_it;
+        CREATE OR REPLACE FUNCTION plts_cancel_it.spin(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => {
+            if (ctx.args.warmup) return 'warm';
+            while (true) {}
+        };
+        $$;
+        SELECT set_config('statement_timeout', '0', true);
+        SELECT set_config('plts.max_runtime_ms', '10000', true);
+        ",
+    )
+    .expect("cancel setup SQL should succeed");
+
+    let warm = Spi::get_one::<JsonB>("SELECT plts_cancel_it.spin('{\"warmup\": true}'::jsonb)")
+        .expect("warmup call should succeed")
+        .expect("warmup call should return jsonb");
+    assert_eq!(warm.0, json!("warm"));
+
+    // `pg_cancel_backend` delivers SIGINT to the target backend; send it to ourselves while the
+    // handler spins. Without a working cancel path the call ends on plts.max_runtime_ms instead.
+    let backend_pid = unsafe { pg_sys::MyProcPid };
+    let cancel_delay = std::time::Duration::from_millis(300);
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(cancel_delay);
+        std::process::Command::new("kill").args(["-INT", &backend_pid.to_string()]).status()
+    });
+
+    let started_at = std::time::Instant::now();
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_cancel_it.spin('{}'::jsonb);
+            RAISE EXCEPTION 'expected cancel to interrupt the handler';
+        EXCEPTION
+            WHEN query_canceled THEN
+                NULL;
+        END;
+        $$;
+        "#,
+    )
+    .expect("cancel should surface as query_canceled");
+    let elapsed = started_at.elapsed();
+
+    let status = canceller.join().expect("canceller thread should not panic");
+    assert!(status.is_ok_and(|status| status.success()), "kill -INT should succeed");
+    assert!(
+        elapsed < cancel_delay + std::time::Duration::from_secs(2),
+        "cancel should interrupt the handler promptly, took {elapsed:?}"
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_cancel_it CASCADE;")
+        .expect("cancel teardown SQL should succeed");
+}
@@ -0,0 +1,102 @@
+#[pg_test]
+fn test_compile_preamble_globals_are_visible_to_handlers() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_preamble_it CASCADE;
+        CREATE SCHEMA plts_preamble_it;
+        SELECT set_config(
+            'plts.compile_preamble',
+            '(globalThis as any).plts_preamble_greet = (name: string) => ''hi '' + name;',
+            true
+        );
+        CREATE OR REPLACE FUNCTION plts_preamble_it.greet(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => ({ greeting: (globalThis as any).plts_preamble_greet(ctx.args.name) });
+        $$;
+        ",
+    )
+    .expect("preamble setup SQL should succeed");
+
+    let payload =
+        Spi::get_one::<JsonB>("SELECT plts_preamble_it.greet('{\"name\": \"ada\"}'::jsonb)")
+            .expect("preamble handler call should succeed")
+            .expect("preamble handler should return jsonb");
+    assert_eq!(payload.0, json!({ "greeting": "hi ada" }));
+
+    let fingerprint = Spi::get_one::<String>(
+        "SELECT compiler_fingerprint FROM plts.compile_ts('export default () => 1;')",
+    )
+    .expect("compile_ts should succeed")
+    .expect("compile_ts should return a fingerprint");
+    assert!(
+        fingerprint.contains(";preamble_sha256@"),
+        "fingerprint should reflect the preamble: {fingerprint}"
+    );
+
+    Spi::run("SELECT set_config('plts.compile_preamble', 'export const leaked = 1;', true)")
+        .expect("preamble override should succeed");
+    let diagnostics =
+        Spi::get_one::<JsonB>("SELECT diagnostics FROM plts.compile_ts('export default () => 1;')")
+            .expect("compile_ts should succeed")
+            .expect("compile_ts should return diagnostics");
+    assert!(
+        diagnostics
+            .0
+            .to_string()
+            .contains("plts.compile_preamble must not contain import or export"),
+        "an exporting preamble should be rejected: {}",
+        diagnostics.0
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_preamble_it CASCADE;")
+        .expect("preamble teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_changing_compile_preamble_recompiles_cached_inline_functions() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_preamble_cache_it CASCADE;
+        CREATE SCHEMA plts_preamble_cache_it;
+        SELECT set_config(
+            'plts.compile_preamble',
+            '(globalThis as any).plts_preamble_version = ''first'';',
+            true
+        );
+        CREATE OR REPLACE FUNCTION plts_preamble_cache_it.version(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default () => ({ version: (globalThis as any).plts_preamble_version });
+        $$;
+        ",
+    )
+    .expect("preamble cache setup SQL should succeed");
+
+    let first = Spi::get_one::<JsonB>("SELECT plts_preamble_cache_it.version('{}'::jsonb)")
+        .expect("first preamble call should succeed")
+        .expect("first preamble call should return jsonb");
+    assert_eq!(first.0, json!({ "version": "first" }));
+
+    Spi::run(
+        "SELECT set_config(
+            'plts.compile_preamble',
+            '(globalThis as any).plts_preamble_version = ''second'';',
+            true
+        )",
+    )
+    .expect("preamble change should succeed");
+    let second = Spi::get_one::<JsonB>("SELECT plts_preamble_cache_it.version('{}'::jsonb)")
+        .expect("second preamble call should succeed")
+        .expect("second preamble call should return jsonb");
+    assert_eq!(
+        second.0,
+        json!({ "version": "second" }),
+        "the cached program built under the old preamble should not be reused"
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_preamble_cache_it CASCADE;")
+        .expect("preamble cache teardown SQL should succeed");
+}
@@ -141,6 +141,121 @@ fn test_runtime_contract_invocation_state_is_isolated() {
         .expect("runtime contract isolation teardown SQL should succeed");
 }
 
+#[pg_test]
+fn test_runtime_contract_memo_runs_factory_once_per_key_within_a_call() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_contract_memo_it CASCADE;
+        CREATE SCHEMA plts_runtime_contract_memo_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_contract_memo_it.memo_shape(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => {
+            let calls = 0;
+            const factory = () => {
+                calls += 1;
+                return ctx.args.id;
+            };
+            const first = ctx.memo("id", factory);
+            const second = ctx.memo("id", factory);
+            const other = ctx.memo("other", () => {
+                calls += 1;
+                return "other";
+            });
+            return { first, second, other, calls };
+        };
+        $$;
+        "#,
+    )
+    .expect("runtime contract memo setup SQL should succeed");
+
+    let first = Spi::get_one::<JsonB>(
+        "SELECT plts_runtime_contract_memo_it.memo_shape('{\"id\": 1}'::jsonb)",
+    )
+    .expect("first runtime contract memo invocation should succeed")
+    .expect("first runtime contract memo invocation should return jsonb");
+
+    let second = Spi::get_one::<JsonB>(
+        "SELECT plts_runtime_contract_memo_it.memo_shape('{\"id\": 2}'::jsonb)",
+    )
+    .expect("second runtime contract memo invocation should succeed")
+    .expect("second runtime contract memo invocation should return jsonb");
+
+    assert_eq!(first.0, json!({ "first": 1, "second": 1, "other": "other", "calls": 2 }));
+    assert_eq!(second.0, json!({ "first": 2, "second": 2, "other": "other", "calls": 2 }));
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_contract_memo_it CASCADE;")
+        .expect("runtime contract memo teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_contract_log_does_not_interrupt_handler() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_contract_log_it CASCADE;
+        CREATE SCHEMA plts_runtime_contract_log_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_contract_log_it.log_shape(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => {
+            ctx.log.info("starting log_shape");
+            ctx.log.warn({ id: ctx.args.id });
+            return { ok: true, id: ctx.args.id };
+        };
+        $$;
+        "#,
+    )
+    .expect("runtime contract log setup SQL should succeed");
+
+    let result = Spi::get_one::<JsonB>(
+        "SELECT plts_runtime_contract_log_it.log_shape('{\"id\": 3}'::jsonb)",
+    )
+    .expect("runtime contract log invocation should succeed")
+    .expect("runtime contract log invocation should return jsonb");
+
+    assert_eq!(result.0, json!({ "ok": true, "id": 3 }));
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_contract_log_it CASCADE;")
+        .expect("runtime contract log teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_contract_console_shim_routes_to_postgres_log() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_contract_console_it CASCADE;
+        CREATE SCHEMA plts_runtime_contract_console_it;
+        SET LOCAL plts.enable_console = on;
+        CREATE OR REPLACE FUNCTION plts_runtime_contract_console_it.console_shape(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => {
+            console.log("console_shape", ctx.args);
+            console.debug("debug line");
+            console.warn({ id: ctx.args.id });
+            console.error("error line");
+            return { ok: true, id: ctx.args.id };
+        };
+        $$;
+        "#,
+    )
+    .expect("runtime contract console setup SQL should succeed");
+
+    let result = Spi::get_one::<JsonB>(
+        "SELECT plts_runtime_contract_console_it.console_shape('{\"id\": 4}'::jsonb)",
+    )
+    .expect("runtime contract console invocation should succeed")
+    .expect("runtime contract console invocation should return jsonb");
+
+    assert_eq!(result.0, json!({ "ok": true, "id": 4 }));
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_contract_console_it CASCADE;")
+        .expect("runtime contract console teardown SQL should succeed");
+}
+
 #[pg_test]
 fn test_runtime_contract_cross_fn_isolation() {
     Spi::run(
@@ -188,3 +303,88 @@ fn test_runtime_contract_cross_fn_isolation() {
     Spi::run("DROP SCHEMA IF EXISTS plts_runtime_contract_cross_fn_it CASCADE;")
         .expect("cross-function isolation teardown SQL should succeed");
 }
+
+#[pg_test]
+fn test_runtime_contract_error_reports_stack_as_detail_and_function_hint() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_contract_error_it CASCADE;
+        CREATE SCHEMA plts_runtime_contract_error_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_contract_error_it.fails(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        function explode() {
+            throw new Error("contract boom");
+        }
+        export default () => explode();
+        $$;
+        "#,
+    )
+    .expect("runtime contract error setup SQL should succeed");
+
+    Spi::run(
+        r#"
+        DO $$
+        DECLARE
+            error_detail text;
+            error_hint text;
+        BEGIN
+            PERFORM plts_runtime_contract_error_it.fails('{}'::jsonb);
+            RAISE EXCEPTION 'expected runtime error';
+        EXCEPTION
+            WHEN OTHERS THEN
+                GET STACKED DIAGNOSTICS
+                    error_detail = PG_EXCEPTION_DETAIL,
+                    error_hint = PG_EXCEPTION_HINT;
+                IF POSITION('contract boom' IN SQLERRM) = 0
+                    OR POSITION('stack=' IN SQLERRM) > 0
+                    OR POSITION('explode' IN error_detail) = 0
+                    OR POSITION('plts_runtime_contract_error_it.fails' IN error_hint) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("runtime errors should carry the stack as DETAIL and the function as HINT");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_contract_error_it CASCADE;")
+        .expect("runtime contract error teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_contract_exposes_fn_args_metadata() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_contract_fn_args_it CASCADE;
+        CREATE SCHEMA plts_runtime_contract_fn_args_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_contract_fn_args_it.describe(
+            customer_id int8,
+            note text
+        )
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => ctx.fn.args;
+        $$;
+        "#,
+    )
+    .expect("runtime contract fn args setup SQL should succeed");
+
+    let args =
+        Spi::get_one::<JsonB>("SELECT plts_runtime_contract_fn_args_it.describe(7, 'hello')")
+            .expect("runtime contract fn args invocation should succeed")
+            .expect("runtime contract fn args function should return jsonb");
+
+    assert_eq!(
+        args.0,
+        json!([
+            { "name": "customer_id", "type_oid": 20, "type_name": "bigint" },
+            { "name": "note", "type_oid": 25, "type_name": "text" }
+        ])
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_contract_fn_args_it CASCADE;")
+        .expect("runtime contract fn args teardown SQL should succeed");
+}
@@ -82,6 +82,226 @@ fn test_runtime_db_exec_accepts_to_sql_input() {
         .expect("runtime toSQL exec teardown SQL should succeed");
 }
 
+#[pg_test]
+fn test_runtime_db_batch_runs_statements_in_order() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_db_batch_it CASCADE;
+        CREATE SCHEMA plts_runtime_db_batch_it;
+        CREATE TABLE plts_runtime_db_batch_it.items(id int4);
+        CREATE OR REPLACE FUNCTION plts_runtime_db_batch_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async (_ctx: any) => {
+            const result = await _ctx.db.batch([
+                { sql: "INSERT INTO plts_runtime_db_batch_it.items(id) VALUES ($1)", params: [1] },
+                { sql: "UPDATE plts_runtime_db_batch_it.items SET id = id + $1", params: [10] }
+            ]);
+            const rows = await _ctx.db.query("SELECT id FROM plts_runtime_db_batch_it.items");
+            return { count: result.count, id: rows[0]?.id ?? null };
+        };
+        $$;
+        "#,
+    )
+    .expect("runtime db batch setup SQL should succeed");
+
+    let payload = Spi::get_one::<JsonB>("SELECT plts_runtime_db_batch_it.wrapped('{}'::jsonb)")
+        .expect("db batch invocation should succeed")
+        .expect("db batch should return jsonb");
+
+    assert_eq!(payload.0.get("count").and_then(Value::as_i64), Some(2));
+    assert_eq!(payload.0.get("id").and_then(Value::as_i64), Some(11));
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_db_batch_it CASCADE;")
+        .expect("runtime db batch teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_db_query_row_returns_single_row_or_null() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_db_query_row_it CASCADE;
+        CREATE SCHEMA plts_runtime_db_query_row_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_db_query_row_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async (_ctx: any) => {
+            const row = await _ctx.db.queryRow("SELECT $1::int4 AS id", [7]);
+            const missing = await _ctx.db.queryRow("SELECT 1 AS id WHERE false");
+            return { id: row?.id ?? null, missing };
+        };
+        $$;
+        CREATE OR REPLACE FUNCTION plts_runtime_db_query_row_it.too_many(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async (_ctx: any) => {
+            return await _ctx.db.queryRow("SELECT gs AS id FROM generate_series(1, 2) AS gs");
+        };
+        $$;
+        "#,
+    )
+    .expect("runtime db queryRow setup SQL should succeed");
+
+    let payload = Spi::get_one::<JsonB>("SELECT plts_runtime_db_query_row_it.wrapped('{}'::jsonb)")
+        .expect("db queryRow invocation should succeed")
+        .expect("db queryRow should return jsonb");
+
+    assert_eq!(payload.0.get("id").and_then(Value::as_i64), Some(7));
+    assert_eq!(payload.0.get("missing"), Some(&Value::Null));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_db_query_row_it.too_many('{}'::jsonb);
+            RAISE EXCEPTION 'expected queryRow rejection for multiple rows';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('db.queryRow expected at most one row, got 2' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("db queryRow should reject multi-row results");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_db_query_row_it CASCADE;")
+        .expect("runtime db queryRow teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_db_query_value_returns_first_column_scalar() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_db_query_value_it CASCADE;
+        CREATE SCHEMA plts_runtime_db_query_value_it;
+        CREATE TABLE plts_runtime_db_query_value_it.items(id int4);
+        INSERT INTO plts_runtime_db_query_value_it.items(id) VALUES (1), (2), (3);
+        CREATE OR REPLACE FUNCTION plts_runtime_db_query_value_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async (_ctx: any) => {
+            const count = await _ctx.db.queryValue(
+                "SELECT count(*)::int4 AS total FROM plts_runtime_db_query_value_it.items WHERE id >= $1",
+                [2]
+            );
+            return { count };
+        };
+        $$;
+        CREATE OR REPLACE FUNCTION plts_runtime_db_query_value_it.no_rows(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async (_ctx: any) => {
+            return await _ctx.db.queryValue("SELECT id FROM plts_runtime_db_query_value_it.items WHERE false");
+        };
+        $$;
+        "#,
+    )
+    .expect("runtime db queryValue setup SQL should succeed");
+
+    let payload =
+        Spi::get_one::<JsonB>("SELECT plts_runtime_db_query_value_it.wrapped('{}'::jsonb)")
+            .expect("db queryValue invocation should succeed")
+            .expect("db queryValue should return jsonb");
+
+    assert_eq!(payload.0.get("count").and_then(Value::as_i64), Some(2));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_db_query_value_it.no_rows('{}'::jsonb);
+            RAISE EXCEPTION 'expected queryValue rejection for zero rows';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('db.queryValue expected exactly one row, got 0' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("db queryValue should reject empty results");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_db_query_value_it CASCADE;")
+        .expect("runtime db queryValue teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_db_query_binds_array_params_for_any() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_db_array_param_it CASCADE;
+        CREATE SCHEMA plts_runtime_db_array_param_it;
+        CREATE TABLE plts_runtime_db_array_param_it.items(id int4, name text);
+        INSERT INTO plts_runtime_db_array_param_it.items(id, name)
+        VALUES (1, 'one'), (2, 'two'), (3, 'three'), (4, 'four');
+        CREATE OR REPLACE FUNCTION plts_runtime_db_array_param_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async (_ctx: any) => {
+            const byId = await _ctx.db.query(
+                "SELECT id FROM plts_runtime_db_array_param_it.items WHERE id = ANY($1) ORDER BY id",
+                [{ "@array": [2, 4, null], element_type: "int4" }]
+            );
+            const byName = await _ctx.db.query(
+                "SELECT id FROM plts_runtime_db_array_param_it.items WHERE name = ANY($1) ORDER BY id",
+                [{ "@array": ["one", "three"], element_type: "text" }]
+            );
+            return { byId: byId.map((row: any) => row.id), byName: byName.map((row: any) => row.id) };
+        };
+        $$;
+        CREATE OR REPLACE FUNCTION plts_runtime_db_array_param_it.bad_element(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async (_ctx: any) => {
+            return await _ctx.db.query(
+                "SELECT id FROM plts_runtime_db_array_param_it.items WHERE id = ANY($1)",
+                [{ "@array": [1, "two"], element_type: "int4" }]
+            );
+        };
+        $$;
+        "#,
+    )
+    .expect("runtime db array param setup SQL should succeed");
+
+    let payload =
+        Spi::get_one::<JsonB>("SELECT plts_runtime_db_array_param_it.wrapped('{}'::jsonb)")
+            .expect("db array param invocation should succeed")
+            .expect("db array param query should return jsonb");
+
+    assert_eq!(payload.0.get("byId"), Some(&json!([2, 4])));
+    assert_eq!(payload.0.get("byName"), Some(&json!([1, 3])));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_db_array_param_it.bad_element('{}'::jsonb);
+            RAISE EXCEPTION 'expected array param rejection';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('db.query param $1 is invalid: array element 1 is not a valid int4' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("db query should reject array params with mistyped elements");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_db_array_param_it CASCADE;")
+        .expect("runtime db array param teardown SQL should succeed");
+}
+
 #[pg_test]
 fn test_runtime_db_query_enforces_max_query_rows_limit() {
     Spi::run(
@@ -122,6 +342,47 @@ fn test_runtime_db_query_enforces_max_query_rows_limit() {
         .expect("runtime query row limit teardown SQL should succeed");
 }
 
+#[pg_test]
+fn test_runtime_db_query_enforces_max_op_ms_limit() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_db_op_timeout_it CASCADE;
+        CREATE SCHEMA plts_runtime_db_op_timeout_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_db_op_timeout_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async (_ctx: any) => {
+            return await _ctx.db.query("SELECT pg_sleep(2) AS slept", []);
+        };
+        $$;
+        "#,
+    )
+    .expect("runtime op timeout setup SQL should succeed");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM set_config('plts.max_op_ms', '50', true);
+            PERFORM set_config('plts.max_runtime_ms', '10000', true);
+            PERFORM plts_runtime_db_op_timeout_it.wrapped('{}'::jsonb);
+            RAISE EXCEPTION 'expected per-op statement timeout';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('statement timeout' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("runtime per-op statement timeout should be enforced");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_db_op_timeout_it CASCADE;")
+        .expect("runtime op timeout teardown SQL should succeed");
+}
+
 #[pg_test]
 fn test_runtime_db_query_enforces_max_params_limit() {
     Spi::run(
@@ -161,3 +422,43 @@ fn test_runtime_db_query_enforces_max_params_limit() {
     Spi::run("DROP SCHEMA IF EXISTS plts_runtime_db_param_limit_it CASCADE;")
         .expect("runtime param limit teardown SQL should succeed");
 }
+
+#[pg_test]
+fn test_runtime_db_query_columnar_returns_columns_and_row_arrays() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_db_columnar_it CASCADE;
+        CREATE SCHEMA plts_runtime_db_columnar_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_db_columnar_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async (ctx: any) => {
+            const table = await ctx.db.queryColumnar(
+                "SELECT g AS z_id, 'item-' || g AS a_name, NULL::int4 AS missing FROM generate_series(1, $1::int4) g",
+                [2]
+            );
+            const empty = await ctx.db.queryColumnar("SELECT 1 AS id WHERE false");
+            return { table, empty };
+        };
+        $$;
+        "#,
+    )
+    .expect("runtime columnar query setup SQL should succeed");
+
+    let payload = Spi::get_one::<JsonB>("SELECT plts_runtime_db_columnar_it.wrapped('{}'::jsonb)")
+        .expect("columnar query invocation should succeed")
+        .expect("columnar query should return jsonb");
+
+    assert_eq!(
+        payload.0["table"],
+        json!({
+            "columns": ["z_id", "a_name", "missing"],
+            "rows": [[1, "item-1", null], [2, "item-2", null]]
+        })
+    );
+    assert_eq!(payload.0["empty"], json!({ "columns": [], "rows": [] }));
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_db_columnar_it CASCADE;")
+        .expect("runtime columnar query teardown SQL should succeed");
+}
@@ -0,0 +1,78 @@
+#[pg_test]
+fn test_invoke_batch_runs_query_handler_for_each_args_element() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_invoke_batch_it CASCADE;
+        CREATE SCHEMA plts_runtime_invoke_batch_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_invoke_batch_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        import { query } from "@stopgap/runtime";
+
+        export default query({ type: "object" }, async (args: any, ctx: any) => {
+            if (args.id === 2) {
+                throw new Error("id 2 is not allowed");
+            }
+            const rows = await ctx.db.query("SELECT $1::int * 10 AS scaled", [args.id]);
+            return { id: args.id, scaled: rows[0].scaled, dbMode: ctx.db.mode };
+        });
+        $$;
+        "#,
+    )
+    .expect("invoke batch setup SQL should succeed");
+
+    let payload = Spi::get_one::<JsonB>(
+        r#"
+        SELECT plts.invoke_batch(
+            'plts_runtime_invoke_batch_it.wrapped(jsonb)'::regprocedure::oid,
+            '[{"id": 1}, {"id": 2}, {"id": 3}]'::jsonb
+        )
+        "#,
+    )
+    .expect("invoke_batch should succeed")
+    .expect("invoke_batch should return jsonb");
+
+    let results = payload.0.as_array().expect("invoke_batch should return an array");
+    assert_eq!(results.len(), 3);
+
+    assert_eq!(results[0].get("ok").and_then(Value::as_bool), Some(true));
+    assert_eq!(results[0].pointer("/value/scaled").and_then(Value::as_i64), Some(10));
+    assert_eq!(results[0].pointer("/value/dbMode").and_then(Value::as_str), Some("ro"));
+
+    assert_eq!(results[1].get("ok").and_then(Value::as_bool), Some(false));
+    assert!(
+        results[1]
+            .pointer("/error/message")
+            .and_then(Value::as_str)
+            .is_some_and(|message| message.contains("id 2 is not allowed")),
+        "failed element should report its error positionally"
+    );
+
+    assert_eq!(results[2].get("ok").and_then(Value::as_bool), Some(true));
+    assert_eq!(results[2].pointer("/value/scaled").and_then(Value::as_i64), Some(30));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts.invoke_batch(
+                'plts_runtime_invoke_batch_it.wrapped(jsonb)'::regprocedure::oid,
+                '[{"id": 1}, {"id": 2}, {"id": 3}]'::jsonb,
+                true
+            );
+            RAISE EXCEPTION 'expected invoke_batch to abort on first error';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('id 2 is not allowed' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("invoke_batch with stop_on_error should abort on the failing element");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_invoke_batch_it CASCADE;")
+        .expect("invoke batch teardown SQL should succeed");
+}
@@ -434,3 +434,54 @@ fn test_runtime_rejects_unknown_artifact_module_specifier() {
     Spi::run("DROP SCHEMA IF EXISTS plts_runtime_module_missing_artifact_it CASCADE;")
         .expect("missing artifact module teardown SQL should succeed");
 }
+
+#[pg_test]
+fn test_resolve_imports_reports_mapped_and_unresolvable_specifiers() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_resolve_imports_it CASCADE;
+        CREATE SCHEMA plts_resolve_imports_it;
+        CREATE OR REPLACE FUNCTION plts_resolve_imports_it.imported(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        // plts-import-map: {"@pkg/math":"data:text/javascript;base64,ZXhwb3J0IGNvbnN0IGJhc2UgPSA0MDs="}
+        // @ts-ignore resolve_imports coverage test
+        import { base } from "@pkg/math";
+        // @ts-ignore resolve_imports coverage test
+        import "@pkg/missing";
+        export default () => ({ base });
+        $$;
+        "#,
+    )
+    .expect("resolve_imports setup SQL should succeed");
+
+    let resolution = Spi::get_one::<JsonB>(
+        "SELECT plts.resolve_imports('plts_resolve_imports_it.imported(jsonb)'::regprocedure)",
+    )
+    .expect("resolve_imports should succeed")
+    .expect("resolve_imports should return jsonb")
+    .0;
+
+    let entries = resolution.as_array().expect("resolve_imports should return an array");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        entries[0],
+        json!({
+            "specifier": "@pkg/math",
+            "resolved": "data:text/javascript;base64,ZXhwb3J0IGNvbnN0IGJhc2UgPSA0MDs=",
+            "kind": "data"
+        })
+    );
+    assert_eq!(entries[1]["specifier"], json!("@pkg/missing"));
+    assert_eq!(entries[1]["kind"], json!("error"));
+    assert!(entries[1]["resolved"].is_null());
+    assert!(
+        entries[1]["error"].as_str().is_some_and(
+            |message| message.contains("unsupported bare module import `@pkg/missing`")
+        )
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_resolve_imports_it CASCADE;")
+        .expect("resolve_imports teardown SQL should succeed");
+}
@@ -0,0 +1,147 @@
+#[pg_test]
+fn test_pool_metrics_reports_pool_counters_after_invocation() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_pool_metrics_it CASCADE;
+        CREATE SCHEMA plts_pool_metrics_it;
+        CREATE OR REPLACE FUNCTION plts_pool_metrics_it.ping(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default () => ({ ok: true });
+        $$;
+        "#,
+    )
+    .expect("pool metrics setup SQL should succeed");
+
+    Spi::get_one::<JsonB>("SELECT plts_pool_metrics_it.ping('{}'::jsonb)")
+        .expect("pool metrics handler invocation should succeed");
+
+    let metrics = Spi::get_one::<JsonB>("SELECT plts.pool_metrics()")
+        .expect("pool metrics query should succeed")
+        .expect("pool metrics should return jsonb")
+        .0;
+
+    for key in [
+        "hits",
+        "misses",
+        "cold_invocations",
+        "warm_invocations",
+        "retired",
+        "active_isolates",
+        "available_count",
+    ] {
+        assert!(
+            metrics.get(key).and_then(Value::as_u64).is_some(),
+            "pool metrics should report a non-negative `{key}`"
+        );
+    }
+    assert!(metrics["retire_reasons"].is_object());
+    assert!(metrics["config"]["max_pool_size"].as_u64().is_some());
+    assert!(
+        metrics["hits"].as_u64().unwrap_or(0) + metrics["cold_invocations"].as_u64().unwrap_or(0)
+            >= 1,
+        "the invocation should have checked out or created a runtime shell"
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_pool_metrics_it CASCADE;")
+        .expect("pool metrics teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_pool_records_hit_when_same_function_runs_twice() {
+    Spi::run(
+        r#"
+        SET LOCAL plts.isolate_reuse = 'on';
+        SET LOCAL plts.isolate_pool_size = '2';
+        DROP SCHEMA IF EXISTS plts_pool_reuse_it CASCADE;
+        CREATE SCHEMA plts_pool_reuse_it;
+        CREATE OR REPLACE FUNCTION plts_pool_reuse_it.ping(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default () => ({ ok: true });
+        $$;
+        "#,
+    )
+    .expect("pool reuse setup SQL should succeed");
+
+    let pool_counter = |key: &str| {
+        Spi::get_one::<JsonB>("SELECT plts.pool_metrics()")
+            .expect("pool metrics query should succeed")
+            .and_then(|metrics| metrics.0.get(key).and_then(Value::as_u64))
+            .unwrap_or(0)
+    };
+
+    Spi::get_one::<JsonB>("SELECT plts_pool_reuse_it.ping('{}'::jsonb)")
+        .expect("first invocation should succeed");
+    let hits_before = pool_counter("hits");
+    let warm_before = pool_counter("warm_invocations");
+
+    Spi::get_one::<JsonB>("SELECT plts_pool_reuse_it.ping('{}'::jsonb)")
+        .expect("second invocation should succeed");
+    assert!(pool_counter("hits") > hits_before, "second call should check out a pooled shell");
+    assert!(pool_counter("warm_invocations") > warm_before, "second call should run warm");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_pool_reuse_it CASCADE;")
+        .expect("pool reuse teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_isolate_metrics_reports_module_code_cache_hits_for_artifact_imports() {
+    let dependency_hash = Spi::get_one::<String>(
+        "SELECT plts.compile_and_store('export const base = 40;', '{}'::jsonb)",
+    )
+    .expect("dependency artifact compile should succeed")
+    .expect("dependency artifact hash should be present");
+
+    Spi::run(&format!(
+        r#"
+        DROP SCHEMA IF EXISTS plts_module_cache_it CASCADE;
+        CREATE SCHEMA plts_module_cache_it;
+        CREATE OR REPLACE FUNCTION plts_module_cache_it.imported(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        import {{ base }} from "plts+artifact:{dependency_hash}";
+        export default () => ({{ base }});
+        $$;
+        SELECT set_config('plts.isolate_reuse', 'on', true);
+        "#
+    ))
+    .expect("module cache setup SQL should succeed");
+
+    let module_cache = || {
+        Spi::get_one::<JsonB>("SELECT plts.isolate_metrics()->'module_cache'")
+            .expect("isolate metrics query should succeed")
+            .expect("isolate metrics should report module_cache")
+            .0
+    };
+    let before = module_cache();
+
+    // A reused isolate bumps its invocation nonce every call; the cache must still hit.
+    for _ in 0..3 {
+        let payload = Spi::get_one::<JsonB>("SELECT plts_module_cache_it.imported('{}'::jsonb)")
+            .expect("artifact import invocation should succeed")
+            .expect("artifact import invocation should return jsonb");
+        assert_eq!(payload.0, json!({ "base": 40 }));
+    }
+
+    let after = module_cache();
+    assert!(
+        after["hits"].as_u64().unwrap_or(0) >= before["hits"].as_u64().unwrap_or(0) + 2,
+        "later invocations should reuse cached module code: {after}"
+    );
+    assert!(after["entries"].as_u64().unwrap_or(0) >= 1);
+    assert!(after["max_entries"].as_u64().is_some_and(|max| max > 0));
+    assert!(
+        Spi::get_one::<bool>("SELECT plts.pool_metrics() ? 'module_cache'")
+            .expect("pool metrics query should succeed")
+            == Some(false),
+        "module cache counters belong to plts.isolate_metrics()"
+    );
+    assert!(after["bytes"].as_u64().unwrap_or(0) > 0);
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_module_cache_it CASCADE;")
+        .expect("module cache teardown SQL should succeed");
+}
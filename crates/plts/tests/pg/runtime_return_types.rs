@@ -0,0 +1,398 @@
+#[pg_test]
+fn test_runtime_coerces_return_value_to_declared_scalar_type() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_return_types_it CASCADE;
+        CREATE SCHEMA plts_return_types_it;
+        CREATE OR REPLACE FUNCTION plts_return_types_it.answer()
+        RETURNS int4
+        LANGUAGE plts
+        AS $$
+        export default () => 42;
+        $$;
+        CREATE OR REPLACE FUNCTION plts_return_types_it.greeting()
+        RETURNS text
+        LANGUAGE plts
+        AS $$
+        export default () => 'hello';
+        $$;
+        CREATE OR REPLACE FUNCTION plts_return_types_it.flag()
+        RETURNS boolean
+        LANGUAGE plts
+        AS $$
+        export default () => true;
+        $$;
+        CREATE OR REPLACE FUNCTION plts_return_types_it.not_a_number()
+        RETURNS int4
+        LANGUAGE plts
+        AS $$
+        export default () => 'forty-two';
+        $$;
+        ",
+    )
+    .expect("return type setup SQL should succeed");
+
+    let answer = Spi::get_one::<i32>("SELECT plts_return_types_it.answer() + 0")
+        .expect("int4 return query should succeed");
+    assert_eq!(answer, Some(42));
+
+    let greeting = Spi::get_one::<String>("SELECT plts_return_types_it.greeting()")
+        .expect("text return query should succeed");
+    assert_eq!(greeting.as_deref(), Some("hello"));
+
+    let flag = Spi::get_one::<bool>("SELECT plts_return_types_it.flag()")
+        .expect("bool return query should succeed");
+    assert_eq!(flag, Some(true));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_return_types_it.not_a_number();
+            RAISE EXCEPTION 'expected return type coercion failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('to declared return type int4' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("uncoercible return values should be rejected");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_return_types_it CASCADE;")
+        .expect("return type teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_rejects_unsupported_declared_return_types() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_return_unsupported_it CASCADE;
+        CREATE SCHEMA plts_return_unsupported_it;
+        ",
+    )
+    .expect("unsupported return type setup SQL should succeed");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            CREATE FUNCTION plts_return_unsupported_it.amount()
+            RETURNS numeric
+            LANGUAGE plts
+            AS $body$ export default () => 1.5; $body$;
+            RAISE EXCEPTION 'expected the validator to reject RETURNS numeric';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('return type numeric is not supported' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("validator should reject unsupported return types");
+
+    // With function bodies unchecked (e.g. during a restore) the validator is skipped, so the
+    // call itself must refuse to hand Postgres a jsonb datum for a numeric result.
+    Spi::run(
+        "
+        SET LOCAL check_function_bodies = off;
+        CREATE FUNCTION plts_return_unsupported_it.amount()
+        RETURNS numeric
+        LANGUAGE plts
+        AS $$ export default () => 1.5; $$;
+        RESET check_function_bodies;
+        ",
+    )
+    .expect("unchecked numeric function should be created");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_return_unsupported_it.amount();
+            RAISE EXCEPTION 'expected numeric return coercion failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('to declared return type numeric' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("numeric results should be rejected instead of returned as jsonb");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_return_unsupported_it CASCADE;")
+        .expect("unsupported return type teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_setof_function_returns_one_row_per_array_element() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_return_setof_it CASCADE;
+        CREATE SCHEMA plts_return_setof_it;
+        CREATE OR REPLACE FUNCTION plts_return_setof_it.rows()
+        RETURNS SETOF jsonb
+        LANGUAGE plts
+        AS $$
+        export default () => [{ a: 1 }, { a: 2 }];
+        $$;
+        CREATE OR REPLACE FUNCTION plts_return_setof_it.ids()
+        RETURNS SETOF int4
+        LANGUAGE plts
+        AS $$
+        export default () => [3, 4, 5];
+        $$;
+        CREATE OR REPLACE FUNCTION plts_return_setof_it.none()
+        RETURNS SETOF jsonb
+        LANGUAGE plts
+        AS $$
+        export default () => undefined;
+        $$;
+        ",
+    )
+    .expect("setof setup SQL should succeed");
+
+    let rows = Spi::get_one::<JsonB>(
+        "SELECT jsonb_agg(value ORDER BY (value->>'a')::int) FROM plts_return_setof_it.rows() AS value",
+    )
+    .expect("setof jsonb query should succeed")
+    .expect("setof jsonb query should return rows");
+    assert_eq!(rows.0, json!([{ "a": 1 }, { "a": 2 }]));
+
+    let id_sum = Spi::get_one::<i64>("SELECT sum(id) FROM plts_return_setof_it.ids() AS id")
+        .expect("setof int4 query should succeed");
+    assert_eq!(id_sum, Some(12));
+
+    let empty_count = Spi::get_one::<i64>("SELECT count(*) FROM plts_return_setof_it.none()")
+        .expect("empty setof query should succeed");
+    assert_eq!(empty_count, Some(0));
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_return_setof_it CASCADE;")
+        .expect("setof teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_streams_generator_rows_into_setof_results() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_return_stream_it CASCADE;
+        CREATE SCHEMA plts_return_stream_it;
+        CREATE OR REPLACE FUNCTION plts_return_stream_it.events()
+        RETURNS SETOF jsonb
+        LANGUAGE plts
+        AS $$
+        export default async function* () {
+            for (let seq = 1; seq <= 4; seq++) {
+                await Promise.resolve();
+                yield { seq, tags: new Set([`t${seq}`]) };
+            }
+        };
+        $$;
+        CREATE OR REPLACE FUNCTION plts_return_stream_it.ids()
+        RETURNS SETOF int4
+        LANGUAGE plts
+        AS $$
+        export default function* () {
+            yield 7;
+            yield 8;
+        };
+        $$;
+        CREATE OR REPLACE FUNCTION plts_return_stream_it.collected(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async function* () {
+            yield 'a';
+            yield 'b';
+        };
+        $$;
+        ",
+    )
+    .expect("streaming setof setup SQL should succeed");
+
+    let events = Spi::get_one::<JsonB>(
+        "SELECT jsonb_agg(value ORDER BY ord) FROM plts_return_stream_it.events() WITH ORDINALITY AS t(value, ord)",
+    )
+    .expect("async generator setof query should succeed")
+    .expect("async generator setof query should return rows");
+    assert_eq!(
+        events.0,
+        json!([
+            { "seq": 1, "tags": ["t1"] },
+            { "seq": 2, "tags": ["t2"] },
+            { "seq": 3, "tags": ["t3"] },
+            { "seq": 4, "tags": ["t4"] }
+        ])
+    );
+
+    let id_sum = Spi::get_one::<i64>("SELECT sum(id) FROM plts_return_stream_it.ids() AS id")
+        .expect("sync generator setof query should succeed");
+    assert_eq!(id_sum, Some(15));
+
+    let collected = Spi::get_one::<JsonB>("SELECT plts_return_stream_it.collected('{}'::jsonb)")
+        .expect("scalar generator query should succeed")
+        .expect("scalar generator query should return jsonb");
+    assert_eq!(collected.0, json!(["a", "b"]));
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_return_stream_it CASCADE;")
+        .expect("streaming setof teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_nested_invoke_batch_generator_does_not_write_into_outer_set() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_return_nested_it CASCADE;
+        CREATE SCHEMA plts_return_nested_it;
+        CREATE OR REPLACE FUNCTION plts_return_nested_it.inner(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default function* () {
+            yield 'inner-a';
+            yield 'inner-b';
+        };
+        $$;
+        CREATE OR REPLACE FUNCTION plts_return_nested_it.outer()
+        RETURNS SETOF jsonb
+        LANGUAGE plts
+        AS $$
+        export default async function* (ctx: any) {
+            yield { outer: 1 };
+            const batch = await ctx.db.queryValue(
+                "SELECT plts.invoke_batch('plts_return_nested_it.inner(jsonb)'::regprocedure::oid, '[{}]'::jsonb)"
+            );
+            yield { outer: 2, batch };
+        };
+        $$;
+        "#,
+    )
+    .expect("nested generator setup SQL should succeed");
+
+    let rows = Spi::get_one::<JsonB>(
+        "SELECT jsonb_agg(value ORDER BY ord) FROM plts_return_nested_it.outer() WITH ORDINALITY AS t(value, ord)",
+    )
+    .expect("outer setof query should succeed")
+    .expect("outer setof query should return rows");
+    assert_eq!(
+        rows.0,
+        json!([
+            { "outer": 1 },
+            { "outer": 2, "batch": [{ "ok": true, "value": ["inner-a", "inner-b"] }] }
+        ])
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_return_nested_it CASCADE;")
+        .expect("nested generator teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_serializes_map_and_set_results() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_return_collections_it CASCADE;
+        CREATE SCHEMA plts_return_collections_it;
+        CREATE OR REPLACE FUNCTION plts_return_collections_it.collections(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async () => ({
+            counts: new Map<string | number, unknown>([['a', 1], [2, new Set(['x', 'y'])]]),
+            tags: new Set(['t']),
+        });
+        $$;
+        CREATE OR REPLACE FUNCTION plts_return_collections_it.object_keys(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default () => new Map([[{ id: 1 }, 'one']]);
+        $$;
+        ",
+    )
+    .expect("map/set result setup SQL should succeed");
+
+    let payload =
+        Spi::get_one::<JsonB>("SELECT plts_return_collections_it.collections('{}'::jsonb)")
+            .expect("map/set result query should succeed")
+            .expect("map/set result should return jsonb");
+    assert_eq!(payload.0, json!({ "counts": { "a": 1, "2": ["x", "y"] }, "tags": ["t"] }));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_return_collections_it.object_keys('{}'::jsonb);
+            RAISE EXCEPTION 'expected Map key rejection';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('handler result Map at $ has a object key' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("Map results with object keys should be rejected");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_return_collections_it CASCADE;")
+        .expect("map/set result teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_runtime_include_timing_wraps_jsonb_results() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_return_timing_it CASCADE;
+        CREATE SCHEMA plts_return_timing_it;
+        CREATE OR REPLACE FUNCTION plts_return_timing_it.timed(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default async (ctx: any) => {
+            const first = await ctx.db.queryValue("SELECT 1 AS n");
+            await ctx.db.query("SELECT pg_sleep(0.01)");
+            return { first };
+        };
+        $$;
+        CREATE OR REPLACE FUNCTION plts_return_timing_it.count()
+        RETURNS int4
+        LANGUAGE plts
+        AS $$
+        export default () => 3;
+        $$;
+        "#,
+    )
+    .expect("timing envelope setup SQL should succeed");
+
+    let bare = Spi::get_one::<JsonB>("SELECT plts_return_timing_it.timed('{}'::jsonb)")
+        .expect("bare invocation should succeed")
+        .expect("bare invocation should return jsonb");
+    assert_eq!(bare.0, json!({ "first": 1 }));
+
+    Spi::run("SET LOCAL plts.include_timing = on").expect("enabling timing should succeed");
+
+    let envelope = Spi::get_one::<JsonB>("SELECT plts_return_timing_it.timed('{}'::jsonb)")
+        .expect("timed invocation should succeed")
+        .expect("timed invocation should return jsonb")
+        .0;
+    assert_eq!(envelope["@plts"], json!(1));
+    assert_eq!(envelope["result"], json!({ "first": 1 }));
+    assert_eq!(envelope["timing"]["db_calls"], json!(2));
+    let total_ms = envelope["timing"]["total_ms"].as_f64().expect("total_ms should be a number");
+    let db_ms = envelope["timing"]["db_ms"].as_f64().expect("db_ms should be a number");
+    assert!(db_ms >= 10.0, "db_ms should include the 10ms sleep, got {db_ms}");
+    assert!(total_ms >= db_ms, "total_ms {total_ms} should cover db_ms {db_ms}");
+
+    let scalar = Spi::get_one::<i32>("SELECT plts_return_timing_it.count()")
+        .expect("scalar invocation should succeed");
+    assert_eq!(scalar, Some(3));
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_return_timing_it CASCADE;")
+        .expect("timing envelope teardown SQL should succeed");
+}
@@ -0,0 +1,10 @@
+#[pg_test]
+fn test_runtime_self_test_evaluates_probe_module() {
+    let report = Spi::get_one::<JsonB>("SELECT plts.runtime_self_test()")
+        .expect("runtime self-test should succeed")
+        .expect("runtime self-test should return jsonb")
+        .0;
+
+    assert_eq!(report.get("available").and_then(Value::as_bool), Some(true));
+    assert_eq!(report.get("result"), Some(&json!({ "ok": true, "sum": 2 })));
+}
@@ -127,6 +127,102 @@ fn test_stopgap_query_wrapper_validates_json_schema() {
         .expect("stopgap schema validation teardown SQL should succeed");
 }
 
+#[pg_test]
+fn test_stopgap_query_wrapper_enforces_json_schema_numeric_bounds() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_stopgap_bounds_it CASCADE;
+        CREATE SCHEMA plts_runtime_stopgap_bounds_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_stopgap_bounds_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        import { query } from "@stopgap/runtime";
+
+        export default query({ type: "integer", minimum: 1 }, async (args: any, _ctx: any) => ({
+            quantity: args
+        }));
+        $$;
+        "#,
+    )
+    .expect("stopgap numeric bounds setup SQL should succeed");
+
+    let payload =
+        Spi::get_one::<JsonB>("SELECT plts_runtime_stopgap_bounds_it.wrapped('5'::jsonb)")
+            .expect("wrapped function invocation should succeed")
+            .expect("wrapped function should return jsonb");
+
+    assert_eq!(payload.0.get("quantity").and_then(Value::as_i64), Some(5));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_stopgap_bounds_it.wrapped('0'::jsonb);
+            RAISE EXCEPTION 'expected schema validation failure for zero quantity';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('args validation failed at $: expected >= 1, got 0' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("query wrapper should reject values below the schema minimum");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_stopgap_bounds_it CASCADE;")
+        .expect("stopgap numeric bounds teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_stopgap_query_wrapper_enforces_json_schema_string_pattern() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_stopgap_pattern_it CASCADE;
+        CREATE SCHEMA plts_runtime_stopgap_pattern_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_stopgap_pattern_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        import { query } from "@stopgap/runtime";
+
+        export default query({ type: "string", pattern: "^[a-z]+$" }, async (args: any, _ctx: any) => ({
+            slug: args
+        }));
+        $$;
+        "#,
+    )
+    .expect("stopgap string pattern setup SQL should succeed");
+
+    let payload =
+        Spi::get_one::<JsonB>("SELECT plts_runtime_stopgap_pattern_it.wrapped('\"abc\"'::jsonb)")
+            .expect("wrapped function invocation should succeed")
+            .expect("wrapped function should return jsonb");
+
+    assert_eq!(payload.0.get("slug").and_then(Value::as_str), Some("abc"));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_stopgap_pattern_it.wrapped('"ABC"'::jsonb);
+            RAISE EXCEPTION 'expected schema validation failure for uppercase slug';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('args validation failed at $: expected to match pattern ^[a-z]+$' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("query wrapper should reject strings that do not match the schema pattern");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_stopgap_pattern_it CASCADE;")
+        .expect("stopgap string pattern teardown SQL should succeed");
+}
+
 #[pg_test]
 fn test_stopgap_query_wrapper_rejects_write_sql_in_db_query() {
     Spi::run(
@@ -170,6 +266,64 @@ fn test_stopgap_query_wrapper_rejects_write_sql_in_db_query() {
         .expect("stopgap query write rejection teardown SQL should succeed");
 }
 
+#[pg_test]
+fn test_stopgap_mutation_wrapper_honors_read_only_call_option() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_stopgap_readonly_opt_it CASCADE;
+        CREATE SCHEMA plts_runtime_stopgap_readonly_opt_it;
+        CREATE TABLE plts_runtime_stopgap_readonly_opt_it.items(id int4);
+        CREATE OR REPLACE FUNCTION plts_runtime_stopgap_readonly_opt_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        import { mutation } from "@stopgap/runtime";
+
+        export default mutation({ type: "object" }, async (_args: any, ctx: any) => {
+            const before = await ctx.db.query(
+                "SELECT count(*)::int4 AS total FROM plts_runtime_stopgap_readonly_opt_it.items",
+                [],
+                { readOnly: true }
+            );
+            await ctx.db.query(
+                "WITH w AS (INSERT INTO plts_runtime_stopgap_readonly_opt_it.items(id) VALUES (1) RETURNING id) SELECT id FROM w",
+                [],
+                { readOnly: true }
+            );
+            return { before: before[0].total };
+        });
+        $$;
+        "#,
+    )
+    .expect("stopgap readOnly option setup SQL should succeed");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_stopgap_readonly_opt_it.wrapped('{}'::jsonb);
+            RAISE EXCEPTION 'expected write SQL rejection for readOnly call';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('db.query was called with { readOnly: true }' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("mutation wrapper should reject write SQL when a call passes readOnly");
+
+    let rows =
+        Spi::get_one::<i64>("SELECT count(*) FROM plts_runtime_stopgap_readonly_opt_it.items")
+            .expect("row count lookup should succeed")
+            .expect("row count should return a row");
+    assert_eq!(rows, 0, "the rejected readOnly call must not write");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_stopgap_readonly_opt_it CASCADE;")
+        .expect("stopgap readOnly option teardown SQL should succeed");
+}
+
 #[pg_test]
 fn test_stopgap_query_wrapper_allows_keyword_literals() {
     Spi::run(
@@ -306,3 +460,220 @@ fn test_stopgap_wrapper_mode_does_not_leak_between_reused_shells() {
     Spi::run("DROP SCHEMA IF EXISTS plts_runtime_stopgap_mode_reuse_it CASCADE;")
         .expect("wrapper mode reuse teardown SQL should succeed");
 }
+
+#[pg_test]
+fn test_stopgap_query_wrapper_validates_result_schema() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_stopgap_result_schema_it CASCADE;
+        CREATE SCHEMA plts_runtime_stopgap_result_schema_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_stopgap_result_schema_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        import { query, v } from "@stopgap/runtime";
+
+        export default query(
+            {
+                args: v.object({ id: v.int() }),
+                result: v.object({ id: v.int(), label: v.string() }),
+            },
+            async (args: any) => ({ id: args.id, label: args.id > 0 ? "positive" : args.id }),
+        );
+        $$;
+        "#,
+    )
+    .expect("stopgap result schema setup SQL should succeed");
+
+    let payload = Spi::get_one::<JsonB>(
+        "SELECT plts_runtime_stopgap_result_schema_it.wrapped('{\"id\": 5}'::jsonb)",
+    )
+    .expect("wrapped function invocation should succeed")
+    .expect("wrapped function should return jsonb");
+    assert_eq!(payload.0.get("label").and_then(Value::as_str), Some("positive"));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_stopgap_result_schema_it.wrapped('{"id": 0}'::jsonb);
+            RAISE EXCEPTION 'expected result schema rejection';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('stopgap result validation failed at $.label' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("query wrapper should reject results violating the result schema");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_stopgap_result_schema_it CASCADE;")
+        .expect("stopgap result schema teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_stopgap_assert_raises_with_chosen_sqlstate() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_stopgap_assert_it CASCADE;
+        CREATE SCHEMA plts_runtime_stopgap_assert_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_stopgap_assert_it.wrapped(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        import { assert, mutation } from "@stopgap/runtime";
+
+        export default mutation({ type: "object" }, async (args: any) => {
+            assert(args.qty > 0, "quantity must be positive", "22023");
+            assert(args.qty < 100, "quantity is too large");
+            return { qty: args.qty };
+        });
+        $$;
+        "#,
+    )
+    .expect("stopgap assert setup SQL should succeed");
+
+    let payload = Spi::get_one::<JsonB>(
+        "SELECT plts_runtime_stopgap_assert_it.wrapped('{\"qty\": 3}'::jsonb)",
+    )
+    .expect("wrapped function invocation should succeed")
+    .expect("wrapped function should return jsonb");
+    assert_eq!(payload.0.get("qty").and_then(Value::as_i64), Some(3));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_stopgap_assert_it.wrapped('{"qty": 0}'::jsonb);
+            RAISE EXCEPTION 'expected failed assert to raise';
+        EXCEPTION
+            WHEN invalid_parameter_value THEN
+                IF SQLERRM <> 'quantity must be positive' THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_stopgap_assert_it.wrapped('{"qty": 500}'::jsonb);
+            RAISE EXCEPTION 'expected failed assert to raise';
+        EXCEPTION
+            WHEN raise_exception THEN
+                IF SQLERRM <> 'quantity is too large' THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("failed asserts should raise with their sqlstate and bare message");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_stopgap_assert_it CASCADE;")
+        .expect("stopgap assert teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_stopgap_error_sqlstate_cannot_be_spoofed_or_reserved() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_stopgap_sqlstate_it CASCADE;
+        CREATE SCHEMA plts_runtime_stopgap_sqlstate_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_stopgap_sqlstate_it.renamed(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default () => {
+            const err = new Error("renamed error");
+            err.name = "StopgapError[40001]";
+            (err as any).sqlstate = "40001";
+            throw err;
+        };
+        $$;
+        CREATE OR REPLACE FUNCTION plts_runtime_stopgap_sqlstate_it.reserved(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        import { StopgapError } from "@stopgap/runtime";
+
+        export default async () => {
+            throw new StopgapError("pretend serialization failure", "40001");
+        };
+        $$;
+        "#,
+    )
+    .expect("stopgap sqlstate setup SQL should succeed");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_stopgap_sqlstate_it.renamed('{}'::jsonb);
+            RAISE EXCEPTION 'expected renamed error to raise';
+        EXCEPTION
+            WHEN internal_error THEN
+                IF POSITION('renamed error' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_stopgap_sqlstate_it.reserved('{}'::jsonb);
+            RAISE EXCEPTION 'expected reserved sqlstate to raise';
+        EXCEPTION
+            WHEN raise_exception THEN
+                IF SQLERRM <> 'pretend serialization failure' THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("only genuine StopgapErrors in allowed classes should choose their sqlstate");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_stopgap_sqlstate_it CASCADE;")
+        .expect("stopgap sqlstate teardown SQL should succeed");
+}
+
+#[pg_test]
+fn test_stopgap_error_raises_sqlstates_pgrx_does_not_list() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS plts_runtime_stopgap_custom_sqlstate_it CASCADE;
+        CREATE SCHEMA plts_runtime_stopgap_custom_sqlstate_it;
+        CREATE OR REPLACE FUNCTION plts_runtime_stopgap_custom_sqlstate_it.custom(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        import { StopgapError } from "@stopgap/runtime";
+
+        export default async () => {
+            throw new StopgapError("custom application failure", "U0001");
+        };
+        $$;
+        "#,
+    )
+    .expect("stopgap custom sqlstate setup SQL should succeed");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_runtime_stopgap_custom_sqlstate_it.custom('{}'::jsonb);
+            RAISE EXCEPTION 'expected custom sqlstate to raise';
+        EXCEPTION
+            WHEN SQLSTATE 'U0001' THEN
+                IF SQLERRM <> 'custom application failure' THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("a StopgapError should raise under a SQLSTATE outside pgrx's code list");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_runtime_stopgap_custom_sqlstate_it CASCADE;")
+        .expect("stopgap custom sqlstate teardown SQL should succeed");
+}
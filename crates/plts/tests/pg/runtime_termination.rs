@@ -0,0 +1,44 @@
+#[pg_test]
+fn test_runtime_heap_limit_termination_reports_memory_reason() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_termination_it CASCADE;
+        CREATE SCHEMA plts_termination_it;
+        CREATE OR REPLACE FUNCTION plts_termination_it.hog(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plts
+        AS $$
+        export default (_ctx: any) => {
+            const chunks: number[][] = [];
+            while (true) {
+                chunks.push(new Array(1_000_000).fill(chunks.length));
+            }
+        };
+        $$;
+        SELECT set_config('plts.max_heap_mb', '32', true);
+        SELECT set_config('plts.max_runtime_ms', '20000', true);
+        ",
+    )
+    .expect("termination setup SQL should succeed");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM plts_termination_it.hog('{}'::jsonb);
+            RAISE EXCEPTION 'expected the heap limit to terminate the handler';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('stage=memory limit' IN SQLERRM) = 0
+                    OR POSITION('reason=memory}' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("heap limit termination should carry reason=memory in the error context");
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_termination_it CASCADE;")
+        .expect("termination teardown SQL should succeed");
+}
@@ -0,0 +1,59 @@
+#[pg_test]
+fn test_runtime_before_insert_trigger_rewrites_new_row() {
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS plts_trigger_it CASCADE;
+        CREATE SCHEMA plts_trigger_it;
+        CREATE TABLE plts_trigger_it.items (
+            id int4 PRIMARY KEY,
+            name text NOT NULL,
+            qty int2,
+            seen jsonb
+        );
+        CREATE OR REPLACE FUNCTION plts_trigger_it.normalize_item()
+        RETURNS trigger
+        LANGUAGE plts
+        AS $$
+        export default (ctx: any) => {
+            const { old, new: row, ...event } = ctx.trigger;
+            return { ...row, name: String(row.name).toUpperCase(), seen: { event, old } };
+        };
+        $$;
+        CREATE TRIGGER normalize_item
+        BEFORE INSERT ON plts_trigger_it.items
+        FOR EACH ROW EXECUTE FUNCTION plts_trigger_it.normalize_item();
+        INSERT INTO plts_trigger_it.items (id, name, qty) VALUES (1, 'widget', 3);
+        ",
+    )
+    .expect("trigger setup SQL should succeed");
+
+    let name = Spi::get_one::<String>("SELECT name FROM plts_trigger_it.items WHERE id = 1")
+        .expect("trigger row query should succeed")
+        .expect("trigger row should have a name");
+    assert_eq!(name, "WIDGET");
+
+    let qty = Spi::get_one::<i16>("SELECT qty FROM plts_trigger_it.items WHERE id = 1")
+        .expect("trigger qty query should succeed");
+    assert_eq!(qty, Some(3));
+
+    let seen = Spi::get_one::<JsonB>("SELECT seen FROM plts_trigger_it.items WHERE id = 1")
+        .expect("trigger event query should succeed")
+        .expect("trigger event should be recorded");
+    assert_eq!(
+        seen.0,
+        json!({
+            "event": {
+                "name": "normalize_item",
+                "operation": "INSERT",
+                "when": "BEFORE",
+                "level": "ROW",
+                "table": "items",
+                "schema": "plts_trigger_it"
+            },
+            "old": null
+        })
+    );
+
+    Spi::run("DROP SCHEMA IF EXISTS plts_trigger_it CASCADE;")
+        .expect("trigger teardown SQL should succeed");
+}
@@ -0,0 +1,21 @@
+#[pg_test]
+fn test_settings_lists_recognized_gucs_with_current_values() {
+    Spi::run("SET LOCAL plts.max_query_rows = '17'").expect("setting override should succeed");
+
+    let current = Spi::get_one::<String>(
+        "SELECT current_value FROM plts.settings() WHERE name = 'plts.max_query_rows'",
+    )
+    .expect("settings query should succeed");
+    assert_eq!(current.as_deref(), Some("17"));
+
+    let default_value = Spi::get_one::<String>(
+        "SELECT default_value FROM plts.settings() WHERE name = 'plts.isolate_pool_size'",
+    )
+    .expect("settings query should succeed");
+    assert_eq!(default_value.as_deref(), Some("2"));
+
+    let described =
+        Spi::get_one::<bool>("SELECT bool_and(length(description) > 0) FROM plts.settings()")
+            .expect("settings query should succeed");
+    assert_eq!(described, Some(true));
+}
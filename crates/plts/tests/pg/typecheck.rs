@@ -0,0 +1,46 @@
+#[pg_test]
+fn test_typecheck_flags_ctx_db_query_misuse_against_runtime_declarations() {
+    let typecheck_errors = |source: &str| {
+        Spi::get_one_with_args::<JsonB>(
+            "SELECT plts.typecheck_ts($1::text, '{}'::jsonb)",
+            &[source.into()],
+        )
+        .expect("typecheck_ts query should succeed")
+        .expect("typecheck_ts should return diagnostics json")
+        .0
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|diagnostic| diagnostic.get("severity").and_then(Value::as_str) == Some("error"))
+        .collect::<Vec<_>>()
+    };
+
+    let valid = typecheck_errors(
+        r#"
+        export default async (ctx: PltsContext) => {
+            const row = await ctx.db.queryRow("SELECT $1::int AS id", [1], { readOnly: true });
+            ctx.log.info(ctx.describe("public", "other").kind);
+            return ctx.memo("row", () => row);
+        };
+        "#,
+    );
+    assert!(valid.is_empty(), "correct ctx usage should typecheck: {valid:?}");
+
+    let misuse = typecheck_errors(
+        r#"
+        import { query } from "@stopgap/runtime";
+
+        export default query({ type: "object" }, async (_args, ctx) => ctx.db.query(42));
+        "#,
+    );
+    assert!(!misuse.is_empty(), "passing a number as SQL to ctx.db.query should be flagged");
+
+    let plain_misuse = typecheck_errors(
+        "export default (ctx: PltsContext) => ctx.db.query(\"SELECT 1\", [], { readOnly: 'yes' });",
+    );
+    assert!(
+        !plain_misuse.is_empty(),
+        "a non-boolean readOnly option should be flagged for plain handlers"
+    );
+}
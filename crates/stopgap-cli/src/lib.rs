@@ -2,11 +2,13 @@ use std::{
     fmt, fs,
     io::Write,
     path::{Path, PathBuf},
+    thread,
+    time::Duration,
 };
 
 use anyhow::{Context, Result};
 use clap::{Parser, ValueEnum};
-use postgres::{Client, NoTls, Row};
+use postgres::{Client, NoTls, Row, Transaction, error::SqlState, types::ToSql};
 use regex::Regex;
 use serde_json::{Value, json};
 
@@ -15,6 +17,11 @@ pub const EXIT_DB_QUERY: u8 = 11;
 pub const EXIT_RESPONSE_DECODE: u8 = 12;
 pub const EXIT_OUTPUT_FORMAT: u8 = 13;
 pub const EXIT_PROJECT_LAYOUT: u8 = 14;
+pub const EXIT_CONFIRMATION_REQUIRED: u8 = 15;
+pub const EXIT_VALIDATION: u8 = 16;
+pub const EXIT_PERMISSION: u8 = 17;
+pub const DEFAULT_ENV: &str = "prod";
+const PROJECT_CONFIG_FILE: &str = "stopgap.json";
 const INIT_EXAMPLE_TEMPLATE: &[u8] = include_bytes!("../templates/example.ts");
 
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
@@ -40,6 +47,105 @@ impl fmt::Display for OutputMode {
     }
 }
 
+/// Transaction isolation requested for the deploy transaction via `--isolation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Self::ReadCommitted => "READ COMMITTED",
+            Self::RepeatableRead => "REPEATABLE READ",
+            Self::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+impl fmt::Display for IsolationLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReadCommitted => write!(f, "read-committed"),
+            Self::RepeatableRead => write!(f, "repeatable-read"),
+            Self::Serializable => write!(f, "serializable"),
+        }
+    }
+}
+
+/// Change type `stopgap diff --only` keeps in the returned `functions` list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffChange {
+    Added,
+    Changed,
+    Removed,
+}
+
+impl DiffChange {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Changed => "changed",
+            Self::Removed => "removed",
+        }
+    }
+}
+
+impl fmt::Display for DiffChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Session statements issued at the start of the deploy transaction, before `stopgap.deploy`.
+/// `SET TRANSACTION ISOLATION LEVEL` must come first: Postgres rejects it after any query.
+pub fn deploy_transaction_statements(
+    prune: bool,
+    isolation: Option<IsolationLevel>,
+) -> Vec<String> {
+    let mut statements = Vec::with_capacity(2);
+    if let Some(isolation) = isolation {
+        statements.push(format!("SET TRANSACTION ISOLATION LEVEL {}", isolation.as_sql()));
+    }
+    let prune_setting = if prune { "on" } else { "off" };
+    statements.push(format!("SET LOCAL stopgap.prune = '{prune_setting}'"));
+    statements
+}
+
+/// Parses a `--timeout` duration such as `500ms`, `30s`, `5m`, or `1h` into milliseconds. A bare
+/// number is read as seconds. Postgres caps `statement_timeout` at `i32::MAX` milliseconds.
+pub fn parse_timeout_ms(raw: &str) -> std::result::Result<u64, String> {
+    let raw = raw.trim();
+    let split_at = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (digits, unit) = raw.split_at(split_at);
+    let value = digits
+        .parse::<u64>()
+        .map_err(|_| format!("invalid timeout `{raw}`; expected e.g. 500ms, 30s, 5m, or 1h"))?;
+    let unit_ms = match unit {
+        "ms" => 1,
+        "" | "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        _ => return Err(format!("invalid timeout unit `{unit}`; expected ms, s, m, or h")),
+    };
+
+    let timeout_ms = value.saturating_mul(unit_ms);
+    if timeout_ms == 0 {
+        return Err("timeout must be positive".to_string());
+    }
+    if timeout_ms > i32::MAX as u64 {
+        return Err(format!("timeout `{raw}` exceeds the Postgres maximum of {}ms", i32::MAX));
+    }
+    Ok(timeout_ms)
+}
+
+/// Statement bounding every query in the current transaction to `timeout_ms`.
+pub fn statement_timeout_statement(timeout_ms: u64) -> String {
+    format!("SET LOCAL statement_timeout = '{timeout_ms}ms'")
+}
+
 #[derive(Debug, Parser)]
 #[command(name = "stopgap", version, about = "Stopgap deployment CLI")]
 pub struct Cli {
@@ -49,6 +155,14 @@ pub struct Cli {
     #[arg(long, value_enum, default_value_t = OutputMode::Human)]
     pub output: OutputMode,
 
+    /// Environment used by subcommands that are not given their own `--env`.
+    #[arg(long = "env", env = "STOPGAP_ENV")]
+    pub default_env: Option<String>,
+
+    /// Upper bound for each database call (e.g. `30s`, `5m`), applied as `statement_timeout`.
+    #[arg(long, global = true, value_parser = parse_timeout_ms)]
+    pub timeout: Option<u64>,
+
     #[command(subcommand)]
     pub command: Command,
 }
@@ -57,37 +171,191 @@ pub struct Cli {
 pub enum Command {
     Init,
     Deploy {
-        #[arg(long, default_value = "prod")]
-        env: String,
+        #[arg(long)]
+        env: Option<String>,
         #[arg(long = "from-schema")]
         from_schema: String,
         #[arg(long)]
         label: Option<String>,
         #[arg(long)]
         prune: bool,
+        #[arg(long, value_enum)]
+        isolation: Option<IsolationLevel>,
+        #[arg(long)]
+        yes: bool,
+        /// Keeps running after the first deploy, polling `stopgap.diff` and deploying again
+        /// whenever the source schema no longer matches the active deployment.
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between `--watch` polls.
+        #[arg(long = "watch-interval", default_value_t = 2, requires = "watch")]
+        watch_interval: u64,
     },
     Rollback {
-        #[arg(long, default_value = "prod")]
-        env: String,
-        #[arg(long, default_value_t = 1)]
-        steps: i32,
+        #[arg(long)]
+        env: Option<String>,
+        /// Steps back from the active deployment; omitted, rollback targets the baseline if one
+        /// is set and otherwise goes one step back.
+        #[arg(long)]
+        steps: Option<i32>,
         #[arg(long = "to")]
         to_id: Option<i64>,
+        #[arg(long)]
+        yes: bool,
     },
     Status {
-        #[arg(long, default_value = "prod")]
-        env: String,
+        #[arg(long)]
+        env: Option<String>,
+        #[arg(long)]
+        stats: bool,
     },
     Deployments {
-        #[arg(long, default_value = "prod")]
-        env: String,
+        #[arg(long)]
+        env: Option<String>,
+    },
+    /// Prints the env's most recent activations (deploys, rollbacks, promotions), newest first.
+    Logs {
+        #[arg(long)]
+        env: Option<String>,
+        #[arg(long, default_value_t = 20)]
+        limit: i32,
+    },
+    Manifest {
+        #[arg(long)]
+        id: i64,
+    },
+    /// Fetches a stored artifact from `plts.artifact`; human output is just its `compiled_js`.
+    Artifact {
+        #[arg(long)]
+        hash: String,
     },
     Diff {
-        #[arg(long, default_value = "prod")]
-        env: String,
+        #[arg(long)]
+        env: Option<String>,
         #[arg(long = "from-schema")]
         from_schema: String,
+        #[arg(long = "ignore-removed")]
+        ignore_removed: bool,
+        #[arg(long, value_enum)]
+        only: Option<DiffChange>,
+        #[arg(long = "with-source")]
+        with_source: bool,
     },
+    Whoami {
+        #[arg(long)]
+        env: Option<String>,
+        #[arg(long = "from-schema")]
+        from_schema: String,
+    },
+    /// Writes the env row, deployments, fn_versions, activations, and referenced artifacts to
+    /// `--file` as one JSON snapshot.
+    ExportEnv {
+        #[arg(long)]
+        env: Option<String>,
+        #[arg(long)]
+        file: PathBuf,
+    },
+    /// Recreates the env recorded in a snapshot written by `export-env`.
+    ImportEnv {
+        #[arg(long)]
+        file: PathBuf,
+    },
+}
+
+impl Command {
+    fn env_mut(&mut self) -> Option<&mut Option<String>> {
+        match self {
+            Self::Init | Self::Manifest { .. } | Self::Artifact { .. } | Self::ImportEnv { .. } => {
+                None
+            }
+            Self::Deploy { env, .. }
+            | Self::Rollback { env, .. }
+            | Self::Status { env, .. }
+            | Self::Deployments { env }
+            | Self::Logs { env, .. }
+            | Self::Diff { env, .. }
+            | Self::Whoami { env, .. }
+            | Self::ExportEnv { env, .. } => Some(env),
+        }
+    }
+
+    /// Fills in `env` for subcommands invoked without their own `--env`.
+    pub fn with_default_env(mut self, default_env: &str) -> Self {
+        if let Some(env) = self.env_mut()
+            && env.is_none()
+        {
+            *env = Some(default_env.to_string());
+        }
+        self
+    }
+}
+
+/// Resolves the default env: `--env`/`STOPGAP_ENV`, then `default_env` from the project's
+/// `stopgap.json`, then `prod`. A subcommand's own `--env` still wins over all of these.
+pub fn resolve_default_env(cli_default_env: Option<String>, start_dir: &Path) -> Result<String> {
+    if let Some(env) = cli_default_env {
+        return Ok(env);
+    }
+
+    Ok(load_project_default_env(start_dir)?.unwrap_or_else(|| DEFAULT_ENV.to_string()))
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+struct ProjectConfig {
+    default_env: Option<String>,
+    protected_envs: Vec<String>,
+}
+
+fn load_project_config(start_dir: &Path) -> Result<ProjectConfig> {
+    let Ok((project_root, _)) = detect_project_root(start_dir) else {
+        return Ok(ProjectConfig::default());
+    };
+    let config_path = project_root.join(PROJECT_CONFIG_FILE);
+    if !config_path.exists() {
+        return Ok(ProjectConfig::default());
+    }
+
+    let raw = fs::read_to_string(&config_path)
+        .with_context(|| format!("failed to read {}", config_path.display()))?;
+    serde_json::from_str(&raw).with_context(|| format!("failed to parse {}", config_path.display()))
+}
+
+fn load_project_default_env(start_dir: &Path) -> Result<Option<String>> {
+    Ok(load_project_config(start_dir)?.default_env)
+}
+
+/// The server trims and lowercases env names before using them, so `--env " PROD"` targets the
+/// same env as `prod`; compare protected envs the same way.
+fn env_match_key(env: &str) -> String {
+    env.trim().to_ascii_lowercase()
+}
+
+/// Refuses `action` against an env listed in `protected_envs` unless `--yes` was passed.
+fn ensure_confirmed(
+    project_root: &Path,
+    env: &str,
+    confirmed: bool,
+    action: &str,
+) -> std::result::Result<(), AppError> {
+    if confirmed {
+        return Ok(());
+    }
+
+    if detect_project_root(project_root).is_err() {
+        eprintln!(
+            "stopgap: warning: no project root found from {}; protected_envs in {PROJECT_CONFIG_FILE} are not checked",
+            project_root.display()
+        );
+    }
+    let config = load_project_config(project_root).map_err(AppError::ProjectLayout)?;
+    let env_key = env_match_key(env);
+    if config.protected_envs.iter().any(|protected| env_match_key(protected) == env_key) {
+        return Err(AppError::ConfirmationRequired(anyhow::anyhow!(
+            "env {env} is protected; re-run {action} with --yes to confirm"
+        )));
+    }
+    Ok(())
 }
 
 #[derive(Debug)]
@@ -97,6 +365,10 @@ pub enum AppError {
     Decode(anyhow::Error),
     Print(anyhow::Error),
     ProjectLayout(anyhow::Error),
+    ConfirmationRequired(anyhow::Error),
+    /// The database rejected the request itself; retrying it unchanged will fail again.
+    Validation(anyhow::Error),
+    Permission(anyhow::Error),
 }
 
 impl AppError {
@@ -107,10 +379,46 @@ impl AppError {
             Self::Decode(_) => EXIT_RESPONSE_DECODE,
             Self::Print(_) => EXIT_OUTPUT_FORMAT,
             Self::ProjectLayout(_) => EXIT_PROJECT_LAYOUT,
+            Self::ConfirmationRequired(_) => EXIT_CONFIRMATION_REQUIRED,
+            Self::Validation(_) => EXIT_VALIDATION,
+            Self::Permission(_) => EXIT_PERMISSION,
         }
     }
 }
 
+/// Splits a failed stopgap call into permission, validation, or other (possibly transient)
+/// database errors by the SQLSTATE the server raised; errors without one are query errors.
+pub fn classify_db_error(err: anyhow::Error) -> AppError {
+    match err
+        .downcast_ref::<postgres::Error>()
+        .and_then(postgres::Error::code)
+        .and_then(sqlstate_error_class)
+    {
+        Some(ErrorClass::Permission) => AppError::Permission(err),
+        Some(ErrorClass::Validation) => AppError::Validation(err),
+        None => AppError::DbQuery(err),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ErrorClass {
+    Permission,
+    Validation,
+}
+
+/// Maps the SQLSTATEs stopgap raises for rejected requests; anything else stays a query error.
+fn sqlstate_error_class(sqlstate: &SqlState) -> Option<ErrorClass> {
+    if *sqlstate == SqlState::INSUFFICIENT_PRIVILEGE {
+        Some(ErrorClass::Permission)
+    } else if *sqlstate == SqlState::INVALID_PARAMETER_VALUE
+        || *sqlstate == SqlState::OBJECT_NOT_IN_PREREQUISITE_STATE
+    {
+        Some(ErrorClass::Validation)
+    } else {
+        None
+    }
+}
+
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -119,6 +427,9 @@ impl fmt::Display for AppError {
             Self::Decode(err) => write!(f, "invalid database response: {err:#}"),
             Self::Print(err) => write!(f, "failed to print output: {err:#}"),
             Self::ProjectLayout(err) => write!(f, "project layout check failed: {err:#}"),
+            Self::ConfirmationRequired(err) => write!(f, "confirmation required: {err:#}"),
+            Self::Validation(err) => write!(f, "request rejected: {err:#}"),
+            Self::Permission(err) => write!(f, "permission denied: {err:#}"),
         }
     }
 }
@@ -130,29 +441,72 @@ pub trait StopgapApi {
         from_schema: &str,
         label: Option<&str>,
         prune: bool,
+        isolation: Option<IsolationLevel>,
         deploy_exports_json: Option<&str>,
     ) -> Result<i64>;
 
-    fn rollback(&mut self, env: &str, steps: i32, to_id: Option<i64>) -> Result<i64>;
+    fn rollback(&mut self, env: &str, steps: Option<i32>, to_id: Option<i64>) -> Result<i64>;
 
     fn status(&mut self, env: &str) -> Result<Option<Value>>;
+    fn env_stats(&mut self, env: &str) -> Result<Option<Value>>;
 
     fn deployments(&mut self, env: &str) -> Result<Value>;
 
-    fn diff(&mut self, env: &str, from_schema: &str) -> Result<Value>;
+    fn activation_history(&mut self, env: &str, limit: i32) -> Result<Value>;
+
+    fn manifest(&mut self, deployment_id: i64) -> Result<Value>;
+
+    fn get_artifact(&mut self, hash: &str) -> Result<Option<Value>>;
+
+    fn diff(
+        &mut self,
+        env: &str,
+        from_schema: &str,
+        ignore_removed: bool,
+        only: Option<DiffChange>,
+        with_source: bool,
+        deploy_exports_json: Option<&str>,
+    ) -> Result<Value>;
+
+    fn whoami(&mut self, env: &str, from_schema: &str) -> Result<Value>;
+
+    fn export_env(&mut self, env: &str) -> Result<Value>;
+
+    fn import_env(&mut self, snapshot: &Value) -> Result<Value>;
 }
 
 pub struct PgStopgapApi {
     client: Client,
+    statement_timeout_ms: Option<u64>,
 }
 
 impl PgStopgapApi {
-    pub fn connect(db: &str) -> std::result::Result<Self, AppError> {
+    pub fn connect(
+        db: &str,
+        statement_timeout_ms: Option<u64>,
+    ) -> std::result::Result<Self, AppError> {
         let client = Client::connect(db, NoTls).map_err(|err| AppError::DbConnect(err.into()))?;
-        Ok(Self { client })
+        Ok(Self { client, statement_timeout_ms })
+    }
+
+    /// Runs a single stopgap call in its own transaction so `--timeout` can be set locally.
+    fn query_one(&mut self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Row> {
+        let mut tx = self.client.transaction()?;
+        apply_statement_timeout(&mut tx, self.statement_timeout_ms)?;
+        let row = tx.query_one(query, params)?;
+        tx.commit()?;
+        Ok(row)
     }
 }
 
+/// Applies `--timeout` to `tx`; must run after any `SET TRANSACTION` statement.
+fn apply_statement_timeout(tx: &mut Transaction<'_>, timeout_ms: Option<u64>) -> Result<()> {
+    if let Some(timeout_ms) = timeout_ms {
+        tx.batch_execute(&statement_timeout_statement(timeout_ms))?;
+    }
+    Ok(())
+}
+
 impl StopgapApi for PgStopgapApi {
     fn deploy(
         &mut self,
@@ -160,11 +514,14 @@ impl StopgapApi for PgStopgapApi {
         from_schema: &str,
         label: Option<&str>,
         prune: bool,
+        isolation: Option<IsolationLevel>,
         deploy_exports_json: Option<&str>,
     ) -> Result<i64> {
         let mut tx = self.client.build_transaction().start()?;
-        let prune_setting = if prune { "on" } else { "off" };
-        tx.batch_execute(&format!("SET LOCAL stopgap.prune = '{prune_setting}'"))?;
+        for statement in deploy_transaction_statements(prune, isolation) {
+            tx.batch_execute(&statement)?;
+        }
+        apply_statement_timeout(&mut tx, self.statement_timeout_ms)?;
         if let Some(raw_exports) = deploy_exports_json {
             tx.execute("SELECT set_config('stopgap.deploy_exports', $1, true)", &[&raw_exports])?;
         }
@@ -176,8 +533,8 @@ impl StopgapApi for PgStopgapApi {
         Ok(row.get("deployment_id"))
     }
 
-    fn rollback(&mut self, env: &str, steps: i32, to_id: Option<i64>) -> Result<i64> {
-        let row = self.client.query_one(
+    fn rollback(&mut self, env: &str, steps: Option<i32>, to_id: Option<i64>) -> Result<i64> {
+        let row = self.query_one(
             "SELECT stopgap.rollback($1, $2, $3) AS deployment_id",
             &[&env, &steps, &to_id],
         )?;
@@ -185,21 +542,76 @@ impl StopgapApi for PgStopgapApi {
     }
 
     fn status(&mut self, env: &str) -> Result<Option<Value>> {
-        let row = self.client.query_one("SELECT stopgap.status($1) AS status", &[&env])?;
+        let row = self.query_one("SELECT stopgap.status($1) AS status", &[&env])?;
         read_json_column(&row, "status")
     }
 
+    fn env_stats(&mut self, env: &str) -> Result<Option<Value>> {
+        let row = self.query_one("SELECT stopgap.env_stats($1) AS stats", &[&env])?;
+        read_json_column(&row, "stats")
+    }
+
     fn deployments(&mut self, env: &str) -> Result<Value> {
-        let row =
-            self.client.query_one("SELECT stopgap.deployments($1) AS deployments", &[&env])?;
+        let row = self.query_one("SELECT stopgap.deployments($1) AS deployments", &[&env])?;
         read_required_json_column(&row, "deployments")
     }
 
-    fn diff(&mut self, env: &str, from_schema: &str) -> Result<Value> {
-        let row =
-            self.client.query_one("SELECT stopgap.diff($1, $2) AS diff", &[&env, &from_schema])?;
+    fn activation_history(&mut self, env: &str, limit: i32) -> Result<Value> {
+        let row = self.query_one(
+            "SELECT stopgap.activation_history($1, $2) AS activations",
+            &[&env, &limit],
+        )?;
+        read_required_json_column(&row, "activations")
+    }
+
+    fn manifest(&mut self, deployment_id: i64) -> Result<Value> {
+        let row = self.query_one("SELECT stopgap.manifest($1) AS manifest", &[&deployment_id])?;
+        read_required_json_column(&row, "manifest")
+    }
+
+    fn get_artifact(&mut self, hash: &str) -> Result<Option<Value>> {
+        let row = self.query_one("SELECT plts.get_artifact($1) AS artifact", &[&hash])?;
+        read_json_column(&row, "artifact")
+    }
+
+    fn diff(
+        &mut self,
+        env: &str,
+        from_schema: &str,
+        ignore_removed: bool,
+        only: Option<DiffChange>,
+        with_source: bool,
+        deploy_exports_json: Option<&str>,
+    ) -> Result<Value> {
+        let only = only.map(DiffChange::as_str);
+        let mut tx = self.client.transaction()?;
+        apply_statement_timeout(&mut tx, self.statement_timeout_ms)?;
+        if let Some(raw_exports) = deploy_exports_json {
+            tx.execute("SELECT set_config('stopgap.deploy_exports', $1, true)", &[&raw_exports])?;
+        }
+        let row = tx.query_one(
+            "SELECT stopgap.diff($1, $2, $3, $4, $5) AS diff",
+            &[&env, &from_schema, &ignore_removed, &only, &with_source],
+        )?;
+        tx.commit()?;
         read_required_json_column(&row, "diff")
     }
+
+    fn whoami(&mut self, env: &str, from_schema: &str) -> Result<Value> {
+        let row =
+            self.query_one("SELECT stopgap.whoami($1, $2) AS whoami", &[&env, &from_schema])?;
+        read_required_json_column(&row, "whoami")
+    }
+
+    fn export_env(&mut self, env: &str) -> Result<Value> {
+        let row = self.query_one("SELECT stopgap.export_env($1) AS snapshot", &[&env])?;
+        read_required_json_column(&row, "snapshot")
+    }
+
+    fn import_env(&mut self, snapshot: &Value) -> Result<Value> {
+        let row = self.query_one("SELECT stopgap.import_env($1) AS summary", &[snapshot])?;
+        read_required_json_column(&row, "summary")
+    }
 }
 
 pub fn run(cli: Cli, writer: &mut dyn Write) -> std::result::Result<(), AppError> {
@@ -208,8 +620,12 @@ pub fn run(cli: Cli, writer: &mut dyn Write) -> std::result::Result<(), AppError
         return execute_command(cli.command, cli.output, &mut api, writer);
     }
 
-    let mut api = PgStopgapApi::connect(&cli.db)?;
-    execute_command(cli.command, cli.output, &mut api, writer)
+    let current_dir = std::env::current_dir().map_err(|err| AppError::ProjectLayout(err.into()))?;
+    let default_env =
+        resolve_default_env(cli.default_env, &current_dir).map_err(AppError::ProjectLayout)?;
+    let command = cli.command.with_default_env(&default_env);
+    let mut api = PgStopgapApi::connect(&cli.db, cli.timeout)?;
+    execute_command(command, cli.output, &mut api, writer)
 }
 
 struct NoopStopgapApi;
@@ -221,12 +637,13 @@ impl StopgapApi for NoopStopgapApi {
         _from_schema: &str,
         _label: Option<&str>,
         _prune: bool,
+        _isolation: Option<IsolationLevel>,
         _deploy_exports_json: Option<&str>,
     ) -> Result<i64> {
         unreachable!("deploy should not be called by local-only commands")
     }
 
-    fn rollback(&mut self, _env: &str, _steps: i32, _to_id: Option<i64>) -> Result<i64> {
+    fn rollback(&mut self, _env: &str, _steps: Option<i32>, _to_id: Option<i64>) -> Result<i64> {
         unreachable!("rollback should not be called by local-only commands")
     }
 
@@ -234,13 +651,49 @@ impl StopgapApi for NoopStopgapApi {
         unreachable!("status should not be called by local-only commands")
     }
 
+    fn env_stats(&mut self, _env: &str) -> Result<Option<Value>> {
+        unreachable!("env_stats should not be called by local-only commands")
+    }
+
     fn deployments(&mut self, _env: &str) -> Result<Value> {
         unreachable!("deployments should not be called by local-only commands")
     }
 
-    fn diff(&mut self, _env: &str, _from_schema: &str) -> Result<Value> {
+    fn activation_history(&mut self, _env: &str, _limit: i32) -> Result<Value> {
+        unreachable!("activation_history should not be called by local-only commands")
+    }
+
+    fn manifest(&mut self, _deployment_id: i64) -> Result<Value> {
+        unreachable!("manifest should not be called by local-only commands")
+    }
+
+    fn get_artifact(&mut self, _hash: &str) -> Result<Option<Value>> {
+        unreachable!("get_artifact should not be called by local-only commands")
+    }
+
+    fn diff(
+        &mut self,
+        _env: &str,
+        _from_schema: &str,
+        _ignore_removed: bool,
+        _only: Option<DiffChange>,
+        _with_source: bool,
+        _deploy_exports_json: Option<&str>,
+    ) -> Result<Value> {
         unreachable!("diff should not be called by local-only commands")
     }
+
+    fn whoami(&mut self, _env: &str, _from_schema: &str) -> Result<Value> {
+        unreachable!("whoami should not be called by local-only commands")
+    }
+
+    fn export_env(&mut self, _env: &str) -> Result<Value> {
+        unreachable!("export_env should not be called by local-only commands")
+    }
+
+    fn import_env(&mut self, _snapshot: &Value) -> Result<Value> {
+        unreachable!("import_env should not be called by local-only commands")
+    }
 }
 
 pub fn execute_command(
@@ -283,52 +736,43 @@ pub fn execute_command_with_project_root(
                 )
             })
         }
-        Command::Deploy { env, from_schema, label, prune } => {
-            let exports =
-                discover_stopgap_exports(project_root).map_err(AppError::ProjectLayout)?;
-            let mut module_paths =
-                exports.iter().map(|item| item.module_path.clone()).collect::<Vec<_>>();
-            module_paths.sort();
-            module_paths.dedup();
-            let function_paths =
-                exports.iter().map(|item| item.function_path.clone()).collect::<Vec<_>>();
-            let deploy_exports_json = serde_json::to_string(&exports)
-                .map_err(|err| AppError::ProjectLayout(err.into()))?;
-            let deployment_id = api
-                .deploy(
-                    &env,
-                    &from_schema,
-                    label.as_deref(),
-                    prune,
-                    Some(deploy_exports_json.as_str()),
-                )
-                .map_err(AppError::DbQuery)?;
-            let payload = json!({
-                "command": "deploy",
-                "env": env,
-                "from_schema": from_schema,
-                "source_root": "stopgap",
-                "module_count": module_paths.len(),
-                "module_paths": module_paths,
-                "function_count": exports.len(),
-                "function_paths": function_paths,
-                "deployment_id": deployment_id,
-                "prune": prune,
-            });
-            print_payload(output, payload, writer, || {
-                format!(
-                    "deployed env={} from_schema={} deployment_id={} prune={} module_count={} function_count={}",
-                    env,
-                    from_schema,
-                    deployment_id,
-                    prune,
-                    module_paths.len(),
-                    exports.len()
-                )
-            })
+        Command::Deploy {
+            env,
+            from_schema,
+            label,
+            prune,
+            isolation,
+            yes,
+            watch,
+            watch_interval,
+        } => {
+            let env = env.unwrap_or_else(|| DEFAULT_ENV.to_string());
+            ensure_confirmed(project_root, &env, yes, "deploy")?;
+            let request = DeployRequest {
+                env: &env,
+                from_schema: &from_schema,
+                label: label.as_deref(),
+                prune,
+                isolation,
+            };
+            deploy_and_print(request, output, api, writer, project_root)?;
+            if !watch {
+                return Ok(());
+            }
+
+            // A failed poll or redeploy (e.g. a compile error mid-edit) is reported and the
+            // watch carries on; the next poll picks up the fixed source.
+            loop {
+                thread::sleep(Duration::from_secs(watch_interval));
+                if let Err(err) = redeploy_if_changed(request, output, api, writer, project_root) {
+                    eprintln!("stopgap: {err}; still watching");
+                }
+            }
         }
-        Command::Rollback { env, steps, to_id } => {
-            let deployment_id = api.rollback(&env, steps, to_id).map_err(AppError::DbQuery)?;
+        Command::Rollback { env, steps, to_id, yes } => {
+            let env = env.unwrap_or_else(|| DEFAULT_ENV.to_string());
+            ensure_confirmed(project_root, &env, yes, "rollback")?;
+            let deployment_id = api.rollback(&env, steps, to_id).map_err(classify_db_error)?;
             let payload = json!({
                 "command": "rollback",
                 "env": env,
@@ -338,30 +782,41 @@ pub fn execute_command_with_project_root(
             });
             print_payload(output, payload, writer, || {
                 format!(
-                    "rolled back env={} target_deployment_id={} steps={}{}",
+                    "rolled back env={} target_deployment_id={}{}{}",
                     env,
                     deployment_id,
-                    steps,
+                    steps.map(|value| format!(" steps={value}")).unwrap_or_default(),
                     to_id.map(|value| format!(" to_id={value}")).unwrap_or_default()
                 )
             })
         }
-        Command::Status { env } => {
-            let status = api.status(&env).map_err(AppError::DbQuery)?;
-            let payload = json!({
+        Command::Status { env, stats } => {
+            let env = env.unwrap_or_else(|| DEFAULT_ENV.to_string());
+            let status = api.status(&env).map_err(classify_db_error)?;
+            let env_stats =
+                if stats { api.env_stats(&env).map_err(classify_db_error)? } else { None };
+            let mut payload = json!({
                 "command": "status",
                 "env": env,
                 "status": status,
             });
+            if stats {
+                payload["stats"] = env_stats.clone().unwrap_or(Value::Null);
+            }
             print_payload(output, payload, writer, || {
-                status
+                let line = status
                     .as_ref()
                     .map(|value| format!("status env={} {}", env, compact_json(value)))
-                    .unwrap_or_else(|| format!("status env={} none", env))
+                    .unwrap_or_else(|| format!("status env={} none", env));
+                match env_stats.as_ref() {
+                    Some(value) => format!("{line} stats={}", compact_json(value)),
+                    None => line,
+                }
             })
         }
         Command::Deployments { env } => {
-            let deployments = api.deployments(&env).map_err(AppError::DbQuery)?;
+            let env = env.unwrap_or_else(|| DEFAULT_ENV.to_string());
+            let deployments = api.deployments(&env).map_err(classify_db_error)?;
             let count = deployments.as_array().map(|entries| entries.len()).unwrap_or(0);
             let payload = json!({
                 "command": "deployments",
@@ -373,16 +828,156 @@ pub fn execute_command_with_project_root(
                 format!("deployments env={} count={}", env, count)
             })
         }
-        Command::Diff { env, from_schema } => {
-            let diff = api.diff(&env, &from_schema).map_err(AppError::DbQuery)?;
+        Command::Logs { env, limit } => {
+            let env = env.unwrap_or_else(|| DEFAULT_ENV.to_string());
+            let activations = api.activation_history(&env, limit).map_err(classify_db_error)?;
+            let entries = activations.as_array().cloned().unwrap_or_default();
+            let payload = json!({
+                "command": "logs",
+                "env": env,
+                "limit": limit,
+                "count": entries.len(),
+                "activations": activations,
+            });
+            print_payload(output, payload, writer, || {
+                let mut lines = vec![format!("logs env={} count={}", env, entries.len())];
+                lines.extend(entries.iter().map(format_activation_line));
+                lines.join("\n")
+            })
+        }
+        Command::Manifest { id } => {
+            let manifest = api.manifest(id).map_err(classify_db_error)?;
+            let payload = json!({
+                "command": "manifest",
+                "deployment_id": id,
+                "manifest": manifest,
+            });
+            print_payload(output, payload, writer, || {
+                format!("manifest deployment_id={} {}", id, compact_json(&manifest))
+            })
+        }
+        Command::Artifact { hash } => {
+            let Some(artifact) = api.get_artifact(&hash).map_err(classify_db_error)? else {
+                return Err(AppError::Validation(anyhow::anyhow!("artifact {hash} not found")));
+            };
+            let compiled_js =
+                artifact.get("compiled_js").and_then(Value::as_str).unwrap_or_default().to_string();
+            let payload = json!({
+                "command": "artifact",
+                "artifact_hash": hash,
+                "artifact": artifact,
+            });
+            print_payload(output, payload, writer, || compiled_js)
+        }
+        Command::Diff { env, from_schema, ignore_removed, only, with_source } => {
+            let env = env.unwrap_or_else(|| DEFAULT_ENV.to_string());
+            let diff = api
+                .diff(&env, &from_schema, ignore_removed, only, with_source, None)
+                .map_err(classify_db_error)?;
             let payload = json!({
                 "command": "diff",
                 "env": env,
                 "from_schema": from_schema,
+                "ignore_removed": ignore_removed,
+                "only": only.map(DiffChange::as_str),
+                "with_source": with_source,
                 "diff": diff,
             });
             print_payload(output, payload, writer, || {
-                format!("diff env={} from_schema={}", env, from_schema)
+                let only = only.map(|only| format!(" only={only}")).unwrap_or_default();
+                format!(
+                    "diff env={} from_schema={} ignore_removed={}{}",
+                    env, from_schema, ignore_removed, only
+                )
+            })
+        }
+        Command::Whoami { env, from_schema } => {
+            let env = env.unwrap_or_else(|| DEFAULT_ENV.to_string());
+            let report = api.whoami(&env, &from_schema).map_err(classify_db_error)?;
+            let failed = report
+                .get("checks")
+                .and_then(Value::as_array)
+                .map(|checks| {
+                    checks
+                        .iter()
+                        .filter(|check| check.get("passed").and_then(Value::as_bool) != Some(true))
+                        .filter_map(|check| check.get("detail").and_then(Value::as_str))
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            let payload = json!({
+                "command": "whoami",
+                "env": env,
+                "from_schema": from_schema,
+                "whoami": report,
+            });
+            print_payload(output, payload, writer, || {
+                let session_user =
+                    report.get("session_user").and_then(Value::as_str).unwrap_or("unknown");
+                if failed.is_empty() {
+                    format!("whoami env={} user={} ok=true", env, session_user)
+                } else {
+                    format!(
+                        "whoami env={} user={} ok=false failed=[{}]",
+                        env,
+                        session_user,
+                        failed.join("; ")
+                    )
+                }
+            })
+        }
+        Command::ExportEnv { env, file } => {
+            let env = env.unwrap_or_else(|| DEFAULT_ENV.to_string());
+            let snapshot = api.export_env(&env).map_err(classify_db_error)?;
+            let path = project_root.join(&file);
+            let raw = serde_json::to_string_pretty(&snapshot)
+                .map_err(|err| AppError::Decode(err.into()))?;
+            fs::write(&path, raw)
+                .with_context(|| format!("failed to write {}", path.display()))
+                .map_err(AppError::ProjectLayout)?;
+            let deployment_count = snapshot["deployments"].as_array().map_or(0, Vec::len);
+            let artifact_count = snapshot["artifacts"].as_array().map_or(0, Vec::len);
+            let payload = json!({
+                "command": "export-env",
+                "env": env,
+                "file": path.display().to_string(),
+                "deployment_count": deployment_count,
+                "artifact_count": artifact_count,
+            });
+            print_payload(output, payload, writer, || {
+                format!(
+                    "exported env={} file={} deployments={} artifacts={}",
+                    env,
+                    path.display(),
+                    deployment_count,
+                    artifact_count
+                )
+            })
+        }
+        Command::ImportEnv { file } => {
+            let path = project_root.join(&file);
+            let raw = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))
+                .map_err(AppError::ProjectLayout)?;
+            let snapshot = serde_json::from_str::<Value>(&raw)
+                .with_context(|| format!("failed to parse {}", path.display()))
+                .map_err(AppError::ProjectLayout)?;
+            let summary = api.import_env(&snapshot).map_err(classify_db_error)?;
+            let payload = json!({
+                "command": "import-env",
+                "file": path.display().to_string(),
+                "env": summary["env"],
+                "summary": summary,
+            });
+            print_payload(output, payload, writer, || {
+                format!(
+                    "imported env={} active_deployment_id={} deployments={} artifacts={}",
+                    summary["env"].as_str().unwrap_or("unknown"),
+                    summary["active_deployment_id"],
+                    summary["deployments"],
+                    summary["artifacts"]
+                )
             })
         }
     }
@@ -592,6 +1187,116 @@ fn normalize_module_path(source_root: &Path, module_path: &Path) -> Result<Strin
     Ok(format!("api.{}", segments.join(".")))
 }
 
+#[derive(Clone, Copy)]
+struct DeployRequest<'a> {
+    env: &'a str,
+    from_schema: &'a str,
+    label: Option<&'a str>,
+    prune: bool,
+    isolation: Option<IsolationLevel>,
+}
+
+fn deploy_and_print(
+    request: DeployRequest<'_>,
+    output: OutputMode,
+    api: &mut dyn StopgapApi,
+    writer: &mut dyn Write,
+    project_root: &Path,
+) -> std::result::Result<(), AppError> {
+    let DeployRequest { env, from_schema, label, prune, isolation } = request;
+    let (exports, deploy_exports_json) = discover_deploy_exports(project_root)?;
+    let mut module_paths = exports.iter().map(|item| item.module_path.clone()).collect::<Vec<_>>();
+    module_paths.sort();
+    module_paths.dedup();
+    let function_paths = exports.iter().map(|item| item.function_path.clone()).collect::<Vec<_>>();
+    let deployment_id = api
+        .deploy(env, from_schema, label, prune, isolation, Some(deploy_exports_json.as_str()))
+        .map_err(classify_db_error)?;
+    let payload = json!({
+        "command": "deploy",
+        "env": env,
+        "from_schema": from_schema,
+        "source_root": "stopgap",
+        "module_count": module_paths.len(),
+        "module_paths": module_paths,
+        "function_count": exports.len(),
+        "function_paths": function_paths,
+        "deployment_id": deployment_id,
+        "prune": prune,
+        "isolation": isolation.map(|level| level.to_string()),
+    });
+    print_payload(output, payload, writer, || {
+        format!(
+            "deployed env={} from_schema={} deployment_id={} prune={} module_count={} function_count={}",
+            env,
+            from_schema,
+            deployment_id,
+            prune,
+            module_paths.len(),
+            exports.len()
+        )
+    })
+}
+
+/// Exports under `stopgap/` plus their `stopgap.deploy_exports` JSON, which decides the
+/// compiler options of each deployed function.
+fn discover_deploy_exports(
+    project_root: &Path,
+) -> std::result::Result<(Vec<StopgapExport>, String), AppError> {
+    let exports = discover_stopgap_exports(project_root).map_err(AppError::ProjectLayout)?;
+    let deploy_exports_json =
+        serde_json::to_string(&exports).map_err(|err| AppError::ProjectLayout(err.into()))?;
+    Ok((exports, deploy_exports_json))
+}
+
+/// One `deploy --watch` poll. The diff is compiled with the same export metadata the deploy
+/// sends, so an unchanged source schema hashes to the active artifacts.
+fn redeploy_if_changed(
+    request: DeployRequest<'_>,
+    output: OutputMode,
+    api: &mut dyn StopgapApi,
+    writer: &mut dyn Write,
+    project_root: &Path,
+) -> std::result::Result<(), AppError> {
+    let DeployRequest { env, from_schema, prune, .. } = request;
+    let (_, deploy_exports_json) = discover_deploy_exports(project_root)?;
+    // Without --prune, functions dropped from the source schema stay live, so they would show
+    // up as `removed` on every poll; only count them when a deploy would act on them.
+    let diff = api
+        .diff(env, from_schema, !prune, None, false, Some(deploy_exports_json.as_str()))
+        .map_err(classify_db_error)?;
+    if diff_has_changes(&diff) {
+        deploy_and_print(request, output, api, writer, project_root)?;
+    }
+    Ok(())
+}
+
+/// One `stopgap logs` timeline line, e.g.
+/// `2024-05-01T12:00:00+00:00 activation=7 3 -> 4 label=v2 by=alice`.
+fn format_activation_line(activation: &Value) -> String {
+    let field = |key: &str| match &activation[key] {
+        Value::Null => "none".to_string(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    format!(
+        "{} activation={} {} -> {} label={} by={}",
+        field("activated_at"),
+        field("activation_id"),
+        field("from_deployment_id"),
+        field("to_deployment_id"),
+        field("to_label"),
+        field("activated_by")
+    )
+}
+
+/// True when a `stopgap.diff` result reports any added, changed, or removed function.
+fn diff_has_changes(diff: &Value) -> bool {
+    ["added", "changed", "removed"]
+        .iter()
+        .any(|key| diff["summary"][*key].as_u64().is_some_and(|count| count > 0))
+}
+
 fn print_payload<F>(
     output: OutputMode,
     payload: Value,
@@ -632,7 +1337,235 @@ mod tests {
         let command = Cli::command();
         let names: Vec<_> =
             command.get_subcommands().map(|subcommand| subcommand.get_name().to_string()).collect();
-        assert_eq!(names, vec!["init", "deploy", "rollback", "status", "deployments", "diff"]);
+        assert_eq!(
+            names,
+            vec![
+                "init",
+                "deploy",
+                "rollback",
+                "status",
+                "deployments",
+                "logs",
+                "manifest",
+                "artifact",
+                "diff",
+                "whoami",
+                "export-env",
+                "import-env"
+            ]
+        );
+    }
+
+    #[test]
+    fn subcommand_env_overrides_global_env() {
+        let cli = Cli::try_parse_from([
+            "stopgap",
+            "--db",
+            "postgres://",
+            "--env",
+            "staging",
+            "status",
+            "--env",
+            "prod",
+        ])
+        .expect("cli should parse");
+        assert_eq!(cli.default_env.as_deref(), Some("staging"));
+
+        let command = cli.command.with_default_env("staging");
+        assert!(matches!(command, Command::Status { env: Some(env), .. } if env == "prod"));
+    }
+
+    #[test]
+    fn timeout_flag_parses_durations_before_or_after_the_subcommand() {
+        let cli =
+            Cli::try_parse_from(["stopgap", "--db", "postgres://", "--timeout", "5m", "status"])
+                .expect("cli should parse");
+        assert_eq!(cli.timeout, Some(300_000));
+
+        let cli = Cli::try_parse_from([
+            "stopgap",
+            "--db",
+            "postgres://",
+            "rollback",
+            "--timeout",
+            "1500ms",
+        ])
+        .expect("cli should parse");
+        assert_eq!(cli.timeout, Some(1_500));
+
+        let cli = Cli::try_parse_from(["stopgap", "--db", "postgres://", "status"])
+            .expect("cli should parse");
+        assert_eq!(cli.timeout, None);
+
+        let rejected =
+            Cli::try_parse_from(["stopgap", "--db", "postgres://", "--timeout", "soon", "status"]);
+        assert!(rejected.is_err(), "unparseable timeouts should be rejected");
+    }
+
+    #[test]
+    fn parse_timeout_ms_converts_units_to_postgres_milliseconds() {
+        assert_eq!(parse_timeout_ms("250ms"), Ok(250));
+        assert_eq!(parse_timeout_ms("30s"), Ok(30_000));
+        assert_eq!(parse_timeout_ms("30"), Ok(30_000));
+        assert_eq!(parse_timeout_ms("2m"), Ok(120_000));
+        assert_eq!(parse_timeout_ms("1h"), Ok(3_600_000));
+        assert!(parse_timeout_ms("0s").is_err());
+        assert!(parse_timeout_ms("10d").is_err());
+        assert!(parse_timeout_ms("ms").is_err());
+        assert!(parse_timeout_ms("1000h").is_err());
+        assert_eq!(statement_timeout_statement(30_000), "SET LOCAL statement_timeout = '30000ms'");
+    }
+
+    #[test]
+    fn deploy_isolation_accepts_only_known_levels() {
+        let cli = Cli::try_parse_from([
+            "stopgap",
+            "--db",
+            "postgres://",
+            "deploy",
+            "--from-schema",
+            "app",
+            "--isolation",
+            "repeatable-read",
+        ])
+        .expect("cli should parse");
+        assert!(matches!(
+            cli.command,
+            Command::Deploy { isolation: Some(IsolationLevel::RepeatableRead), .. }
+        ));
+
+        let rejected = Cli::try_parse_from([
+            "stopgap",
+            "--db",
+            "postgres://",
+            "deploy",
+            "--from-schema",
+            "app",
+            "--isolation",
+            "snapshot",
+        ]);
+        assert!(rejected.is_err(), "unknown isolation levels should be rejected");
+    }
+
+    #[test]
+    fn deploy_watch_flag_parses_into_deploy_variant() {
+        let cli = Cli::try_parse_from([
+            "stopgap",
+            "--db",
+            "postgres://",
+            "deploy",
+            "--from-schema",
+            "app",
+            "--watch",
+            "--watch-interval",
+            "5",
+        ])
+        .expect("cli should parse");
+        assert!(matches!(cli.command, Command::Deploy { watch: true, watch_interval: 5, .. }));
+
+        let cli = Cli::try_parse_from([
+            "stopgap",
+            "--db",
+            "postgres://",
+            "deploy",
+            "--from-schema",
+            "app",
+        ])
+        .expect("cli should parse");
+        assert!(matches!(cli.command, Command::Deploy { watch: false, watch_interval: 2, .. }));
+
+        let rejected = Cli::try_parse_from([
+            "stopgap",
+            "--db",
+            "postgres://",
+            "deploy",
+            "--from-schema",
+            "app",
+            "--watch-interval",
+            "5",
+        ]);
+        assert!(rejected.is_err(), "--watch-interval should require --watch");
+    }
+
+    #[test]
+    fn diff_has_changes_ignores_unchanged_functions() {
+        assert!(!diff_has_changes(
+            &json!({"summary": {"added": 0, "changed": 0, "removed": 0, "unchanged": 3}})
+        ));
+        assert!(diff_has_changes(
+            &json!({"summary": {"added": 0, "changed": 1, "removed": 0, "unchanged": 2}})
+        ));
+        assert!(!diff_has_changes(&json!({})));
+    }
+
+    #[test]
+    fn global_env_fills_in_missing_subcommand_env() {
+        let cli = Cli::try_parse_from([
+            "stopgap",
+            "--db",
+            "postgres://",
+            "--env",
+            "staging",
+            "deployments",
+        ])
+        .expect("cli should parse");
+        let default_env = resolve_default_env(cli.default_env, Path::new("/"))
+            .expect("default env should resolve");
+        assert_eq!(default_env, "staging");
+
+        let command = cli.command.with_default_env(&default_env);
+        assert!(matches!(command, Command::Deployments { env: Some(env) } if env == "staging"));
+    }
+
+    #[test]
+    fn resolve_default_env_uses_project_config_then_prod() {
+        let root =
+            std::env::temp_dir().join(format!("stopgap-cli-default-env-{}", std::process::id()));
+        fs::create_dir_all(&root).expect("temp project root should be created");
+        fs::write(root.join("package.json"), "{}").expect("marker should be written");
+
+        assert_eq!(resolve_default_env(None, &root).expect("fallback resolves"), DEFAULT_ENV);
+
+        fs::write(root.join(PROJECT_CONFIG_FILE), r#"{"default_env": "staging"}"#)
+            .expect("config should be written");
+        assert_eq!(resolve_default_env(None, &root).expect("config resolves"), "staging");
+        assert_eq!(
+            resolve_default_env(Some("dev".to_string()), &root).expect("flag resolves"),
+            "dev"
+        );
+
+        fs::write(root.join(PROJECT_CONFIG_FILE), r#"{"default_env": 1}"#)
+            .expect("config should be written");
+        assert!(resolve_default_env(None, &root).is_err());
+
+        fs::remove_dir_all(&root).expect("temp project root should be removed");
+    }
+
+    #[test]
+    fn classify_db_error_ignores_message_text_without_a_sqlstate() {
+        let classify = |message: &str| classify_db_error(anyhow::anyhow!(message.to_string()));
+
+        assert_eq!(classify("db error: ERROR: permission denied for function deploy").code(), 11);
+        assert_eq!(classify("db error: ERROR: invalid stopgap env name ''").code(), 11);
+        assert_eq!(classify("connection reset by peer").code(), 11);
+    }
+
+    #[test]
+    fn sqlstate_error_class_uses_the_codes_stopgap_raises() {
+        assert_eq!(
+            sqlstate_error_class(&SqlState::INSUFFICIENT_PRIVILEGE),
+            Some(ErrorClass::Permission)
+        );
+        assert_eq!(
+            sqlstate_error_class(&SqlState::INVALID_PARAMETER_VALUE),
+            Some(ErrorClass::Validation)
+        );
+        assert_eq!(
+            sqlstate_error_class(&SqlState::OBJECT_NOT_IN_PREREQUISITE_STATE),
+            Some(ErrorClass::Validation)
+        );
+        assert_eq!(sqlstate_error_class(&SqlState::INTERNAL_ERROR), None);
+        assert_eq!(sqlstate_error_class(&SqlState::T_R_SERIALIZATION_FAILURE), None);
     }
 
     #[test]
@@ -648,6 +1581,9 @@ mod tests {
         assert_eq!(EXIT_RESPONSE_DECODE, 12);
         assert_eq!(EXIT_OUTPUT_FORMAT, 13);
         assert_eq!(EXIT_PROJECT_LAYOUT, 14);
+        assert_eq!(EXIT_CONFIRMATION_REQUIRED, 15);
+        assert_eq!(EXIT_VALIDATION, 16);
+        assert_eq!(EXIT_PERMISSION, 17);
     }
 
     #[test]
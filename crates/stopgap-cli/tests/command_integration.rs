@@ -1,23 +1,42 @@
 use std::{
     fs,
+    io::{Read, Write},
+    net::TcpListener,
     path::{Path, PathBuf},
+    thread,
     time::{SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{Result, anyhow};
 use serde_json::{Value, json};
 use stopgap_cli::{
-    AppError, Command, EXIT_DB_QUERY, EXIT_PROJECT_LAYOUT, OutputMode, StopgapApi,
-    discover_stopgap_exports, discover_stopgap_modules, execute_command_with_project_root,
+    AppError, Command, DiffChange, EXIT_CONFIRMATION_REQUIRED, EXIT_DB_QUERY, EXIT_PERMISSION,
+    EXIT_PROJECT_LAYOUT, EXIT_VALIDATION, IsolationLevel, OutputMode, StopgapApi,
+    classify_db_error, deploy_transaction_statements, discover_stopgap_exports,
+    discover_stopgap_modules, execute_command_with_project_root,
 };
 
 struct MockApi {
     deploy_result: Result<i64>,
     rollback_result: Result<i64>,
+    rollback_steps: Option<Option<i32>>,
     status_result: Result<Option<Value>>,
+    env_stats_result: Result<Option<Value>>,
     deployments_result: Result<Value>,
+    activation_history_result: Result<Value>,
+    activation_history_limit: Option<i32>,
+    manifest_result: Result<Value>,
+    artifact_result: Result<Option<Value>>,
     diff_result: Result<Value>,
+    whoami_result: Result<Value>,
+    export_env_result: Result<Value>,
+    import_env_result: Result<Value>,
+    imported_snapshot: Option<Value>,
     deploy_exports_json: Option<String>,
+    deploy_isolation: Option<IsolationLevel>,
+    diff_only: Option<DiffChange>,
+    diff_with_source: bool,
+    diff_exports_json: Option<String>,
 }
 
 impl Default for MockApi {
@@ -25,10 +44,24 @@ impl Default for MockApi {
         Self {
             deploy_result: Ok(0),
             rollback_result: Ok(0),
+            rollback_steps: None,
             status_result: Ok(None),
+            env_stats_result: Ok(None),
             deployments_result: Ok(json!([])),
+            activation_history_result: Ok(json!([])),
+            activation_history_limit: None,
+            manifest_result: Ok(json!({})),
+            artifact_result: Ok(None),
             diff_result: Ok(json!({})),
+            whoami_result: Ok(json!({})),
+            export_env_result: Ok(json!({})),
+            import_env_result: Ok(json!({})),
+            imported_snapshot: None,
             deploy_exports_json: None,
+            deploy_isolation: None,
+            diff_only: None,
+            diff_with_source: false,
+            diff_exports_json: None,
         }
     }
 }
@@ -40,31 +73,83 @@ impl StopgapApi for MockApi {
         _from_schema: &str,
         _label: Option<&str>,
         _prune: bool,
+        isolation: Option<IsolationLevel>,
         deploy_exports_json: Option<&str>,
     ) -> Result<i64> {
+        self.deploy_isolation = isolation;
         self.deploy_exports_json = deploy_exports_json.map(str::to_string);
-        self.deploy_result.as_ref().map(|value| *value).map_err(clone_error)
+        take_result(&mut self.deploy_result)
     }
 
-    fn rollback(&mut self, _env: &str, _steps: i32, _to_id: Option<i64>) -> Result<i64> {
-        self.rollback_result.as_ref().map(|value| *value).map_err(clone_error)
+    fn rollback(&mut self, _env: &str, steps: Option<i32>, _to_id: Option<i64>) -> Result<i64> {
+        self.rollback_steps = Some(steps);
+        take_result(&mut self.rollback_result)
     }
 
     fn status(&mut self, _env: &str) -> Result<Option<Value>> {
-        self.status_result.as_ref().map(|value| value.clone()).map_err(clone_error)
+        take_result(&mut self.status_result)
+    }
+
+    fn env_stats(&mut self, _env: &str) -> Result<Option<Value>> {
+        take_result(&mut self.env_stats_result)
     }
 
     fn deployments(&mut self, _env: &str) -> Result<Value> {
-        self.deployments_result.as_ref().map(|value| value.clone()).map_err(clone_error)
+        take_result(&mut self.deployments_result)
+    }
+
+    fn activation_history(&mut self, _env: &str, limit: i32) -> Result<Value> {
+        self.activation_history_limit = Some(limit);
+        take_result(&mut self.activation_history_result)
+    }
+
+    fn manifest(&mut self, _deployment_id: i64) -> Result<Value> {
+        take_result(&mut self.manifest_result)
     }
 
-    fn diff(&mut self, _env: &str, _from_schema: &str) -> Result<Value> {
-        self.diff_result.as_ref().map(|value| value.clone()).map_err(clone_error)
+    fn get_artifact(&mut self, _hash: &str) -> Result<Option<Value>> {
+        take_result(&mut self.artifact_result)
+    }
+
+    fn diff(
+        &mut self,
+        _env: &str,
+        _from_schema: &str,
+        _ignore_removed: bool,
+        only: Option<DiffChange>,
+        with_source: bool,
+        deploy_exports_json: Option<&str>,
+    ) -> Result<Value> {
+        self.diff_exports_json = deploy_exports_json.map(str::to_string);
+        self.diff_only = only;
+        self.diff_with_source = with_source;
+        take_result(&mut self.diff_result)
+    }
+
+    fn whoami(&mut self, _env: &str, _from_schema: &str) -> Result<Value> {
+        take_result(&mut self.whoami_result)
+    }
+
+    fn export_env(&mut self, _env: &str) -> Result<Value> {
+        take_result(&mut self.export_env_result)
+    }
+
+    fn import_env(&mut self, snapshot: &Value) -> Result<Value> {
+        self.imported_snapshot = Some(snapshot.clone());
+        take_result(&mut self.import_env_result)
     }
 }
 
-fn clone_error(error: &anyhow::Error) -> anyhow::Error {
-    anyhow!(error.to_string())
+/// Hands out a stored mock result. An error comes back as-is the first time, so it still
+/// downcasts to its source (e.g. a `postgres::Error`), and as a text copy after that.
+fn take_result<T: Clone>(result: &mut Result<T>) -> Result<T> {
+    match result {
+        Ok(value) => Ok(value.clone()),
+        Err(error) => {
+            let copy = anyhow!(error.to_string());
+            Err(std::mem::replace(error, copy))
+        }
+    }
 }
 
 fn parse_json_output(buffer: Vec<u8>) -> Value {
@@ -86,10 +171,14 @@ fn deploy_json_output_schema_is_stable() {
     );
     execute_command_with_project_root(
         Command::Deploy {
-            env: "prod".to_string(),
+            env: Some("prod".to_string()),
             from_schema: "app".to_string(),
             label: Some("v1".to_string()),
             prune: true,
+            isolation: None,
+            yes: false,
+            watch: false,
+            watch_interval: 2,
         },
         OutputMode::Json,
         &mut api,
@@ -130,7 +219,12 @@ fn rollback_json_output_schema_is_stable() {
     let mut api = MockApi { rollback_result: Ok(40), ..Default::default() };
     let mut out = Vec::new();
     execute_command_with_project_root(
-        Command::Rollback { env: "prod".to_string(), steps: 2, to_id: Some(40) },
+        Command::Rollback {
+            env: Some("prod".to_string()),
+            steps: Some(2),
+            to_id: Some(40),
+            yes: false,
+        },
         OutputMode::Json,
         &mut api,
         &mut out,
@@ -144,6 +238,31 @@ fn rollback_json_output_schema_is_stable() {
     assert_eq!(payload["steps"], 2);
     assert_eq!(payload["to_id"], 40);
     assert_eq!(payload["deployment_id"], 40);
+    assert_eq!(api.rollback_steps, Some(Some(2)));
+}
+
+#[test]
+fn rollback_without_steps_passes_null_so_the_server_can_use_the_baseline() {
+    let mut api = MockApi { rollback_result: Ok(12), ..Default::default() };
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Rollback {
+            env: Some("staging".to_string()),
+            steps: None,
+            to_id: None,
+            yes: false,
+        },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect("rollback succeeds");
+
+    let payload = parse_json_output(out);
+    assert_eq!(api.rollback_steps, Some(None));
+    assert_eq!(payload["steps"], Value::Null);
+    assert_eq!(payload["deployment_id"], 12);
 }
 
 #[test]
@@ -154,7 +273,7 @@ fn status_json_output_schema_is_stable() {
     };
     let mut out = Vec::new();
     execute_command_with_project_root(
-        Command::Status { env: "prod".to_string() },
+        Command::Status { env: Some("prod".to_string()), stats: false },
         OutputMode::Json,
         &mut api,
         &mut out,
@@ -166,6 +285,48 @@ fn status_json_output_schema_is_stable() {
     assert_eq!(payload["command"], "status");
     assert_eq!(payload["env"], "prod");
     assert_eq!(payload["status"]["active_deployment_id"], 7);
+    assert!(payload.get("stats").is_none());
+}
+
+#[test]
+fn status_with_stats_includes_env_footprint() {
+    let mut api = MockApi {
+        status_result: Ok(Some(json!({"active_deployment_id": 7, "env": "prod"}))),
+        env_stats_result: Ok(Some(json!({
+            "env": "prod",
+            "live_schema": "live_deployment",
+            "deployment_count": 3,
+            "active_function_count": 2,
+            "active_artifact_bytes": 512
+        }))),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Status { env: Some("prod".to_string()), stats: true },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect("status with stats succeeds");
+
+    let payload = parse_json_output(out);
+    assert_eq!(payload["status"]["active_deployment_id"], 7);
+    assert_eq!(payload["stats"]["deployment_count"], 3);
+    assert_eq!(payload["stats"]["active_artifact_bytes"], 512);
+
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Status { env: Some("prod".to_string()), stats: true },
+        OutputMode::Human,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect("human status with stats succeeds");
+    let rendered = String::from_utf8(out).expect("utf8 output");
+    assert!(rendered.contains("stats={\"active_artifact_bytes\":512"));
 }
 
 #[test]
@@ -179,7 +340,7 @@ fn deployments_json_output_schema_is_stable() {
     };
     let mut out = Vec::new();
     execute_command_with_project_root(
-        Command::Deployments { env: "prod".to_string() },
+        Command::Deployments { env: Some("prod".to_string()) },
         OutputMode::Json,
         &mut api,
         &mut out,
@@ -194,6 +355,150 @@ fn deployments_json_output_schema_is_stable() {
     assert!(payload["deployments"].is_array());
 }
 
+#[test]
+fn logs_human_output_prints_activation_timeline() {
+    let mut api = MockApi {
+        activation_history_result: Ok(json!([
+            {
+                "activation_id": 8,
+                "from_deployment_id": 4,
+                "to_deployment_id": 5,
+                "to_label": "v2",
+                "activated_at": "2024-05-01T12:00:00+00:00",
+                "activated_by": "alice"
+            },
+            {
+                "activation_id": 7,
+                "from_deployment_id": null,
+                "to_deployment_id": 4,
+                "to_label": null,
+                "activated_at": "2024-04-30T09:30:00+00:00",
+                "activated_by": "bob"
+            }
+        ])),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Logs { env: Some("prod".to_string()), limit: 2 },
+        OutputMode::Human,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect("logs succeeds");
+
+    assert_eq!(api.activation_history_limit, Some(2));
+    let rendered = String::from_utf8(out).expect("utf8 output");
+    assert_eq!(
+        rendered,
+        "logs env=prod count=2\n\
+         2024-05-01T12:00:00+00:00 activation=8 4 -> 5 label=v2 by=alice\n\
+         2024-04-30T09:30:00+00:00 activation=7 none -> 4 label=none by=bob\n"
+    );
+}
+
+#[test]
+fn logs_json_output_schema_is_stable() {
+    let mut api = MockApi {
+        activation_history_result: Ok(json!([{"activation_id": 3, "to_deployment_id": 2}])),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Logs { env: Some("staging".to_string()), limit: 20 },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect("logs succeeds");
+
+    let payload = parse_json_output(out);
+    assert_eq!(payload["command"], "logs");
+    assert_eq!(payload["env"], "staging");
+    assert_eq!(payload["limit"], 20);
+    assert_eq!(payload["count"], 1);
+    assert_eq!(payload["activations"][0]["activation_id"], 3);
+}
+
+#[test]
+fn manifest_json_output_schema_is_stable() {
+    let mut api = MockApi {
+        manifest_result: Ok(json!({"env": "prod", "label": "v1", "functions": []})),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Manifest { id: 7 },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect("manifest succeeds");
+
+    let payload = parse_json_output(out);
+    assert_eq!(payload["command"], "manifest");
+    assert_eq!(payload["deployment_id"], 7);
+    assert_eq!(payload["manifest"]["label"], "v1");
+}
+
+#[test]
+fn artifact_prints_compiled_js_in_human_mode_and_full_artifact_in_json() {
+    let mut api = MockApi {
+        artifact_result: Ok(Some(json!({
+            "source_ts": "export const answer: number = 42;",
+            "compiled_js": "export const answer = 42;\n",
+            "compiler_fingerprint": "tsgo@test",
+        }))),
+        ..Default::default()
+    };
+
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Artifact { hash: "sha256:abc".to_string() },
+        OutputMode::Human,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect("artifact succeeds");
+    assert_eq!(String::from_utf8(out).expect("utf8 output"), "export const answer = 42;\n\n");
+
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Artifact { hash: "sha256:abc".to_string() },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect("artifact succeeds");
+    let payload = parse_json_output(out);
+    assert_eq!(payload["command"], "artifact");
+    assert_eq!(payload["artifact_hash"], "sha256:abc");
+    assert_eq!(payload["artifact"]["source_ts"], "export const answer: number = 42;");
+}
+
+#[test]
+fn missing_artifact_is_a_validation_error() {
+    let mut api = MockApi::default();
+    let mut out = Vec::new();
+
+    let error = execute_command_with_project_root(
+        Command::Artifact { hash: "sha256:missing".to_string() },
+        OutputMode::Human,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect_err("missing artifact should fail");
+    assert!(matches!(error, AppError::Validation(_)));
+    assert!(error.to_string().contains("artifact sha256:missing not found"));
+    assert!(out.is_empty());
+}
+
 #[test]
 fn diff_json_output_schema_is_stable() {
     let mut api = MockApi {
@@ -202,7 +507,13 @@ fn diff_json_output_schema_is_stable() {
     };
     let mut out = Vec::new();
     execute_command_with_project_root(
-        Command::Diff { env: "prod".to_string(), from_schema: "app".to_string() },
+        Command::Diff {
+            env: Some("prod".to_string()),
+            from_schema: "app".to_string(),
+            ignore_removed: true,
+            only: None,
+            with_source: false,
+        },
         OutputMode::Json,
         &mut api,
         &mut out,
@@ -214,16 +525,148 @@ fn diff_json_output_schema_is_stable() {
     assert_eq!(payload["command"], "diff");
     assert_eq!(payload["env"], "prod");
     assert_eq!(payload["from_schema"], "app");
+    assert_eq!(payload["ignore_removed"], true);
     assert_eq!(payload["diff"]["added"][0], "new_fn");
 }
 
+#[test]
+fn diff_forwards_only_and_with_source_flags() {
+    let mut api = MockApi::default();
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Diff {
+            env: Some("prod".to_string()),
+            from_schema: "app".to_string(),
+            ignore_removed: false,
+            only: Some(DiffChange::Changed),
+            with_source: true,
+        },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect("diff succeeds");
+
+    assert_eq!(api.diff_only, Some(DiffChange::Changed));
+    assert!(api.diff_with_source);
+    assert_eq!(api.diff_exports_json, None, "plain diff should not send deploy export metadata");
+    let payload = parse_json_output(out);
+    assert_eq!(payload["only"], "changed");
+    assert_eq!(payload["with_source"], true);
+}
+
+#[test]
+fn export_env_writes_snapshot_file() {
+    let project = create_project_root("export-env");
+    let snapshot = json!({
+        "snapshot_version": 1,
+        "environment": {"env": "prod"},
+        "deployments": [{"id": 1}, {"id": 2}],
+        "artifacts": [{"artifact_hash": "sha256:abc"}]
+    });
+    let mut api = MockApi { export_env_result: Ok(snapshot.clone()), ..Default::default() };
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::ExportEnv { env: Some("prod".to_string()), file: PathBuf::from("env.json") },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project,
+    )
+    .expect("export-env succeeds");
+
+    let payload = parse_json_output(out);
+    assert_eq!(payload["command"], "export-env");
+    assert_eq!(payload["deployment_count"], 2);
+    assert_eq!(payload["artifact_count"], 1);
+    let written = fs::read_to_string(project.join("env.json")).expect("snapshot file exists");
+    assert_eq!(serde_json::from_str::<Value>(&written).expect("snapshot is json"), snapshot);
+}
+
+#[test]
+fn import_env_sends_snapshot_file_contents() {
+    let project = create_project_root("import-env");
+    write_file(
+        project.join("env.json"),
+        r#"{"snapshot_version": 1, "environment": {"env": "prod"}}"#,
+    );
+    let mut api = MockApi {
+        import_env_result: Ok(json!({"env": "prod", "active_deployment_id": 4, "deployments": 2})),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::ImportEnv { file: PathBuf::from("env.json") },
+        OutputMode::Human,
+        &mut api,
+        &mut out,
+        &project,
+    )
+    .expect("import-env succeeds");
+
+    assert_eq!(
+        api.imported_snapshot.as_ref().map(|snapshot| &snapshot["environment"]["env"]),
+        Some(&json!("prod"))
+    );
+    let line = String::from_utf8(out).expect("utf8 output");
+    assert!(line.starts_with("imported env=prod active_deployment_id=4 deployments=2"));
+}
+
+#[test]
+fn import_env_rejects_unreadable_snapshot_file() {
+    let project = create_project_root("import-env-missing");
+    let mut api = MockApi::default();
+    let err = execute_command_with_project_root(
+        Command::ImportEnv { file: PathBuf::from("missing.json") },
+        OutputMode::Json,
+        &mut api,
+        &mut Vec::new(),
+        &project,
+    )
+    .expect_err("missing snapshot file should fail");
+
+    assert_eq!(err.code(), EXIT_PROJECT_LAYOUT);
+    assert!(api.imported_snapshot.is_none());
+}
+
+#[test]
+fn whoami_human_output_lists_failed_checks() {
+    let mut api = MockApi {
+        whoami_result: Ok(json!({
+            "session_user": "alice",
+            "ok": false,
+            "checks": [
+                {"check": "source_schema_usage", "passed": false, "detail": "USAGE on source schema app"},
+                {"check": "deployer_role_member", "passed": true, "detail": "session_user is a member of stopgap_deployer"}
+            ]
+        })),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Whoami { env: Some("prod".to_string()), from_schema: "app".to_string() },
+        OutputMode::Human,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect("whoami succeeds");
+
+    let rendered = String::from_utf8(out).expect("human output should be utf8");
+    assert_eq!(
+        rendered.trim_end(),
+        "whoami env=prod user=alice ok=false failed=[USAGE on source schema app]"
+    );
+}
+
 #[test]
 fn db_query_failures_use_non_zero_query_exit_code() {
     let mut api = MockApi { status_result: Err(anyhow!("query failed")), ..Default::default() };
     let mut out = Vec::new();
 
     let error = execute_command_with_project_root(
-        Command::Status { env: "prod".to_string() },
+        Command::Status { env: Some("prod".to_string()), stats: false },
         OutputMode::Json,
         &mut api,
         &mut out,
@@ -235,6 +678,152 @@ fn db_query_failures_use_non_zero_query_exit_code() {
     assert_eq!(error.code(), EXIT_DB_QUERY);
 }
 
+/// Returns the `postgres::Error` a client gets from a server that rejects its startup packet
+/// with `sqlstate`, so classification is exercised on a real `DbError` rather than message text.
+fn db_error(sqlstate: &str, message: &str) -> postgres::Error {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("fake server should bind");
+    let port = listener.local_addr().expect("fake server should have an address").port();
+    let mut fields = Vec::new();
+    for (tag, value) in [(b'S', "ERROR"), (b'C', sqlstate), (b'M', message)] {
+        fields.push(tag);
+        fields.extend_from_slice(value.as_bytes());
+        fields.push(0);
+    }
+    fields.push(0);
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("fake server should accept");
+        let mut len = [0; 4];
+        stream.read_exact(&mut len).expect("startup length should be readable");
+        let mut startup = vec![0; u32::from_be_bytes(len) as usize - 4];
+        stream.read_exact(&mut startup).expect("startup packet should be readable");
+        let mut response = vec![b'E'];
+        response.extend_from_slice(&(fields.len() as u32 + 4).to_be_bytes());
+        response.extend_from_slice(&fields);
+        stream.write_all(&response).expect("error response should be written");
+    });
+    let error = postgres::Client::connect(
+        &format!("host=127.0.0.1 port={port} user=stopgap sslmode=disable"),
+        postgres::NoTls,
+    )
+    .err()
+    .expect("fake server should reject the connection");
+    server.join().expect("fake server should finish");
+    error
+}
+
+#[test]
+fn classify_db_error_uses_the_sqlstate_stopgap_raised() {
+    let classify = |sqlstate: &str, message: &str| {
+        classify_db_error(anyhow::Error::from(db_error(sqlstate, message)))
+    };
+
+    assert!(matches!(
+        classify("42501", "permission denied for stopgap deploy"),
+        AppError::Permission(_)
+    ));
+    assert!(matches!(classify("22023", "invalid env name"), AppError::Validation(_)));
+    assert!(matches!(
+        classify("55000", "stopgap deploy conflict for env prod"),
+        AppError::Validation(_)
+    ));
+    // Wording that reads like a rejection does not override the SQLSTATE.
+    assert!(matches!(
+        classify("40001", "permission denied: could not serialize access"),
+        AppError::DbQuery(_)
+    ));
+    assert!(matches!(classify("57014", "invalid request canceled"), AppError::DbQuery(_)));
+}
+
+#[test]
+fn permission_and_validation_failures_use_distinct_exit_codes() {
+    let mut api = MockApi {
+        rollback_result: Err(db_error(
+            "42501",
+            "permission denied for stopgap rollback: session_user must be a member of role stopgap_deployer",
+        )
+        .into()),
+        manifest_result: Err(db_error("22023", "stopgap deployment 99 does not exist").into()),
+        status_result: Err(db_error("55000", "stopgap env prod has no active deployment").into()),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+
+    let error = execute_command_with_project_root(
+        Command::Rollback { env: Some("prod".to_string()), steps: Some(1), to_id: None, yes: true },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect_err("rollback should fail");
+    assert!(matches!(error, AppError::Permission(_)));
+    assert_eq!(error.code(), EXIT_PERMISSION);
+
+    let error = execute_command_with_project_root(
+        Command::Manifest { id: 99 },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect_err("manifest should fail");
+    assert!(matches!(error, AppError::Validation(_)));
+    assert_eq!(error.code(), EXIT_VALIDATION);
+
+    let error = execute_command_with_project_root(
+        Command::Status { env: Some("prod".to_string()), stats: false },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project_root_for_non_deploy_tests(),
+    )
+    .expect_err("status should fail");
+    assert!(matches!(error, AppError::Validation(_)));
+    assert_eq!(error.code(), EXIT_VALIDATION);
+}
+
+#[test]
+fn deploy_forwards_isolation_level_to_transaction_setup() {
+    let mut api = MockApi { deploy_result: Ok(7), ..Default::default() };
+    let mut out = Vec::new();
+    let project = create_project_root("deploy_forwards_isolation_level_to_transaction_setup");
+    write_file(
+        project.join("stopgap/orders.ts"),
+        "export const list = query(v.object({}), async () => []);",
+    );
+    execute_command_with_project_root(
+        Command::Deploy {
+            env: Some("prod".to_string()),
+            from_schema: "app".to_string(),
+            label: None,
+            prune: false,
+            isolation: Some(IsolationLevel::Serializable),
+            yes: false,
+            watch: false,
+            watch_interval: 2,
+        },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project,
+    )
+    .expect("deploy succeeds");
+
+    assert_eq!(api.deploy_isolation, Some(IsolationLevel::Serializable));
+    assert_eq!(parse_json_output(out)["isolation"], "serializable");
+    assert_eq!(
+        deploy_transaction_statements(false, Some(IsolationLevel::RepeatableRead)),
+        vec![
+            "SET TRANSACTION ISOLATION LEVEL REPEATABLE READ".to_string(),
+            "SET LOCAL stopgap.prune = 'off'".to_string(),
+        ]
+    );
+    assert_eq!(
+        deploy_transaction_statements(true, None),
+        vec!["SET LOCAL stopgap.prune = 'on'".to_string()]
+    );
+}
+
 #[test]
 fn deploy_fails_fast_when_stopgap_source_root_missing() {
     let mut api = MockApi { deploy_result: Ok(42), ..Default::default() };
@@ -243,10 +832,14 @@ fn deploy_fails_fast_when_stopgap_source_root_missing() {
 
     let error = execute_command_with_project_root(
         Command::Deploy {
-            env: "prod".to_string(),
+            env: Some("prod".to_string()),
             from_schema: "app".to_string(),
             label: None,
             prune: false,
+            isolation: None,
+            yes: false,
+            watch: false,
+            watch_interval: 2,
         },
         OutputMode::Json,
         &mut api,
@@ -263,6 +856,129 @@ fn deploy_fails_fast_when_stopgap_source_root_missing() {
     );
 }
 
+#[test]
+fn deploy_to_protected_env_requires_confirmation() {
+    let mut api = MockApi { deploy_result: Ok(42), ..Default::default() };
+    let mut out = Vec::new();
+    let project = create_project_root("deploy_to_protected_env_requires_confirmation");
+    write_file(project.join("package.json"), "{}\n");
+    write_file(project.join("stopgap.json"), r#"{"protected_envs": ["prod"]}"#);
+    write_file(
+        project.join("stopgap/users.ts"),
+        "export const list = query(v.object({}), async () => []);",
+    );
+
+    let error = execute_command_with_project_root(
+        Command::Deploy {
+            env: Some("prod".to_string()),
+            from_schema: "app".to_string(),
+            label: None,
+            prune: false,
+            isolation: None,
+            yes: false,
+            watch: false,
+            watch_interval: 2,
+        },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project,
+    )
+    .expect_err("deploy to a protected env should need --yes");
+
+    assert!(matches!(error, AppError::ConfirmationRequired(_)));
+    assert_eq!(error.code(), EXIT_CONFIRMATION_REQUIRED);
+    assert!(error.to_string().contains("env prod is protected"));
+    assert!(api.deploy_exports_json.is_none(), "api should not be called without confirmation");
+    assert!(out.is_empty());
+
+    execute_command_with_project_root(
+        Command::Deploy {
+            env: Some("prod".to_string()),
+            from_schema: "app".to_string(),
+            label: None,
+            prune: false,
+            isolation: None,
+            yes: true,
+            watch: false,
+            watch_interval: 2,
+        },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project,
+    )
+    .expect("deploy with --yes should proceed");
+    assert_eq!(parse_json_output(out)["deployment_id"], 42);
+}
+
+#[test]
+fn rollback_to_protected_env_requires_confirmation() {
+    let mut api = MockApi { rollback_result: Ok(40), ..Default::default() };
+    let project = create_project_root("rollback_to_protected_env_requires_confirmation");
+    write_file(project.join("package.json"), "{}\n");
+    write_file(project.join("stopgap.json"), r#"{"protected_envs": ["prod"]}"#);
+
+    let error = execute_command_with_project_root(
+        Command::Rollback {
+            env: Some("prod".to_string()),
+            steps: Some(1),
+            to_id: None,
+            yes: false,
+        },
+        OutputMode::Json,
+        &mut api,
+        &mut Vec::new(),
+        &project,
+    )
+    .expect_err("rollback of a protected env should need --yes");
+    assert_eq!(error.code(), EXIT_CONFIRMATION_REQUIRED);
+
+    for env in ["PROD", " prod ", "Prod"] {
+        let error = execute_command_with_project_root(
+            Command::Rollback {
+                env: Some(env.to_string()),
+                steps: Some(1),
+                to_id: None,
+                yes: false,
+            },
+            OutputMode::Json,
+            &mut api,
+            &mut Vec::new(),
+            &project,
+        )
+        .expect_err("env names the server normalizes to prod should need --yes");
+        assert_eq!(error.code(), EXIT_CONFIRMATION_REQUIRED, "env {env:?}");
+    }
+
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Rollback {
+            env: Some("staging".to_string()),
+            steps: Some(1),
+            to_id: None,
+            yes: false,
+        },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project,
+    )
+    .expect("rollback of an unprotected env should not need --yes");
+    assert_eq!(parse_json_output(out)["deployment_id"], 40);
+
+    let mut out = Vec::new();
+    execute_command_with_project_root(
+        Command::Rollback { env: Some("prod".to_string()), steps: Some(1), to_id: None, yes: true },
+        OutputMode::Json,
+        &mut api,
+        &mut out,
+        &project,
+    )
+    .expect("rollback with --yes should proceed");
+    assert_eq!(parse_json_output(out)["env"], "prod");
+}
+
 #[test]
 fn discover_stopgap_modules_normalizes_paths_deterministically() {
     let project =
@@ -310,8 +1026,10 @@ fn discover_stopgap_exports_rejects_non_wrapper_named_exports() {
 
 #[test]
 fn init_creates_stopgap_example_without_db_calls() {
-    let mut api = MockApi::default();
-    api.deploy_result = Err(anyhow!("deploy should never run for init"));
+    let mut api = MockApi {
+        deploy_result: Err(anyhow!("deploy should never run for init")),
+        ..Default::default()
+    };
     let mut out = Vec::new();
     let project = create_project_root("init_creates_stopgap_example_without_db_calls");
     write_file(project.join(".gitignore"), "node_modules\n");
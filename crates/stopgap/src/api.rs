@@ -1,17 +1,66 @@
 use pgrx::JsonB;
+use pgrx::iter::TableIterator;
 use pgrx::pg_sys::panic::CaughtError;
 use pgrx::prelude::*;
 use serde_json::json;
+use std::ffi::CString;
 
 use crate::{
-    DeploymentStatus, STOPGAP_DEPLOYER_ROLE, ensure_deploy_permissions,
-    ensure_deployment_belongs_to_env, ensure_no_overloaded_plts_functions, ensure_role_membership,
-    find_rollback_target_by_steps, hash_lock_key, load_deployment_status, load_deployments,
-    load_diff, load_environment_state, load_status, observability, reactivate_deployment,
-    resolve_default_env, resolve_live_schema, rollback_steps_to_offset, run_deploy_flow,
-    run_sql_with_args, transition_deployment_status, transition_if_active, update_failed_manifest,
+    DeploymentStatus, MANIFEST_VERSION, RECOGNIZED_SETTINGS, STOPGAP_DEPLOYER_ROLE,
+    STOPGAP_OWNER_ROLE, ensure_deploy_permissions, ensure_deployment_belongs_to_env,
+    ensure_expected_active_deployment, ensure_no_overloaded_plts_functions,
+    ensure_promote_permissions, ensure_role_membership, ensure_source_schema_allowed,
+    hash_lock_key, load_activation_history, load_deploy_permission_report, load_deployment_status,
+    load_deployments, load_diff, load_env_snapshot, load_env_stats, load_environment_state,
+    load_manifest, load_orphans, load_rollback_preview, load_status, load_validation,
+    normalize_deploy_limits, observability, reactivate_deployment, reap_stale_open_deployments,
+    resolve_default_env, resolve_env_name, resolve_live_arg_name, resolve_live_schema,
+    resolve_rollback_target, rollback_steps_to_offset, run_deploy_flow, run_import_env,
+    run_promote_flow, run_shadow_deploy, run_sql_with_args, transition_deployment_status,
+    transition_if_active, update_failed_manifest,
 };
 
+/// Raises `message` under the SQLSTATE for its operation error class so clients can branch on the
+/// code instead of the wording. Any other class keeps `cause`, the packed SQLSTATE of the Postgres
+/// error behind the failure (e.g. `40001`, `57014`), and is otherwise an internal error.
+fn raise_classified_error(class: &str, message: &str, cause: Option<i32>) -> ! {
+    let code = match class {
+        "permission" => PgSqlErrorCode::ERRCODE_INSUFFICIENT_PRIVILEGE as i32,
+        "validation" => PgSqlErrorCode::ERRCODE_INVALID_PARAMETER_VALUE as i32,
+        "state" => PgSqlErrorCode::ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE as i32,
+        _ => cause.unwrap_or(PgSqlErrorCode::ERRCODE_INTERNAL_ERROR as i32),
+    };
+    let message = CString::new(message.replace('\0', "")).expect("NUL bytes were removed");
+    let mut edata = pg_sys::ErrorData {
+        elevel: PgLogLevel::ERROR as i32,
+        sqlerrcode: code,
+        message: message.as_ptr().cast_mut(),
+        ..Default::default()
+    };
+    // `ErrorReport` reports SQLSTATEs `PgSqlErrorCode` does not list as `XX000`; `ThrowErrorData`
+    // raises `code` as-is and pgrx rethrows that error unchanged once the Rust frames unwind.
+    // SAFETY: `ThrowErrorData` copies `message`, which outlives the guarded FFI call.
+    unsafe { pg_sys::ThrowErrorData(&mut edata) };
+    unreachable!("ERROR-level reports do not return")
+}
+
+fn raise_operation_error(err: &str) -> ! {
+    raise_classified_error(observability::classify_operation_error(err), err, None)
+}
+
+/// Packed SQLSTATE of the Postgres error a catch handler is handling, including codes the
+/// `PgSqlErrorCode` in its `ErrorReport` cannot represent.
+fn caught_sqlstate() -> i32 {
+    // SAFETY: catch handlers run before pgrx flushes the error state, so the caught error is still
+    // the top entry of Postgres' error stack, and pgrx has left the error memory context.
+    unsafe {
+        let edata = pg_sys::CopyErrorData();
+        let code = (*edata).sqlerrcode;
+        pg_sys::FreeErrorData(edata);
+        code
+    }
+}
+
 fn validate_call_path(path: &str) -> Result<(), String> {
     let mut segments = path.split('.');
     let valid_prefix = matches!(segments.next(), Some("api"));
@@ -168,9 +217,73 @@ mod stopgap {
         JsonB(observability::metrics_json())
     }
 
+    #[pg_extern]
+    fn metrics_prometheus() -> String {
+        observability::render_prometheus(&observability::metrics_json())
+    }
+
+    #[pg_extern]
+    fn settings() -> TableIterator<
+        'static,
+        (
+            name!(name, String),
+            name!(current_value, Option<String>),
+            name!(default_value, Option<String>),
+            name!(description, String),
+        ),
+    > {
+        let rows = RECOGNIZED_SETTINGS
+            .iter()
+            .map(|setting| {
+                let current_value = Spi::get_one_with_args::<String>(
+                    "SELECT current_setting($1, true)",
+                    &[setting.name.into()],
+                )
+                .ok()
+                .flatten();
+                (
+                    setting.name.to_string(),
+                    current_value,
+                    setting.default_value.map(str::to_string),
+                    setting.description.to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        TableIterator::new(rows)
+    }
+
     #[pg_extern(security_definer)]
-    fn deploy(env: &str, from_schema: &str, label: default!(Option<&str>, "NULL")) -> i64 {
+    fn deploy(
+        env: &str,
+        from_schema: &str,
+        label: default!(Option<&str>, "NULL"),
+        limits: default!(Option<JsonB>, "NULL"),
+        expect_active_id: default!(Option<i64>, "NULL"),
+    ) -> i64 {
         let started_at = observability::record_deploy_start();
+        let normalized_env = resolve_env_name(env).unwrap_or_else(|err| {
+            observability::record_deploy_error(
+                started_at,
+                observability::classify_operation_error(err.as_str()),
+            );
+            raise_operation_error(&err)
+        });
+        let env = normalized_env.as_str();
+        let limits = normalize_deploy_limits(limits.as_ref().map(|limits| &limits.0))
+            .unwrap_or_else(|err| {
+                observability::record_deploy_error(
+                    started_at,
+                    observability::classify_operation_error(err.as_str()),
+                );
+                raise_operation_error(&err)
+            });
+        let live_arg_name = resolve_live_arg_name().unwrap_or_else(|err| {
+            observability::record_deploy_error(
+                started_at,
+                observability::classify_operation_error(err.as_str()),
+            );
+            raise_operation_error(&err)
+        });
         observability::log_info(&format!(
             "stopgap.deploy start env={} source_schema={}",
             env, from_schema
@@ -180,7 +293,7 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
         let lock_key = hash_lock_key(env);
         run_sql_with_args(
@@ -193,8 +306,17 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
+        if let Some(expect_active_id) = expect_active_id {
+            ensure_expected_active_deployment(env, expect_active_id).unwrap_or_else(|err| {
+                observability::record_deploy_error(
+                    started_at,
+                    observability::classify_operation_error(err.as_str()),
+                );
+                raise_operation_error(&err)
+            });
+        }
 
         let live_schema = resolve_live_schema();
         ensure_deploy_permissions(from_schema, &live_schema).unwrap_or_else(|err| {
@@ -202,7 +324,14 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
+        });
+        ensure_source_schema_allowed(env, from_schema).unwrap_or_else(|err| {
+            observability::record_deploy_error(
+                started_at,
+                observability::classify_operation_error(err.as_str()),
+            );
+            raise_operation_error(&err)
         });
 
         run_sql_with_args(
@@ -221,16 +350,19 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
 
         ensure_no_overloaded_plts_functions(from_schema);
 
         let manifest = JsonB(json!({
+            "manifest_version": MANIFEST_VERSION,
             "env": env,
             "source_schema": from_schema,
             "live_schema": live_schema,
             "label": label,
+            "limits": limits,
+            "live_arg_name": live_arg_name,
             "functions": []
         }));
         let deployment_id = Spi::get_one_with_args::<i64>(
@@ -245,20 +377,24 @@ mod stopgap {
         .flatten()
         .expect("failed to create deployment");
 
-        if let Err(err) = run_deploy_flow(deployment_id, env, from_schema, &live_schema) {
-            observability::record_deploy_error(
-                started_at,
-                observability::classify_operation_error(err.as_str()),
-            );
+        if let Err(err) =
+            run_deploy_flow(deployment_id, env, from_schema, &live_schema, &live_arg_name, &limits)
+        {
+            let class = observability::classify_operation_error(err.as_str());
+            observability::record_deploy_error(started_at, class);
             observability::log_warn(&format!(
                 "stopgap.deploy failed env={} source_schema={} deployment_id={} err={}",
                 env, from_schema, deployment_id, err
             ));
             let _ = transition_deployment_status(deployment_id, DeploymentStatus::Failed);
             let _ = update_failed_manifest(deployment_id, &err);
-            error!(
-                "stopgap deploy failed for env={} schema={} deployment_id={}: {}",
-                env, from_schema, deployment_id, err
+            raise_classified_error(
+                class,
+                &format!(
+                    "stopgap deploy failed for env={} schema={} deployment_id={}: {}",
+                    env, from_schema, deployment_id, err
+                ),
+                None,
             );
         }
 
@@ -271,14 +407,128 @@ mod stopgap {
         deployment_id
     }
 
+    #[pg_extern(security_definer)]
+    fn promote(from_env: &str, to_env: &str, label: default!(Option<&str>, "NULL")) -> i64 {
+        let from_env = resolve_env_name(from_env).unwrap_or_else(|err| raise_operation_error(&err));
+        let to_env = resolve_env_name(to_env).unwrap_or_else(|err| raise_operation_error(&err));
+        if from_env == to_env {
+            raise_classified_error(
+                "validation",
+                &format!("stopgap promote source and target env are both {}", to_env),
+                None,
+            );
+        }
+        ensure_role_membership(STOPGAP_DEPLOYER_ROLE, "stopgap promote")
+            .unwrap_or_else(|err| raise_operation_error(&err));
+
+        // Lock both envs in a fixed order so concurrent promotions between them cannot deadlock.
+        let mut lock_keys = [hash_lock_key(&from_env), hash_lock_key(&to_env)];
+        lock_keys.sort_unstable();
+        for lock_key in lock_keys {
+            run_sql_with_args(
+                "SELECT pg_advisory_xact_lock($1)",
+                &[lock_key.into()],
+                "failed to acquire promote lock",
+            )
+            .unwrap_or_else(|err| raise_operation_error(&err));
+        }
+
+        let live_schema = resolve_live_schema();
+        ensure_promote_permissions(&live_schema).unwrap_or_else(|err| raise_operation_error(&err));
+        let deployment_id = run_promote_flow(&from_env, &to_env, &live_schema, label)
+            .unwrap_or_else(|err| {
+                observability::log_warn(&format!(
+                    "stopgap.promote failed from_env={} to_env={} err={}",
+                    from_env, to_env, err
+                ));
+                raise_operation_error(&err)
+            });
+        observability::log_info(&format!(
+            "stopgap.promote success from_env={} to_env={} deployment_id={}",
+            from_env, to_env, deployment_id
+        ));
+
+        deployment_id
+    }
+
     #[pg_extern]
     fn status(env: &str) -> Option<JsonB> {
-        load_status(env).map(JsonB)
+        let env = resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        load_status(env.as_str()).map(JsonB)
+    }
+
+    #[pg_extern]
+    fn env_stats(env: &str) -> Option<JsonB> {
+        let env = resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        load_env_stats(env.as_str()).map(JsonB)
     }
 
     #[pg_extern]
     fn deployments(env: &str) -> JsonB {
-        JsonB(load_deployments(env))
+        let env = resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        JsonB(load_deployments(env.as_str()))
+    }
+
+    #[pg_extern]
+    fn activation_history(env: &str, limit: default!(i32, "50")) -> JsonB {
+        let env = resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        JsonB(
+            load_activation_history(env.as_str(), limit)
+                .unwrap_or_else(|err| raise_operation_error(&err)),
+        )
+    }
+
+    #[pg_extern]
+    fn export_env(env: &str) -> JsonB {
+        let env = resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        JsonB(load_env_snapshot(env.as_str()).unwrap_or_else(|err| raise_operation_error(&err)))
+    }
+
+    #[pg_extern(security_definer)]
+    fn import_env(snapshot: JsonB) -> JsonB {
+        ensure_role_membership(STOPGAP_OWNER_ROLE, "stopgap import_env")
+            .unwrap_or_else(|err| raise_operation_error(&err));
+        let env = snapshot
+            .0
+            .pointer("/environment/env")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_else(|| {
+                raise_classified_error(
+                    "validation",
+                    "stopgap import_env snapshot is missing environment.env",
+                    None,
+                )
+            });
+        let normalized_env =
+            resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        let env = normalized_env.as_str();
+        run_sql_with_args(
+            "SELECT pg_advisory_xact_lock($1)",
+            &[hash_lock_key(env).into()],
+            "failed to acquire deploy lock",
+        )
+        .unwrap_or_else(|err| raise_operation_error(&err));
+
+        let summary = run_import_env(env, &snapshot.0).unwrap_or_else(|err| {
+            observability::log_warn(&format!("stopgap.import_env failed env={} err={}", env, err));
+            raise_operation_error(&err)
+        });
+        observability::log_info(&format!(
+            "stopgap.import_env success env={} active_deployment_id={}",
+            env, summary["active_deployment_id"]
+        ));
+
+        JsonB(summary)
+    }
+
+    #[pg_extern]
+    fn orphans() -> JsonB {
+        JsonB(load_orphans().unwrap_or_else(|err| raise_operation_error(&err)))
+    }
+
+    #[pg_extern]
+    fn manifest(deployment_id: i64) -> JsonB {
+        JsonB(load_manifest(deployment_id).unwrap_or_else(|err| raise_operation_error(&err)))
     }
 
     #[pg_extern]
@@ -296,7 +546,8 @@ mod stopgap {
         validate_call_path(path).unwrap_or_else(|message| fail(message));
 
         let export_name = path.rsplit('.').next().expect("validated non-empty path");
-        let env = resolve_default_env();
+        let env = resolve_env_name(resolve_default_env().as_str())
+            .unwrap_or_else(|message| fail(format!("stopgap.call_fn {message}")));
 
         let env_row = Spi::connect(|client| {
             let mut rows = client
@@ -368,13 +619,19 @@ mod stopgap {
                 .unwrap_or_else(|e| fail(classify_routed_execution_error(path, &e.to_string())))
         })
         .catch_others(|caught| {
-            let detail = match caught {
-                CaughtError::PostgresError(report) | CaughtError::ErrorReport(report) => {
-                    report.message().to_string()
+            let (detail, cause) = match caught {
+                CaughtError::PostgresError(report) => {
+                    (report.message().to_string(), Some(caught_sqlstate()))
+                }
+                CaughtError::ErrorReport(report) => {
+                    (report.message().to_string(), Some(report.sql_error_code() as i32))
                 }
-                CaughtError::RustPanic { ereport, .. } => ereport.message().to_string(),
+                CaughtError::RustPanic { ereport, .. } => (ereport.message().to_string(), None),
             };
-            fail(classify_routed_execution_error(path, detail.as_str()))
+            let message = classify_routed_execution_error(path, detail.as_str());
+            let class = observability::classify_call_fn_error(message.as_str());
+            observability::record_call_fn_error(started_at, class);
+            raise_classified_error(class, &message, cause)
         })
         .execute();
         observability::record_call_fn_success(started_at);
@@ -382,28 +639,44 @@ mod stopgap {
     }
 
     #[pg_extern(security_definer)]
-    fn rollback(env: &str, steps: default!(i32, "1"), to_id: default!(Option<i64>, "NULL")) -> i64 {
+    fn rollback(
+        env: &str,
+        steps: default!(Option<i32>, "NULL"),
+        to_id: default!(Option<i64>, "NULL"),
+        to_label: default!(Option<&str>, "NULL"),
+    ) -> i64 {
         let started_at = observability::record_rollback_start();
-        observability::log_info(&format!(
-            "stopgap.rollback start env={} steps={} to_id={}",
-            env,
-            steps,
-            to_id.map(|value| value.to_string()).unwrap_or_else(|| "null".to_string())
-        ));
-        ensure_role_membership(STOPGAP_DEPLOYER_ROLE, "stopgap rollback").unwrap_or_else(|err| {
+        let normalized_env = resolve_env_name(env).unwrap_or_else(|err| {
             observability::record_rollback_error(
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
-        rollback_steps_to_offset(steps).unwrap_or_else(|err| {
+        let env = normalized_env.as_str();
+        observability::log_info(&format!(
+            "stopgap.rollback start env={} steps={} to_id={} to_label={}",
+            env,
+            steps.map(|value| value.to_string()).unwrap_or_else(|| "null".to_string()),
+            to_id.map(|value| value.to_string()).unwrap_or_else(|| "null".to_string()),
+            to_label.unwrap_or("null")
+        ));
+        ensure_role_membership(STOPGAP_DEPLOYER_ROLE, "stopgap rollback").unwrap_or_else(|err| {
             observability::record_rollback_error(
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
+        if let Some(steps) = steps {
+            rollback_steps_to_offset(steps).unwrap_or_else(|err| {
+                observability::record_rollback_error(
+                    started_at,
+                    observability::classify_operation_error(err.as_str()),
+                );
+                raise_operation_error(&err)
+            });
+        }
 
         let lock_key = hash_lock_key(env);
         run_sql_with_args(
@@ -416,7 +689,7 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
 
         let (live_schema, current_active) = load_environment_state(env).unwrap_or_else(|err| {
@@ -424,30 +697,19 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
 
-        let target_deployment_id = match to_id {
-            Some(explicit_id) => {
-                ensure_deployment_belongs_to_env(env, explicit_id).unwrap_or_else(|err| {
+        let target_deployment_id =
+            resolve_rollback_target(env, current_active, steps, to_id, to_label).unwrap_or_else(
+                |err| {
                     observability::record_rollback_error(
                         started_at,
                         observability::classify_operation_error(err.as_str()),
                     );
-                    error!("{err}")
-                });
-                explicit_id
-            }
-            None => {
-                find_rollback_target_by_steps(env, current_active, steps).unwrap_or_else(|err| {
-                    observability::record_rollback_error(
-                        started_at,
-                        observability::classify_operation_error(err.as_str()),
-                    );
-                    error!("{err}")
-                })
-            }
-        };
+                    raise_operation_error(&err)
+                },
+            );
 
         if target_deployment_id == current_active {
             observability::record_rollback_error(started_at, "state");
@@ -455,9 +717,13 @@ mod stopgap {
                 "stopgap.rollback failed env={} target_deployment_id={} reason=already-active",
                 env, target_deployment_id
             ));
-            error!(
-                "stopgap rollback target {} is already active for env {}",
-                target_deployment_id, env
+            raise_classified_error(
+                "state",
+                &format!(
+                    "stopgap rollback target {} is already active for env {}",
+                    target_deployment_id, env
+                ),
+                None,
             );
         }
 
@@ -466,7 +732,7 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
         if target_status != DeploymentStatus::Active
             && target_status != DeploymentStatus::RolledBack
@@ -478,10 +744,14 @@ mod stopgap {
                 target_deployment_id,
                 target_status.as_str()
             ));
-            error!(
-                "stopgap rollback target {} has invalid status {}; expected active or rolled_back",
-                target_deployment_id,
-                target_status.as_str()
+            raise_classified_error(
+                "state",
+                &format!(
+                    "stopgap rollback target {} has invalid status {}; expected active or rolled_back",
+                    target_deployment_id,
+                    target_status.as_str()
+                ),
+                None,
             );
         }
 
@@ -490,7 +760,7 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
 
         transition_if_active(current_active, DeploymentStatus::RolledBack).unwrap_or_else(|err| {
@@ -498,7 +768,7 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
         if target_status == DeploymentStatus::RolledBack {
             transition_deployment_status(target_deployment_id, DeploymentStatus::Active)
@@ -507,7 +777,7 @@ mod stopgap {
                         started_at,
                         observability::classify_operation_error(err.as_str()),
                     );
-                    error!("{err}")
+                    raise_operation_error(&err)
                 });
         }
 
@@ -526,7 +796,7 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
 
         run_sql_with_args(
@@ -542,7 +812,7 @@ mod stopgap {
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
 
         observability::log_info(&format!(
@@ -555,30 +825,255 @@ mod stopgap {
     }
 
     #[pg_extern(security_definer)]
-    fn diff(env: &str, from_schema: &str) -> JsonB {
-        let started_at = observability::record_diff_start();
+    fn set_baseline(env: &str, deployment_id: i64) -> i64 {
+        let normalized_env =
+            resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        let env = normalized_env.as_str();
+        ensure_role_membership(STOPGAP_DEPLOYER_ROLE, "stopgap set_baseline")
+            .unwrap_or_else(|err| raise_operation_error(&err));
+        ensure_deployment_belongs_to_env(env, deployment_id)
+            .unwrap_or_else(|err| raise_operation_error(&err));
+
+        let status =
+            load_deployment_status(deployment_id).unwrap_or_else(|err| raise_operation_error(&err));
+        if status != DeploymentStatus::Active && status != DeploymentStatus::RolledBack {
+            raise_classified_error(
+                "state",
+                &format!(
+                    "stopgap baseline {} has invalid status {}; expected active or rolled_back",
+                    deployment_id,
+                    status.as_str()
+                ),
+            );
+        }
+
+        run_sql_with_args(
+            "
+            UPDATE stopgap.environment
+            SET baseline_deployment_id = $1,
+                updated_at = now()
+            WHERE env = $2
+            ",
+            &[deployment_id.into(), env.into()],
+            "failed to set baseline deployment",
+        )
+        .unwrap_or_else(|err| raise_operation_error(&err));
         observability::log_info(&format!(
-            "stopgap.diff start env={} source_schema={}",
-            env, from_schema
+            "stopgap.set_baseline env={} deployment_id={}",
+            env, deployment_id
         ));
-        ensure_role_membership(STOPGAP_DEPLOYER_ROLE, "stopgap diff").unwrap_or_else(|err| {
+
+        deployment_id
+    }
+
+    #[pg_extern(security_definer)]
+    fn reap_stale_deployments(env: &str, older_than: Interval) -> i64 {
+        let normalized_env =
+            resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        let env = normalized_env.as_str();
+        ensure_role_membership(STOPGAP_DEPLOYER_ROLE, "stopgap reap_stale_deployments")
+            .unwrap_or_else(|err| raise_operation_error(&err));
+        run_sql_with_args(
+            "SELECT pg_advisory_xact_lock($1)",
+            &[hash_lock_key(env).into()],
+            "failed to acquire deploy lock",
+        )
+        .unwrap_or_else(|err| raise_operation_error(&err));
+
+        let reaped = reap_stale_open_deployments(env, older_than)
+            .unwrap_or_else(|err| raise_operation_error(&err));
+        observability::log_info(&format!(
+            "stopgap.reap_stale_deployments env={} reaped={:?}",
+            env, reaped
+        ));
+
+        reaped.len() as i64
+    }
+
+    #[pg_extern(security_definer)]
+    fn prune_exclude(env: &str, fn_name: &str) -> bool {
+        let normalized_env =
+            resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        let env = normalized_env.as_str();
+        ensure_role_membership(STOPGAP_DEPLOYER_ROLE, "stopgap prune_exclude")
+            .unwrap_or_else(|err| raise_operation_error(&err));
+        let fn_name = fn_name.trim();
+        if fn_name.is_empty() {
+            raise_classified_error(
+                "validation",
+                "stopgap prune_exclude requires a non-empty function name",
+            );
+        }
+
+        let added = Spi::get_one_with_args::<bool>(
+            "
+            INSERT INTO stopgap.prune_exclusion (env, fn_name)
+            VALUES ($1, $2)
+            ON CONFLICT (env, fn_name) DO NOTHING
+            RETURNING true
+            ",
+            &[env.into(), fn_name.into()],
+        )
+        .map_err(|e| format!("failed to register prune exclusion: {e}"))
+        .unwrap_or_else(|err| raise_operation_error(&err))
+        .unwrap_or(false);
+        observability::log_info(&format!(
+            "stopgap.prune_exclude env={} fn={} added={}",
+            env, fn_name, added
+        ));
+
+        added
+    }
+
+    #[pg_extern(security_definer)]
+    fn set_allowed_sources(env: &str, schemas: Option<Vec<Option<String>>>) -> Option<Vec<String>> {
+        let normalized_env =
+            resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        let env = normalized_env.as_str();
+        ensure_role_membership(STOPGAP_OWNER_ROLE, "stopgap set_allowed_sources")
+            .unwrap_or_else(|err| raise_operation_error(&err));
+
+        let allowed = schemas.map(|schemas| {
+            let allowed = schemas
+                .into_iter()
+                .map(|schema| match schema.as_deref().map(str::trim) {
+                    Some(schema) if !schema.is_empty() => schema.to_string(),
+                    _ => raise_classified_error(
+                        "validation",
+                        "stopgap set_allowed_sources schema names must be non-empty",
+                    ),
+                })
+                .collect::<std::collections::BTreeSet<_>>();
+            if allowed.is_empty() {
+                raise_classified_error(
+                    "validation",
+                    "stopgap set_allowed_sources requires at least one schema; pass NULL to allow any source schema",
+                );
+            }
+            allowed.into_iter().collect::<Vec<_>>()
+        });
+
+        run_sql_with_args(
+            "
+            INSERT INTO stopgap.environment (env, live_schema, allowed_source_schemas)
+            VALUES ($1, $2, $3::name[])
+            ON CONFLICT (env) DO UPDATE
+            SET allowed_source_schemas = EXCLUDED.allowed_source_schemas,
+                updated_at = now()
+            ",
+            &[env.into(), resolve_live_schema().as_str().into(), allowed.clone().into()],
+            "failed to set allowed source schemas",
+        )
+        .unwrap_or_else(|err| raise_operation_error(&err));
+        observability::log_info(&format!(
+            "stopgap.set_allowed_sources env={} schemas={}",
+            env,
+            allowed.as_ref().map_or_else(|| "any".to_string(), |allowed| allowed.join(","))
+        ));
+
+        allowed
+    }
+
+    #[pg_extern(security_definer)]
+    fn rollback_preview(
+        env: &str,
+        steps: default!(Option<i32>, "NULL"),
+        to_id: default!(Option<i64>, "NULL"),
+        to_label: default!(Option<&str>, "NULL"),
+    ) -> JsonB {
+        let normalized_env =
+            resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        let env = normalized_env.as_str();
+        ensure_role_membership(STOPGAP_DEPLOYER_ROLE, "stopgap rollback_preview")
+            .unwrap_or_else(|err| raise_operation_error(&err));
+        if let Some(steps) = steps {
+            rollback_steps_to_offset(steps).unwrap_or_else(|err| raise_operation_error(&err));
+        }
+
+        let preview = load_rollback_preview(env, steps, to_id, to_label).unwrap_or_else(|err| {
+            observability::log_warn(&format!(
+                "stopgap.rollback_preview failed env={} steps={} err={}",
+                env,
+                steps.map(|value| value.to_string()).unwrap_or_else(|| "null".to_string()),
+                err
+            ));
+            raise_operation_error(&err)
+        });
+        JsonB(preview)
+    }
+
+    #[pg_extern]
+    fn whoami(env: &str, from_schema: &str) -> JsonB {
+        let env = resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        let live_schema = resolve_live_schema();
+        let report = load_deploy_permission_report(env.as_str(), from_schema, &live_schema)
+            .unwrap_or_else(|err| raise_operation_error(&err));
+        JsonB(report)
+    }
+
+    #[pg_extern]
+    fn validate(from_schema: &str) -> JsonB {
+        JsonB(load_validation(from_schema).unwrap_or_else(|err| raise_operation_error(&err)))
+    }
+
+    #[pg_extern(security_definer)]
+    fn shadow_deploy(env: &str, from_schema: &str) -> JsonB {
+        let normalized_env =
+            resolve_env_name(env).unwrap_or_else(|err| raise_operation_error(&err));
+        let env = normalized_env.as_str();
+        ensure_role_membership(STOPGAP_DEPLOYER_ROLE, "stopgap shadow_deploy")
+            .unwrap_or_else(|err| raise_operation_error(&err));
+
+        let report = run_shadow_deploy(env, from_schema).unwrap_or_else(|err| {
+            observability::log_warn(&format!(
+                "stopgap.shadow_deploy failed env={} source_schema={} err={}",
+                env, from_schema, err
+            ));
+            raise_operation_error(&err)
+        });
+        JsonB(report)
+    }
+
+    #[pg_extern(security_definer)]
+    fn diff(
+        env: &str,
+        from_schema: &str,
+        ignore_removed: default!(bool, "false"),
+        only: default!(Option<&str>, "NULL"),
+        with_source: default!(bool, "false"),
+    ) -> JsonB {
+        let started_at = observability::record_diff_start();
+        let normalized_env = resolve_env_name(env).unwrap_or_else(|err| {
             observability::record_diff_error(
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            error!("{err}")
+            raise_operation_error(&err)
         });
-        let diff = load_diff(env, from_schema).unwrap_or_else(|err| {
+        let env = normalized_env.as_str();
+        observability::log_info(&format!(
+            "stopgap.diff start env={} source_schema={} ignore_removed={}",
+            env, from_schema, ignore_removed
+        ));
+        ensure_role_membership(STOPGAP_DEPLOYER_ROLE, "stopgap diff").unwrap_or_else(|err| {
             observability::record_diff_error(
                 started_at,
                 observability::classify_operation_error(err.as_str()),
             );
-            observability::log_warn(&format!(
-                "stopgap.diff failed env={} source_schema={} err={}",
-                env, from_schema, err
-            ));
-            error!("{err}")
+            raise_operation_error(&err)
         });
+        let diff =
+            load_diff(env, from_schema, ignore_removed, only, with_source).unwrap_or_else(|err| {
+                observability::record_diff_error(
+                    started_at,
+                    observability::classify_operation_error(err.as_str()),
+                );
+                observability::log_warn(&format!(
+                    "stopgap.diff failed env={} source_schema={} err={}",
+                    env, from_schema, err
+                ));
+                raise_operation_error(&err)
+            });
         observability::record_diff_success(started_at);
         JsonB(diff)
     }
@@ -1,18 +1,29 @@
+use common::import_map::{
+    import_map_target_specifier, parse_inline_import_map, validate_import_map,
+};
 use pgrx::JsonB;
 use pgrx::prelude::*;
 use serde_json::Value;
 use serde_json::json;
 use std::collections::{BTreeMap, BTreeSet};
+use std::time::Instant;
 
+use crate::domain::{DiffRow, DiffSummary};
 use crate::{
-    CandidateFn, DeploymentStatus, PruneReport, compute_diff_rows, deployment_import_map,
-    ensure_diff_permissions, fetch_deployable_functions, fetch_fn_versions,
-    fetch_live_deployable_functions, harden_live_schema, live_function_has_dependents,
-    load_environment_state, materialize_live_pointer, prune_manifest_item, quote_ident,
-    resolve_prune_enabled, run_sql, run_sql_with_args, transition_deployment_status,
-    update_deployment_manifest,
+    CandidateFn, DeploymentStatus, MANIFEST_VERSION, PruneReport, add_function_path_imports,
+    audit_materialized_pointers, compute_diff_rows, deployment_import_map, diff_rows_with_change,
+    ensure_diff_permissions, ensure_source_schema_allowed, fetch_deployable_functions,
+    fetch_fn_versions, fetch_live_deployable_functions, fetch_materialized_pointers,
+    fetch_prune_exclusions, harden_live_schema, live_function_has_dependents,
+    live_schema_owner_env, load_deployment_status, load_environment_state,
+    materialize_live_pointer, migrate_manifest, promoted_manifest, prune_manifest_item,
+    quote_ident, reactivate_deployment, resolve_live_arg_name, resolve_prune_enabled,
+    resolve_rollback_target, run_sql, run_sql_with_args, transition_deployment_status,
+    update_deployment_manifest, without_removed_rows,
 };
 
+const ENV_SNAPSHOT_VERSION: i64 = 1;
+
 #[derive(Clone, Debug)]
 struct DeployExportOverride {
     function_path: String,
@@ -29,6 +40,7 @@ struct DeployedFunction {
     module_path: String,
     export_name: String,
     kind: String,
+    compile_ms: u64,
 }
 
 fn compiler_opts_for_export(override_meta: Option<&DeployExportOverride>) -> Value {
@@ -173,6 +185,8 @@ pub(crate) fn run_deploy_flow(
     env: &str,
     from_schema: &str,
     live_schema: &str,
+    live_arg_name: &str,
+    limits: &serde_json::Map<String, Value>,
 ) -> Result<(), String> {
     let fns = fetch_deployable_functions(from_schema)?;
     let export_overrides = deploy_export_overrides()?;
@@ -192,11 +206,54 @@ pub(crate) fn run_deploy_flow(
         let override_meta = export_overrides.get(item.fn_name.as_str());
         let export_meta = resolve_export_metadata(item.fn_name.as_str(), override_meta);
         let compiler_opts = compiler_opts_for_export(override_meta);
+        let compile_started_at = Instant::now();
         let artifact_hash = compile_checked_artifact_hash(
             item.prosrc.as_str(),
             item.fn_name.as_str(),
             &compiler_opts,
         )?;
+        let compile_ms = compile_started_at.elapsed().as_millis().min(u128::from(u64::MAX)) as u64;
+
+        deployed_functions.push(DeployedFunction {
+            fn_name: item.fn_name.clone(),
+            artifact_hash,
+            function_path: export_meta.function_path,
+            module_path: export_meta.module_path,
+            export_name: export_meta.export_name,
+            kind: export_meta.kind,
+            compile_ms,
+        });
+    }
+
+    let compiled_functions = deployed_functions
+        .iter()
+        .map(|item| CandidateFn {
+            fn_name: item.fn_name.clone(),
+            artifact_hash: item.artifact_hash.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut import_map = deployment_import_map(from_schema, &compiled_functions);
+    add_function_path_imports(
+        &mut import_map,
+        deployed_functions
+            .iter()
+            .map(|item| (item.function_path.as_str(), item.artifact_hash.as_str())),
+    );
+
+    for item in &mut deployed_functions {
+        materialize_live_pointer(
+            live_schema,
+            &item.fn_name,
+            live_arg_name,
+            &item.artifact_hash,
+            &item.export_name,
+            &import_map,
+            limits,
+        )?;
+        if let Some(kind) = detect_handler_kind(live_schema, &item.fn_name)? {
+            item.kind = kind;
+        }
 
         run_sql_with_args(
             "
@@ -221,44 +278,16 @@ pub(crate) fn run_deploy_flow(
                 from_schema.into(),
                 live_schema.into(),
                 item.fn_name.as_str().into(),
-                export_meta.function_path.as_str().into(),
-                export_meta.module_path.as_str().into(),
-                export_meta.export_name.as_str().into(),
-                export_meta.kind.as_str().into(),
-                artifact_hash.as_str().into(),
+                item.function_path.as_str().into(),
+                item.module_path.as_str().into(),
+                item.export_name.as_str().into(),
+                item.kind.as_str().into(),
+                item.artifact_hash.as_str().into(),
             ],
             "failed to insert stopgap.fn_version",
         )?;
 
-        deployed_functions.push(DeployedFunction {
-            fn_name: item.fn_name.clone(),
-            artifact_hash,
-            function_path: export_meta.function_path,
-            module_path: export_meta.module_path,
-            export_name: export_meta.export_name,
-            kind: export_meta.kind,
-        });
-    }
-
-    let compiled_functions = deployed_functions
-        .iter()
-        .map(|item| CandidateFn {
-            fn_name: item.fn_name.clone(),
-            artifact_hash: item.artifact_hash.clone(),
-        })
-        .collect::<Vec<_>>();
-
-    let import_map = deployment_import_map(from_schema, &compiled_functions);
-
-    for item in &deployed_functions {
-        materialize_live_pointer(
-            live_schema,
-            &item.fn_name,
-            &item.artifact_hash,
-            &item.export_name,
-            &import_map,
-        )?;
-        let manifest_item = crate::fn_manifest_item(
+        let mut manifest_item = crate::fn_manifest_item(
             from_schema,
             live_schema,
             &item.fn_name,
@@ -269,6 +298,7 @@ pub(crate) fn run_deploy_flow(
             &item.artifact_hash,
             &import_map,
         );
+        manifest_item["compile_ms"] = json!(item.compile_ms);
         manifest_functions_by_path.insert(item.function_path.clone(), manifest_item.clone());
         manifest_functions.push(manifest_item);
     }
@@ -276,20 +306,146 @@ pub(crate) fn run_deploy_flow(
     let deployed_fn_names =
         deployed_functions.iter().map(|item| item.fn_name.clone()).collect::<BTreeSet<_>>();
     let prune_report = if prune_enabled {
-        prune_stale_live_functions(live_schema, &deployed_fn_names)?
+        prune_stale_live_functions(env, live_schema, &deployed_fn_names)?
     } else {
-        PruneReport { enabled: false, dropped: Vec::new(), skipped_with_dependents: Vec::new() }
+        PruneReport {
+            enabled: false,
+            dropped: Vec::new(),
+            skipped_with_dependents: Vec::new(),
+            excluded: Vec::new(),
+        }
     };
 
     update_deployment_manifest(
         deployment_id,
         json!({
+            "manifest_version": MANIFEST_VERSION,
             "functions": manifest_functions,
             "functions_by_path": Value::Object(manifest_functions_by_path),
-            "prune": prune_manifest_item(&prune_report)
+            "prune": prune_manifest_item(&prune_report),
+            "engine": deployment_engine_versions()?
         }),
     )?;
 
+    seal_and_activate_deployment(env, deployment_id)
+}
+
+/// Copies the active deployment of `from_env` into a new deployment of `to_env` that reuses the
+/// same artifact hashes, materializes its pointers into `live_schema`, and activates it.
+pub(crate) fn run_promote_flow(
+    from_env: &str,
+    to_env: &str,
+    live_schema: &str,
+    label: Option<&str>,
+) -> Result<i64, String> {
+    let (from_live_schema, source_deployment_id) =
+        load_environment_state(from_env).map_err(|_| {
+            format!(
+                "cannot promote from env {}: environment missing or has no active deployment",
+                from_env
+            )
+        })?;
+    if from_live_schema == live_schema {
+        return Err(format!(
+            "cannot promote env {} into env {}: both use live schema {}; set stopgap.live_schema to the target env's live schema",
+            from_env, to_env, live_schema
+        ));
+    }
+
+    let (source_schema, source_label) = load_deployment_origin(source_deployment_id)?;
+    ensure_source_schema_allowed(to_env, source_schema.as_str())?;
+    let label = label.or(source_label.as_deref());
+    let manifest = promoted_manifest(
+        &load_manifest(source_deployment_id)?,
+        to_env,
+        live_schema,
+        label,
+        from_env,
+        source_deployment_id,
+    );
+
+    run_sql_with_args(
+        "
+        INSERT INTO stopgap.environment (env, live_schema)
+        VALUES ($1, $2)
+        ON CONFLICT (env) DO UPDATE
+        SET live_schema = EXCLUDED.live_schema,
+            updated_at = now()
+        ",
+        &[to_env.into(), live_schema.into()],
+        "failed to upsert stopgap.environment",
+    )?;
+    run_sql(
+        &format!("CREATE SCHEMA IF NOT EXISTS {}", quote_ident(live_schema)),
+        "failed to create live schema",
+    )?;
+    harden_live_schema(live_schema)?;
+
+    let deployment_id = Spi::get_one_with_args::<i64>(
+        "
+        INSERT INTO stopgap.deployment (env, label, source_schema, status, manifest)
+        VALUES ($1, $2, $3, 'open', $4)
+        RETURNING id
+        ",
+        &[to_env.into(), label.into(), source_schema.as_str().into(), JsonB(manifest).into()],
+    )
+    .map_err(|e| format!("failed to create promoted deployment in env {}: {e}", to_env))?
+    .ok_or_else(|| format!("failed to create promoted deployment in env {}", to_env))?;
+
+    run_sql_with_args(
+        "
+        INSERT INTO stopgap.fn_version
+            (
+                deployment_id,
+                fn_name,
+                fn_schema,
+                live_fn_schema,
+                live_fn_name,
+                function_path,
+                module_path,
+                export_name,
+                kind,
+                artifact_hash
+            )
+        SELECT $1, fn_name, fn_schema, $2, live_fn_name, function_path, module_path,
+               export_name, kind, artifact_hash
+        FROM stopgap.fn_version
+        WHERE deployment_id = $3
+        ",
+        &[deployment_id.into(), live_schema.into(), source_deployment_id.into()],
+        "failed to copy stopgap.fn_version rows for promotion",
+    )?;
+
+    reactivate_deployment(live_schema, deployment_id)?;
+    seal_and_activate_deployment(to_env, deployment_id)?;
+
+    Ok(deployment_id)
+}
+
+fn load_deployment_origin(deployment_id: i64) -> Result<(String, Option<String>), String> {
+    Spi::connect(|client| {
+        let mut rows = client.select(
+            "
+            SELECT source_schema::text AS source_schema, label
+            FROM stopgap.deployment
+            WHERE id = $1
+            ",
+            None,
+            &[deployment_id.into()],
+        )?;
+        let Some(row) = rows.next() else {
+            return Ok(None);
+        };
+
+        let source_schema = row.get_by_name::<String, _>("source_schema")?.unwrap_or_default();
+        let label = row.get_by_name::<String, _>("label")?;
+        Ok::<_, pgrx::spi::Error>(Some((source_schema, label)))
+    })
+    .map_err(|e| format!("failed to load deployment {deployment_id}: {e}"))?
+    .ok_or_else(|| format!("stopgap deployment {deployment_id} does not exist"))
+}
+
+fn seal_and_activate_deployment(env: &str, deployment_id: i64) -> Result<(), String> {
     let previous_active = Spi::get_one_with_args::<i64>(
         "SELECT active_deployment_id FROM stopgap.environment WHERE env = $1",
         &[env.into()],
@@ -323,16 +479,150 @@ pub(crate) fn run_deploy_flow(
     Ok(())
 }
 
+/// Compiles `from_schema` and materializes its pointers into a throwaway schema, audits what
+/// was created, and drops the schema again. The environment's active deployment is untouched.
+pub(crate) fn run_shadow_deploy(env: &str, from_schema: &str) -> Result<Value, String> {
+    ensure_diff_permissions(from_schema)?;
+
+    let shadow_schema = Spi::get_one::<String>(
+        "SELECT 'stopgap_shadow_' || substr(md5(random()::text || clock_timestamp()::text), 1, 12)",
+    )
+    .map_err(|e| format!("failed to generate shadow schema name: {e}"))?
+    .ok_or_else(|| "failed to generate shadow schema name".to_string())?;
+
+    let report = materialize_shadow_schema(env, from_schema, &shadow_schema);
+    let cleanup = run_sql(
+        &format!("DROP SCHEMA IF EXISTS {} CASCADE", quote_ident(&shadow_schema)),
+        "failed to drop shadow schema",
+    );
+
+    let report = report?;
+    cleanup?;
+    Ok(report)
+}
+
+fn materialize_shadow_schema(
+    env: &str,
+    from_schema: &str,
+    shadow_schema: &str,
+) -> Result<Value, String> {
+    let live_arg_name = resolve_live_arg_name()?;
+    let fns = fetch_deployable_functions(from_schema)?;
+    let export_overrides = deploy_export_overrides()?;
+    validate_deploy_export_coverage(&fns, &export_overrides)?;
+    run_sql(
+        &format!("CREATE SCHEMA {}", quote_ident(shadow_schema)),
+        "failed to create shadow schema",
+    )?;
+    harden_live_schema(shadow_schema)?;
+
+    let mut deployed_functions: Vec<DeployedFunction> = Vec::with_capacity(fns.len());
+    for item in &fns {
+        let override_meta = export_overrides.get(item.fn_name.as_str());
+        let export_meta = resolve_export_metadata(item.fn_name.as_str(), override_meta);
+        let compiler_opts = compiler_opts_for_export(override_meta);
+        let compile_started_at = Instant::now();
+        let artifact_hash = compile_checked_artifact_hash(
+            item.prosrc.as_str(),
+            item.fn_name.as_str(),
+            &compiler_opts,
+        )?;
+        let compile_ms = compile_started_at.elapsed().as_millis().min(u128::from(u64::MAX)) as u64;
+
+        deployed_functions.push(DeployedFunction {
+            fn_name: item.fn_name.clone(),
+            artifact_hash,
+            function_path: export_meta.function_path,
+            module_path: export_meta.module_path,
+            export_name: export_meta.export_name,
+            kind: export_meta.kind,
+            compile_ms,
+        });
+    }
+
+    let compiled_functions = deployed_functions
+        .iter()
+        .map(|item| CandidateFn {
+            fn_name: item.fn_name.clone(),
+            artifact_hash: item.artifact_hash.clone(),
+        })
+        .collect::<Vec<_>>();
+    let mut import_map = deployment_import_map(from_schema, &compiled_functions);
+    add_function_path_imports(
+        &mut import_map,
+        deployed_functions
+            .iter()
+            .map(|item| (item.function_path.as_str(), item.artifact_hash.as_str())),
+    );
+
+    for item in &mut deployed_functions {
+        materialize_live_pointer(
+            shadow_schema,
+            &item.fn_name,
+            &live_arg_name,
+            &item.artifact_hash,
+            &item.export_name,
+            &import_map,
+            &serde_json::Map::new(),
+        )?;
+        if let Some(kind) = detect_handler_kind(shadow_schema, &item.fn_name)? {
+            item.kind = kind;
+        }
+    }
+
+    let materialized = fetch_materialized_pointers(shadow_schema)?;
+    let mut known_artifacts = BTreeSet::new();
+    for item in &compiled_functions {
+        if artifact_exists(&item.artifact_hash)? {
+            known_artifacts.insert(item.artifact_hash.clone());
+        }
+    }
+    let issues = audit_materialized_pointers(&compiled_functions, &materialized, &known_artifacts);
+
+    Ok(json!({
+        "env": env,
+        "source_schema": from_schema,
+        "shadow_schema": shadow_schema,
+        "ok": issues.is_empty(),
+        "functions": deployed_functions
+            .iter()
+            .map(|item| json!({
+                "fn_name": item.fn_name,
+                "function_path": item.function_path,
+                "kind": item.kind,
+                "artifact_hash": item.artifact_hash
+            }))
+            .collect::<Vec<_>>(),
+        "issues": issues
+    }))
+}
+
+fn deployment_engine_versions() -> Result<Value, String> {
+    Spi::get_one::<JsonB>(
+        "
+        SELECT jsonb_build_object(
+            'pg_version', version(),
+            'stopgap_version', (SELECT extversion FROM pg_extension WHERE extname = 'stopgap'),
+            'plts_version', (SELECT extversion FROM pg_extension WHERE extname = 'plts')
+        )
+        ",
+    )
+    .map_err(|e| format!("failed to read engine versions for deployment manifest: {e}"))
+    .map(|value| value.map(|value| value.0).unwrap_or_else(|| json!({})))
+}
+
 fn prune_stale_live_functions(
+    env: &str,
     live_schema: &str,
     deployed_fn_names: &BTreeSet<String>,
 ) -> Result<PruneReport, String> {
     let live_rows = fetch_live_deployable_functions(live_schema)?;
+    let excluded = fetch_prune_exclusions(env)?;
     let mut dropped = Vec::new();
     let mut skipped_with_dependents = Vec::new();
 
     for row in live_rows {
-        if deployed_fn_names.contains(row.fn_name.as_str()) {
+        if deployed_fn_names.contains(row.fn_name.as_str()) || excluded.contains(&row.fn_name) {
             continue;
         }
 
@@ -353,7 +643,7 @@ fn prune_stale_live_functions(
     dropped.sort();
     skipped_with_dependents.sort();
 
-    Ok(PruneReport { enabled: true, dropped, skipped_with_dependents })
+    Ok(PruneReport { enabled: true, dropped, skipped_with_dependents, excluded })
 }
 
 pub(crate) fn load_status(env: &str) -> Option<Value> {
@@ -362,6 +652,8 @@ pub(crate) fn load_status(env: &str) -> Option<Value> {
             'env', e.env,
             'live_schema', e.live_schema,
             'active_deployment_id', e.active_deployment_id,
+            'baseline_deployment_id', e.baseline_deployment_id,
+            'allowed_source_schemas', e.allowed_source_schemas,
             'updated_at', e.updated_at,
             'active_deployment', CASE
                 WHEN d.id IS NULL THEN NULL
@@ -381,9 +673,55 @@ pub(crate) fn load_status(env: &str) -> Option<Value> {
         WHERE e.env = $1
         ";
 
+    let mut status =
+        Spi::get_one_with_args::<JsonB>(sql, &[env.into()]).ok().flatten().map(|json| json.0)?;
+    if let Some(manifest) = status.pointer_mut("/active_deployment/manifest") {
+        *manifest = migrate_manifest(manifest.take());
+    }
+    Some(status)
+}
+
+pub(crate) fn load_env_stats(env: &str) -> Option<Value> {
+    let sql = "
+        SELECT jsonb_build_object(
+            'env', e.env,
+            'live_schema', e.live_schema,
+            'active_deployment_id', e.active_deployment_id,
+            'deployment_count', (
+                SELECT count(*) FROM stopgap.deployment d WHERE d.env = e.env
+            ),
+            'active_function_count', (
+                SELECT count(*)
+                FROM stopgap.fn_version fv
+                WHERE fv.deployment_id = e.active_deployment_id
+            ),
+            'active_artifact_bytes', (
+                SELECT COALESCE(sum(octet_length(a.source_ts) + octet_length(a.compiled_js)), 0)
+                FROM plts.artifact a
+                WHERE a.artifact_hash IN (
+                    SELECT fv.artifact_hash
+                    FROM stopgap.fn_version fv
+                    WHERE fv.deployment_id = e.active_deployment_id
+                )
+            )
+        )
+        FROM stopgap.environment e
+        WHERE e.env = $1
+        ";
+
     Spi::get_one_with_args::<JsonB>(sql, &[env.into()]).ok().flatten().map(|json| json.0)
 }
 
+pub(crate) fn load_manifest(deployment_id: i64) -> Result<Value, String> {
+    Spi::get_one_with_args::<JsonB>(
+        "SELECT manifest FROM stopgap.deployment WHERE id = $1",
+        &[deployment_id.into()],
+    )
+    .map_err(|e| format!("failed to load manifest for deployment {deployment_id}: {e}"))?
+    .map(|json| migrate_manifest(json.0))
+    .ok_or_else(|| format!("stopgap deployment {deployment_id} does not exist"))
+}
+
 pub(crate) fn load_deployments(env: &str) -> Value {
     let sql = "
         SELECT COALESCE(jsonb_agg(deploy_row ORDER BY created_at DESC), '[]'::jsonb)
@@ -406,23 +744,494 @@ pub(crate) fn load_deployments(env: &str) -> Value {
         ) rows
         ";
 
-    Spi::get_one_with_args::<JsonB>(sql, &[env.into()])
+    let mut deployments = Spi::get_one_with_args::<JsonB>(sql, &[env.into()])
         .ok()
         .flatten()
         .map(|json| json.0)
-        .unwrap_or_else(|| json!([]))
+        .unwrap_or_else(|| json!([]));
+    if let Some(rows) = deployments.as_array_mut() {
+        for manifest in rows.iter_mut().filter_map(|row| row.get_mut("manifest")) {
+            *manifest = migrate_manifest(manifest.take());
+        }
+    }
+    deployments
+}
+
+/// Most recent activations of `env` first, each with the deployment it switched from and to.
+pub(crate) fn load_activation_history(env: &str, limit: i32) -> Result<Value, String> {
+    if limit <= 0 {
+        return Err(format!("stopgap activation_history limit must be positive; got {limit}"));
+    }
+
+    let sql = "
+        SELECT COALESCE(jsonb_agg(activation ORDER BY activation_id DESC), '[]'::jsonb)
+        FROM (
+            SELECT l.id AS activation_id,
+                   jsonb_build_object(
+                       'activation_id', l.id,
+                       'from_deployment_id', l.from_deployment_id,
+                       'to_deployment_id', l.to_deployment_id,
+                       'to_label', d.label,
+                       'activated_at', l.activated_at,
+                       'activated_by', l.activated_by
+                   ) AS activation
+            FROM stopgap.activation_log l
+            JOIN stopgap.deployment d ON d.id = l.to_deployment_id
+            WHERE l.env = $1
+            ORDER BY l.id DESC
+            LIMIT $2
+        ) rows
+        ";
+
+    Spi::get_one_with_args::<JsonB>(sql, &[env.into(), limit.into()])
+        .map_err(|e| format!("failed to load activation history for env {env}: {e}"))
+        .map(|json| json.map(|json| json.0).unwrap_or_else(|| json!([])))
+}
+
+/// Everything stopgap records for `env` (prune exclusions included), plus every artifact its
+/// deployments reach (including artifacts named by inline import maps), so `run_import_env` can
+/// rebuild it elsewhere.
+pub(crate) fn load_env_snapshot(env: &str) -> Result<Value, String> {
+    let sql = "
+        SELECT jsonb_build_object(
+            'environment', to_jsonb(e) - 'updated_at',
+            'deployments', COALESCE((
+                SELECT jsonb_agg(to_jsonb(d) - 'env' ORDER BY d.id)
+                FROM stopgap.deployment d
+                WHERE d.env = e.env
+            ), '[]'::jsonb),
+            'fn_versions', COALESCE((
+                SELECT jsonb_agg(to_jsonb(fv) ORDER BY fv.deployment_id, fv.fn_schema, fv.fn_name)
+                FROM stopgap.fn_version fv
+                JOIN stopgap.deployment d ON d.id = fv.deployment_id
+                WHERE d.env = e.env
+            ), '[]'::jsonb),
+            'activations', COALESCE((
+                SELECT jsonb_agg(to_jsonb(l) - 'env' ORDER BY l.id)
+                FROM stopgap.activation_log l
+                WHERE l.env = e.env
+            ), '[]'::jsonb),
+            'prune_exclusions', COALESCE((
+                SELECT jsonb_agg(to_jsonb(p) - 'env' ORDER BY p.fn_name)
+                FROM stopgap.prune_exclusion p
+                WHERE p.env = e.env
+            ), '[]'::jsonb)
+        )
+        FROM stopgap.environment e
+        WHERE e.env = $1
+        ";
+
+    let mut snapshot = Spi::get_one_with_args::<JsonB>(sql, &[env.into()])
+        .map_err(|e| format!("failed to export env {env}: {e}"))?
+        .map(|json| json.0)
+        .ok_or_else(|| format!("stopgap export_env env {env} does not exist"))?;
+
+    let mut pending = snapshot["fn_versions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|row| row.get("artifact_hash").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let mut artifacts = BTreeMap::new();
+    while let Some(artifact_hash) = pending.pop() {
+        if artifacts.contains_key(&artifact_hash) {
+            continue;
+        }
+
+        let mut artifact = Spi::get_one_with_args::<JsonB>(
+            "SELECT plts.get_artifact($1)",
+            &[artifact_hash.as_str().into()],
+        )
+        .map_err(|e| format!("failed to load artifact {artifact_hash}: {e}"))?
+        .map(|json| json.0)
+        .ok_or_else(|| {
+            format!("stopgap export_env artifact {artifact_hash} is missing from plts.artifact")
+        })?;
+        let source_ts = artifact.get("source_ts").and_then(Value::as_str).unwrap_or_default();
+        pending.extend(parse_inline_import_map(source_ts).values().filter_map(|target| {
+            import_map_target_specifier(target).strip_prefix("plts+artifact:").map(str::to_string)
+        }));
+        artifact["artifact_hash"] = json!(artifact_hash);
+        artifacts.insert(artifact_hash, artifact);
+    }
+
+    snapshot["snapshot_version"] = json!(ENV_SNAPSHOT_VERSION);
+    snapshot["artifacts"] = Value::Array(artifacts.into_values().collect());
+    Ok(snapshot)
 }
 
-pub(crate) fn load_diff(env: &str, from_schema: &str) -> Result<Value, String> {
+/// Recreates the env described by a `load_env_snapshot` document. Artifacts go back through
+/// `plts.upsert_artifact`; one whose recomputed hash differs from the recorded hash is rejected,
+/// so an edited snapshot cannot change the code a deployment points at.
+pub(crate) fn run_import_env(env: &str, snapshot: &Value) -> Result<Value, String> {
+    let version = snapshot.get("snapshot_version").and_then(Value::as_i64);
+    if version != Some(ENV_SNAPSHOT_VERSION) {
+        return Err(format!(
+            "stopgap import_env unsupported snapshot_version {}; expected {}",
+            snapshot.get("snapshot_version").unwrap_or(&Value::Null),
+            ENV_SNAPSHOT_VERSION
+        ));
+    }
+
+    let env_exists = Spi::get_one_with_args::<bool>(
+        "SELECT EXISTS (SELECT 1 FROM stopgap.environment WHERE env = $1)",
+        &[env.into()],
+    )
+    .map_err(|e| format!("failed to check environment existence: {e}"))?
+    .unwrap_or(false);
+    if env_exists {
+        return Err(format!("stopgap import_env env {env} already exists"));
+    }
+
+    let section =
+        |key: &str| snapshot.get(key).and_then(Value::as_array).cloned().unwrap_or_default();
+    let environment = snapshot.get("environment").cloned().unwrap_or_else(|| json!({}));
+    let deployments = section("deployments");
+    let fn_versions = section("fn_versions");
+    let activations = section("activations");
+    let prune_exclusions = section("prune_exclusions");
+    let artifacts = section("artifacts");
+
+    let live_schema = environment.get("live_schema").and_then(Value::as_str).unwrap_or_default();
+    if let Some(owner_env) = live_schema_owner_env(live_schema)? {
+        return Err(format!(
+            "stopgap import_env env {env} cannot use live schema {live_schema}: it is already the live schema of env {owner_env}"
+        ));
+    }
+
+    let mut imported_artifacts = BTreeSet::new();
+    for artifact in &artifacts {
+        let recorded_hash =
+            artifact.get("artifact_hash").and_then(Value::as_str).unwrap_or_default();
+        let artifact_hash = Spi::get_one_with_args::<String>(
+            "SELECT plts.upsert_artifact($1::text, $2::text, $3::jsonb, $4::jsonb)",
+            &[
+                artifact.get("source_ts").and_then(Value::as_str).into(),
+                artifact.get("compiled_js").and_then(Value::as_str).into(),
+                JsonB(artifact.get("compiler_opts").cloned().unwrap_or_else(|| json!({}))).into(),
+                artifact
+                    .get("diagnostics")
+                    .filter(|value| !value.is_null())
+                    .cloned()
+                    .map(JsonB)
+                    .into(),
+            ],
+        )
+        .map_err(|e| format!("failed to import artifact {recorded_hash}: {e}"))?
+        .ok_or_else(|| format!("upsert_artifact returned no artifact hash for {recorded_hash}"))?;
+        if artifact_hash != recorded_hash {
+            return Err(format!(
+                "stopgap import_env artifact {recorded_hash} failed integrity check; contents hash to {artifact_hash} (edited snapshot or different plts compiler)"
+            ));
+        }
+        imported_artifacts.insert(artifact_hash);
+    }
+
+    for row in &fn_versions {
+        let artifact_hash = row.get("artifact_hash").and_then(Value::as_str).unwrap_or_default();
+        if !imported_artifacts.contains(artifact_hash) && !artifact_exists(artifact_hash)? {
+            return Err(format!(
+                "stopgap import_env fn_version {} references artifact {artifact_hash}, which is neither in the snapshot nor in plts.artifact",
+                row.get("fn_name").and_then(Value::as_str).unwrap_or_default()
+            ));
+        }
+    }
+
+    run_sql_with_args(
+        "
+        INSERT INTO stopgap.environment (env, live_schema, allowed_source_schemas)
+        SELECT $1, r.live_schema, r.allowed_source_schemas
+        FROM jsonb_populate_record(NULL::stopgap.environment, $2) r
+        ",
+        &[env.into(), JsonB(environment.clone()).into()],
+        "failed to insert imported stopgap.environment",
+    )?;
+
+    // Deployment ids are assigned by this database, so every reference to an exported id is
+    // rewritten through `deployment_ids` (exported id -> new id).
+    let mut deployment_ids = serde_json::Map::new();
+    for deployment in &deployments {
+        let exported_id = deployment.get("id").cloned().unwrap_or(Value::Null);
+        let deployment_id = Spi::get_one_with_args::<i64>(
+            "
+            INSERT INTO stopgap.deployment
+                (env, label, created_at, created_by, source_schema, status, manifest)
+            SELECT $1, r.label, r.created_at, r.created_by, r.source_schema, r.status, r.manifest
+            FROM jsonb_populate_record(NULL::stopgap.deployment, $2) r
+            RETURNING id
+            ",
+            &[env.into(), JsonB(deployment.clone()).into()],
+        )
+        .map_err(|e| format!("failed to insert imported deployment {exported_id}: {e}"))?
+        .ok_or_else(|| format!("failed to insert imported deployment {exported_id}"))?;
+        deployment_ids.insert(exported_id.to_string(), json!(deployment_id));
+    }
+    let deployment_ids = Value::Object(deployment_ids);
+
+    run_sql_with_args(
+        "
+        INSERT INTO stopgap.fn_version
+        SELECT (jsonb_populate_record(
+            NULL::stopgap.fn_version,
+            fv || jsonb_build_object('deployment_id', $2 -> (fv ->> 'deployment_id'))
+        )).*
+        FROM jsonb_array_elements($1) fv
+        ",
+        &[JsonB(json!(fn_versions)).into(), JsonB(deployment_ids.clone()).into()],
+        "failed to insert imported stopgap.fn_version rows",
+    )?;
+    run_sql_with_args(
+        "
+        INSERT INTO stopgap.activation_log
+            (env, from_deployment_id, to_deployment_id, activated_at, activated_by)
+        SELECT $1,
+               ($3 ->> r.from_deployment_id::text)::bigint,
+               ($3 ->> r.to_deployment_id::text)::bigint,
+               r.activated_at,
+               r.activated_by
+        FROM jsonb_array_elements($2) a
+        CROSS JOIN LATERAL jsonb_populate_record(NULL::stopgap.activation_log, a) r
+        ORDER BY r.id
+        ",
+        &[env.into(), JsonB(json!(activations)).into(), JsonB(deployment_ids.clone()).into()],
+        "failed to insert imported stopgap.activation_log rows",
+    )?;
+    run_sql_with_args(
+        "
+        INSERT INTO stopgap.prune_exclusion (env, fn_name, created_at, created_by)
+        SELECT $1, r.fn_name, r.created_at, r.created_by
+        FROM jsonb_array_elements($2) p
+        CROSS JOIN LATERAL jsonb_populate_record(NULL::stopgap.prune_exclusion, p) r
+        ",
+        &[env.into(), JsonB(json!(prune_exclusions)).into()],
+        "failed to insert imported stopgap.prune_exclusion rows",
+    )?;
+    run_sql_with_args(
+        "
+        UPDATE stopgap.environment
+        SET active_deployment_id = ($3 ->> ($2 ->> 'active_deployment_id'))::bigint,
+            baseline_deployment_id = ($3 ->> ($2 ->> 'baseline_deployment_id'))::bigint,
+            updated_at = now()
+        WHERE env = $1
+        ",
+        &[env.into(), JsonB(environment).into(), JsonB(deployment_ids).into()],
+        "failed to set imported active deployment",
+    )?;
+
+    let (live_schema, active_deployment_id) = Spi::get_two_with_args::<String, i64>(
+        "SELECT live_schema::text, active_deployment_id FROM stopgap.environment WHERE env = $1",
+        &[env.into()],
+    )
+    .map_err(|e| format!("failed to read imported environment: {e}"))?;
+    let live_schema =
+        live_schema.ok_or_else(|| format!("imported env {env} has no live schema"))?;
+    if let Some(active_deployment_id) = active_deployment_id {
+        run_sql(
+            &format!("CREATE SCHEMA IF NOT EXISTS {}", quote_ident(&live_schema)),
+            "failed to create live schema",
+        )?;
+        harden_live_schema(&live_schema)?;
+        reactivate_deployment(&live_schema, active_deployment_id)?;
+    }
+
+    Ok(json!({
+        "env": env,
+        "live_schema": live_schema,
+        "active_deployment_id": active_deployment_id,
+        "deployments": deployments.len(),
+        "fn_versions": fn_versions.len(),
+        "activations": activations.len(),
+        "prune_exclusions": prune_exclusions.len(),
+        "artifacts": artifacts.len()
+    }))
+}
+
+pub(crate) fn load_diff(
+    env: &str,
+    from_schema: &str,
+    ignore_removed: bool,
+    only: Option<&str>,
+    with_source: bool,
+) -> Result<Value, String> {
     let (live_schema, active_deployment_id) = load_environment_state(env)?;
     ensure_diff_permissions(from_schema)?;
 
     let active = fetch_fn_versions(active_deployment_id)?;
     let candidate = compile_candidate_functions(from_schema)?;
-    let (rows, summary) = compute_diff_rows(&active, &candidate);
+    let (mut rows, mut summary) = compute_diff_rows(&active, &candidate);
+    if ignore_removed {
+        (rows, summary) = without_removed_rows(rows, summary);
+    }
+    if let Some(only) = only {
+        rows = diff_rows_with_change(rows, only)?;
+    }
+    let functions = if with_source {
+        diff_functions_json_with_source(rows)?
+    } else {
+        diff_functions_json(rows)
+    };
+
+    Ok(json!({
+        "env": env,
+        "source_schema": from_schema,
+        "live_schema": live_schema,
+        "active_deployment_id": active_deployment_id,
+        "ignore_removed": ignore_removed,
+        "only": only,
+        "with_source": with_source,
+        "summary": diff_summary_json(summary),
+        "functions": functions
+    }))
+}
+
+/// Rows the foreign keys should have prevented but that manual fixes or partial restores can
+/// leave behind, plus `fn_version` artifact hashes with no `plts.artifact` row.
+pub(crate) fn load_orphans() -> Result<Value, String> {
+    let sql = "
+        SELECT jsonb_build_object(
+            'fn_versions_without_deployment', COALESCE((
+                SELECT jsonb_agg(jsonb_build_object(
+                    'deployment_id', fv.deployment_id,
+                    'fn_schema', fv.fn_schema,
+                    'fn_name', fv.fn_name,
+                    'artifact_hash', fv.artifact_hash
+                ) ORDER BY fv.deployment_id, fv.fn_schema, fv.fn_name)
+                FROM stopgap.fn_version fv
+                WHERE NOT EXISTS (SELECT 1 FROM stopgap.deployment d WHERE d.id = fv.deployment_id)
+            ), '[]'::jsonb),
+            'deployments_without_environment', COALESCE((
+                SELECT jsonb_agg(jsonb_build_object(
+                    'id', d.id,
+                    'env', d.env,
+                    'label', d.label,
+                    'status', d.status
+                ) ORDER BY d.id)
+                FROM stopgap.deployment d
+                WHERE NOT EXISTS (SELECT 1 FROM stopgap.environment e WHERE e.env = d.env)
+            ), '[]'::jsonb),
+            'missing_artifacts', COALESCE((
+                SELECT jsonb_agg(jsonb_build_object(
+                    'artifact_hash', missing.artifact_hash,
+                    'deployment_ids', missing.deployment_ids
+                ) ORDER BY missing.artifact_hash)
+                FROM (
+                    SELECT fv.artifact_hash, jsonb_agg(DISTINCT fv.deployment_id) AS deployment_ids
+                    FROM stopgap.fn_version fv
+                    WHERE NOT EXISTS (
+                        SELECT 1 FROM plts.artifact a WHERE a.artifact_hash = fv.artifact_hash
+                    )
+                    GROUP BY fv.artifact_hash
+                ) missing
+            ), '[]'::jsonb)
+        )
+        ";
+
+    let mut report = Spi::get_one::<JsonB>(sql)
+        .map_err(|e| format!("failed to load stopgap orphans: {e}"))?
+        .map(|json| json.0)
+        .unwrap_or_else(|| json!({}));
+    let ok =
+        ["fn_versions_without_deployment", "deployments_without_environment", "missing_artifacts"]
+            .iter()
+            .all(|key| report[*key].as_array().is_none_or(Vec::is_empty));
+    report["ok"] = json!(ok);
+    Ok(report)
+}
+
+/// Transpiles every deployable function in `from_schema` through `plts.compile_ts` without
+/// storing artifacts, so CI can catch compile errors before `stopgap.deploy` runs.
+pub(crate) fn load_validation(from_schema: &str) -> Result<Value, String> {
+    let schema_exists = Spi::get_one_with_args::<bool>(
+        "SELECT EXISTS (SELECT 1 FROM pg_namespace WHERE nspname = $1)",
+        &[from_schema.into()],
+    )
+    .map_err(|e| format!("failed to check source schema existence: {e}"))?
+    .unwrap_or(false);
+    if !schema_exists {
+        return Err(format!("stopgap validate source schema {from_schema} does not exist"));
+    }
+
+    let mut functions = Vec::new();
+    for item in fetch_deployable_functions(from_schema)? {
+        let mut diagnostics = transpile_diagnostics(item.prosrc.as_str(), item.fn_name.as_str())?;
+        if let Err(err) = validate_source_import_map(item.prosrc.as_str(), item.fn_name.as_str()) {
+            diagnostics.push(json!({ "severity": "error", "phase": "import_map", "message": err }));
+        }
+
+        let error_count = diagnostics
+            .iter()
+            .filter(|entry| entry.get("severity").and_then(Value::as_str) == Some("error"))
+            .count();
+        functions.push(json!({
+            "name": item.fn_name,
+            "error_count": error_count,
+            "diagnostics": diagnostics
+        }));
+    }
+    let ok = functions.iter().all(|function| function["error_count"] == 0);
+
+    Ok(json!({
+        "source_schema": from_schema,
+        "ok": ok,
+        "functions": functions
+    }))
+}
 
-    let functions = rows
+pub(crate) fn load_rollback_preview(
+    env: &str,
+    steps: Option<i32>,
+    to_id: Option<i64>,
+    to_label: Option<&str>,
+) -> Result<Value, String> {
+    let (live_schema, current_active) = load_environment_state(env)?;
+    let target_deployment_id =
+        resolve_rollback_target(env, current_active, steps, to_id, to_label)?;
+
+    if target_deployment_id == current_active {
+        return Err(format!(
+            "stopgap rollback target {} is already active for env {}",
+            target_deployment_id, env
+        ));
+    }
+
+    let target_status = load_deployment_status(target_deployment_id)?;
+    if target_status != DeploymentStatus::Active && target_status != DeploymentStatus::RolledBack {
+        return Err(format!(
+            "stopgap rollback target {} has invalid status {}; expected active or rolled_back",
+            target_deployment_id,
+            target_status.as_str()
+        ));
+    }
+
+    let active = fetch_fn_versions(current_active)?;
+    let target = fetch_fn_versions(target_deployment_id)?
         .into_iter()
+        .map(|row| CandidateFn { fn_name: row.fn_name, artifact_hash: row.artifact_hash })
+        .collect::<Vec<_>>();
+    let (rows, summary) = compute_diff_rows(&active, &target);
+
+    Ok(json!({
+        "env": env,
+        "live_schema": live_schema,
+        "from": current_active,
+        "to": target_deployment_id,
+        "summary": diff_summary_json(summary),
+        "functions": diff_functions_json(rows)
+    }))
+}
+
+fn diff_summary_json(summary: DiffSummary) -> Value {
+    json!({
+        "added": summary.added,
+        "changed": summary.changed,
+        "removed": summary.removed,
+        "unchanged": summary.unchanged
+    })
+}
+
+fn diff_functions_json(rows: Vec<DiffRow>) -> Vec<Value> {
+    rows.into_iter()
         .map(|row| {
             json!({
                 "fn_name": row.fn_name,
@@ -431,29 +1240,60 @@ pub(crate) fn load_diff(env: &str, from_schema: &str) -> Result<Value, String> {
                 "candidate_artifact_hash": row.candidate_artifact_hash
             })
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
 
-    Ok(json!({
-        "env": env,
-        "source_schema": from_schema,
-        "live_schema": live_schema,
-        "active_deployment_id": active_deployment_id,
-        "summary": {
-            "added": summary.added,
-            "changed": summary.changed,
-            "removed": summary.removed,
-            "unchanged": summary.unchanged
-        },
-        "functions": functions
-    }))
+/// Like `diff_functions_json`, but `changed` rows also carry the `source_ts` stored in
+/// `plts.artifact` for both hashes so reviewers can compare the code itself.
+fn diff_functions_json_with_source(rows: Vec<DiffRow>) -> Result<Vec<Value>, String> {
+    let mut functions = Vec::with_capacity(rows.len());
+    for row in rows {
+        let sources = if row.change == "changed" {
+            Some((
+                artifact_source_ts(row.active_artifact_hash.as_deref())?,
+                artifact_source_ts(row.candidate_artifact_hash.as_deref())?,
+            ))
+        } else {
+            None
+        };
+
+        let mut function = json!({
+            "fn_name": row.fn_name,
+            "change": row.change,
+            "active_artifact_hash": row.active_artifact_hash,
+            "candidate_artifact_hash": row.candidate_artifact_hash
+        });
+        if let Some((active_source_ts, candidate_source_ts)) = sources {
+            function["active_source_ts"] = json!(active_source_ts);
+            function["candidate_source_ts"] = json!(candidate_source_ts);
+        }
+        functions.push(function);
+    }
+
+    Ok(functions)
 }
 
+fn artifact_source_ts(artifact_hash: Option<&str>) -> Result<Option<String>, String> {
+    let Some(artifact_hash) = artifact_hash else {
+        return Ok(None);
+    };
+
+    Spi::get_one_with_args::<String>(
+        "SELECT (SELECT source_ts FROM plts.artifact WHERE artifact_hash = $1)",
+        &[artifact_hash.into()],
+    )
+    .map_err(|e| format!("failed to load source for artifact {artifact_hash}: {e}"))
+}
+
+/// Compiles `from_schema` the way `run_deploy_flow` would, including the per-export compiler
+/// options from `stopgap.deploy_exports`, so unchanged sources hash to the active artifacts.
 fn compile_candidate_functions(from_schema: &str) -> Result<Vec<CandidateFn>, String> {
     let deployables = fetch_deployable_functions(from_schema)?;
+    let export_overrides = deploy_export_overrides()?;
     let mut out = Vec::with_capacity(deployables.len());
 
     for item in deployables {
-        let compiler_opts = json!({});
+        let compiler_opts = compiler_opts_for_export(export_overrides.get(item.fn_name.as_str()));
         let artifact_hash = compile_checked_artifact_hash(
             item.prosrc.as_str(),
             item.fn_name.as_str(),
@@ -465,11 +1305,52 @@ fn compile_candidate_functions(from_schema: &str) -> Result<Vec<CandidateFn>, St
     Ok(out)
 }
 
+fn artifact_exists(artifact_hash: &str) -> Result<bool, String> {
+    Spi::get_one_with_args::<bool>(
+        "SELECT EXISTS (SELECT 1 FROM plts.artifact WHERE artifact_hash = $1)",
+        &[artifact_hash.into()],
+    )
+    .map(|exists| exists.unwrap_or(false))
+    .map_err(|e| format!("failed to check artifact {artifact_hash}: {e}"))
+}
+
+/// Handler kind plts reports for a materialized pointer, or `None` when the runtime cannot
+/// evaluate it and the export metadata kind should stand.
+fn detect_handler_kind(schema: &str, fn_name: &str) -> Result<Option<String>, String> {
+    Spi::get_one_with_args::<String>(
+        "SELECT plts.describe(format('%I.%I(jsonb)', $1::text, $2::text)::regprocedure) ->> 'kind'",
+        &[schema.into(), fn_name.into()],
+    )
+    .map(|kind| kind.filter(|kind| matches!(kind.as_str(), "query" | "mutation")))
+    .map_err(|e| format!("failed to detect handler kind for {fn_name}: {e}"))
+}
+
+fn validate_source_import_map(source_ts: &str, fn_name: &str) -> Result<(), String> {
+    let import_map = parse_inline_import_map(source_ts);
+    validate_import_map(
+        import_map.iter().map(|(key, target)| (key.as_str(), target.as_str())),
+        artifact_exists,
+    )
+    .map_err(|err| format!("stopgap deploy rejected inline import map for {fn_name}: {err}"))
+}
+
+fn transpile_diagnostics(source_ts: &str, fn_name: &str) -> Result<Vec<Value>, String> {
+    Spi::get_one_with_args::<JsonB>(
+        "SELECT diagnostics FROM plts.compile_ts($1::text, '{}'::jsonb)",
+        &[source_ts.into()],
+    )
+    .map_err(|e| format!("compile_ts SPI error for {fn_name}: {e}"))?
+    .map(|value| value.0.as_array().cloned().unwrap_or_default())
+    .ok_or_else(|| format!("compile_ts returned no row for {fn_name}"))
+}
+
 fn compile_checked_artifact_hash(
     source_ts: &str,
     fn_name: &str,
     compiler_opts: &Value,
 ) -> Result<String, String> {
+    validate_source_import_map(source_ts, fn_name)?;
+
     let compiled_row = Spi::get_one_with_args::<JsonB>(
         "SELECT to_jsonb(t) FROM plts.compile_ts_checked($1::text, $2::jsonb) AS t",
         &[source_ts.into(), JsonB(compiler_opts.clone()).into()],
@@ -491,8 +1372,13 @@ fn compile_checked_artifact_hash(
     }
 
     Spi::get_one_with_args::<String>(
-        "SELECT plts.upsert_artifact($1::text, $2::text, $3::jsonb)",
-        &[source_ts.into(), compiled_js.into(), JsonB(compiler_opts.clone()).into()],
+        "SELECT plts.upsert_artifact($1::text, $2::text, $3::jsonb, $4::jsonb)",
+        &[
+            source_ts.into(),
+            compiled_js.into(),
+            JsonB(compiler_opts.clone()).into(),
+            JsonB(diagnostics).into(),
+        ],
     )
     .map_err(|e| format!("upsert_artifact SPI error for {fn_name}: {e}"))?
     .ok_or_else(|| format!("upsert_artifact returned no artifact hash for {fn_name}"))
@@ -5,10 +5,28 @@ use serde_json::json;
 
 use crate::deployment_utils::materialize_live_pointer;
 use crate::domain::{
-    CandidateFn, DeploymentStatus, FnVersionRow, deployment_import_map, is_allowed_transition,
-    rollback_steps_to_offset,
+    CandidateFn, DeploymentStatus, FnVersionRow, add_function_path_imports, deployment_import_map,
+    is_allowed_transition, pick_env_name, rollback_steps_to_offset,
 };
-use crate::runtime_config::run_sql_with_args;
+use crate::runtime_config::{
+    DEFAULT_LIVE_ARG_NAME, resolve_lowercase_env_names, run_sql_with_args,
+};
+
+/// Resolves the env a caller named against the recorded envs; see `pick_env_name`.
+pub(crate) fn resolve_env_name(raw: &str) -> Result<String, String> {
+    let existing = Spi::get_one_with_args::<Vec<String>>(
+        "
+        SELECT COALESCE(array_agg(env ORDER BY env), '{}')
+        FROM stopgap.environment
+        WHERE env = $1 OR lower(btrim(env)) = lower(btrim($1))
+        ",
+        &[raw.into()],
+    )
+    .map_err(|e| format!("failed to look up stopgap env '{raw}': {e}"))?
+    .unwrap_or_default();
+
+    pick_env_name(raw, resolve_lowercase_env_names(), &existing)
+}
 
 pub(crate) fn load_environment_state(env: &str) -> Result<(String, i64), String> {
     Spi::connect(|client| {
@@ -40,7 +58,7 @@ pub(crate) fn load_environment_state(env: &str) -> Result<(String, i64), String>
     })
 }
 
-pub(crate) fn find_rollback_target_by_steps(
+fn find_rollback_target_by_steps(
     env: &str,
     current_active: i64,
     steps: i32,
@@ -65,6 +83,63 @@ pub(crate) fn find_rollback_target_by_steps(
     })
 }
 
+fn find_rollback_target_by_label(env: &str, label: &str) -> Result<i64, String> {
+    Spi::get_one_with_args::<i64>(
+        "
+        SELECT id
+        FROM stopgap.deployment
+        WHERE env = $1
+          AND label = $2
+          AND status IN ('active', 'rolled_back')
+        ORDER BY id DESC
+        LIMIT 1
+        ",
+        &[env.into(), label.into()],
+    )
+    .map_err(|e| format!("failed to find rollback target labeled {} for env {}: {e}", label, env))?
+    .ok_or_else(|| {
+        format!(
+            "cannot rollback env {} to label {}: no active or rolled_back deployment has that label",
+            env, label
+        )
+    })
+}
+
+/// Resolves the rollback target: an explicit id wins, then the newest deployment carrying an
+/// explicit label, then an explicit step count, then the environment's baseline deployment,
+/// falling back to one step back when no baseline is set.
+pub(crate) fn resolve_rollback_target(
+    env: &str,
+    current_active: i64,
+    steps: Option<i32>,
+    to_id: Option<i64>,
+    to_label: Option<&str>,
+) -> Result<i64, String> {
+    if let Some(explicit_id) = to_id {
+        ensure_deployment_belongs_to_env(env, explicit_id)?;
+        return Ok(explicit_id);
+    }
+    if let Some(label) = to_label {
+        return find_rollback_target_by_label(env, label);
+    }
+    if let Some(steps) = steps {
+        return find_rollback_target_by_steps(env, current_active, steps);
+    }
+
+    match load_baseline_deployment(env)? {
+        Some(baseline_id) => Ok(baseline_id),
+        None => find_rollback_target_by_steps(env, current_active, 1),
+    }
+}
+
+pub(crate) fn load_baseline_deployment(env: &str) -> Result<Option<i64>, String> {
+    Spi::get_one_with_args::<i64>(
+        "SELECT baseline_deployment_id FROM stopgap.environment WHERE env = $1",
+        &[env.into()],
+    )
+    .map_err(|e| format!("failed to load baseline deployment for env {}: {e}", env))
+}
+
 pub(crate) fn ensure_deployment_belongs_to_env(
     env: &str,
     deployment_id: i64,
@@ -83,6 +158,27 @@ pub(crate) fn ensure_deployment_belongs_to_env(
     }
 }
 
+/// Optimistic concurrency check for `stopgap.deploy(..., expect_active_id)`: the deploy only
+/// proceeds while `env` still has the active deployment the caller based it on.
+pub(crate) fn ensure_expected_active_deployment(env: &str, expected: i64) -> Result<(), String> {
+    let active = Spi::get_one_with_args::<i64>(
+        "SELECT (SELECT active_deployment_id FROM stopgap.environment WHERE env = $1)",
+        &[env.into()],
+    )
+    .map_err(|e| format!("failed to read active deployment for env {}: {e}", env))?;
+
+    if active == Some(expected) {
+        Ok(())
+    } else {
+        Err(format!(
+            "stopgap deploy conflict for env {}: expected active deployment {} but found {}",
+            env,
+            expected,
+            active.map_or_else(|| "none".to_string(), |id| id.to_string())
+        ))
+    }
+}
+
 pub(crate) fn load_deployment_status(deployment_id: i64) -> Result<DeploymentStatus, String> {
     let status = Spi::get_one_with_args::<String>(
         "SELECT status FROM stopgap.deployment WHERE id = $1",
@@ -104,6 +200,7 @@ pub(crate) fn transition_if_active(deployment_id: i64, to: DeploymentStatus) ->
 }
 
 pub(crate) fn reactivate_deployment(live_schema: &str, deployment_id: i64) -> Result<(), String> {
+    ensure_deployment_artifacts_exist(deployment_id)?;
     let rows = fetch_fn_versions(deployment_id)?;
     let source_schema = load_deployment_source_schema(deployment_id)?;
     let candidates = rows
@@ -113,7 +210,15 @@ pub(crate) fn reactivate_deployment(live_schema: &str, deployment_id: i64) -> Re
             artifact_hash: row.artifact_hash.clone(),
         })
         .collect::<Vec<_>>();
-    let import_map = deployment_import_map(source_schema.as_str(), &candidates);
+    let mut import_map = deployment_import_map(source_schema.as_str(), &candidates);
+    add_function_path_imports(
+        &mut import_map,
+        rows.iter().filter_map(|row| {
+            row.function_path.as_deref().map(|path| (path, row.artifact_hash.as_str()))
+        }),
+    );
+    let limits = load_deployment_limits(deployment_id)?;
+    let live_arg_name = load_deployment_live_arg_name(deployment_id)?;
 
     for row in rows {
         let schema =
@@ -121,15 +226,70 @@ pub(crate) fn reactivate_deployment(live_schema: &str, deployment_id: i64) -> Re
         materialize_live_pointer(
             schema,
             row.live_fn_name.as_str(),
+            live_arg_name.as_str(),
             row.artifact_hash.as_str(),
             row.export_name.as_deref().unwrap_or("default"),
             &import_map,
+            &limits,
         )?;
     }
 
     Ok(())
 }
 
+fn ensure_deployment_artifacts_exist(deployment_id: i64) -> Result<(), String> {
+    let missing = Spi::get_one_with_args::<String>(
+        "
+        SELECT string_agg(DISTINCT fv.artifact_hash, ', ' ORDER BY fv.artifact_hash)
+        FROM stopgap.fn_version fv
+        WHERE fv.deployment_id = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM plts.artifact a WHERE a.artifact_hash = fv.artifact_hash
+          )
+        ",
+        &[deployment_id.into()],
+    )
+    .map_err(|e| format!("failed to check artifacts for deployment {}: {e}", deployment_id))?;
+
+    match missing {
+        Some(missing) => Err(format!(
+            "stopgap deployment {} references missing plts artifacts: {}",
+            deployment_id, missing
+        )),
+        None => Ok(()),
+    }
+}
+
+fn load_deployment_limits(deployment_id: i64) -> Result<serde_json::Map<String, Value>, String> {
+    let limits = Spi::get_one_with_args::<JsonB>(
+        "SELECT manifest->'limits' FROM stopgap.deployment WHERE id = $1",
+        &[deployment_id.into()],
+    )
+    .map_err(|e| format!("failed to load limits for deployment {}: {e}", deployment_id))?;
+
+    Ok(limits.and_then(|limits| limits.0.as_object().cloned()).unwrap_or_default())
+}
+
+/// Argument name recorded when the deployment was made; deployments recorded before the name
+/// was stored used the default.
+fn load_deployment_live_arg_name(deployment_id: i64) -> Result<String, String> {
+    let name = Spi::get_one_with_args::<String>(
+        "SELECT manifest->>'live_arg_name' FROM stopgap.deployment WHERE id = $1",
+        &[deployment_id.into()],
+    )
+    .map_err(|e| format!("failed to load live_arg_name for deployment {}: {e}", deployment_id))?
+    .unwrap_or_else(|| DEFAULT_LIVE_ARG_NAME.to_string());
+
+    if common::sql::is_simple_identifier(&name) {
+        Ok(name)
+    } else {
+        Err(format!(
+            "deployment {} records invalid live_arg_name {name:?}; expected a lowercase identifier",
+            deployment_id
+        ))
+    }
+}
+
 fn load_deployment_source_schema(deployment_id: i64) -> Result<String, String> {
     Spi::get_one_with_args::<String>(
         "SELECT source_schema::text FROM stopgap.deployment WHERE id = $1",
@@ -220,6 +380,59 @@ pub(crate) fn update_failed_manifest(deployment_id: i64, err: &str) -> Result<()
     )
 }
 
+/// Moves deployments of `env` that have stayed `open` since before `now() - older_than` to
+/// `failed`, recording why in their manifest. Returns the reaped deployment ids.
+pub(crate) fn reap_stale_open_deployments(
+    env: &str,
+    older_than: Interval,
+) -> Result<Vec<i64>, String> {
+    let stale = Spi::connect(|client| {
+        let rows = client.select(
+            "
+            SELECT id, $2::interval::text AS threshold
+            FROM stopgap.deployment
+            WHERE env = $1
+              AND status = 'open'
+              AND created_at < now() - $2::interval
+            ORDER BY id
+            ",
+            None,
+            &[env.into(), older_than.into()],
+        )?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            let Some(id) = row.get_by_name::<i64, _>("id")? else {
+                continue;
+            };
+            let threshold = row.get_by_name::<String, _>("threshold")?.unwrap_or_default();
+            out.push((id, threshold));
+        }
+
+        Ok::<Vec<(i64, String)>, pgrx::spi::Error>(out)
+    })
+    .map_err(|e| format!("failed to load stale open deployments for env {env}: {e}"))?;
+
+    let mut reaped = Vec::with_capacity(stale.len());
+    for (deployment_id, threshold) in stale {
+        transition_deployment_status(deployment_id, DeploymentStatus::Failed)?;
+        update_deployment_manifest(
+            deployment_id,
+            json!({
+                "error": {
+                    "message": format!(
+                        "deployment was still open after {threshold}; the deploy that created it never finished"
+                    ),
+                    "at": "stopgap.reap_stale_deployments"
+                }
+            }),
+        )?;
+        reaped.push(deployment_id);
+    }
+
+    Ok(reaped)
+}
+
 pub(crate) fn transition_deployment_status(
     deployment_id: i64,
     to: DeploymentStatus,
@@ -1,7 +1,7 @@
 use pgrx::prelude::*;
 use serde_json::json;
 
-use crate::domain::LiveFnRow;
+use crate::domain::{LiveFnRow, MaterializedPointerRow};
 use crate::runtime_config::{quote_ident, run_sql};
 use crate::{APP_RUNTIME_ROLE, STOPGAP_OWNER_ROLE};
 
@@ -49,6 +49,40 @@ pub(crate) fn fetch_live_deployable_functions(live_schema: &str) -> Result<Vec<L
     .map_err(|e| format!("failed to load live deployable functions in schema {live_schema}: {e}"))
 }
 
+pub(crate) fn fetch_materialized_pointers(
+    schema: &str,
+) -> Result<Vec<MaterializedPointerRow>, String> {
+    Spi::connect(|client| {
+        let rows = client.select(
+            "
+            SELECT p.proname::text AS fn_name,
+                   p.prosrc AS body
+            FROM pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE n.nspname = $1
+              AND l.lanname = 'plts'
+            ORDER BY p.proname
+            ",
+            None,
+            &[schema.into()],
+        )?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            let fn_name = row
+                .get_by_name::<String, _>("fn_name")
+                .expect("fn_name must be text")
+                .expect("fn_name cannot be null");
+            let body = row.get_by_name::<String, _>("body")?.unwrap_or_default();
+            out.push(MaterializedPointerRow { fn_name, body });
+        }
+
+        Ok::<Vec<MaterializedPointerRow>, pgrx::spi::Error>(out)
+    })
+    .map_err(|e| format!("failed to load materialized pointers in schema {schema}: {e}"))
+}
+
 pub(crate) fn live_function_has_dependents(function_oid: i64) -> Result<bool, String> {
     Spi::get_one_with_args::<bool>(
         "
@@ -69,6 +103,35 @@ pub(crate) fn live_function_has_dependents(function_oid: i64) -> Result<bool, St
     .map(|value| value.unwrap_or(false))
 }
 
+/// The env whose `stopgap.environment` row already uses `live_schema`, if any.
+pub(crate) fn live_schema_owner_env(live_schema: &str) -> Result<Option<String>, String> {
+    Spi::get_one_with_args::<String>(
+        "SELECT (SELECT env FROM stopgap.environment WHERE live_schema = $1 ORDER BY env LIMIT 1)",
+        &[live_schema.into()],
+    )
+    .map_err(|e| format!("failed to check live schema {live_schema} ownership: {e}"))
+}
+
+pub(crate) fn fetch_prune_exclusions(env: &str) -> Result<Vec<String>, String> {
+    Spi::connect(|client| {
+        let rows = client.select(
+            "SELECT fn_name::text AS fn_name FROM stopgap.prune_exclusion WHERE env = $1 ORDER BY fn_name",
+            None,
+            &[env.into()],
+        )?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            if let Some(fn_name) = row.get_by_name::<String, _>("fn_name")? {
+                out.push(fn_name);
+            }
+        }
+
+        Ok::<Vec<String>, pgrx::spi::Error>(out)
+    })
+    .map_err(|e| format!("failed to load prune exclusions for env {env}: {e}"))
+}
+
 pub(crate) fn fetch_deployable_functions(from_schema: &str) -> Result<Vec<DeployableFn>, String> {
     Spi::connect(|client| {
         let rows = client.select(
@@ -135,9 +198,11 @@ pub(crate) fn ensure_no_overloaded_plts_functions(from_schema: &str) {
 pub(crate) fn materialize_live_pointer(
     live_schema: &str,
     fn_name: &str,
+    arg_name: &str,
     artifact_hash: &str,
     entrypoint_export: &str,
     import_map: &serde_json::Map<String, serde_json::Value>,
+    limits: &serde_json::Map<String, serde_json::Value>,
 ) -> Result<(), String> {
     let mut pointer = json!({
         "plts": 1,
@@ -149,18 +214,47 @@ pub(crate) fn materialize_live_pointer(
     if !import_map.is_empty() {
         pointer["import_map"] = serde_json::Value::Object(import_map.clone());
     }
+    if !limits.is_empty() {
+        pointer["limits"] = serde_json::Value::Object(limits.clone());
+    }
 
     let body = pointer.to_string().replace('\'', "''");
 
+    // CREATE OR REPLACE cannot rename a parameter, so a pointer created under a different
+    // argument name has to be dropped first, which dependent objects would block.
+    let (current_oid, current_arg_name) = Spi::get_two_with_args::<i64, String>(
+        "
+        SELECT p.oid::bigint, NULLIF(p.proargnames[1], '')
+        FROM (SELECT to_regprocedure(format('%I.%I(jsonb)', $1::text, $2::text)) AS oid) r
+        LEFT JOIN pg_proc p ON p.oid = r.oid
+        ",
+        &[live_schema.into(), fn_name.into()],
+    )
+    .map_err(|e| format!("failed to inspect live pointer function {live_schema}.{fn_name}: {e}"))?;
+    if let (Some(oid), Some(current)) = (current_oid, current_arg_name)
+        && current != arg_name
+    {
+        if live_function_has_dependents(oid)? {
+            return Err(format!(
+                "cannot rename argument {current} of live pointer function {live_schema}.{fn_name} to {arg_name}: other objects depend on it; drop them first or keep the argument name {current}"
+            ));
+        }
+        run_sql(
+            &format!("DROP FUNCTION {}.{}(jsonb)", quote_ident(live_schema), quote_ident(fn_name)),
+            "failed to drop live pointer function before renaming its argument",
+        )?;
+    }
+
     let sql = format!(
         "
-        CREATE OR REPLACE FUNCTION {}.{}(args jsonb)
+        CREATE OR REPLACE FUNCTION {}.{}({} jsonb)
         RETURNS jsonb
         LANGUAGE plts
         AS $$ {} $$
         ",
         quote_ident(live_schema),
         quote_ident(fn_name),
+        quote_ident(arg_name),
         body
     );
 
@@ -5,7 +5,6 @@ use serde_json::json;
 pub(crate) struct FnVersionRow {
     pub(crate) fn_name: String,
     pub(crate) live_fn_name: String,
-    #[allow(dead_code)]
     pub(crate) function_path: Option<String>,
     pub(crate) export_name: Option<String>,
     pub(crate) live_fn_schema: String,
@@ -23,6 +22,7 @@ pub(crate) struct PruneReport {
     pub(crate) enabled: bool,
     pub(crate) dropped: Vec<String>,
     pub(crate) skipped_with_dependents: Vec<String>,
+    pub(crate) excluded: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +31,12 @@ pub(crate) struct CandidateFn {
     pub(crate) artifact_hash: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MaterializedPointerRow {
+    pub(crate) fn_name: String,
+    pub(crate) body: String,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct DiffRow {
     pub(crate) fn_name: String,
@@ -79,11 +85,29 @@ impl DeploymentStatus {
     }
 }
 
+pub(crate) const MANIFEST_VERSION: u64 = 1;
+
+/// Upgrades a stored deployment manifest to the current `MANIFEST_VERSION` shape. Manifests
+/// written before versioning carry no `manifest_version` and already match version 1.
+pub(crate) fn migrate_manifest(manifest: Value) -> Value {
+    let Value::Object(mut manifest) = manifest else {
+        return manifest;
+    };
+
+    if manifest.get("manifest_version").and_then(Value::as_u64).is_none() {
+        manifest.entry("functions").or_insert_with(|| json!([]));
+        manifest.insert("manifest_version".to_string(), json!(MANIFEST_VERSION));
+    }
+
+    Value::Object(manifest)
+}
+
 pub(crate) fn prune_manifest_item(report: &PruneReport) -> Value {
     json!({
         "enabled": report.enabled,
         "dropped": report.dropped,
-        "skipped_with_dependents": report.skipped_with_dependents
+        "skipped_with_dependents": report.skipped_with_dependents,
+        "excluded": report.excluded
     })
 }
 
@@ -144,6 +168,101 @@ pub(crate) fn compute_diff_rows(
     (rows, summary)
 }
 
+/// Drops `removed` rows from a diff so callers deploying a partial source schema only see what
+/// the deploy would add or change.
+pub(crate) fn without_removed_rows(
+    rows: Vec<DiffRow>,
+    summary: DiffSummary,
+) -> (Vec<DiffRow>, DiffSummary) {
+    let rows = rows.into_iter().filter(|row| row.change != "removed").collect();
+    (rows, DiffSummary { removed: 0, ..summary })
+}
+
+/// Keeps only the diff rows whose change is `only` (`added`, `changed`, or `removed`). The
+/// summary is left to the caller so it still counts every category.
+pub(crate) fn diff_rows_with_change(
+    rows: Vec<DiffRow>,
+    only: &str,
+) -> Result<Vec<DiffRow>, String> {
+    if !matches!(only, "added" | "changed" | "removed") {
+        return Err(format!(
+            "stopgap diff only must be one of added, changed, removed; got {only}"
+        ));
+    }
+
+    Ok(rows.into_iter().filter(|row| row.change == only).collect())
+}
+
+/// Compares pointer functions materialized into a schema against the functions that were
+/// compiled for it. Every problem found becomes one `{fn_name, issue, detail}` entry.
+pub(crate) fn audit_materialized_pointers(
+    expected: &[CandidateFn],
+    materialized: &[MaterializedPointerRow],
+    known_artifacts: &std::collections::BTreeSet<String>,
+) -> Vec<Value> {
+    let materialized_by_name = materialized
+        .iter()
+        .map(|row| (row.fn_name.as_str(), row.body.as_str()))
+        .collect::<std::collections::BTreeMap<_, _>>();
+    let mut issues = Vec::new();
+
+    for function in expected {
+        if !known_artifacts.contains(&function.artifact_hash) {
+            issues.push(json!({
+                "fn_name": function.fn_name,
+                "issue": "missing_artifact",
+                "detail": format!("plts.artifact has no row for {}", function.artifact_hash)
+            }));
+        }
+
+        let Some(body) = materialized_by_name.get(function.fn_name.as_str()) else {
+            issues.push(json!({
+                "fn_name": function.fn_name,
+                "issue": "missing_pointer",
+                "detail": "no plts pointer function was materialized"
+            }));
+            continue;
+        };
+
+        let pointer = serde_json::from_str::<Value>(body.trim())
+            .ok()
+            .filter(|pointer| pointer.get("kind").and_then(Value::as_str) == Some("artifact_ptr"));
+        match pointer.as_ref().and_then(|pointer| pointer.get("artifact_hash")) {
+            None => issues.push(json!({
+                "fn_name": function.fn_name,
+                "issue": "invalid_pointer",
+                "detail": "function body is not an artifact pointer"
+            })),
+            Some(hash) if hash.as_str() != Some(function.artifact_hash.as_str()) => {
+                issues.push(json!({
+                    "fn_name": function.fn_name,
+                    "issue": "artifact_mismatch",
+                    "detail": format!(
+                        "pointer references {} but {} was compiled",
+                        hash.as_str().unwrap_or_default(),
+                        function.artifact_hash
+                    )
+                }))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let expected_names =
+        expected.iter().map(|function| function.fn_name.as_str()).collect::<Vec<_>>();
+    for row in materialized {
+        if !expected_names.contains(&row.fn_name.as_str()) {
+            issues.push(json!({
+                "fn_name": row.fn_name,
+                "issue": "unexpected_function",
+                "detail": "function is not part of the candidate deployment"
+            }));
+        }
+    }
+
+    issues
+}
+
 pub(crate) fn fn_manifest_item(
     source_schema: &str,
     live_schema: &str,
@@ -180,6 +299,42 @@ pub(crate) fn fn_manifest_item(
     })
 }
 
+/// Rewrites a deployment manifest for a promoted copy in another environment. Functions keep
+/// their artifacts but point at the target live schema; the source prune report is dropped
+/// because promotion never prunes.
+pub(crate) fn promoted_manifest(
+    source: &Value,
+    to_env: &str,
+    live_schema: &str,
+    label: Option<&str>,
+    from_env: &str,
+    from_deployment_id: i64,
+) -> Value {
+    let mut manifest = source.as_object().cloned().unwrap_or_default();
+    manifest.remove("prune");
+    manifest.insert("env".to_string(), json!(to_env));
+    manifest.insert("live_schema".to_string(), json!(live_schema));
+    manifest.insert("label".to_string(), json!(label));
+    manifest.insert(
+        "promoted_from".to_string(),
+        json!({ "env": from_env, "deployment_id": from_deployment_id }),
+    );
+
+    let retarget = |function: &mut Value| {
+        if let Some(function) = function.as_object_mut() {
+            function.insert("live_schema".to_string(), json!(live_schema));
+        }
+    };
+    if let Some(functions) = manifest.get_mut("functions").and_then(Value::as_array_mut) {
+        functions.iter_mut().for_each(retarget);
+    }
+    if let Some(functions) = manifest.get_mut("functions_by_path").and_then(Value::as_object_mut) {
+        functions.values_mut().for_each(retarget);
+    }
+
+    Value::Object(manifest)
+}
+
 pub(crate) fn deployment_import_specifier(source_schema: &str, fn_name: &str) -> String {
     format!("@stopgap/{source_schema}/{fn_name}")
 }
@@ -198,6 +353,103 @@ pub(crate) fn deployment_import_map(
     import_map
 }
 
+/// Adds a `@stopgap/<function_path>` alias for each deployed function. Unlike the
+/// schema-qualified specifiers these stay the same whichever source schema deployed the
+/// function, so handlers can import each other by route name.
+pub(crate) fn add_function_path_imports<'a>(
+    import_map: &mut serde_json::Map<String, Value>,
+    functions: impl IntoIterator<Item = (&'a str, &'a str)>,
+) {
+    for (function_path, artifact_hash) in functions {
+        import_map.insert(
+            format!("@stopgap/{function_path}"),
+            Value::String(format!("plts+artifact:{artifact_hash}")),
+        );
+    }
+}
+
+pub(crate) const MAX_ENV_NAME_LEN: usize = 63;
+
+/// Trims and validates an env name, lowercasing it when `lowercase` is set.
+pub(crate) fn normalize_env_name(raw: &str, lowercase: bool) -> Result<String, String> {
+    let trimmed = raw.trim();
+    let normalized = if lowercase { trimmed.to_ascii_lowercase() } else { trimmed.to_string() };
+    if normalized.is_empty() {
+        return Err(format!("invalid stopgap env name '{}': empty after trimming", raw));
+    }
+
+    if normalized.len() > MAX_ENV_NAME_LEN {
+        return Err(format!(
+            "invalid stopgap env name '{}': longer than {} characters",
+            normalized, MAX_ENV_NAME_LEN
+        ));
+    }
+
+    if !normalized.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.')) {
+        return Err(format!(
+            "invalid stopgap env name '{}': only letters, digits, '_', '-', and '.' are allowed",
+            normalized
+        ));
+    }
+
+    Ok(normalized)
+}
+
+/// Picks the env `raw` addresses given `existing`, the recorded envs equal to it after trimming
+/// and ASCII case folding. An exact match wins so envs recorded before names were normalized stay
+/// reachable; a normalized name that would only sit beside such an env is rejected instead of
+/// silently starting a second environment.
+pub(crate) fn pick_env_name(
+    raw: &str,
+    lowercase: bool,
+    existing: &[String],
+) -> Result<String, String> {
+    if existing.iter().any(|env| env == raw) {
+        return Ok(raw.to_string());
+    }
+
+    let normalized = normalize_env_name(raw, lowercase)?;
+    if existing.is_empty() || existing.contains(&normalized) {
+        return Ok(normalized);
+    }
+
+    Err(format!(
+        "stopgap env name '{}' matches existing env {} only after normalization; pass the exact name or rename the existing env",
+        raw,
+        existing.iter().map(|env| format!("'{env}'")).collect::<Vec<_>>().join(", ")
+    ))
+}
+
+pub(crate) const DEPLOY_LIMIT_KEYS: [&str; 3] = ["max_runtime_ms", "max_heap_mb", "max_query_rows"];
+
+pub(crate) fn normalize_deploy_limits(
+    raw: Option<&Value>,
+) -> Result<serde_json::Map<String, Value>, String> {
+    let Some(raw) = raw.filter(|value| !value.is_null()) else {
+        return Ok(serde_json::Map::new());
+    };
+    let Some(entries) = raw.as_object() else {
+        return Err("stopgap deploy limits must be a JSON object".to_string());
+    };
+
+    let mut limits = serde_json::Map::new();
+    for (key, value) in entries {
+        if !DEPLOY_LIMIT_KEYS.contains(&key.as_str()) {
+            return Err(format!(
+                "unknown stopgap deploy limit '{}'; expected one of: {}",
+                key,
+                DEPLOY_LIMIT_KEYS.join(", ")
+            ));
+        }
+        let Some(limit) = value.as_u64().filter(|limit| *limit > 0) else {
+            return Err(format!("stopgap deploy limit '{}' must be a positive integer", key));
+        };
+        limits.insert(key.clone(), json!(limit));
+    }
+
+    Ok(limits)
+}
+
 pub(crate) fn rollback_steps_to_offset(steps: i32) -> Result<i64, String> {
     if steps < 1 {
         return Err("stopgap.rollback requires steps >= 1".to_string());
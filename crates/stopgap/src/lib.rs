@@ -11,31 +11,40 @@ mod sql_bootstrap;
 #[cfg(feature = "pg_test")]
 use pgrx::prelude::*;
 
-use api_ops::{load_deployments, load_diff, load_status, run_deploy_flow};
+use api_ops::{
+    load_activation_history, load_deployments, load_diff, load_env_snapshot, load_env_stats,
+    load_manifest, load_orphans, load_rollback_preview, load_status, load_validation,
+    run_deploy_flow, run_import_env, run_promote_flow, run_shadow_deploy,
+};
 
 pub(crate) use deployment_state::{
-    ensure_deployment_belongs_to_env, fetch_fn_versions, find_rollback_target_by_steps,
+    ensure_deployment_belongs_to_env, ensure_expected_active_deployment, fetch_fn_versions,
     load_deployment_status, load_environment_state, reactivate_deployment,
+    reap_stale_open_deployments, resolve_env_name, resolve_rollback_target,
     transition_deployment_status, transition_if_active, update_deployment_manifest,
     update_failed_manifest,
 };
 pub(crate) use deployment_utils::{
     ensure_no_overloaded_plts_functions, fetch_deployable_functions,
-    fetch_live_deployable_functions, harden_live_schema, live_function_has_dependents,
+    fetch_live_deployable_functions, fetch_materialized_pointers, fetch_prune_exclusions,
+    harden_live_schema, live_function_has_dependents, live_schema_owner_env,
     materialize_live_pointer,
 };
 pub(crate) use domain::{
-    CandidateFn, DeploymentStatus, PruneReport, compute_diff_rows, deployment_import_map,
-    fn_manifest_item, hash_lock_key, prune_manifest_item, rollback_steps_to_offset,
+    CandidateFn, DeploymentStatus, MANIFEST_VERSION, PruneReport, add_function_path_imports,
+    audit_materialized_pointers, compute_diff_rows, deployment_import_map, diff_rows_with_change,
+    fn_manifest_item, hash_lock_key, migrate_manifest, normalize_deploy_limits, promoted_manifest,
+    prune_manifest_item, rollback_steps_to_offset, without_removed_rows,
 };
 #[cfg(test)]
-pub(crate) use domain::{FnVersionRow, is_allowed_transition};
+pub(crate) use domain::{FnVersionRow, is_allowed_transition, normalize_env_name, pick_env_name};
 pub(crate) use runtime_config::{
-    quote_ident, resolve_default_env, resolve_deploy_exports_json, resolve_live_schema,
-    resolve_prune_enabled, run_sql, run_sql_with_args,
+    RECOGNIZED_SETTINGS, quote_ident, resolve_default_env, resolve_deploy_exports_json,
+    resolve_live_arg_name, resolve_live_schema, resolve_prune_enabled, run_sql, run_sql_with_args,
 };
 pub(crate) use security::{
-    ensure_deploy_permissions, ensure_diff_permissions, ensure_role_membership,
+    ensure_deploy_permissions, ensure_diff_permissions, ensure_promote_permissions,
+    ensure_role_membership, ensure_source_schema_allowed, load_deploy_permission_report,
 };
 
 ::pgrx::pg_module_magic!(name, version);
@@ -126,6 +135,24 @@ mod unit_tests {
         );
     }
 
+    #[test]
+    fn test_add_function_path_imports_uses_schema_independent_specifiers() {
+        let mut import_map = serde_json::Map::new();
+        crate::add_function_path_imports(
+            &mut import_map,
+            [("api.billing.charge", "sha256:a"), ("api.orders.checkout", "sha256:b")],
+        );
+
+        assert_eq!(
+            import_map.get("@stopgap/api.billing.charge").and_then(|v| v.as_str()),
+            Some("plts+artifact:sha256:a")
+        );
+        assert_eq!(
+            import_map.get("@stopgap/api.orders.checkout").and_then(|v| v.as_str()),
+            Some("plts+artifact:sha256:b")
+        );
+    }
+
     #[test]
     fn test_fn_manifest_item_includes_pointer_import_map_when_present() {
         let mut import_map = serde_json::Map::new();
@@ -162,6 +189,54 @@ mod unit_tests {
         assert!(crate::rollback_steps_to_offset(0).is_err());
     }
 
+    #[test]
+    fn test_normalize_env_name_trims_and_lowercases() {
+        assert_eq!(crate::normalize_env_name("prod", true).as_deref(), Ok("prod"));
+        assert_eq!(crate::normalize_env_name("  Prod ", true).as_deref(), Ok("prod"));
+        assert_eq!(
+            crate::normalize_env_name("eu-west.staging_2", true).as_deref(),
+            Ok("eu-west.staging_2")
+        );
+    }
+
+    #[test]
+    fn test_normalize_env_name_keeps_case_when_lowercasing_is_off() {
+        assert_eq!(crate::normalize_env_name("  Prod ", false).as_deref(), Ok("Prod"));
+    }
+
+    #[test]
+    fn test_normalize_env_name_rejects_invalid_values() {
+        assert!(crate::normalize_env_name("", true).is_err());
+        assert!(crate::normalize_env_name("   ", true).is_err());
+        assert!(crate::normalize_env_name("prod env", true).is_err());
+        assert!(crate::normalize_env_name("prod;drop", true).is_err());
+        assert!(
+            crate::normalize_env_name(&"a".repeat(crate::domain::MAX_ENV_NAME_LEN), true).is_ok()
+        );
+        assert!(
+            crate::normalize_env_name(&"a".repeat(crate::domain::MAX_ENV_NAME_LEN + 1), true)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_pick_env_name_prefers_exact_legacy_names_and_rejects_collisions() {
+        let legacy = vec!["Prod".to_string()];
+        assert_eq!(crate::pick_env_name("Prod", true, &legacy).as_deref(), Ok("Prod"));
+        assert_eq!(
+            crate::pick_env_name("eu west", true, &["eu west".to_string()]).as_deref(),
+            Ok("eu west")
+        );
+        assert!(crate::pick_env_name("prod", true, &legacy).is_err());
+        assert!(crate::pick_env_name(" PROD", true, &legacy).is_err());
+
+        let unified = vec!["prod".to_string()];
+        assert_eq!(crate::pick_env_name(" PROD", true, &unified).as_deref(), Ok("prod"));
+        assert_eq!(crate::pick_env_name("Staging", true, &[]).as_deref(), Ok("staging"));
+        assert_eq!(crate::pick_env_name("Staging", false, &[]).as_deref(), Ok("Staging"));
+        assert!(crate::pick_env_name("eu west", true, &[]).is_err());
+    }
+
     #[test]
     fn test_compute_diff_rows_covers_added_changed_removed_and_unchanged() {
         let active = vec![
@@ -222,6 +297,221 @@ mod unit_tests {
         assert_eq!(changes.get("delta").copied(), Some("removed"));
     }
 
+    #[test]
+    fn test_without_removed_rows_drops_removed_rows_and_count() {
+        let active = vec![crate::FnVersionRow {
+            fn_name: "delta".to_string(),
+            live_fn_name: "delta".to_string(),
+            function_path: None,
+            export_name: None,
+            live_fn_schema: "live_deployment".to_string(),
+            artifact_hash: "sha256:4".to_string(),
+        }];
+        let candidate = vec![crate::CandidateFn {
+            fn_name: "gamma".to_string(),
+            artifact_hash: "sha256:5".to_string(),
+        }];
+
+        let (rows, summary) = crate::compute_diff_rows(&active, &candidate);
+        let (rows, summary) = crate::without_removed_rows(rows, summary);
+
+        assert_eq!(
+            summary,
+            crate::domain::DiffSummary { added: 1, changed: 0, removed: 0, unchanged: 0 }
+        );
+        assert_eq!(
+            rows.iter().map(|row| (row.fn_name.as_str(), row.change)).collect::<Vec<_>>(),
+            vec![("gamma", "added")]
+        );
+    }
+
+    #[test]
+    fn test_diff_rows_with_change_keeps_only_requested_change() {
+        let active = ["alpha", "beta", "delta"]
+            .into_iter()
+            .map(|fn_name| crate::FnVersionRow {
+                fn_name: fn_name.to_string(),
+                live_fn_name: fn_name.to_string(),
+                function_path: None,
+                export_name: None,
+                live_fn_schema: "live_deployment".to_string(),
+                artifact_hash: format!("sha256:{fn_name}"),
+            })
+            .collect::<Vec<_>>();
+        let candidate = vec![
+            crate::CandidateFn {
+                fn_name: "alpha".to_string(),
+                artifact_hash: "sha256:alpha".to_string(),
+            },
+            crate::CandidateFn {
+                fn_name: "beta".to_string(),
+                artifact_hash: "sha256:beta-2".to_string(),
+            },
+            crate::CandidateFn {
+                fn_name: "gamma".to_string(),
+                artifact_hash: "sha256:gamma".to_string(),
+            },
+        ];
+
+        let (rows, _) = crate::compute_diff_rows(&active, &candidate);
+        let changed = crate::diff_rows_with_change(rows.clone(), "changed")
+            .expect("changed should be an accepted filter");
+        assert_eq!(
+            changed.iter().map(|row| row.fn_name.as_str()).collect::<Vec<_>>(),
+            vec!["beta"]
+        );
+
+        let err = crate::diff_rows_with_change(rows, "unchanged")
+            .expect_err("unchanged should not be an accepted filter");
+        assert!(err.contains("added, changed, removed"));
+    }
+
+    #[test]
+    fn test_promoted_manifest_retargets_env_and_live_schema() {
+        let source = serde_json::json!({
+            "env": "staging",
+            "source_schema": "app",
+            "live_schema": "staging_live",
+            "label": "rc1",
+            "limits": {"max_runtime_ms": 5000},
+            "functions": [{"fn_name": "hello", "live_schema": "staging_live"}],
+            "functions_by_path": {"api.hello": {"fn_name": "hello", "live_schema": "staging_live"}},
+            "prune": {"enabled": true, "dropped": ["old_fn"]}
+        });
+
+        let manifest = crate::promoted_manifest(&source, "prod", "prod_live", None, "staging", 7);
+
+        assert_eq!(manifest["env"], "prod");
+        assert_eq!(manifest["live_schema"], "prod_live");
+        assert_eq!(manifest["label"], serde_json::Value::Null);
+        assert_eq!(manifest["source_schema"], "app");
+        assert_eq!(manifest["limits"]["max_runtime_ms"], 5000);
+        assert_eq!(manifest["functions"][0]["live_schema"], "prod_live");
+        assert_eq!(manifest["functions_by_path"]["api.hello"]["live_schema"], "prod_live");
+        assert_eq!(
+            manifest["promoted_from"],
+            serde_json::json!({"env": "staging", "deployment_id": 7})
+        );
+        assert!(manifest.get("prune").is_none());
+    }
+
+    #[test]
+    fn test_migrate_manifest_stamps_version_on_unversioned_manifest() {
+        let legacy = serde_json::json!({
+            "env": "prod",
+            "source_schema": "app",
+            "live_schema": "live_deployment",
+            "label": null
+        });
+
+        let migrated = crate::migrate_manifest(legacy);
+
+        assert_eq!(migrated["manifest_version"], crate::MANIFEST_VERSION);
+        assert_eq!(migrated["functions"], serde_json::json!([]));
+        assert_eq!(migrated["env"], "prod");
+
+        let current = serde_json::json!({
+            "manifest_version": crate::MANIFEST_VERSION,
+            "functions": [{"fn_name": "hello"}]
+        });
+        assert_eq!(crate::migrate_manifest(current.clone()), current);
+    }
+
+    #[test]
+    fn test_normalize_deploy_limits_validates_keys_and_values() {
+        let limits = crate::normalize_deploy_limits(Some(&serde_json::json!({
+            "max_runtime_ms": 5000,
+            "max_query_rows": 1000
+        })))
+        .expect("known positive limits should be accepted");
+        assert_eq!(limits.get("max_runtime_ms").and_then(|v| v.as_u64()), Some(5000));
+        assert_eq!(limits.get("max_query_rows").and_then(|v| v.as_u64()), Some(1000));
+
+        assert!(crate::normalize_deploy_limits(None).expect("missing limits").is_empty());
+        assert!(
+            crate::normalize_deploy_limits(Some(&serde_json::json!({ "max_cpu": 1 })))
+                .expect_err("unknown keys should be rejected")
+                .contains("unknown stopgap deploy limit 'max_cpu'")
+        );
+        assert!(
+            crate::normalize_deploy_limits(Some(&serde_json::json!({ "max_heap_mb": 0 })))
+                .expect_err("zero should be rejected")
+                .contains("must be a positive integer")
+        );
+        assert!(crate::normalize_deploy_limits(Some(&serde_json::json!([1]))).is_err());
+    }
+
+    #[test]
+    fn test_audit_materialized_pointers_reports_each_issue_kind() {
+        let expected = vec![
+            crate::CandidateFn {
+                fn_name: "alpha".to_string(),
+                artifact_hash: "sha256:1".to_string(),
+            },
+            crate::CandidateFn {
+                fn_name: "beta".to_string(),
+                artifact_hash: "sha256:2".to_string(),
+            },
+            crate::CandidateFn {
+                fn_name: "gamma".to_string(),
+                artifact_hash: "sha256:3".to_string(),
+            },
+            crate::CandidateFn {
+                fn_name: "delta".to_string(),
+                artifact_hash: "sha256:4".to_string(),
+            },
+        ];
+        let materialized = vec![
+            crate::domain::MaterializedPointerRow {
+                fn_name: "alpha".to_string(),
+                body: r#" {"plts":1,"kind":"artifact_ptr","artifact_hash":"sha256:1"} "#
+                    .to_string(),
+            },
+            crate::domain::MaterializedPointerRow {
+                fn_name: "beta".to_string(),
+                body: r#"{"plts":1,"kind":"artifact_ptr","artifact_hash":"sha256:9"}"#.to_string(),
+            },
+            crate::domain::MaterializedPointerRow {
+                fn_name: "gamma".to_string(),
+                body: "export default () => null;".to_string(),
+            },
+            crate::domain::MaterializedPointerRow {
+                fn_name: "stray".to_string(),
+                body: r#"{"plts":1,"kind":"artifact_ptr","artifact_hash":"sha256:1"}"#.to_string(),
+            },
+        ];
+        let known = ["sha256:1", "sha256:2", "sha256:3"]
+            .into_iter()
+            .map(str::to_string)
+            .collect::<std::collections::BTreeSet<_>>();
+
+        let issues = crate::audit_materialized_pointers(&expected, &materialized, &known);
+        let kinds = issues
+            .iter()
+            .map(|issue| {
+                (
+                    issue["fn_name"].as_str().unwrap_or_default().to_string(),
+                    issue["issue"].as_str().unwrap_or_default().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                ("beta".to_string(), "artifact_mismatch".to_string()),
+                ("gamma".to_string(), "invalid_pointer".to_string()),
+                ("delta".to_string(), "missing_artifact".to_string()),
+                ("delta".to_string(), "missing_pointer".to_string()),
+                ("stray".to_string(), "unexpected_function".to_string()),
+            ]
+        );
+        assert!(
+            crate::audit_materialized_pointers(&expected[..1], &materialized[..1], &known)
+                .is_empty()
+        );
+    }
+
     #[test]
     fn test_parse_bool_setting_accepts_common_values() {
         assert_eq!(crate::runtime_config::parse_bool_setting("true"), Some(true));
@@ -237,6 +527,16 @@ mod unit_tests {
         assert_eq!(crate::runtime_config::parse_bool_setting("maybe"), None);
     }
 
+    #[test]
+    fn test_recognized_settings_are_unique_and_namespaced() {
+        let mut seen = std::collections::BTreeSet::new();
+        for setting in crate::RECOGNIZED_SETTINGS {
+            assert!(setting.name.starts_with("stopgap."));
+            assert!(seen.insert(setting.name), "{} should be listed once", setting.name);
+        }
+        assert!(seen.contains("stopgap.default_env"));
+    }
+
     #[test]
     fn test_role_constants_are_stable() {
         assert_eq!(crate::STOPGAP_OWNER_ROLE, "stopgap_owner");
@@ -250,6 +550,7 @@ mod unit_tests {
             enabled: true,
             dropped: vec!["old_fn".to_string()],
             skipped_with_dependents: vec!["kept_fn".to_string()],
+            excluded: vec!["external_fn".to_string()],
         };
 
         let payload = crate::prune_manifest_item(&report);
@@ -270,6 +571,7 @@ mod unit_tests {
                 .and_then(|v| v.as_str()),
             Some("kept_fn")
         );
+        assert_eq!(payload.get("excluded"), Some(&serde_json::json!(["external_fn"])));
     }
 }
 
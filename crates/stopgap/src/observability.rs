@@ -1,6 +1,7 @@
 use pgrx::prelude::*;
 use serde_json::Value;
 use serde_json::json;
+use std::fmt::Write;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 
@@ -213,6 +214,7 @@ pub(crate) fn classify_call_fn_error(message: &str) -> &'static str {
     let lowered = message.to_ascii_lowercase();
     if lowered.contains("invalid path")
         || lowered.contains("invalid args")
+        || lowered.contains("invalid stopgap env name")
         || lowered.contains("wrong wrapper mode")
     {
         "validation"
@@ -231,15 +233,20 @@ pub(crate) fn classify_call_fn_error(message: &str) -> &'static str {
 
 pub(crate) fn classify_operation_error(message: &str) -> &'static str {
     let lowered = message.to_ascii_lowercase();
-    if lowered.contains("permission") || lowered.contains("must be") {
+    if lowered.contains("permission denied") || lowered.contains("must be a member of") {
         "permission"
     } else if lowered.contains("not found")
         || lowered.contains("does not exist")
         || lowered.contains("invalid")
         || lowered.contains("must be positive")
+        || lowered.contains("typescript checked compile failed")
     {
         "validation"
-    } else if lowered.contains("status") || lowered.contains("already active") {
+    } else if lowered.contains("status")
+        || lowered.contains("already active")
+        || lowered.contains("conflict")
+        || lowered.contains("depend on it")
+    {
         "state"
     } else if lowered.contains("sql") || lowered.contains("spi") || lowered.contains("query") {
         "sql"
@@ -321,6 +328,96 @@ pub(crate) fn metrics_json() -> Value {
     })
 }
 
+/// Renders a `metrics_json()` snapshot in the Prometheus text exposition format, one family per
+/// counter with `error_classes`/`route_counts` as labels.
+pub(crate) fn render_prometheus(metrics: &Value) -> String {
+    let mut out = String::new();
+    for operation in ["deploy", "rollback", "diff", "call_fn"] {
+        let Some(group) = metrics.get(operation) else {
+            continue;
+        };
+        let prefix = format!("stopgap_{operation}");
+        let value = |path: &[&str]| {
+            path.iter()
+                .try_fold(group, |value, segment| value.get(*segment))
+                .and_then(Value::as_u64)
+                .unwrap_or(0)
+        };
+
+        push_prometheus_family(
+            &mut out,
+            &format!("{prefix}_calls_total"),
+            "counter",
+            &format!("stopgap.{operation} calls in this backend."),
+            &[(String::new(), value(&["calls"]))],
+        );
+        push_prometheus_family(
+            &mut out,
+            &format!("{prefix}_errors_total"),
+            "counter",
+            &format!("stopgap.{operation} calls that failed."),
+            &[(String::new(), value(&["errors"]))],
+        );
+        push_prometheus_family(
+            &mut out,
+            &format!("{prefix}_latency_ms_total"),
+            "counter",
+            &format!("Cumulative stopgap.{operation} latency in milliseconds."),
+            &[(String::new(), value(&["latency_ms", "total"]))],
+        );
+        push_prometheus_family(
+            &mut out,
+            &format!("{prefix}_latency_ms_last"),
+            "gauge",
+            &format!("Latency of the most recent stopgap.{operation} call in milliseconds."),
+            &[(String::new(), value(&["latency_ms", "last"]))],
+        );
+        push_prometheus_family(
+            &mut out,
+            &format!("{prefix}_latency_ms_max"),
+            "gauge",
+            &format!("Slowest stopgap.{operation} call in milliseconds."),
+            &[(String::new(), value(&["latency_ms", "max"]))],
+        );
+        for (key, label, help) in [
+            ("error_classes", "class", "failures by error class"),
+            ("route_counts", "route", "calls by route kind"),
+        ] {
+            let Some(counts) = group.get(key).and_then(Value::as_object) else {
+                continue;
+            };
+            let samples = counts
+                .iter()
+                .map(|(name, count)| {
+                    (format!("{{{label}=\"{name}\"}}"), count.as_u64().unwrap_or(0))
+                })
+                .collect::<Vec<_>>();
+            push_prometheus_family(
+                &mut out,
+                &format!("{prefix}_{key}_total"),
+                "counter",
+                &format!("stopgap.{operation} {help}."),
+                &samples,
+            );
+        }
+    }
+    out
+}
+
+fn push_prometheus_family(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: &[(String, u64)],
+) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{name}{labels} {value}");
+    }
+}
+
 fn increment_error_class(
     class: &str,
     permission: &AtomicU64,
@@ -458,6 +555,88 @@ mod tests {
         );
     }
 
+    #[test]
+    fn classify_operation_error_treats_deploy_conflicts_as_state() {
+        assert_eq!(
+            super::classify_operation_error(
+                "stopgap deploy conflict for env prod: expected active deployment 3 but found 4"
+            ),
+            "state"
+        );
+        assert_eq!(
+            super::classify_operation_error(
+                "cannot rename argument args of live pointer function live.hello to payload: other objects depend on it; drop them first or keep the argument name args"
+            ),
+            "state"
+        );
+    }
+
+    #[test]
+    fn classify_operation_error_keeps_validation_out_of_the_permission_class() {
+        assert_eq!(
+            super::classify_operation_error(
+                "stopgap activation_history limit must be positive; got 0"
+            ),
+            "validation"
+        );
+        assert_eq!(
+            super::classify_operation_error(
+                "TypeScript checked compile failed for api.users.get: Type 'string' is not assignable"
+            ),
+            "validation"
+        );
+        assert_eq!(
+            super::classify_operation_error("failed to evaluate permission check: out of memory"),
+            "unknown"
+        );
+        assert_eq!(
+            super::classify_operation_error(
+                "permission denied for stopgap deploy: session_user must be a member of role stopgap_deployer"
+            ),
+            "permission"
+        );
+    }
+
+    #[test]
+    fn render_prometheus_emits_typed_families_with_labels() {
+        let metrics = serde_json::json!({
+            "deploy": {
+                "calls": 7,
+                "errors": 2,
+                "latency_ms": {"total": 90, "last": 10, "max": 40},
+                "error_classes": {"permission": 1, "validation": 1}
+            },
+            "call_fn": {
+                "calls": 3,
+                "errors": 0,
+                "latency_ms": {"total": 3, "last": 1, "max": 1},
+                "route_counts": {"exact": 3, "legacy": 0},
+                "error_classes": {}
+            }
+        });
+
+        let rendered = super::render_prometheus(&metrics);
+        for line in [
+            "# HELP stopgap_deploy_calls_total stopgap.deploy calls in this backend.",
+            "# TYPE stopgap_deploy_calls_total counter",
+            "stopgap_deploy_calls_total 7",
+            "stopgap_deploy_errors_total 2",
+            "stopgap_deploy_latency_ms_total 90",
+            "# TYPE stopgap_deploy_latency_ms_max gauge",
+            "stopgap_deploy_latency_ms_max 40",
+            "stopgap_deploy_latency_ms_last 10",
+            "# TYPE stopgap_deploy_error_classes_total counter",
+            "stopgap_deploy_error_classes_total{class=\"permission\"} 1",
+            "stopgap_call_fn_route_counts_total{route=\"exact\"} 3",
+        ] {
+            assert!(
+                rendered.lines().any(|rendered| rendered == line),
+                "missing `{line}` in:\n{rendered}"
+            );
+        }
+        assert!(!rendered.contains("stopgap_rollback_"), "absent groups should be skipped");
+    }
+
     fn metric_u64(root: &Value, path: &[&str]) -> u64 {
         path.iter()
             .fold(Some(root), |current, segment| current.and_then(|value| value.get(*segment)))
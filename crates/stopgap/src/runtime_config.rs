@@ -26,6 +26,26 @@ pub(crate) fn resolve_live_schema() -> String {
     live.unwrap_or_else(|| "live_deployment".to_string())
 }
 
+pub(crate) const DEFAULT_LIVE_ARG_NAME: &str = "args";
+
+/// Parameter name of generated live pointer functions, from `stopgap.live_arg_name`. Deploy
+/// records it in the manifest; rollback and promote reuse the recorded name.
+pub(crate) fn resolve_live_arg_name() -> Result<String, String> {
+    let name =
+        Spi::get_one::<String>("SELECT NULLIF(current_setting('stopgap.live_arg_name', true), '')")
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| DEFAULT_LIVE_ARG_NAME.to_string());
+
+    if common::sql::is_simple_identifier(&name) {
+        Ok(name)
+    } else {
+        Err(format!(
+            "stopgap.live_arg_name must be a lowercase identifier of letters, digits, and underscores; got {name:?}"
+        ))
+    }
+}
+
 pub(crate) fn resolve_prune_enabled() -> bool {
     let raw = Spi::get_one::<String>(
         "SELECT COALESCE(current_setting('stopgap.prune', true), 'false')::text",
@@ -36,6 +56,16 @@ pub(crate) fn resolve_prune_enabled() -> bool {
     raw.as_deref().and_then(parse_bool_setting).unwrap_or(false)
 }
 
+pub(crate) fn resolve_lowercase_env_names() -> bool {
+    let raw = Spi::get_one::<String>(
+        "SELECT COALESCE(current_setting('stopgap.lowercase_env_names', true), 'true')::text",
+    )
+    .ok()
+    .flatten();
+
+    raw.as_deref().and_then(parse_bool_setting).unwrap_or(true)
+}
+
 pub(crate) fn resolve_default_env() -> String {
     let env = Spi::get_one::<String>(
         "SELECT COALESCE(current_setting('stopgap.default_env', true), 'prod')",
@@ -57,3 +87,49 @@ pub(crate) fn resolve_deploy_exports_json() -> Option<String> {
 pub(crate) fn parse_bool_setting(value: &str) -> Option<bool> {
     common::settings::parse_bool_setting(value)
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RecognizedSetting {
+    pub(crate) name: &'static str,
+    pub(crate) default_value: Option<&'static str>,
+    pub(crate) description: &'static str,
+}
+
+// Keep this registry in sync with the `resolve_*` readers above and the observability log level.
+pub(crate) const RECOGNIZED_SETTINGS: &[RecognizedSetting] = &[
+    RecognizedSetting {
+        name: "stopgap.live_schema",
+        default_value: Some("live_deployment"),
+        description: "Schema that receives generated live pointer functions on deploy.",
+    },
+    RecognizedSetting {
+        name: "stopgap.live_arg_name",
+        default_value: Some("args"),
+        description: "Parameter name of the jsonb argument on generated live pointer functions.",
+    },
+    RecognizedSetting {
+        name: "stopgap.prune",
+        default_value: Some("false"),
+        description: "Drop stale live functions that are absent from the new deployment.",
+    },
+    RecognizedSetting {
+        name: "stopgap.lowercase_env_names",
+        default_value: Some("true"),
+        description: "Lowercase env names when normalizing them at stopgap entry points.",
+    },
+    RecognizedSetting {
+        name: "stopgap.default_env",
+        default_value: Some("prod"),
+        description: "Environment used by stopgap.call_fn route resolution.",
+    },
+    RecognizedSetting {
+        name: "stopgap.deploy_exports",
+        default_value: None,
+        description: "JSON array of CLI-discovered export metadata for the current deploy.",
+    },
+    RecognizedSetting {
+        name: "stopgap.log_level",
+        default_value: Some("warn"),
+        description: "Log verbosity for deploy/rollback/diff events (off, error, warn, info, debug).",
+    },
+];
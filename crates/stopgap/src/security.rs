@@ -1,9 +1,18 @@
+use pgrx::datum::DatumWithOid;
 use pgrx::prelude::*;
+use serde_json::{Value, json};
 
 pub(crate) fn ensure_deploy_permissions(
     from_schema: &str,
     live_schema: &str,
 ) -> Result<(), String> {
+    if from_schema == live_schema {
+        return Err(format!(
+            "stopgap deploy source schema {} is the live schema; deploy from the schema holding authored functions, not deployed pointers",
+            from_schema
+        ));
+    }
+
     ensure_required_role_exists(crate::STOPGAP_OWNER_ROLE)?;
     ensure_required_role_exists(crate::STOPGAP_DEPLOYER_ROLE)?;
     ensure_required_role_exists(crate::APP_RUNTIME_ROLE)?;
@@ -14,6 +23,32 @@ pub(crate) fn ensure_deploy_permissions(
     Ok(())
 }
 
+/// Enforces `stopgap.environment.allowed_source_schemas` when the env has an allowlist set.
+pub(crate) fn ensure_source_schema_allowed(env: &str, from_schema: &str) -> Result<(), String> {
+    let Some(allowed) = load_allowed_source_schemas(env)? else {
+        return Ok(());
+    };
+
+    if allowed.iter().any(|schema| schema == from_schema) {
+        Ok(())
+    } else {
+        Err(format!(
+            "stopgap deploy source schema {} is not allowed for env {}; allowed source schemas: [{}]",
+            from_schema,
+            env,
+            allowed.join(", ")
+        ))
+    }
+}
+
+fn load_allowed_source_schemas(env: &str) -> Result<Option<Vec<String>>, String> {
+    Spi::get_one_with_args::<Vec<String>>(
+        "SELECT (SELECT allowed_source_schemas::text[] FROM stopgap.environment WHERE env = $1)",
+        &[env.into()],
+    )
+    .map_err(|e| format!("failed to load allowed source schemas for env {env}: {e}"))
+}
+
 fn ensure_supported_deploy_workflow_permissions(from_schema: &str) -> Result<(), String> {
     ensure_schema_exists(from_schema, "source")?;
 
@@ -45,14 +80,14 @@ fn ensure_supported_deploy_workflow_permissions(from_schema: &str) -> Result<(),
     }
 
     let can_execute_upsert = Spi::get_one::<bool>(
-        "SELECT has_function_privilege(session_user, 'plts.upsert_artifact(text, text, jsonb)', 'EXECUTE')",
+        "SELECT has_function_privilege(session_user, 'plts.upsert_artifact(text, text, jsonb, jsonb)', 'EXECUTE')",
     )
     .map_err(|e| format!("failed to check plts.upsert_artifact execute privilege: {e}"))?
     .unwrap_or(false);
 
     if !can_execute_upsert {
         return Err(
-            "permission denied for stopgap deploy: TS-first deploy requires EXECUTE on plts.upsert_artifact(text, text, jsonb)"
+            "permission denied for stopgap deploy: TS-first deploy requires EXECUTE on plts.upsert_artifact(text, text, jsonb, jsonb)"
                 .to_string(),
         );
     }
@@ -64,6 +99,16 @@ fn ensure_compatibility_bridge_guards(live_schema: &str) -> Result<(), String> {
     ensure_live_schema_is_stopgap_managed(live_schema)
 }
 
+/// Promotion reuses already compiled artifacts, so unlike deploy it needs no access to the
+/// source schema; only the target live schema is checked.
+pub(crate) fn ensure_promote_permissions(live_schema: &str) -> Result<(), String> {
+    ensure_required_role_exists(crate::STOPGAP_OWNER_ROLE)?;
+    ensure_required_role_exists(crate::STOPGAP_DEPLOYER_ROLE)?;
+    ensure_required_role_exists(crate::APP_RUNTIME_ROLE)?;
+
+    ensure_compatibility_bridge_guards(live_schema)
+}
+
 pub(crate) fn ensure_diff_permissions(from_schema: &str) -> Result<(), String> {
     ensure_required_role_exists(crate::STOPGAP_DEPLOYER_ROLE)?;
 
@@ -168,3 +213,97 @@ pub(crate) fn ensure_role_membership(required_role: &str, operation: &str) -> Re
         ))
     }
 }
+
+pub(crate) fn load_deploy_permission_report(
+    env: &str,
+    from_schema: &str,
+    live_schema: &str,
+) -> Result<Value, String> {
+    let mut checks = Vec::new();
+    let mut record = |name: &str, passed: bool, detail: String| {
+        checks.push(json!({ "check": name, "passed": passed, "detail": detail }));
+    };
+
+    for role_name in
+        [crate::STOPGAP_OWNER_ROLE, crate::STOPGAP_DEPLOYER_ROLE, crate::APP_RUNTIME_ROLE]
+    {
+        let exists = ensure_required_role_exists(role_name).is_ok();
+        record("role_exists", exists, format!("role {role_name} exists"));
+    }
+
+    let deployer_member = report_bool(
+        "SELECT COALESCE(bool_or(pg_has_role(session_user, oid, 'MEMBER')), false) FROM pg_roles WHERE rolname = $1",
+        &[crate::STOPGAP_DEPLOYER_ROLE.into()],
+    )?;
+    record(
+        "deployer_role_member",
+        deployer_member,
+        format!("session_user is a member of {}", crate::STOPGAP_DEPLOYER_ROLE),
+    );
+
+    record(
+        "source_is_not_live_schema",
+        from_schema != live_schema,
+        format!("source schema {from_schema} differs from live schema {live_schema}"),
+    );
+
+    let allowed_sources = load_allowed_source_schemas(env)?;
+    record(
+        "source_schema_allowed",
+        allowed_sources.as_ref().is_none_or(|allowed| allowed.iter().any(|s| s == from_schema)),
+        match &allowed_sources {
+            Some(allowed) => format!(
+                "source schema {from_schema} is in the env allowlist [{}]",
+                allowed.join(", ")
+            ),
+            None => format!("env {env} has no source schema allowlist"),
+        },
+    );
+
+    let source_exists = ensure_schema_exists(from_schema, "source").is_ok();
+    record("source_schema_exists", source_exists, format!("source schema {from_schema} exists"));
+
+    let source_usage = source_exists
+        && report_bool(
+            "SELECT has_schema_privilege(session_user, $1, 'USAGE')",
+            &[from_schema.into()],
+        )?;
+    record("source_schema_usage", source_usage, format!("USAGE on source schema {from_schema}"));
+
+    for signature in
+        ["plts.compile_ts_checked(text, jsonb)", "plts.upsert_artifact(text, text, jsonb, jsonb)"]
+    {
+        let can_execute = report_bool(
+            "SELECT COALESCE(has_function_privilege(session_user, to_regprocedure($1), 'EXECUTE'), false)",
+            &[signature.into()],
+        )?;
+        record("function_execute", can_execute, format!("EXECUTE on {signature}"));
+    }
+
+    let live_managed = ensure_live_schema_is_stopgap_managed(live_schema).is_ok();
+    record(
+        "live_schema_managed",
+        live_managed,
+        format!("live schema {live_schema} is absent or owned by {}", crate::STOPGAP_OWNER_ROLE),
+    );
+
+    let session_user = Spi::get_one::<String>("SELECT session_user::text")
+        .map_err(|e| format!("failed to read session_user: {e}"))?
+        .unwrap_or_default();
+    let ok = checks.iter().all(|check| check.get("passed") == Some(&Value::Bool(true)));
+
+    Ok(json!({
+        "env": env,
+        "session_user": session_user,
+        "from_schema": from_schema,
+        "live_schema": live_schema,
+        "ok": ok,
+        "checks": checks,
+    }))
+}
+
+fn report_bool(sql: &str, args: &[DatumWithOid<'_>]) -> Result<bool, String> {
+    Spi::get_one_with_args::<bool>(sql, args)
+        .map_err(|e| format!("failed to evaluate permission check: {e}"))
+        .map(|value| value.unwrap_or(false))
+}
@@ -36,6 +36,12 @@ extension_sql!(
         PRIMARY KEY (deployment_id, fn_schema, fn_name)
     );
 
+    ALTER TABLE stopgap.environment
+        ADD COLUMN IF NOT EXISTS baseline_deployment_id bigint;
+
+    ALTER TABLE stopgap.environment
+        ADD COLUMN IF NOT EXISTS allowed_source_schemas name[];
+
     ALTER TABLE stopgap.fn_version
         ADD COLUMN IF NOT EXISTS live_fn_name name;
 
@@ -59,6 +65,14 @@ extension_sql!(
         ON stopgap.fn_version (deployment_id, function_path)
         WHERE function_path IS NOT NULL;
 
+    CREATE TABLE IF NOT EXISTS stopgap.prune_exclusion (
+        env text NOT NULL,
+        fn_name name NOT NULL,
+        created_at timestamptz NOT NULL DEFAULT now(),
+        created_by name NOT NULL DEFAULT current_user,
+        PRIMARY KEY (env, fn_name)
+    );
+
     CREATE TABLE IF NOT EXISTS stopgap.activation_log (
         id bigserial PRIMARY KEY,
         env text NOT NULL,
@@ -142,24 +156,54 @@ extension_sql!(
     END;
     $$;
 
-    ALTER FUNCTION stopgap.deploy(text, text, text) SECURITY DEFINER;
-    ALTER FUNCTION stopgap.rollback(text, integer, bigint) SECURITY DEFINER;
-    ALTER FUNCTION stopgap.diff(text, text) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.deploy(text, text, text, jsonb, bigint) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.rollback(text, integer, bigint, text) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.promote(text, text, text) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.rollback_preview(text, integer, bigint, text) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.set_baseline(text, bigint) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.reap_stale_deployments(text, interval) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.prune_exclude(text, text) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.set_allowed_sources(text, text[]) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.diff(text, text, boolean, text, boolean) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.shadow_deploy(text, text) SECURITY DEFINER;
+    ALTER FUNCTION stopgap.import_env(jsonb) SECURITY DEFINER;
     ALTER FUNCTION stopgap.call_fn(text, jsonb) SECURITY INVOKER;
 
-    ALTER FUNCTION stopgap.deploy(text, text, text) SET search_path TO pg_catalog, pg_temp;
-    ALTER FUNCTION stopgap.rollback(text, integer, bigint) SET search_path TO pg_catalog, pg_temp;
-    ALTER FUNCTION stopgap.diff(text, text) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.deploy(text, text, text, jsonb, bigint) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.rollback(text, integer, bigint, text) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.promote(text, text, text) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.rollback_preview(text, integer, bigint, text) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.set_baseline(text, bigint) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.reap_stale_deployments(text, interval) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.prune_exclude(text, text) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.set_allowed_sources(text, text[]) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.diff(text, text, boolean, text, boolean) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.shadow_deploy(text, text) SET search_path TO pg_catalog, pg_temp;
+    ALTER FUNCTION stopgap.import_env(jsonb) SET search_path TO pg_catalog, pg_temp;
     ALTER FUNCTION stopgap.call_fn(text, jsonb) SET search_path TO pg_catalog, pg_temp;
 
-    REVOKE ALL ON FUNCTION stopgap.deploy(text, text, text) FROM PUBLIC;
-    REVOKE ALL ON FUNCTION stopgap.rollback(text, integer, bigint) FROM PUBLIC;
-    REVOKE ALL ON FUNCTION stopgap.diff(text, text) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.deploy(text, text, text, jsonb, bigint) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.rollback(text, integer, bigint, text) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.promote(text, text, text) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.rollback_preview(text, integer, bigint, text) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.set_baseline(text, bigint) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.reap_stale_deployments(text, interval) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.prune_exclude(text, text) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.set_allowed_sources(text, text[]) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.diff(text, text, boolean, text, boolean) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.shadow_deploy(text, text) FROM PUBLIC;
+    REVOKE ALL ON FUNCTION stopgap.import_env(jsonb) FROM PUBLIC;
     REVOKE ALL ON FUNCTION stopgap.call_fn(text, jsonb) FROM PUBLIC;
 
-    GRANT EXECUTE ON FUNCTION stopgap.deploy(text, text, text) TO stopgap_deployer;
-    GRANT EXECUTE ON FUNCTION stopgap.rollback(text, integer, bigint) TO stopgap_deployer;
-    GRANT EXECUTE ON FUNCTION stopgap.diff(text, text) TO stopgap_deployer;
+    GRANT EXECUTE ON FUNCTION stopgap.deploy(text, text, text, jsonb, bigint) TO stopgap_deployer;
+    GRANT EXECUTE ON FUNCTION stopgap.rollback(text, integer, bigint, text) TO stopgap_deployer;
+    GRANT EXECUTE ON FUNCTION stopgap.promote(text, text, text) TO stopgap_deployer;
+    GRANT EXECUTE ON FUNCTION stopgap.rollback_preview(text, integer, bigint, text) TO stopgap_deployer;
+    GRANT EXECUTE ON FUNCTION stopgap.set_baseline(text, bigint) TO stopgap_deployer;
+    GRANT EXECUTE ON FUNCTION stopgap.reap_stale_deployments(text, interval) TO stopgap_deployer;
+    GRANT EXECUTE ON FUNCTION stopgap.prune_exclude(text, text) TO stopgap_deployer;
+    GRANT EXECUTE ON FUNCTION stopgap.diff(text, text, boolean, text, boolean) TO stopgap_deployer;
+    GRANT EXECUTE ON FUNCTION stopgap.shadow_deploy(text, text) TO stopgap_deployer;
     GRANT EXECUTE ON FUNCTION stopgap.call_fn(text, jsonb) TO app_user;
     "#,
     name = "stopgap_security_finalize",
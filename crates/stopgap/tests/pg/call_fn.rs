@@ -674,3 +674,108 @@ fn test_call_fn_preserves_routed_guardrail_failure_detail() {
     )
     .expect("guardrail failures should preserve path-aware call_fn error context and detail");
 }
+
+#[pg_test]
+fn test_call_fn_keeps_the_routed_failure_sqlstate() {
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS call_fn_sqlstate_live CASCADE;
+        CREATE SCHEMA call_fn_sqlstate_live;
+
+        CREATE OR REPLACE FUNCTION call_fn_sqlstate_live.fail_impl(args jsonb)
+        RETURNS jsonb
+        LANGUAGE plpgsql
+        AS $$
+        BEGIN
+            RAISE EXCEPTION USING
+                ERRCODE = args->>'sqlstate',
+                MESSAGE = 'routed failure ' || (args->>'sqlstate');
+        END;
+        $$;
+
+        INSERT INTO stopgap.environment (env, live_schema, active_deployment_id)
+        VALUES ('call_fn_sqlstate_env', 'call_fn_sqlstate_live', NULL)
+        ON CONFLICT (env) DO UPDATE
+        SET live_schema = EXCLUDED.live_schema,
+            active_deployment_id = NULL,
+            updated_at = now();
+
+        INSERT INTO stopgap.deployment (id, env, label, source_schema, status, manifest)
+        VALUES (91008, 'call_fn_sqlstate_env', 'call-fn-sqlstate', 'call_fn_src', 'active', '{"functions":[]}'::jsonb)
+        ON CONFLICT (id) DO NOTHING;
+
+        UPDATE stopgap.environment
+        SET active_deployment_id = 91008,
+            updated_at = now()
+        WHERE env = 'call_fn_sqlstate_env';
+
+        INSERT INTO stopgap.fn_version (
+            deployment_id,
+            fn_name,
+            fn_schema,
+            live_fn_schema,
+            live_fn_name,
+            function_path,
+            module_path,
+            export_name,
+            kind,
+            artifact_hash
+        )
+        VALUES (
+            91008,
+            'fail_impl',
+            'call_fn_src',
+            'call_fn_sqlstate_live',
+            'fail_impl',
+            'api.users.fail',
+            'users',
+            'fail',
+            'mutation',
+            'sha256:sqlstate'
+        )
+        ON CONFLICT (deployment_id, fn_schema, fn_name) DO NOTHING;
+
+        SELECT set_config('stopgap.default_env', 'call_fn_sqlstate_env', true);
+        "#,
+    )
+    .expect("test should prepare routed sqlstate fixtures");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM stopgap.call_fn('api.users.fail', '{"sqlstate":"40001"}'::jsonb);
+            RAISE EXCEPTION 'expected call_fn serialization failure';
+        EXCEPTION
+            WHEN serialization_failure THEN
+                IF POSITION('execution failed for ''api.users.fail''' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END
+        $$;
+        DO $$
+        BEGIN
+            PERFORM stopgap.call_fn('api.users.fail', '{"sqlstate":"57014"}'::jsonb);
+            RAISE EXCEPTION 'expected call_fn query cancellation';
+        EXCEPTION
+            WHEN query_canceled THEN
+                IF POSITION('routed failure 57014' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END
+        $$;
+        DO $$
+        BEGIN
+            PERFORM stopgap.call_fn('api.users.fail', '{"sqlstate":"U0001"}'::jsonb);
+            RAISE EXCEPTION 'expected call_fn application failure';
+        EXCEPTION
+            WHEN SQLSTATE 'U0001' THEN
+                IF POSITION('routed failure U0001' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END
+        $$;
+        "#,
+    )
+    .expect("routed failures should keep the SQLSTATE the routed function raised");
+}
@@ -51,6 +51,109 @@ fn test_deploy_materializes_pointer_import_map_for_live_functions() {
     );
 }
 
+#[pg_test]
+fn test_deploy_maps_function_path_imports_across_source_schemas() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS sg_it_src_path_imports CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_live_path_imports CASCADE;
+        CREATE SCHEMA sg_it_src_path_imports;
+        SELECT set_config('stopgap.live_schema', 'sg_it_live_path_imports', true);
+        SELECT set_config(
+            'stopgap.deploy_exports',
+            '[
+                {
+                    "module_path": "billing",
+                    "export_name": "charge",
+                    "function_path": "api.billing.charge",
+                    "kind": "mutation"
+                },
+                {
+                    "module_path": "orders",
+                    "export_name": "checkout",
+                    "function_path": "api.orders.checkout",
+                    "kind": "mutation"
+                }
+            ]',
+            true
+        );
+        "#,
+    )
+    .expect("function-path import setup should succeed");
+
+    create_deployable_function(
+        "sg_it_src_path_imports",
+        "charge",
+        "BEGIN RETURN jsonb_build_object('charged', true); END",
+    );
+    // The checkout handler imports the billing handler as `@stopgap/api.billing.charge`.
+    create_deployable_function(
+        "sg_it_src_path_imports",
+        "checkout",
+        "BEGIN RETURN jsonb_build_object('checkout', 'uses @stopgap/api.billing.charge'); END",
+    );
+
+    let deployment_id = Spi::get_one::<i64>(
+        "SELECT stopgap.deploy('it_env_path_imports', 'sg_it_src_path_imports', 'v1')",
+    )
+    .expect("deploy should succeed")
+    .expect("deploy should return deployment id");
+
+    let checkout_pointer = pointer_body_json("sg_it_live_path_imports", "checkout");
+    let charge_hash = fn_version_artifact_hash(deployment_id, "charge");
+    let expected_charge = format!("plts+artifact:{charge_hash}");
+    assert_eq!(
+        checkout_pointer
+            .get("import_map")
+            .and_then(|v| v.get("@stopgap/api.billing.charge"))
+            .and_then(Value::as_str),
+        Some(expected_charge.as_str())
+    );
+}
+
+#[pg_test]
+fn test_deploy_rejects_inline_import_map_with_unknown_artifact() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_src_bad_import_map CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_live_bad_import_map CASCADE;
+        CREATE SCHEMA sg_it_src_bad_import_map;
+        SELECT set_config('stopgap.live_schema', 'sg_it_live_bad_import_map', true);
+        ",
+    )
+    .expect("bad import-map setup should succeed");
+
+    create_deployable_function(
+        "sg_it_src_bad_import_map",
+        "alpha",
+        r#"
+        -- plts-import-map: {"@app/math":"sha256:0000000000000000000000000000000000000000000000000000000000000000"}
+        BEGIN RETURN args; END
+        "#,
+    );
+
+    Spi::run(
+        "
+        DO $$
+        BEGIN
+            PERFORM stopgap.deploy('it_env_bad_import_map', 'sg_it_src_bad_import_map', NULL);
+            RAISE EXCEPTION 'expected unknown import map artifact deploy failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('import map entry `@app/math` points at unknown artifact' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("deploy should fail naming the unknown import map artifact");
+}
+
 fn pointer_body_json(live_schema: &str, fn_name: &str) -> Value {
     let pointer = Spi::get_one_with_args::<String>(
         "
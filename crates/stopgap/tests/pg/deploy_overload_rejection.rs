@@ -41,3 +41,34 @@ fn test_deploy_rejects_overloaded_plts_fns() {
     )
     .expect("deploy should fail with overloaded-function error");
 }
+
+#[pg_test]
+fn test_deploy_rejects_live_schema_as_source() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_self_live CASCADE;
+        CREATE SCHEMA sg_it_self_live;
+        SELECT set_config('stopgap.live_schema', 'sg_it_self_live', true);
+        ",
+    )
+    .expect("self-deploy setup should succeed");
+
+    Spi::run(
+        "
+        DO $$
+        BEGIN
+            PERFORM stopgap.deploy('it_env_self_live', 'sg_it_self_live', NULL);
+            RAISE EXCEPTION 'expected self-referential deploy failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('is the live schema' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("deploy should reject the live schema as its source");
+}
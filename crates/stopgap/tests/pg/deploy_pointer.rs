@@ -65,6 +65,199 @@ fn test_deploy_updates_active_pointer_and_live_pointer() {
     assert!(artifact_exists, "deployed artifact hash should exist in plts.artifact");
 }
 
+#[pg_test]
+fn test_deploy_records_limits_in_manifest_and_pointer() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_limits_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_limits_live CASCADE;
+        CREATE SCHEMA sg_limits_src;
+        SELECT set_config('stopgap.live_schema', 'sg_limits_live', true);
+        ",
+    )
+    .expect("limits test setup should succeed");
+
+    create_deployable_function(
+        "sg_limits_src",
+        "heavy",
+        "BEGIN RETURN jsonb_build_object('ok', true); END",
+    );
+
+    let deployment_id = Spi::get_one::<i64>(
+        r#"SELECT stopgap.deploy('it_env_limits', 'sg_limits_src', 'limits', '{"max_runtime_ms": 5000, "max_query_rows": 1000}'::jsonb)"#,
+    )
+    .expect("deploy with limits should succeed")
+    .expect("deploy with limits should return deployment id");
+
+    let manifest_limits = Spi::get_one_with_args::<JsonB>(
+        "SELECT manifest->'limits' FROM stopgap.deployment WHERE id = $1",
+        &[deployment_id.into()],
+    )
+    .expect("manifest limits lookup should succeed")
+    .expect("manifest should record limits");
+    assert_eq!(manifest_limits.0.get("max_runtime_ms").and_then(Value::as_u64), Some(5000));
+
+    let pointer_limits = Spi::get_one::<JsonB>(
+        "
+        SELECT (p.prosrc::jsonb)->'limits'
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE n.nspname = 'sg_limits_live' AND p.proname = 'heavy'
+        ",
+    )
+    .expect("pointer limits lookup should succeed")
+    .expect("live pointer should carry limits");
+    assert_eq!(pointer_limits.0.get("max_query_rows").and_then(Value::as_u64), Some(1000));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM stopgap.deploy('it_env_limits', 'sg_limits_src', 'bad', '{"max_cpu": 1}'::jsonb);
+            RAISE EXCEPTION 'expected unknown deploy limit rejection';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('unknown stopgap deploy limit' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END
+        $$;
+        "#,
+    )
+    .expect("deploy should reject unknown limit keys");
+}
+
+#[pg_test]
+fn test_deploy_records_compile_ms_per_manifest_function() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_compile_ms_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_compile_ms_live CASCADE;
+        CREATE SCHEMA sg_compile_ms_src;
+        SELECT set_config('stopgap.live_schema', 'sg_compile_ms_live', true);
+        ",
+    )
+    .expect("compile_ms test setup should succeed");
+
+    create_deployable_function(
+        "sg_compile_ms_src",
+        "alpha",
+        "BEGIN RETURN jsonb_build_object('fn', 'alpha'); END",
+    );
+    create_deployable_function(
+        "sg_compile_ms_src",
+        "beta",
+        "BEGIN RETURN jsonb_build_object('fn', 'beta'); END",
+    );
+
+    let deployment_id = Spi::get_one::<i64>(
+        "SELECT stopgap.deploy('it_env_compile_ms', 'sg_compile_ms_src', 'timed')",
+    )
+    .expect("deploy should succeed")
+    .expect("deploy should return deployment id");
+
+    let manifest = Spi::get_one_with_args::<JsonB>(
+        "SELECT manifest FROM stopgap.deployment WHERE id = $1",
+        &[deployment_id.into()],
+    )
+    .expect("manifest lookup should succeed")
+    .expect("deployment should have a manifest")
+    .0;
+
+    let functions =
+        manifest.get("functions").and_then(Value::as_array).cloned().unwrap_or_default();
+    assert_eq!(functions.len(), 2);
+    for function in &functions {
+        assert!(
+            function.get("compile_ms").is_some_and(Value::is_u64),
+            "manifest function entry should carry numeric compile_ms: {function}"
+        );
+    }
+    let by_path = manifest.get("functions_by_path").and_then(Value::as_object).cloned();
+    assert!(
+        by_path.is_some_and(|entries| entries.values().all(|entry| entry["compile_ms"].is_u64())),
+        "functions_by_path entries should carry compile_ms"
+    );
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_compile_ms_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_compile_ms_live CASCADE;
+        ",
+    )
+    .expect("compile_ms test teardown should succeed");
+}
+
+#[pg_test]
+fn test_deploy_stores_detected_handler_kind_in_fn_version() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_kind_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_kind_live CASCADE;
+        CREATE SCHEMA sg_kind_src;
+        SELECT set_config('stopgap.live_schema', 'sg_kind_live', true);
+        ",
+    )
+    .expect("handler kind test setup should succeed");
+
+    create_deployable_function(
+        "sg_kind_src",
+        "list_items",
+        "/* mock:kind-query */ BEGIN RETURN jsonb_build_object('fn', 'list_items'); END",
+    );
+    create_deployable_function(
+        "sg_kind_src",
+        "save_item",
+        "BEGIN RETURN jsonb_build_object('fn', 'save_item'); END",
+    );
+
+    let deployment_id =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_kind', 'sg_kind_src', 'kinds')")
+            .expect("deploy should succeed")
+            .expect("deploy should return deployment id");
+
+    let kinds = Spi::get_one_with_args::<JsonB>(
+        "
+        SELECT jsonb_object_agg(fn_name, kind)
+        FROM stopgap.fn_version
+        WHERE deployment_id = $1
+        ",
+        &[deployment_id.into()],
+    )
+    .expect("fn_version kind lookup should succeed")
+    .expect("deployment should have fn_version rows")
+    .0;
+    assert_eq!(kinds.get("list_items").and_then(Value::as_str), Some("query"));
+    assert_eq!(kinds.get("save_item").and_then(Value::as_str), Some("mutation"));
+
+    let manifest_kind = Spi::get_one_with_args::<String>(
+        "
+        SELECT item->>'kind'
+        FROM stopgap.deployment d,
+             jsonb_array_elements(d.manifest->'functions') AS item
+        WHERE d.id = $1
+          AND item->>'fn_name' = 'list_items'
+        ",
+        &[deployment_id.into()],
+    )
+    .expect("manifest kind lookup should succeed");
+    assert_eq!(manifest_kind.as_deref(), Some("query"));
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_kind_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_kind_live CASCADE;
+        ",
+    )
+    .expect("handler kind test teardown should succeed");
+}
+
 #[pg_test]
 fn test_deploy_uses_cli_export_metadata_for_pointer() {
     ensure_mock_plts_runtime();
@@ -144,6 +337,22 @@ fn test_deploy_uses_cli_export_metadata_for_pointer() {
         Some("admin/users"),
         "deployment manifest should include function-path keyed metadata"
     );
+    assert!(
+        manifest
+            .0
+            .pointer("/engine/pg_version")
+            .and_then(|value| value.as_str())
+            .is_some_and(|version| version.starts_with("PostgreSQL")),
+        "deployment manifest should record the postgres version"
+    );
+    assert!(
+        manifest
+            .0
+            .pointer("/engine/stopgap_version")
+            .and_then(|value| value.as_str())
+            .is_some_and(|version| !version.is_empty()),
+        "deployment manifest should record the stopgap extension version"
+    );
 
     let pointer = Spi::get_one::<String>(
         "
@@ -376,3 +585,205 @@ fn test_deploy_rejects_duplicate_function_path_metadata_entries() {
     )
     .expect("deploy should reject duplicate function-path metadata entries");
 }
+
+#[pg_test]
+fn test_deploy_expect_active_id_rejects_stale_base() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_expect_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_expect_live CASCADE;
+        CREATE SCHEMA sg_it_expect_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_expect_live', true);
+        ",
+    )
+    .expect("integration setup should succeed");
+
+    create_deployable_function(
+        "sg_it_expect_src",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('version', 'v1'); END",
+    );
+    let first_deployment =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_expect', 'sg_it_expect_src', 'v1')")
+            .expect("first deploy should succeed")
+            .expect("first deploy should return deployment id");
+    let second_deployment = Spi::get_one_with_args::<i64>(
+        "SELECT stopgap.deploy('it_env_expect', 'sg_it_expect_src', 'v2', expect_active_id => $1)",
+        &[first_deployment.into()],
+    )
+    .expect("deploy with matching expect_active_id should succeed")
+    .expect("deploy should return deployment id");
+
+    let stale_deploy = format!(
+        "
+        DO $$
+        BEGIN
+            PERFORM stopgap.deploy(
+                'it_env_expect',
+                'sg_it_expect_src',
+                'v3',
+                expect_active_id => {first_deployment}
+            );
+            RAISE EXCEPTION 'expected stale expect_active_id deploy failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION(
+                    'expected active deployment {first_deployment} but found {second_deployment}'
+                    IN SQLERRM
+                ) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "
+    );
+    Spi::run(&stale_deploy).expect("stale expect_active_id deploy should report the conflict");
+
+    let (active, deployments) = Spi::get_two::<i64, i64>(
+        "
+        SELECT e.active_deployment_id,
+               (SELECT count(*) FROM stopgap.deployment d WHERE d.env = e.env)
+        FROM stopgap.environment e
+        WHERE e.env = 'it_env_expect'
+        ",
+    )
+    .expect("environment lookup should succeed");
+    assert_eq!(active, Some(second_deployment));
+    assert_eq!(deployments, Some(2), "rejected deploy should not record a deployment");
+}
+
+#[pg_test]
+fn test_deploy_names_live_argument_from_live_arg_name_setting() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_argname_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_argname_live CASCADE;
+        CREATE SCHEMA sg_it_argname_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_argname_live', true);
+        ",
+    )
+    .expect("integration setup should succeed");
+
+    create_deployable_function(
+        "sg_it_argname_src",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('version', 'v1'); END",
+    );
+    let live_arg_name = || {
+        Spi::get_one::<String>(
+            "SELECT proargnames[1] FROM pg_proc WHERE oid = 'sg_it_argname_live.hello(jsonb)'::regprocedure",
+        )
+        .expect("live argument name lookup should succeed")
+        .expect("live pointer function should have a named argument")
+    };
+
+    Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_argname', 'sg_it_argname_src', 'v1')")
+        .expect("default deploy should succeed");
+    assert_eq!(live_arg_name(), "args");
+
+    Spi::run("SELECT set_config('stopgap.live_arg_name', 'payload', true)")
+        .expect("live_arg_name override should succeed");
+    Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_argname', 'sg_it_argname_src', 'v2')")
+        .expect("deploy with custom live_arg_name should succeed");
+    assert_eq!(live_arg_name(), "payload");
+
+    Spi::run(
+        "
+        SELECT set_config('stopgap.live_arg_name', 'Payload; DROP', true);
+        DO $$
+        BEGIN
+            PERFORM stopgap.deploy('it_env_argname', 'sg_it_argname_src', 'v3');
+            RAISE EXCEPTION 'expected invalid live_arg_name deploy failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('stopgap.live_arg_name must be a lowercase identifier' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("invalid live_arg_name should be rejected");
+}
+
+#[pg_test]
+fn test_rollback_and_promote_reapply_the_recorded_live_arg_name() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_argkeep_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_argkeep_live CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_argkeep_prod CASCADE;
+        CREATE SCHEMA sg_it_argkeep_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_argkeep_live', true);
+        SELECT set_config('stopgap.live_arg_name', 'payload', true);
+        ",
+    )
+    .expect("integration setup should succeed");
+
+    create_deployable_function(
+        "sg_it_argkeep_src",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('version', 'v1'); END",
+    );
+    let live_arg_name = |schema: &str| {
+        Spi::get_one::<String>(&format!(
+            "SELECT proargnames[1] FROM pg_proc WHERE oid = '{schema}.hello(jsonb)'::regprocedure"
+        ))
+        .expect("live argument name lookup should succeed")
+        .expect("live pointer function should have a named argument")
+    };
+
+    let first =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_argkeep', 'sg_it_argkeep_src', 'v1')")
+            .expect("deploy with payload should succeed")
+            .expect("deploy should return an id");
+    let recorded = Spi::get_one_with_args::<String>(
+        "SELECT manifest->>'live_arg_name' FROM stopgap.deployment WHERE id = $1",
+        &[first.into()],
+    )
+    .expect("manifest lookup should succeed");
+    assert_eq!(recorded.as_deref(), Some("payload"));
+
+    Spi::run("SELECT set_config('stopgap.live_arg_name', '', true)")
+        .expect("live_arg_name reset should succeed");
+    Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_argkeep', 'sg_it_argkeep_src', 'v2')")
+        .expect("deploy with the default name should succeed");
+    assert_eq!(live_arg_name("sg_it_argkeep_live"), "args");
+
+    Spi::get_one::<i64>("SELECT stopgap.rollback('it_env_argkeep', 1, NULL)")
+        .expect("rollback should succeed");
+    assert_eq!(live_arg_name("sg_it_argkeep_live"), "payload");
+
+    Spi::run("SELECT set_config('stopgap.live_schema', 'sg_it_argkeep_prod', true)")
+        .expect("target live schema override should succeed");
+    Spi::get_one::<i64>("SELECT stopgap.promote('it_env_argkeep', 'it_env_argkeep_prod')")
+        .expect("promote should succeed");
+    assert_eq!(live_arg_name("sg_it_argkeep_prod"), "payload");
+
+    Spi::run(
+        "
+        CREATE VIEW sg_it_argkeep_prod.hello_view AS
+        SELECT sg_it_argkeep_prod.hello('{}'::jsonb) AS result;
+        SELECT set_config('stopgap.live_arg_name', 'body', true);
+        DO $$
+        BEGIN
+            PERFORM stopgap.deploy('it_env_argkeep_prod', 'sg_it_argkeep_src', 'renamed');
+            RAISE EXCEPTION 'expected dependent view to block the argument rename';
+        EXCEPTION
+            WHEN object_not_in_prerequisite_state THEN
+                IF POSITION('cannot rename argument payload of live pointer function sg_it_argkeep_prod.hello to body: other objects depend on it' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("a dependent view should block renaming the live argument with a clear error");
+    assert_eq!(live_arg_name("sg_it_argkeep_prod"), "payload");
+}
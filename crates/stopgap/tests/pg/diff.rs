@@ -0,0 +1,218 @@
+#[pg_test]
+fn test_diff_only_filters_functions_but_keeps_full_summary() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_diff_only_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_diff_only_live CASCADE;
+        CREATE SCHEMA sg_diff_only_src;
+        SELECT set_config('stopgap.live_schema', 'sg_diff_only_live', true);
+        ",
+    )
+    .expect("diff only test setup should succeed");
+
+    create_deployable_function(
+        "sg_diff_only_src",
+        "alpha",
+        "BEGIN RETURN jsonb_build_object('fn', 'alpha'); END",
+    );
+    create_deployable_function(
+        "sg_diff_only_src",
+        "beta",
+        "BEGIN RETURN jsonb_build_object('fn', 'beta'); END",
+    );
+    create_deployable_function(
+        "sg_diff_only_src",
+        "delta",
+        "BEGIN RETURN jsonb_build_object('fn', 'delta'); END",
+    );
+    Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_diff_only', 'sg_diff_only_src', 'base')")
+        .expect("initial deploy should succeed");
+
+    create_deployable_function(
+        "sg_diff_only_src",
+        "beta",
+        "BEGIN RETURN jsonb_build_object('fn', 'beta', 'version', 2); END",
+    );
+    create_deployable_function(
+        "sg_diff_only_src",
+        "gamma",
+        "BEGIN RETURN jsonb_build_object('fn', 'gamma'); END",
+    );
+    Spi::run("DROP FUNCTION sg_diff_only_src.delta(jsonb);")
+        .expect("dropping a source function should succeed");
+
+    let diff = Spi::get_one::<JsonB>(
+        "SELECT stopgap.diff('it_env_diff_only', 'sg_diff_only_src', only => 'changed')",
+    )
+    .expect("diff should succeed")
+    .expect("diff should return jsonb")
+    .0;
+
+    let functions = diff.get("functions").and_then(Value::as_array).cloned().unwrap_or_default();
+    assert_eq!(functions.len(), 1, "only changed rows should be returned: {functions:?}");
+    assert_eq!(functions[0].get("fn_name").and_then(Value::as_str), Some("beta"));
+    assert_eq!(functions[0].get("change").and_then(Value::as_str), Some("changed"));
+    assert_eq!(diff.get("only").and_then(Value::as_str), Some("changed"));
+
+    let summary = diff.get("summary").cloned().unwrap_or(Value::Null);
+    assert_eq!(summary.get("added").and_then(Value::as_u64), Some(1));
+    assert_eq!(summary.get("changed").and_then(Value::as_u64), Some(1));
+    assert_eq!(summary.get("removed").and_then(Value::as_u64), Some(1));
+    assert_eq!(summary.get("unchanged").and_then(Value::as_u64), Some(1));
+
+    Spi::run(
+        "
+        DO $$
+        BEGIN
+            PERFORM stopgap.diff('it_env_diff_only', 'sg_diff_only_src', only => 'unchanged');
+            RAISE EXCEPTION 'expected unknown diff filter to fail';
+        EXCEPTION
+            WHEN others THEN
+                IF POSITION('only must be one of added, changed, removed' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        DROP SCHEMA IF EXISTS sg_diff_only_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_diff_only_live CASCADE;
+        ",
+    )
+    .expect("unknown diff filter should be rejected");
+}
+
+#[pg_test]
+fn test_diff_with_source_includes_source_text_for_changed_functions() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_diff_source_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_diff_source_live CASCADE;
+        CREATE SCHEMA sg_diff_source_src;
+        SELECT set_config('stopgap.live_schema', 'sg_diff_source_live', true);
+        ",
+    )
+    .expect("diff source test setup should succeed");
+
+    create_deployable_function(
+        "sg_diff_source_src",
+        "alpha",
+        "BEGIN RETURN jsonb_build_object('fn', 'alpha', 'version', 1); END",
+    );
+    create_deployable_function(
+        "sg_diff_source_src",
+        "beta",
+        "BEGIN RETURN jsonb_build_object('fn', 'beta'); END",
+    );
+    Spi::get_one::<i64>(
+        "SELECT stopgap.deploy('it_env_diff_source', 'sg_diff_source_src', 'base')",
+    )
+    .expect("initial deploy should succeed");
+
+    create_deployable_function(
+        "sg_diff_source_src",
+        "alpha",
+        "BEGIN RETURN jsonb_build_object('fn', 'alpha', 'version', 2); END",
+    );
+
+    let plain = Spi::get_one::<JsonB>(
+        "SELECT stopgap.diff('it_env_diff_source', 'sg_diff_source_src', only => 'changed')",
+    )
+    .expect("diff should succeed")
+    .expect("diff should return jsonb")
+    .0;
+    let plain_alpha = plain.pointer("/functions/0").cloned().unwrap_or(Value::Null);
+    assert!(plain_alpha.get("active_source_ts").is_none(), "source is opt-in: {plain_alpha:?}");
+
+    let diff = Spi::get_one::<JsonB>(
+        "SELECT stopgap.diff('it_env_diff_source', 'sg_diff_source_src', with_source => true)",
+    )
+    .expect("diff with source should succeed")
+    .expect("diff with source should return jsonb")
+    .0;
+    assert_eq!(diff.get("with_source").and_then(Value::as_bool), Some(true));
+
+    let functions = diff.get("functions").and_then(Value::as_array).cloned().unwrap_or_default();
+    let alpha = functions
+        .iter()
+        .find(|function| function.get("fn_name").and_then(Value::as_str) == Some("alpha"))
+        .expect("changed function should be listed");
+    assert_eq!(alpha.get("change").and_then(Value::as_str), Some("changed"));
+    let active_source = alpha.get("active_source_ts").and_then(Value::as_str).unwrap_or_default();
+    let candidate_source =
+        alpha.get("candidate_source_ts").and_then(Value::as_str).unwrap_or_default();
+    assert!(active_source.contains("'version', 1"), "active source: {active_source}");
+    assert!(candidate_source.contains("'version', 2"), "candidate source: {candidate_source}");
+
+    let beta = functions
+        .iter()
+        .find(|function| function.get("fn_name").and_then(Value::as_str) == Some("beta"))
+        .expect("unchanged function should be listed");
+    assert!(beta.get("active_source_ts").is_none(), "only changed rows carry source: {beta:?}");
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_diff_source_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_diff_source_live CASCADE;
+        ",
+    )
+    .expect("diff source test teardown should succeed");
+}
+
+#[pg_test]
+fn test_diff_after_cli_deploy_without_source_changes_reports_no_changes() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        r#"
+        DROP SCHEMA IF EXISTS sg_diff_exports_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_diff_exports_live CASCADE;
+        CREATE SCHEMA sg_diff_exports_src;
+        SELECT set_config('stopgap.live_schema', 'sg_diff_exports_live', true);
+        SELECT set_config(
+            'stopgap.deploy_exports',
+            '[
+                {
+                    "module_path": "users",
+                    "export_name": "alpha",
+                    "function_path": "api.users.alpha",
+                    "kind": "query"
+                }
+            ]',
+            true
+        );
+        "#,
+    )
+    .expect("diff exports test setup should succeed");
+
+    create_deployable_function(
+        "sg_diff_exports_src",
+        "alpha",
+        "BEGIN RETURN jsonb_build_object('fn', 'alpha'); END",
+    );
+    Spi::get_one::<i64>(
+        "SELECT stopgap.deploy('it_env_diff_exports', 'sg_diff_exports_src', 'v1')",
+    )
+    .expect("deploy with export metadata should succeed");
+
+    // `stopgap deploy --watch` polls with the same `stopgap.deploy_exports`, so the candidate
+    // must compile with the same per-export compiler options as the deploy did.
+    let diff =
+        Spi::get_one::<JsonB>("SELECT stopgap.diff('it_env_diff_exports', 'sg_diff_exports_src')")
+            .expect("diff should succeed")
+            .expect("diff should return jsonb")
+            .0;
+    let summary = diff.get("summary").cloned().unwrap_or(Value::Null);
+    assert_eq!(summary.get("changed").and_then(Value::as_u64), Some(0), "diff: {diff}");
+    assert_eq!(summary.get("unchanged").and_then(Value::as_u64), Some(1), "diff: {diff}");
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_diff_exports_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_diff_exports_live CASCADE;
+        ",
+    )
+    .expect("diff exports test teardown should succeed");
+}
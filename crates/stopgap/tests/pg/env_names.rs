@@ -0,0 +1,153 @@
+#[pg_test]
+fn test_deploy_unifies_differently_spaced_env_names() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_env_name_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_env_name_live CASCADE;
+        CREATE SCHEMA sg_env_name_src;
+        SELECT set_config('stopgap.live_schema', 'sg_env_name_live', true);
+        ",
+    )
+    .expect("integration setup should succeed");
+
+    create_deployable_function(
+        "sg_env_name_src",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('version', 'v1'); END",
+    );
+
+    let first_deployment =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_name ', 'sg_env_name_src', 'v1')")
+            .expect("first deploy should succeed")
+            .expect("first deploy should return deployment id");
+    let second_deployment =
+        Spi::get_one::<i64>("SELECT stopgap.deploy(' IT_ENV_NAME', 'sg_env_name_src', 'v2')")
+            .expect("second deploy should succeed")
+            .expect("second deploy should return deployment id");
+
+    let env_rows = Spi::get_one::<i64>(
+        "SELECT count(*) FROM stopgap.environment WHERE lower(trim(env)) = 'it_env_name'",
+    )
+    .expect("environment lookup should succeed")
+    .expect("environment count should return a row");
+    assert_eq!(env_rows, 1, "differently spaced env names should share one environment");
+
+    let deployment_envs = Spi::get_one_with_args::<i64>(
+        "SELECT count(DISTINCT env) FROM stopgap.deployment WHERE id IN ($1, $2)",
+        &[first_deployment.into(), second_deployment.into()],
+    )
+    .expect("deployment lookup should succeed")
+    .expect("deployment count should return a row");
+    assert_eq!(deployment_envs, 1, "both deployments should be recorded under one env");
+
+    let status_env = Spi::get_one::<JsonB>("SELECT stopgap.status('It_Env_Name')")
+        .expect("status lookup should succeed")
+        .and_then(|status| status.0.get("env").and_then(Value::as_str).map(str::to_string));
+    assert_eq!(status_env.as_deref(), Some("it_env_name"));
+}
+
+#[pg_test]
+fn test_deploy_rejects_blank_env_name() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_env_name_blank_src CASCADE;
+        CREATE SCHEMA sg_env_name_blank_src;
+        DO $$
+        BEGIN
+            PERFORM stopgap.deploy('   ', 'sg_env_name_blank_src', NULL);
+            RAISE EXCEPTION 'expected blank env name deploy failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('invalid stopgap env name' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("deploy should fail with invalid env name error");
+}
+
+#[pg_test]
+fn test_legacy_env_names_stay_reachable_and_block_colliding_normalized_names() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_env_legacy_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_env_legacy_live CASCADE;
+        CREATE SCHEMA sg_env_legacy_src;
+        SELECT set_config('stopgap.live_schema', 'sg_env_legacy_live', true);
+        INSERT INTO stopgap.environment (env, live_schema)
+        VALUES ('IT_LEGACY_ENV', 'sg_env_legacy_live');
+        ",
+    )
+    .expect("legacy env setup should succeed");
+
+    create_deployable_function(
+        "sg_env_legacy_src",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('version', 'v1'); END",
+    );
+
+    let deployment_id =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('IT_LEGACY_ENV', 'sg_env_legacy_src', 'v1')")
+            .expect("deploy to the exact legacy env name should succeed")
+            .expect("deploy should return deployment id");
+    let deployment_env = Spi::get_one_with_args::<String>(
+        "SELECT env FROM stopgap.deployment WHERE id = $1",
+        &[deployment_id.into()],
+    )
+    .expect("deployment lookup should succeed");
+    assert_eq!(deployment_env.as_deref(), Some("IT_LEGACY_ENV"));
+
+    Spi::run(
+        "
+        DO $$
+        BEGIN
+            PERFORM stopgap.deploy('it_legacy_env', 'sg_env_legacy_src', 'v2');
+            RAISE EXCEPTION 'expected colliding env name deploy failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('matches existing env ''IT_LEGACY_ENV'' only after normalization' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("deploy to a name that only matches a legacy env after normalization should fail");
+}
+
+#[pg_test]
+fn test_env_names_keep_case_when_lowercasing_is_off() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_env_cased_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_env_cased_live CASCADE;
+        CREATE SCHEMA sg_env_cased_src;
+        SELECT set_config('stopgap.live_schema', 'sg_env_cased_live', true);
+        SELECT set_config('stopgap.lowercase_env_names', 'off', true);
+        ",
+    )
+    .expect("cased env setup should succeed");
+
+    create_deployable_function(
+        "sg_env_cased_src",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('version', 'v1'); END",
+    );
+    Spi::get_one::<i64>("SELECT stopgap.deploy(' It_Cased_Env ', 'sg_env_cased_src', 'v1')")
+        .expect("deploy with lowercasing off should succeed");
+
+    let env_rows =
+        Spi::get_one::<i64>("SELECT count(*) FROM stopgap.environment WHERE env = 'It_Cased_Env'")
+            .expect("environment lookup should succeed");
+    assert_eq!(env_rows, Some(1), "the env should be recorded trimmed but with its case kept");
+}
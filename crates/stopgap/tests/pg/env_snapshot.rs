@@ -0,0 +1,167 @@
+fn clear_snapshot_env() {
+    Spi::run(
+        "
+        DELETE FROM stopgap.activation_log WHERE env = 'it_env_snapshot';
+        DELETE FROM stopgap.prune_exclusion WHERE env = 'it_env_snapshot';
+        DELETE FROM stopgap.fn_version
+        WHERE deployment_id IN (SELECT id FROM stopgap.deployment WHERE env = 'it_env_snapshot');
+        DELETE FROM stopgap.deployment WHERE env = 'it_env_snapshot';
+        DELETE FROM stopgap.environment WHERE env = 'it_env_snapshot';
+        DROP SCHEMA IF EXISTS sg_it_snapshot_live CASCADE;
+        ",
+    )
+    .expect("snapshot env cleanup should succeed");
+}
+
+#[pg_test]
+fn test_import_env_restores_exported_deployments_and_live_pointers() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_snapshot_src CASCADE;
+        CREATE SCHEMA sg_it_snapshot_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_snapshot_live', true);
+        ",
+    )
+    .expect("snapshot setup should succeed");
+    clear_snapshot_env();
+
+    create_deployable_function(
+        "sg_it_snapshot_src",
+        "greet",
+        "BEGIN RETURN jsonb_build_object('version', 'v1'); END",
+    );
+    Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_snapshot', 'sg_it_snapshot_src', 'v1')")
+        .expect("first deploy should succeed");
+    create_deployable_function(
+        "sg_it_snapshot_src",
+        "greet",
+        "BEGIN RETURN jsonb_build_object('version', 'v2'); END",
+    );
+    let second_deployment =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_snapshot', 'sg_it_snapshot_src', 'v2')")
+            .expect("second deploy should succeed")
+            .expect("second deploy should return deployment id");
+    let active_hash = fn_version_artifact_hash(second_deployment, "greet");
+    Spi::run("SELECT stopgap.prune_exclude('it_env_snapshot', 'legacy_report')")
+        .expect("prune_exclude should succeed");
+
+    let snapshot = Spi::get_one::<JsonB>("SELECT stopgap.export_env('it_env_snapshot')")
+        .expect("export_env should succeed")
+        .expect("export_env should return a snapshot")
+        .0;
+    assert_eq!(snapshot["snapshot_version"], serde_json::json!(1));
+    assert_eq!(snapshot["deployments"].as_array().map(Vec::len), Some(2));
+    assert_eq!(snapshot["activations"].as_array().map(Vec::len), Some(2));
+    assert_eq!(snapshot["artifacts"].as_array().map(Vec::len), Some(2));
+    assert_eq!(snapshot["prune_exclusions"][0]["fn_name"], serde_json::json!("legacy_report"));
+
+    clear_snapshot_env();
+    let summary = Spi::get_one_with_args::<JsonB>(
+        "SELECT stopgap.import_env($1)",
+        &[JsonB(snapshot.clone()).into()],
+    )
+    .expect("import_env should succeed")
+    .expect("import_env should return a summary")
+    .0;
+    assert_eq!(summary["deployments"], serde_json::json!(2));
+    assert_eq!(summary["fn_versions"], serde_json::json!(2));
+    assert_eq!(summary["activations"], serde_json::json!(2));
+    assert_eq!(summary["prune_exclusions"], serde_json::json!(1));
+    let restored_exclusions = Spi::get_one::<i64>(
+        "
+        SELECT count(*) FROM stopgap.prune_exclusion
+        WHERE env = 'it_env_snapshot' AND fn_name = 'legacy_report'
+        ",
+    )
+    .expect("restored prune exclusion lookup should succeed");
+    assert_eq!(restored_exclusions, Some(1));
+
+    let restored = Spi::get_one::<JsonB>(
+        "
+        SELECT jsonb_build_object(
+            'id', d.id,
+            'label', d.label,
+            'status', d.status,
+            'activations', (
+                SELECT count(*) FROM stopgap.activation_log l WHERE l.env = e.env
+            )
+        )
+        FROM stopgap.environment e
+        JOIN stopgap.deployment d ON d.id = e.active_deployment_id
+        WHERE e.env = 'it_env_snapshot'
+        ",
+    )
+    .expect("restored env lookup should succeed")
+    .expect("restored env should have an active deployment");
+    assert_eq!(summary["active_deployment_id"], restored.0["id"]);
+    assert_eq!(restored.0["label"], serde_json::json!("v2"));
+    assert_eq!(restored.0["status"], serde_json::json!("active"));
+    assert_eq!(restored.0["activations"], serde_json::json!(2));
+    assert_eq!(pointer_artifact_hash("sg_it_snapshot_live", "greet"), active_hash);
+
+    clear_snapshot_env();
+    Spi::run_with_args(
+        "
+        INSERT INTO stopgap.environment (env, live_schema)
+        VALUES ('it_env_snapshot_other', 'sg_it_snapshot_live');
+        CREATE TEMP TABLE sg_it_snapshot_claimed AS SELECT $1::jsonb AS snapshot;
+        ",
+        &[JsonB(snapshot.clone()).into()],
+    )
+    .expect("conflicting env should be staged");
+    Spi::run(
+        "
+        DO $$
+        BEGIN
+            PERFORM stopgap.import_env((SELECT snapshot FROM sg_it_snapshot_claimed));
+            RAISE EXCEPTION 'expected claimed live schema import failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('already the live schema of env it_env_snapshot_other' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        DROP TABLE sg_it_snapshot_claimed;
+        DELETE FROM stopgap.environment WHERE env = 'it_env_snapshot_other';
+        ",
+    )
+    .expect("import into a live schema owned by another env should fail");
+
+    let mut tampered = snapshot;
+    for artifact in tampered["artifacts"].as_array_mut().expect("snapshot should list artifacts") {
+        artifact["source_ts"] = serde_json::json!("BEGIN RETURN '{}'::jsonb; END");
+    }
+    Spi::run_with_args(
+        "
+        CREATE TEMP TABLE sg_it_snapshot_tampered AS SELECT $1::jsonb AS snapshot;
+        ",
+        &[JsonB(tampered).into()],
+    )
+    .expect("tampered snapshot should be staged");
+    Spi::run(
+        "
+        DO $$
+        BEGIN
+            PERFORM stopgap.import_env((SELECT snapshot FROM sg_it_snapshot_tampered));
+            RAISE EXCEPTION 'expected tampered snapshot import failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('failed integrity check' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        DROP TABLE sg_it_snapshot_tampered;
+        ",
+    )
+    .expect("tampered snapshot import should fail its integrity check");
+
+    let env_rows = Spi::get_one::<i64>(
+        "SELECT count(*) FROM stopgap.environment WHERE env = 'it_env_snapshot'",
+    )
+    .expect("environment lookup should succeed");
+    assert_eq!(env_rows, Some(0), "failed import should not leave an environment behind");
+}
@@ -0,0 +1,61 @@
+#[pg_test]
+fn test_env_stats_reports_live_surface_size() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_env_stats_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_env_stats_live CASCADE;
+        CREATE SCHEMA sg_env_stats_src;
+        SELECT set_config('stopgap.live_schema', 'sg_env_stats_live', true);
+        ",
+    )
+    .expect("integration setup should succeed");
+
+    create_deployable_function(
+        "sg_env_stats_src",
+        "first",
+        "BEGIN RETURN jsonb_build_object('fn', 'first'); END",
+    );
+    create_deployable_function(
+        "sg_env_stats_src",
+        "second",
+        "BEGIN RETURN jsonb_build_object('fn', 'second'); END",
+    );
+
+    Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_stats', 'sg_env_stats_src', 'v1')")
+        .expect("first deploy should succeed");
+    let active_deployment =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_stats', 'sg_env_stats_src', 'v2')")
+            .expect("second deploy should succeed")
+            .expect("second deploy should return deployment id");
+
+    let stats = Spi::get_one::<JsonB>("SELECT stopgap.env_stats('it_env_stats')")
+        .expect("env_stats lookup should succeed")
+        .expect("env_stats should return jsonb for a deployed env")
+        .0;
+
+    assert_eq!(stats.get("live_schema").and_then(Value::as_str), Some("sg_env_stats_live"));
+    assert_eq!(stats.get("active_deployment_id").and_then(Value::as_i64), Some(active_deployment));
+    assert_eq!(stats.get("deployment_count").and_then(Value::as_i64), Some(2));
+    assert_eq!(stats.get("active_function_count").and_then(Value::as_i64), Some(2));
+
+    let expected_bytes = Spi::get_one_with_args::<i64>(
+        "
+        SELECT sum(octet_length(a.source_ts) + octet_length(a.compiled_js))::bigint
+        FROM plts.artifact a
+        WHERE a.artifact_hash IN (
+            SELECT artifact_hash FROM stopgap.fn_version WHERE deployment_id = $1
+        )
+        ",
+        &[active_deployment.into()],
+    )
+    .expect("artifact size lookup should succeed")
+    .expect("artifact size should return a row");
+    assert!(expected_bytes > 0);
+    assert_eq!(stats.get("active_artifact_bytes").and_then(Value::as_i64), Some(expected_bytes));
+
+    let missing = Spi::get_one::<JsonB>("SELECT stopgap.env_stats('it_env_stats_missing')")
+        .expect("env_stats lookup for unknown env should succeed");
+    assert!(missing.is_none());
+}
@@ -0,0 +1,53 @@
+#[pg_test]
+fn test_operation_errors_raise_classified_sqlstates() {
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM stopgap.activation_history('it_env_codes', 0);
+            RAISE EXCEPTION 'expected activation_history limit rejection';
+        EXCEPTION
+            WHEN invalid_parameter_value THEN
+                IF POSITION('limit must be positive' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("a non-positive limit should raise invalid_parameter_value");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM stopgap.promote('it_env_codes', 'it_env_codes');
+            RAISE EXCEPTION 'expected same-env promote rejection';
+        EXCEPTION
+            WHEN invalid_parameter_value THEN
+                IF POSITION('source and target env are both it_env_codes' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("promoting an env onto itself should raise invalid_parameter_value");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM stopgap.set_allowed_sources('it_env_codes', ARRAY['']);
+            RAISE EXCEPTION 'expected empty schema name rejection';
+        EXCEPTION
+            WHEN invalid_parameter_value THEN
+                IF POSITION('schema names must be non-empty' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("an empty allowed source should raise invalid_parameter_value");
+}
@@ -49,6 +49,39 @@ fn ensure_mock_plts_runtime() {
             SELECT source_ts, '[]'::jsonb, 'mock-fingerprint'
         $$;
 
+        CREATE OR REPLACE FUNCTION plts.compile_ts(
+            source_ts text,
+            compiler_opts jsonb DEFAULT '{}'::jsonb
+        )
+        RETURNS TABLE(compiled_js text, diagnostics jsonb, compiler_fingerprint text)
+        LANGUAGE sql
+        AS $$
+            SELECT
+                source_ts,
+                CASE
+                    WHEN POSITION('mock:ts-error' IN source_ts) > 0 THEN
+                        jsonb_build_array(jsonb_build_object(
+                            'severity', 'error',
+                            'phase', 'transpile',
+                            'message', 'mock transpile error'
+                        ))
+                    ELSE '[]'::jsonb
+                END,
+                'mock-fingerprint'
+        $$;
+
+        CREATE OR REPLACE FUNCTION plts.describe(fn_oid oid)
+        RETURNS jsonb
+        LANGUAGE sql
+        AS $$
+            SELECT jsonb_build_object('kind', 'query', 'args_schema', NULL)
+            FROM pg_proc p
+            JOIN plts.artifact a
+              ON POSITION(a.artifact_hash IN p.prosrc) > 0
+            WHERE p.oid = fn_oid
+              AND POSITION('mock:kind-query' IN a.compiled_js) > 0
+        $$;
+
         CREATE OR REPLACE FUNCTION plts.typecheck_ts(source_ts text, compiler_opts jsonb DEFAULT '{}'::jsonb)
         RETURNS jsonb
         LANGUAGE sql
@@ -56,10 +89,25 @@ fn ensure_mock_plts_runtime() {
             SELECT '[]'::jsonb
         $$;
 
+        CREATE OR REPLACE FUNCTION plts.get_artifact(artifact_hash text)
+        RETURNS jsonb
+        LANGUAGE sql
+        AS $$
+            SELECT jsonb_build_object(
+                'source_ts', a.source_ts,
+                'compiled_js', a.compiled_js,
+                'compiler_opts', a.compiler_opts,
+                'diagnostics', NULL
+            )
+            FROM plts.artifact a
+            WHERE a.artifact_hash = $1
+        $$;
+
         CREATE OR REPLACE FUNCTION plts.upsert_artifact(
             source_ts text,
             compiled_js text,
-            compiler_opts jsonb DEFAULT '{}'::jsonb
+            compiler_opts jsonb DEFAULT '{}'::jsonb,
+            diagnostics jsonb DEFAULT NULL
         )
         RETURNS text
         LANGUAGE plpgsql
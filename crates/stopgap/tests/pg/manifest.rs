@@ -0,0 +1,67 @@
+#[pg_test]
+fn test_manifest_returns_any_deployment_manifest() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_manifest_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_manifest_live CASCADE;
+        CREATE SCHEMA sg_manifest_src;
+        SELECT set_config('stopgap.live_schema', 'sg_manifest_live', true);
+        ",
+    )
+    .expect("integration setup should succeed");
+
+    create_deployable_function(
+        "sg_manifest_src",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('version', 'v1'); END",
+    );
+    let first_deployment =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_manifest', 'sg_manifest_src', 'v1')")
+            .expect("first deploy should succeed")
+            .expect("first deploy should return deployment id");
+    Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_manifest', 'sg_manifest_src', 'v2')")
+        .expect("second deploy should succeed");
+
+    let manifest =
+        Spi::get_one_with_args::<JsonB>("SELECT stopgap.manifest($1)", &[first_deployment.into()])
+            .expect("manifest lookup should succeed")
+            .expect("manifest should return jsonb")
+            .0;
+    let stored = Spi::get_one_with_args::<JsonB>(
+        "SELECT manifest FROM stopgap.deployment WHERE id = $1",
+        &[first_deployment.into()],
+    )
+    .expect("stored manifest lookup should succeed")
+    .expect("deployment should have a manifest")
+    .0;
+    assert_eq!(manifest, stored);
+    assert_eq!(manifest.get("label").and_then(Value::as_str), Some("v1"));
+    assert_eq!(manifest.get("manifest_version").and_then(Value::as_u64), Some(1));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM stopgap.manifest(-1);
+            RAISE EXCEPTION 'expected missing deployment rejection';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('stopgap deployment -1 does not exist' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("manifest should reject unknown deployments");
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_manifest_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_manifest_live CASCADE;
+        ",
+    )
+    .expect("integration teardown should succeed");
+}
@@ -232,3 +232,85 @@ fn test_metrics_call_fn_route_counts_and_error_classes() {
         "validation error class count should increase"
     );
 }
+
+#[pg_test]
+fn test_metrics_counts_failed_deploy_as_call_and_error() {
+    ensure_mock_plts_runtime();
+
+    let deploy_counter = |key: &str| {
+        Spi::get_one_with_args::<i64>(
+            "SELECT (stopgap.metrics() -> 'deploy' ->> $1)::bigint",
+            &[key.into()],
+        )
+        .expect("metrics query should succeed")
+        .unwrap_or_else(|| panic!("deploy.{key} should be present"))
+    };
+    let before_calls = deploy_counter("calls");
+    let before_errors = deploy_counter("errors");
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_metrics_failed_src CASCADE;
+        CREATE SCHEMA sg_metrics_failed_src;
+        DO $$
+        BEGIN
+            PERFORM stopgap.deploy('   ', 'sg_metrics_failed_src', NULL);
+            RAISE EXCEPTION 'expected blank env name deploy failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('invalid stopgap env name' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("deploy should fail with invalid env name error");
+
+    assert!(deploy_counter("calls") > before_calls, "failed deploy should count as a call");
+    assert!(deploy_counter("errors") > before_errors, "failed deploy should count as an error");
+}
+
+#[pg_test]
+fn test_metrics_classifies_permission_denied_deploy() {
+    ensure_mock_plts_runtime();
+
+    let before = Spi::get_one::<i64>(
+        "SELECT (stopgap.metrics() #>> '{deploy,error_classes,permission}')::bigint",
+    )
+    .expect("metrics query should succeed")
+    .expect("deploy.error_classes.permission should be present");
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_metrics_denied_src CASCADE;
+        CREATE SCHEMA sg_metrics_denied_src;
+        DROP ROLE IF EXISTS sg_metrics_outsider;
+        CREATE ROLE sg_metrics_outsider NOLOGIN;
+        GRANT USAGE ON SCHEMA stopgap TO sg_metrics_outsider;
+        GRANT EXECUTE ON FUNCTION stopgap.deploy(text, text, text, jsonb, bigint)
+            TO sg_metrics_outsider;
+        DO $$
+        BEGIN
+            EXECUTE 'SET SESSION AUTHORIZATION sg_metrics_outsider';
+            PERFORM stopgap.deploy('it_env_metrics_denied', 'sg_metrics_denied_src', NULL);
+            RAISE EXCEPTION 'expected deployer membership failure';
+        EXCEPTION
+            WHEN OTHERS THEN
+                EXECUTE 'RESET SESSION AUTHORIZATION';
+                IF POSITION('session_user must be a member of role stopgap_deployer' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("deploy by a non-deployer should be denied");
+
+    let after = Spi::get_one::<i64>(
+        "SELECT (stopgap.metrics() #>> '{deploy,error_classes,permission}')::bigint",
+    )
+    .expect("metrics query should succeed")
+    .expect("deploy.error_classes.permission should be present");
+    assert!(after > before, "denied deploy should count as a permission error");
+}
@@ -7,7 +7,20 @@ include!("call_fn.rs");
 include!("deploy_overload_rejection.rs");
 include!("deploy_import_map.rs");
 include!("deploy_pointer.rs");
+include!("diff.rs");
+include!("env_names.rs");
+include!("env_snapshot.rs");
+include!("env_stats.rs");
+include!("error_codes.rs");
+include!("manifest.rs");
 include!("metrics.rs");
+include!("orphans.rs");
+include!("promote.rs");
+include!("prune_exclusion.rs");
 include!("rollback.rs");
 include!("security_acl.rs");
 include!("security_definer.rs");
+include!("settings.rs");
+include!("shadow_deploy.rs");
+include!("stale_deployments.rs");
+include!("validate.rs");
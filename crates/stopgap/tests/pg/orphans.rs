@@ -0,0 +1,71 @@
+#[pg_test]
+fn test_orphans_reports_dangling_rows_by_category() {
+    ensure_mock_plts_runtime();
+
+    let clean = Spi::get_one::<JsonB>("SELECT stopgap.orphans()")
+        .expect("orphans query should succeed")
+        .expect("orphans report should exist");
+    assert_eq!(clean.0.get("ok"), Some(&Value::Bool(true)));
+
+    Spi::run(
+        "
+        SET LOCAL session_replication_role = replica;
+        INSERT INTO stopgap.deployment (id, env, label, source_schema, status, manifest)
+        VALUES (93001, 'sg_orphans_gone', 'lost', 'sg_orphans_src', 'active', '{}'::jsonb);
+        INSERT INTO stopgap.fn_version (
+            deployment_id, fn_name, fn_schema, live_fn_schema, live_fn_name, kind, artifact_hash
+        )
+        VALUES
+            (93001, 'kept', 'sg_orphans_src', 'sg_orphans_live', 'kept', 'mutation', 'sha256:sg_orphans_missing'),
+            (93002, 'stray', 'sg_orphans_src', 'sg_orphans_live', 'stray', 'mutation', 'sha256:sg_orphans_missing');
+        RESET session_replication_role;
+        ",
+    )
+    .expect("orphan setup should succeed");
+
+    let report = Spi::get_one::<JsonB>("SELECT stopgap.orphans()")
+        .expect("orphans query should succeed")
+        .expect("orphans report should exist")
+        .0;
+    assert_eq!(report.get("ok"), Some(&Value::Bool(false)));
+
+    let entries = |key: &str| {
+        report
+            .get(key)
+            .and_then(Value::as_array)
+            .cloned()
+            .expect("report category should be an array")
+    };
+
+    let stray_versions = entries("fn_versions_without_deployment");
+    assert!(stray_versions.iter().any(|entry| {
+        entry.get("deployment_id") == Some(&Value::from(93002))
+            && entry.get("fn_name") == Some(&Value::from("stray"))
+    }));
+    assert!(
+        !stray_versions.iter().any(|entry| entry.get("deployment_id") == Some(&Value::from(93001)))
+    );
+
+    let homeless = entries("deployments_without_environment");
+    assert!(homeless.iter().any(|entry| {
+        entry.get("id") == Some(&Value::from(93001))
+            && entry.get("env") == Some(&Value::from("sg_orphans_gone"))
+    }));
+
+    let missing = entries("missing_artifacts");
+    let missing_entry = missing
+        .iter()
+        .find(|entry| entry.get("artifact_hash") == Some(&Value::from("sha256:sg_orphans_missing")))
+        .expect("missing artifact should be reported");
+    assert_eq!(missing_entry.get("deployment_ids"), Some(&serde_json::json!([93001, 93002])));
+
+    Spi::run(
+        "
+        SET LOCAL session_replication_role = replica;
+        DELETE FROM stopgap.fn_version WHERE deployment_id IN (93001, 93002);
+        DELETE FROM stopgap.deployment WHERE id = 93001;
+        RESET session_replication_role;
+        ",
+    )
+    .expect("orphan teardown should succeed");
+}
@@ -0,0 +1,91 @@
+#[pg_test]
+fn test_promote_copies_active_deployment_artifacts_between_envs() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_promote_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_promote_stage_live CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_promote_prod_live CASCADE;
+        CREATE SCHEMA sg_it_promote_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_promote_stage_live', true);
+        ",
+    )
+    .expect("promote setup should succeed");
+
+    create_deployable_function(
+        "sg_it_promote_src",
+        "greet",
+        "BEGIN RETURN jsonb_build_object('hello', 'staging'); END",
+    );
+    let staging_deployment = Spi::get_one::<i64>(
+        "SELECT stopgap.deploy('it_env_promote_stage', 'sg_it_promote_src', 'rc1')",
+    )
+    .expect("staging deploy should succeed")
+    .expect("staging deploy should return id");
+
+    // Source changes after the staging deploy must not leak into the promoted deployment.
+    create_deployable_function(
+        "sg_it_promote_src",
+        "greet",
+        "BEGIN RETURN jsonb_build_object('hello', 'edited'); END",
+    );
+    Spi::run("SELECT set_config('stopgap.live_schema', 'sg_it_promote_prod_live', true);")
+        .expect("prod live schema setting should apply");
+
+    let promoted = Spi::get_one::<i64>(
+        "SELECT stopgap.promote('it_env_promote_stage', 'it_env_promote_prod')",
+    )
+    .expect("promote should succeed")
+    .expect("promote should return deployment id");
+    assert_ne!(promoted, staging_deployment);
+
+    assert_eq!(
+        pointer_artifact_hash("sg_it_promote_prod_live", "greet"),
+        pointer_artifact_hash("sg_it_promote_stage_live", "greet"),
+        "promoted live pointer should reference the staging artifact"
+    );
+    assert_eq!(
+        fn_version_artifact_hash(promoted, "greet"),
+        fn_version_artifact_hash(staging_deployment, "greet")
+    );
+
+    let active = Spi::get_one::<i64>(
+        "SELECT active_deployment_id FROM stopgap.environment WHERE env = 'it_env_promote_prod'",
+    )
+    .expect("prod active deployment lookup should succeed");
+    assert_eq!(active, Some(promoted));
+
+    let promoted_row = Spi::get_one_with_args::<JsonB>(
+        "
+        SELECT jsonb_build_object(
+            'env', env,
+            'label', label,
+            'status', status,
+            'promoted_from', manifest->'promoted_from',
+            'live_schema', manifest->>'live_schema'
+        )
+        FROM stopgap.deployment
+        WHERE id = $1
+        ",
+        &[promoted.into()],
+    )
+    .expect("promoted deployment lookup should succeed")
+    .expect("promoted deployment should exist");
+    assert_eq!(
+        promoted_row.0,
+        serde_json::json!({
+            "env": "it_env_promote_prod",
+            "label": "rc1",
+            "status": "active",
+            "promoted_from": {"env": "it_env_promote_stage", "deployment_id": staging_deployment},
+            "live_schema": "sg_it_promote_prod_live"
+        })
+    );
+
+    let staging_active = Spi::get_one::<i64>(
+        "SELECT active_deployment_id FROM stopgap.environment WHERE env = 'it_env_promote_stage'",
+    )
+    .expect("staging active deployment lookup should succeed");
+    assert_eq!(staging_active, Some(staging_deployment), "promote should not touch the source env");
+}
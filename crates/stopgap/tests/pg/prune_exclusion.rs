@@ -0,0 +1,77 @@
+#[pg_test]
+fn test_prune_keeps_excluded_live_functions() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_prune_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_prune_live CASCADE;
+        CREATE SCHEMA sg_prune_src;
+        SELECT set_config('stopgap.live_schema', 'sg_prune_live', true);
+        ",
+    )
+    .expect("prune exclusion setup should succeed");
+
+    for fn_name in ["kept", "external_fn", "stale_fn"] {
+        create_deployable_function(
+            "sg_prune_src",
+            fn_name,
+            "BEGIN RETURN jsonb_build_object('ok', true); END",
+        );
+    }
+
+    Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_prune', 'sg_prune_src', 'v1')")
+        .expect("first deploy should succeed")
+        .expect("first deploy should return deployment id");
+
+    let added = Spi::get_one::<bool>("SELECT stopgap.prune_exclude('it_env_prune', 'external_fn')")
+        .expect("prune_exclude should succeed");
+    assert_eq!(added, Some(true));
+    let added_again =
+        Spi::get_one::<bool>("SELECT stopgap.prune_exclude('it_env_prune', 'external_fn')")
+            .expect("repeated prune_exclude should succeed");
+    assert_eq!(added_again, Some(false));
+
+    Spi::run(
+        "
+        DROP FUNCTION sg_prune_src.external_fn(jsonb);
+        DROP FUNCTION sg_prune_src.stale_fn(jsonb);
+        SELECT set_config('stopgap.prune', 'on', true);
+        ",
+    )
+    .expect("prune exclusion source update should succeed");
+
+    let second_deployment =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_prune', 'sg_prune_src', 'v2')")
+            .expect("pruning deploy should succeed")
+            .expect("pruning deploy should return deployment id");
+
+    let live_names = Spi::get_one::<Vec<String>>(
+        "
+        SELECT array_agg(p.proname::text ORDER BY p.proname)
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE n.nspname = 'sg_prune_live'
+        ",
+    )
+    .expect("live function lookup should succeed")
+    .expect("live schema should contain functions");
+    assert_eq!(live_names, vec!["external_fn".to_string(), "kept".to_string()]);
+
+    let prune = Spi::get_one_with_args::<JsonB>(
+        "SELECT manifest->'prune' FROM stopgap.deployment WHERE id = $1",
+        &[second_deployment.into()],
+    )
+    .expect("prune report lookup should succeed")
+    .expect("deployment manifest should carry a prune report");
+    assert_eq!(prune.0.get("dropped"), Some(&serde_json::json!(["stale_fn"])));
+    assert_eq!(prune.0.get("excluded"), Some(&serde_json::json!(["external_fn"])));
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_prune_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_prune_live CASCADE;
+        ",
+    )
+    .expect("prune exclusion teardown should succeed");
+}
@@ -186,3 +186,363 @@ fn test_rollback_rematerializes_multiple_exports_from_same_module() {
 
     assert!(deploy_one < deploy_two, "second deploy id should be newer");
 }
+
+#[pg_test]
+fn test_rollback_preview_reports_changes_without_mutating_state() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_rb_preview_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_rb_preview_live CASCADE;
+        CREATE SCHEMA sg_it_rb_preview_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_rb_preview_live', true);
+        ",
+    )
+    .expect("rollback preview setup should succeed");
+
+    create_deployable_function(
+        "sg_it_rb_preview_src",
+        "stable",
+        "BEGIN RETURN jsonb_build_object('version', 'one'); END",
+    );
+    create_deployable_function(
+        "sg_it_rb_preview_src",
+        "stepper",
+        "BEGIN RETURN jsonb_build_object('version', 'one'); END",
+    );
+    let deploy_one = Spi::get_one::<i64>(
+        "SELECT stopgap.deploy('it_env_rb_preview', 'sg_it_rb_preview_src', 'one')",
+    )
+    .expect("deploy one should succeed")
+    .expect("deploy one should return id");
+
+    create_deployable_function(
+        "sg_it_rb_preview_src",
+        "stepper",
+        "BEGIN RETURN jsonb_build_object('version', 'two'); END",
+    );
+    create_deployable_function(
+        "sg_it_rb_preview_src",
+        "extra",
+        "BEGIN RETURN jsonb_build_object('version', 'two'); END",
+    );
+    let deploy_two = Spi::get_one::<i64>(
+        "SELECT stopgap.deploy('it_env_rb_preview', 'sg_it_rb_preview_src', 'two')",
+    )
+    .expect("deploy two should succeed")
+    .expect("deploy two should return id");
+
+    let preview =
+        Spi::get_one::<JsonB>("SELECT stopgap.rollback_preview('it_env_rb_preview', 1, NULL)")
+            .expect("rollback preview should succeed")
+            .expect("rollback preview should return jsonb");
+
+    assert_eq!(preview.0.get("from").and_then(Value::as_i64), Some(deploy_two));
+    assert_eq!(preview.0.get("to").and_then(Value::as_i64), Some(deploy_one));
+    let summary = preview.0.get("summary").expect("preview should include summary");
+    assert_eq!(summary.get("added").and_then(Value::as_u64), Some(0));
+    assert_eq!(summary.get("changed").and_then(Value::as_u64), Some(1));
+    assert_eq!(summary.get("removed").and_then(Value::as_u64), Some(1));
+    assert_eq!(summary.get("unchanged").and_then(Value::as_u64), Some(1));
+
+    let active_deployment = Spi::get_one::<i64>(
+        "SELECT active_deployment_id FROM stopgap.environment WHERE env = 'it_env_rb_preview'",
+    )
+    .expect("active deployment lookup should succeed")
+    .expect("active deployment should be present");
+    assert_eq!(active_deployment, deploy_two, "rollback preview must not change active deployment");
+    assert_eq!(
+        pointer_artifact_hash("sg_it_rb_preview_live", "stepper"),
+        fn_version_artifact_hash(deploy_two, "stepper"),
+        "rollback preview must not rematerialize live pointers"
+    );
+}
+
+#[pg_test]
+fn test_bare_rollback_reverts_to_baseline_deployment() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_baseline_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_baseline_live CASCADE;
+        CREATE SCHEMA sg_it_baseline_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_baseline_live', true);
+        ",
+    )
+    .expect("baseline rollback setup should succeed");
+
+    let mut deployments = Vec::new();
+    for version in ["one", "two", "three"] {
+        create_deployable_function(
+            "sg_it_baseline_src",
+            "stepper",
+            &format!("BEGIN RETURN jsonb_build_object('version', '{version}'); END"),
+        );
+        let deployment_id = Spi::get_one_with_args::<i64>(
+            "SELECT stopgap.deploy('it_env_baseline', 'sg_it_baseline_src', $1)",
+            &[version.into()],
+        )
+        .expect("deploy should succeed")
+        .expect("deploy should return id");
+        deployments.push(deployment_id);
+    }
+    let (deploy_one, deploy_three) = (deployments[0], deployments[2]);
+
+    let baseline = Spi::get_one_with_args::<i64>(
+        "SELECT stopgap.set_baseline('it_env_baseline', $1)",
+        &[deploy_one.into()],
+    )
+    .expect("set_baseline should succeed")
+    .expect("set_baseline should return the baseline id");
+    assert_eq!(baseline, deploy_one);
+
+    let status_baseline = Spi::get_one::<JsonB>("SELECT stopgap.status('it_env_baseline')")
+        .expect("status lookup should succeed")
+        .and_then(|status| status.0.get("baseline_deployment_id").and_then(Value::as_i64));
+    assert_eq!(status_baseline, Some(deploy_one));
+
+    let rolled_back_to = Spi::get_one::<i64>("SELECT stopgap.rollback('it_env_baseline')")
+        .expect("bare rollback should succeed")
+        .expect("bare rollback should return target deployment id");
+    assert_eq!(rolled_back_to, deploy_one, "bare rollback should target the baseline");
+
+    let live_pointer_hash = pointer_artifact_hash("sg_it_baseline_live", "stepper");
+    assert_eq!(live_pointer_hash, fn_version_artifact_hash(deploy_one, "stepper"));
+
+    let deploy_three_status = Spi::get_one_with_args::<String>(
+        "SELECT status FROM stopgap.deployment WHERE id = $1",
+        &[deploy_three.into()],
+    )
+    .expect("status lookup should succeed")
+    .expect("deployment should exist");
+    assert_eq!(deploy_three_status, "rolled_back");
+
+    Spi::run(
+        "
+        DO $$
+        BEGIN
+            PERFORM stopgap.rollback('it_env_baseline');
+            RAISE EXCEPTION 'expected bare rollback onto the active baseline to fail';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('is already active' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("bare rollback should refuse when the baseline is already active");
+}
+
+#[pg_test]
+fn test_rollback_rejects_target_with_missing_artifacts() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_rb_missing_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_rb_missing_live CASCADE;
+        CREATE SCHEMA sg_it_rb_missing_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_rb_missing_live', true);
+        ",
+    )
+    .expect("missing artifact rollback setup should succeed");
+
+    create_deployable_function(
+        "sg_it_rb_missing_src",
+        "stepper",
+        "BEGIN RETURN jsonb_build_object('version', 'one'); END",
+    );
+    let deploy_one = Spi::get_one::<i64>(
+        "SELECT stopgap.deploy('it_env_rb_missing', 'sg_it_rb_missing_src', 'one')",
+    )
+    .expect("deploy one should succeed")
+    .expect("deploy one should return id");
+
+    create_deployable_function(
+        "sg_it_rb_missing_src",
+        "stepper",
+        "BEGIN RETURN jsonb_build_object('version', 'two'); END",
+    );
+    let deploy_two = Spi::get_one::<i64>(
+        "SELECT stopgap.deploy('it_env_rb_missing', 'sg_it_rb_missing_src', 'two')",
+    )
+    .expect("deploy two should succeed")
+    .expect("deploy two should return id");
+
+    let missing_hash = fn_version_artifact_hash(deploy_one, "stepper");
+    Spi::run_with_args(
+        "DELETE FROM plts.artifact WHERE artifact_hash = $1",
+        &[missing_hash.as_str().into()],
+    )
+    .expect("artifact removal should succeed");
+
+    Spi::run(&format!(
+        "
+        DO $$
+        BEGIN
+            PERFORM stopgap.rollback('it_env_rb_missing', 1, NULL);
+            RAISE EXCEPTION 'expected rollback onto missing artifacts to fail';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('references missing plts artifacts: {missing_hash}' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "
+    ))
+    .expect("rollback should refuse a target whose artifacts are missing");
+
+    let active_deployment = Spi::get_one::<i64>(
+        "SELECT active_deployment_id FROM stopgap.environment WHERE env = 'it_env_rb_missing'",
+    )
+    .expect("active deployment lookup should succeed")
+    .expect("active deployment should be present");
+    assert_eq!(active_deployment, deploy_two, "failed rollback should keep the active deployment");
+}
+
+#[pg_test]
+fn test_rollback_to_label_targets_newest_matching_deployment() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_rb_label_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_rb_label_live CASCADE;
+        CREATE SCHEMA sg_it_rb_label_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_rb_label_live', true);
+        ",
+    )
+    .expect("labeled rollback setup should succeed");
+
+    let mut deploy_ids = Vec::new();
+    for label in ["v1", "v2", "v3"] {
+        create_deployable_function(
+            "sg_it_rb_label_src",
+            "stepper",
+            &format!("BEGIN RETURN jsonb_build_object('version', '{label}'); END"),
+        );
+        let deployment_id = Spi::get_one_with_args::<i64>(
+            "SELECT stopgap.deploy('it_env_rb_label', 'sg_it_rb_label_src', $1)",
+            &[label.into()],
+        )
+        .expect("labeled deploy should succeed")
+        .expect("labeled deploy should return id");
+        deploy_ids.push(deployment_id);
+    }
+
+    let rolled_back_to =
+        Spi::get_one::<i64>("SELECT stopgap.rollback('it_env_rb_label', to_label => 'v1')")
+            .expect("labeled rollback should succeed")
+            .expect("labeled rollback should return target deployment id");
+    assert_eq!(rolled_back_to, deploy_ids[0], "rollback to label v1 should target the v1 deploy");
+
+    let active_deployment = Spi::get_one::<i64>(
+        "SELECT active_deployment_id FROM stopgap.environment WHERE env = 'it_env_rb_label'",
+    )
+    .expect("active deployment lookup should succeed")
+    .expect("active deployment should be present after labeled rollback");
+    assert_eq!(active_deployment, deploy_ids[0]);
+    assert_eq!(
+        pointer_artifact_hash("sg_it_rb_label_live", "stepper"),
+        fn_version_artifact_hash(deploy_ids[0], "stepper"),
+        "labeled rollback should rematerialize live pointer to the v1 artifact"
+    );
+
+    Spi::run(
+        "
+        DO $$
+        BEGIN
+            PERFORM stopgap.rollback('it_env_rb_label', to_label => 'v9');
+            RAISE EXCEPTION 'expected rollback to an unknown label to fail';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('no active or rolled_back deployment has that label' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        ",
+    )
+    .expect("rollback to an unknown label should fail with a clear error");
+}
+
+#[pg_test]
+fn test_activation_history_lists_deploys_and_rollback_newest_first() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_rb_history_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_rb_history_live CASCADE;
+        CREATE SCHEMA sg_it_rb_history_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_rb_history_live', true);
+        ",
+    )
+    .expect("activation history setup should succeed");
+
+    let mut deploy_ids = Vec::new();
+    for version in ["v1", "v2"] {
+        create_deployable_function(
+            "sg_it_rb_history_src",
+            "stepper",
+            format!("BEGIN RETURN jsonb_build_object('version', '{version}'); END").as_str(),
+        );
+        let deploy_id = Spi::get_one_with_args::<i64>(
+            "SELECT stopgap.deploy('it_env_rb_history', 'sg_it_rb_history_src', $1)",
+            &[version.into()],
+        )
+        .expect("deploy should succeed")
+        .expect("deploy should return id");
+        deploy_ids.push(deploy_id);
+    }
+    Spi::get_one::<i64>("SELECT stopgap.rollback('it_env_rb_history', 1, NULL)")
+        .expect("rollback should succeed");
+
+    let history = Spi::get_one::<JsonB>("SELECT stopgap.activation_history('it_env_rb_history')")
+        .expect("activation history should succeed")
+        .expect("activation history should return jsonb")
+        .0;
+    let entries = history.as_array().cloned().unwrap_or_default();
+    let transitions = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.get("from_deployment_id").and_then(Value::as_i64),
+                entry.get("to_deployment_id").and_then(Value::as_i64),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        transitions,
+        vec![
+            (Some(deploy_ids[1]), Some(deploy_ids[0])),
+            (Some(deploy_ids[0]), Some(deploy_ids[1])),
+            (None, Some(deploy_ids[0])),
+        ],
+        "activation history should list the rollback, then both deploys: {history}"
+    );
+    assert_eq!(entries[0].get("to_label").and_then(Value::as_str), Some("v1"));
+    assert!(entries.iter().all(|entry| entry.get("activated_at").is_some_and(Value::is_string)));
+    assert!(entries.iter().all(|entry| entry.get("activated_by").is_some_and(Value::is_string)));
+
+    let limited =
+        Spi::get_one::<JsonB>("SELECT stopgap.activation_history('it_env_rb_history', 1)")
+            .expect("limited activation history should succeed")
+            .expect("limited activation history should return jsonb")
+            .0;
+    assert_eq!(limited.as_array().map(Vec::len), Some(1));
+    assert_eq!(limited.pointer("/0/to_deployment_id").and_then(Value::as_i64), Some(deploy_ids[0]));
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_rb_history_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_rb_history_live CASCADE;
+        ",
+    )
+    .expect("activation history teardown should succeed");
+}
@@ -49,3 +49,104 @@ fn test_deploy_security_model_sets_live_fn_acl() {
     .expect("execute privilege check should return a row");
     assert!(app_can_execute, "app_user should have execute on live pointer function");
 }
+
+#[pg_test]
+fn test_whoami_reports_deploy_permission_checks() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_whoami_src CASCADE;
+        CREATE SCHEMA sg_it_whoami_src;
+        SELECT set_config('stopgap.live_schema', 'sg_it_whoami_live', true);
+        ",
+    )
+    .expect("whoami setup should succeed");
+
+    let report =
+        Spi::get_one::<JsonB>("SELECT stopgap.whoami('it_env_whoami', 'sg_it_whoami_src')")
+            .expect("whoami should succeed")
+            .expect("whoami should return a report");
+    assert_eq!(report.0.get("live_schema").and_then(Value::as_str), Some("sg_it_whoami_live"));
+    assert_eq!(report.0.get("ok").and_then(Value::as_bool), Some(true), "{}", report.0);
+
+    let missing =
+        Spi::get_one::<JsonB>("SELECT stopgap.whoami('it_env_whoami', 'sg_it_whoami_missing')")
+            .expect("whoami for a missing schema should still report")
+            .expect("whoami should return a report");
+    assert_eq!(missing.0.get("ok").and_then(Value::as_bool), Some(false));
+    let source_check = missing
+        .0
+        .get("checks")
+        .and_then(Value::as_array)
+        .and_then(|checks| {
+            checks.iter().find(|check| {
+                check.get("check").and_then(Value::as_str) == Some("source_schema_exists")
+            })
+        })
+        .and_then(|check| check.get("passed"))
+        .and_then(Value::as_bool);
+    assert_eq!(source_check, Some(false));
+}
+
+#[pg_test]
+fn test_deploy_rejects_source_schema_outside_env_allowlist() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_it_allow_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_allow_rogue CASCADE;
+        DROP SCHEMA IF EXISTS sg_it_allow_live CASCADE;
+        CREATE SCHEMA sg_it_allow_src;
+        CREATE SCHEMA sg_it_allow_rogue;
+        SELECT set_config('stopgap.live_schema', 'sg_it_allow_live', true);
+        ",
+    )
+    .expect("allowlist setup should succeed");
+
+    create_deployable_function(
+        "sg_it_allow_src",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('ok', true); END",
+    );
+    create_deployable_function(
+        "sg_it_allow_rogue",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('ok', false); END",
+    );
+
+    let allowed = Spi::get_one::<Vec<String>>(
+        "SELECT stopgap.set_allowed_sources('it_env_allow', ARRAY['sg_it_allow_src', 'sg_it_allow_src'])",
+    )
+    .expect("set_allowed_sources should succeed");
+    assert_eq!(allowed, Some(vec!["sg_it_allow_src".to_string()]));
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM stopgap.deploy('it_env_allow', 'sg_it_allow_rogue', 'rogue');
+            RAISE EXCEPTION 'expected allowlist rejection';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('source schema sg_it_allow_rogue is not allowed for env it_env_allow' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("deploy from a source schema outside the allowlist should be rejected");
+
+    let deployment_id =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_allow', 'sg_it_allow_src', 'ok')")
+            .expect("deploy from an allowed source schema should succeed")
+            .expect("deploy should return deployment id");
+    assert!(deployment_id > 0);
+
+    Spi::run("SELECT stopgap.set_allowed_sources('it_env_allow', NULL)")
+        .expect("clearing the allowlist should succeed");
+    Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_allow', 'sg_it_allow_rogue', 'any')")
+        .expect("deploy should succeed once the allowlist is cleared");
+}
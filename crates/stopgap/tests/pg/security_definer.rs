@@ -4,7 +4,7 @@ fn test_deploy_function_is_security_definer() {
         "
         SELECT p.prosecdef
         FROM pg_proc p
-        WHERE p.oid = 'stopgap.deploy(text, text, text)'::regprocedure
+        WHERE p.oid = 'stopgap.deploy(text, text, text, jsonb, bigint)'::regprocedure
         ",
     )
     .expect("deploy function lookup should succeed")
@@ -0,0 +1,17 @@
+#[pg_test]
+fn test_settings_lists_recognized_gucs_with_current_values() {
+    Spi::run("SELECT set_config('stopgap.default_env', 'staging', true)")
+        .expect("setting override should succeed");
+
+    let current = Spi::get_one::<String>(
+        "SELECT current_value FROM stopgap.settings() WHERE name = 'stopgap.default_env'",
+    )
+    .expect("settings query should succeed");
+    assert_eq!(current.as_deref(), Some("staging"));
+
+    let default_value = Spi::get_one::<String>(
+        "SELECT default_value FROM stopgap.settings() WHERE name = 'stopgap.live_schema'",
+    )
+    .expect("settings query should succeed");
+    assert_eq!(default_value.as_deref(), Some("live_deployment"));
+}
@@ -0,0 +1,86 @@
+#[pg_test]
+fn test_shadow_deploy_audits_candidate_without_touching_active_deployment() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_shadow_src CASCADE;
+        DROP SCHEMA IF EXISTS sg_shadow_live CASCADE;
+        CREATE SCHEMA sg_shadow_src;
+        SELECT set_config('stopgap.live_schema', 'sg_shadow_live', true);
+        ",
+    )
+    .expect("integration setup should succeed");
+
+    create_deployable_function(
+        "sg_shadow_src",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('version', 'v1'); END",
+    );
+
+    let active_deployment =
+        Spi::get_one::<i64>("SELECT stopgap.deploy('it_env_shadow', 'sg_shadow_src', 'v1')")
+            .expect("deploy should succeed")
+            .expect("deploy should return deployment id");
+
+    create_deployable_function(
+        "sg_shadow_src",
+        "hello",
+        "BEGIN RETURN jsonb_build_object('version', 'v2'); END",
+    );
+    create_deployable_function(
+        "sg_shadow_src",
+        "goodbye",
+        "BEGIN RETURN jsonb_build_object('version', 'v2'); END",
+    );
+
+    let report =
+        Spi::get_one::<JsonB>("SELECT stopgap.shadow_deploy('it_env_shadow', 'sg_shadow_src')")
+            .expect("shadow deploy should succeed")
+            .expect("shadow deploy should return a report")
+            .0;
+
+    assert_eq!(report.get("ok").and_then(Value::as_bool), Some(true));
+    assert_eq!(report.get("issues").and_then(Value::as_array).map(Vec::len), Some(0));
+    assert_eq!(report.get("functions").and_then(Value::as_array).map(Vec::len), Some(2));
+
+    let shadow_schema = report
+        .get("shadow_schema")
+        .and_then(Value::as_str)
+        .expect("report should name the shadow schema")
+        .to_string();
+    assert!(shadow_schema.starts_with("stopgap_shadow_"));
+
+    let shadow_schema_exists = Spi::get_one_with_args::<bool>(
+        "SELECT EXISTS (SELECT 1 FROM pg_namespace WHERE nspname = $1)",
+        &[shadow_schema.as_str().into()],
+    )
+    .expect("shadow schema lookup should succeed")
+    .expect("shadow schema lookup should return a row");
+    assert!(!shadow_schema_exists, "shadow schema should be dropped after the audit");
+
+    let env_active = Spi::get_one::<i64>(
+        "SELECT active_deployment_id FROM stopgap.environment WHERE env = 'it_env_shadow'",
+    )
+    .expect("environment lookup should succeed")
+    .expect("environment should have an active deployment");
+    assert_eq!(env_active, active_deployment);
+
+    let deployment_count =
+        Spi::get_one::<i64>("SELECT count(*) FROM stopgap.deployment WHERE env = 'it_env_shadow'")
+            .expect("deployment count lookup should succeed")
+            .expect("deployment count should return a row");
+    assert_eq!(deployment_count, 1, "shadow deploy must not record a deployment");
+
+    let live_goodbye = Spi::get_one::<bool>(
+        "SELECT EXISTS (
+            SELECT 1
+            FROM pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            WHERE n.nspname = 'sg_shadow_live' AND p.proname = 'goodbye'
+        )",
+    )
+    .expect("live function lookup should succeed")
+    .expect("live function lookup should return a row");
+    assert!(!live_goodbye, "shadow deploy must not materialize into the live schema");
+}
@@ -0,0 +1,44 @@
+#[pg_test]
+fn test_reap_stale_deployments_fails_stuck_open_deployments() {
+    Spi::run(
+        "
+        INSERT INTO stopgap.environment (env, live_schema, active_deployment_id)
+        VALUES ('reap_env', 'reap_live', NULL)
+        ON CONFLICT (env) DO NOTHING;
+
+        INSERT INTO stopgap.deployment (id, env, label, created_at, source_schema, status, manifest)
+        VALUES
+            (93001, 'reap_env', 'stuck', now() - interval '2 hours', 'reap_src', 'open', '{}'::jsonb),
+            (93002, 'reap_env', 'in-flight', now(), 'reap_src', 'open', '{}'::jsonb),
+            (93003, 'reap_env', 'done', now() - interval '2 hours', 'reap_src', 'sealed', '{}'::jsonb);
+        ",
+    )
+    .expect("stale deployment setup should succeed");
+
+    let reaped =
+        Spi::get_one::<i64>("SELECT stopgap.reap_stale_deployments('reap_env', interval '1 hour')")
+            .expect("reap_stale_deployments should succeed");
+    assert_eq!(reaped, Some(1));
+
+    let status_of = |id: i64| {
+        Spi::get_one_with_args::<String>(
+            "SELECT status FROM stopgap.deployment WHERE id = $1",
+            &[id.into()],
+        )
+        .expect("deployment status query should succeed")
+    };
+    assert_eq!(status_of(93001).as_deref(), Some("failed"));
+    assert_eq!(status_of(93002).as_deref(), Some("open"));
+    assert_eq!(status_of(93003).as_deref(), Some("sealed"));
+
+    let error_at = Spi::get_one::<String>(
+        "SELECT manifest->'error'->>'at' FROM stopgap.deployment WHERE id = 93001",
+    )
+    .expect("reaped manifest query should succeed");
+    assert_eq!(error_at.as_deref(), Some("stopgap.reap_stale_deployments"));
+
+    let reaped_again =
+        Spi::get_one::<i64>("SELECT stopgap.reap_stale_deployments('reap_env', interval '1 hour')")
+            .expect("second reap should succeed");
+    assert_eq!(reaped_again, Some(0));
+}
@@ -0,0 +1,69 @@
+#[pg_test]
+fn test_validate_reports_diagnostics_without_storing_artifacts() {
+    ensure_mock_plts_runtime();
+
+    Spi::run(
+        "
+        DROP SCHEMA IF EXISTS sg_validate_src CASCADE;
+        CREATE SCHEMA sg_validate_src;
+        ",
+    )
+    .expect("validate setup should succeed");
+
+    create_deployable_function(
+        "sg_validate_src",
+        "good",
+        "BEGIN RETURN jsonb_build_object('ok', true); END",
+    );
+    create_deployable_function(
+        "sg_validate_src",
+        "broken",
+        "BEGIN RETURN NULL; END /* mock:ts-error */",
+    );
+    let artifacts_before = Spi::get_one::<i64>("SELECT count(*) FROM plts.artifact")
+        .expect("artifact count should succeed");
+
+    let report = Spi::get_one::<JsonB>("SELECT stopgap.validate('sg_validate_src')")
+        .expect("validate should succeed")
+        .expect("validate should return jsonb")
+        .0;
+
+    assert_eq!(report.get("ok").and_then(Value::as_bool), Some(false));
+    let functions = report.get("functions").and_then(Value::as_array).cloned().unwrap_or_default();
+    let names = functions
+        .iter()
+        .filter_map(|function| function.get("name").and_then(Value::as_str))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["broken", "good"]);
+    assert_eq!(functions[0].get("error_count").and_then(Value::as_u64), Some(1));
+    assert_eq!(
+        functions[0].pointer("/diagnostics/0/message").and_then(Value::as_str),
+        Some("mock transpile error")
+    );
+    assert_eq!(functions[1].get("error_count").and_then(Value::as_u64), Some(0));
+    assert_eq!(functions[1].get("diagnostics"), Some(&serde_json::json!([])));
+
+    let artifacts_after = Spi::get_one::<i64>("SELECT count(*) FROM plts.artifact")
+        .expect("artifact count should succeed");
+    assert_eq!(artifacts_before, artifacts_after, "validate must not store artifacts");
+
+    Spi::run(
+        r#"
+        DO $$
+        BEGIN
+            PERFORM stopgap.validate('sg_validate_missing');
+            RAISE EXCEPTION 'expected missing source schema rejection';
+        EXCEPTION
+            WHEN OTHERS THEN
+                IF POSITION('source schema sg_validate_missing does not exist' IN SQLERRM) = 0 THEN
+                    RAISE;
+                END IF;
+        END;
+        $$;
+        "#,
+    )
+    .expect("validate should reject a missing source schema");
+
+    Spi::run("DROP SCHEMA IF EXISTS sg_validate_src CASCADE;")
+        .expect("validate teardown should succeed");
+}
@@ -77,3 +77,12 @@ SELECT EXISTS (
     WHERE row->>'fn_name' = 'beta'
       AND row->>'change' = 'removed'
 ) AS beta_is_removed;
+
+SELECT (stopgap.diff('rg_diff', 'sg_reg_diff_src', true)->'summary'->>'removed')::int = 0 AS ignored_removed_is_zero;
+SELECT (stopgap.diff('rg_diff', 'sg_reg_diff_src', true)->'summary'->>'added')::int = 1 AS ignored_added_is_one;
+
+SELECT NOT EXISTS (
+    SELECT 1
+    FROM jsonb_array_elements(stopgap.diff('rg_diff', 'sg_reg_diff_src', true)->'functions') row
+    WHERE row->>'change' = 'removed'
+) AS ignored_has_no_removed_rows;
@@ -83,15 +83,15 @@ SELECT stopgap.deploy('rg_sec', 'sg_reg_sec_src', 'sec') > 0 AS deployed_securit
 
 SELECT p.prosecdef
 FROM pg_proc p
-WHERE p.oid = 'stopgap.deploy(text, text, text)'::regprocedure;
+WHERE p.oid = 'stopgap.deploy(text, text, text, jsonb, bigint)'::regprocedure;
 
 SELECT p.prosecdef
 FROM pg_proc p
-WHERE p.oid = 'stopgap.rollback(text, integer, bigint)'::regprocedure;
+WHERE p.oid = 'stopgap.rollback(text, integer, bigint, text)'::regprocedure;
 
 SELECT p.prosecdef
 FROM pg_proc p
-WHERE p.oid = 'stopgap.diff(text, text)'::regprocedure;
+WHERE p.oid = 'stopgap.diff(text, text, boolean, text, boolean)'::regprocedure;
 
 SELECT pg_get_userbyid(p.proowner)::text AS owner
 FROM pg_proc p
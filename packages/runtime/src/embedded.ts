@@ -71,13 +71,16 @@ const isMissingRequiredIssue = (issue: ValidationIssue, value: unknown): boolean
   return isPlainObject(parent) && !Object.prototype.hasOwnProperty.call(parent, lastSegment);
 };
 
+type ValidationTarget = "args" | "result";
+
 const formatSafeParseIssue = (
   issue: ValidationIssue | undefined,
   fallbackPath: string,
-  value: unknown
+  value: unknown,
+  target: ValidationTarget
 ): string => {
   if (!issue) {
-    return `stopgap args validation failed at ${fallbackPath}: schema rejected value`;
+    return `stopgap ${target} validation failed at ${fallbackPath}: schema rejected value`;
   }
 
   const issuePath =
@@ -86,26 +89,32 @@ const formatSafeParseIssue = (
       : toIssuePath(issue.path, fallbackPath);
 
   if (issue.code === "unrecognized_keys") {
-    return `stopgap args validation failed at ${issuePath}: additional properties are not allowed`;
+    return `stopgap ${target} validation failed at ${issuePath}: additional properties are not allowed`;
   }
 
   if (isMissingRequiredIssue(issue, value)) {
-    return `stopgap args validation failed at ${issuePath}: missing required property`;
+    return `stopgap ${target} validation failed at ${issuePath}: missing required property`;
   }
 
   const message = issue.message?.trim();
   if (message && message.length > 0) {
-    return `stopgap args validation failed at ${issuePath}: ${message}`;
+    return `stopgap ${target} validation failed at ${issuePath}: ${message}`;
   }
 
-  return `stopgap args validation failed at ${issuePath}: schema rejected value`;
+  return `stopgap ${target} validation failed at ${issuePath}: schema rejected value`;
 };
 
 const isSchemaLike = (candidate: unknown): candidate is SchemaLike =>
   isPlainObject(candidate) &&
   (typeof candidate.safeParse === "function" || typeof candidate.parse === "function");
 
-const validateSchemaLikeArgs = (schemaValue: SchemaLike, value: unknown, path = "$", root = true): void => {
+const validateSchemaLikeValue = (
+  schemaValue: SchemaLike,
+  value: unknown,
+  path: string,
+  root: boolean,
+  target: ValidationTarget
+): void => {
   if (typeof schemaValue.safeParse === "function") {
     try {
       const parsed = schemaValue.safeParse(value);
@@ -113,13 +122,13 @@ const validateSchemaLikeArgs = (schemaValue: SchemaLike, value: unknown, path =
         return;
       }
 
-      throw new TypeError(formatSafeParseIssue(parsed.error?.issues?.[0], path, value));
+      throw new TypeError(formatSafeParseIssue(parsed.error?.issues?.[0], path, value, target));
     } catch (error) {
       if (error instanceof TypeError) {
         throw error;
       }
       const text = error instanceof Error ? error.message : String(error);
-      throw new TypeError(`stopgap args validation failed at ${path}: ${text}`);
+      throw new TypeError(`stopgap ${target} validation failed at ${path}: ${text}`);
     }
   }
 
@@ -129,12 +138,12 @@ const validateSchemaLikeArgs = (schemaValue: SchemaLike, value: unknown, path =
       return;
     } catch (error) {
       const text = error instanceof Error ? error.message : String(error);
-      throw new TypeError(`stopgap args validation failed at ${path}: ${text}`);
+      throw new TypeError(`stopgap ${target} validation failed at ${path}: ${text}`);
     }
   }
 
   throw new TypeError(
-    `stopgap args validation failed at ${path}: schema must provide parse/safeParse (root=${root})`
+    `stopgap ${target} validation failed at ${path}: schema must provide parse/safeParse (root=${root})`
   );
 };
 
@@ -170,9 +179,14 @@ const typeMatches = (expectedType: string, value: unknown): boolean => {
   }
 };
 
-export const validateArgs = (schemaValue: unknown, value: unknown, path = "$"): void => {
+const validateValue = (
+  schemaValue: unknown,
+  value: unknown,
+  path: string,
+  target: ValidationTarget
+): void => {
   if (isSchemaLike(schemaValue)) {
-    validateSchemaLikeArgs(schemaValue, value, path, true);
+    validateSchemaLikeValue(schemaValue, value, path, true, target);
     return;
   }
 
@@ -181,17 +195,17 @@ export const validateArgs = (schemaValue: unknown, value: unknown, path = "$"):
   }
 
   if (schemaValue === false) {
-    throw new TypeError(`stopgap args validation failed at ${path}: schema forbids all values`);
+    throw new TypeError(`stopgap ${target} validation failed at ${path}: schema forbids all values`);
   }
 
   if (!isPlainObject(schemaValue)) {
-    throw new TypeError(`stopgap args validation failed at ${path}: schema must be an object`);
+    throw new TypeError(`stopgap ${target} validation failed at ${path}: schema must be an object`);
   }
 
   if (Array.isArray(schemaValue.enum)) {
     const matched = schemaValue.enum.some((entry) => sameJson(entry, value));
     if (!matched) {
-      throw new TypeError(`stopgap args validation failed at ${path}: value is not in enum`);
+      throw new TypeError(`stopgap ${target} validation failed at ${path}: value is not in enum`);
     }
   }
 
@@ -199,7 +213,7 @@ export const validateArgs = (schemaValue: unknown, value: unknown, path = "$"):
     let matched = false;
     for (const branch of schemaValue.anyOf) {
       try {
-        validateArgs(branch, value, path);
+        validateValue(branch, value, path, target);
         matched = true;
         break;
       } catch {
@@ -207,7 +221,7 @@ export const validateArgs = (schemaValue: unknown, value: unknown, path = "$"):
       }
     }
     if (!matched) {
-      throw new TypeError(`stopgap args validation failed at ${path}: value does not match anyOf branches`);
+      throw new TypeError(`stopgap ${target} validation failed at ${path}: value does not match anyOf branches`);
     }
   }
 
@@ -216,31 +230,60 @@ export const validateArgs = (schemaValue: unknown, value: unknown, path = "$"):
     const matches = expected.some((entry) => typeMatches(String(entry), value));
     if (!matches) {
       throw new TypeError(
-        `stopgap args validation failed at ${path}: expected ${expected.join("|")}, got ${describeValue(value)}`
+        `stopgap ${target} validation failed at ${path}: expected ${expected.join("|")}, got ${describeValue(value)}`
       );
     }
   }
 
+  if (typeof value === "number") {
+    const numeric = value;
+    const bounds: Array<[string, (limit: number) => boolean, string]> = [
+      ["minimum", (limit) => numeric >= limit, ">="],
+      ["maximum", (limit) => numeric <= limit, "<="],
+      ["exclusiveMinimum", (limit) => numeric > limit, ">"],
+      ["exclusiveMaximum", (limit) => numeric < limit, "<"],
+    ];
+    for (const [keyword, satisfied, operator] of bounds) {
+      const limit = schemaValue[keyword];
+      if (typeof limit === "number" && !satisfied(limit)) {
+        throw new TypeError(`stopgap ${target} validation failed at ${path}: expected ${operator} ${limit}, got ${value}`);
+      }
+    }
+  }
+
+  if (typeof value === "string") {
+    const length = [...value].length;
+    if (typeof schemaValue.minLength === "number" && length < schemaValue.minLength) {
+      throw new TypeError(`stopgap ${target} validation failed at ${path}: expected length >= ${schemaValue.minLength}, got ${length}`);
+    }
+    if (typeof schemaValue.maxLength === "number" && length > schemaValue.maxLength) {
+      throw new TypeError(`stopgap ${target} validation failed at ${path}: expected length <= ${schemaValue.maxLength}, got ${length}`);
+    }
+    if (typeof schemaValue.pattern === "string" && !new RegExp(schemaValue.pattern).test(value)) {
+      throw new TypeError(`stopgap ${target} validation failed at ${path}: expected to match pattern ${schemaValue.pattern}`);
+    }
+  }
+
   if (isPlainObject(value)) {
     const properties = isPlainObject(schemaValue.properties) ? schemaValue.properties : {};
     const required = Array.isArray(schemaValue.required) ? schemaValue.required : [];
 
     for (const key of required) {
       if (!Object.prototype.hasOwnProperty.call(value, key)) {
-        throw new TypeError(`stopgap args validation failed at ${path}.${key}: missing required property`);
+        throw new TypeError(`stopgap ${target} validation failed at ${path}.${key}: missing required property`);
       }
     }
 
     for (const [key, propertySchema] of Object.entries(properties)) {
       if (Object.prototype.hasOwnProperty.call(value, key)) {
-        validateArgs(propertySchema, value[key], `${path}.${key}`);
+        validateValue(propertySchema, value[key], `${path}.${key}`, target);
       }
     }
 
     if (schemaValue.additionalProperties === false) {
       for (const key of Object.keys(value)) {
         if (!Object.prototype.hasOwnProperty.call(properties, key)) {
-          throw new TypeError(`stopgap args validation failed at ${path}.${key}: additional properties are not allowed`);
+          throw new TypeError(`stopgap ${target} validation failed at ${path}.${key}: additional properties are not allowed`);
         }
       }
     }
@@ -248,25 +291,106 @@ export const validateArgs = (schemaValue: unknown, value: unknown, path = "$"):
 
   if (Array.isArray(value) && schemaValue.items !== undefined) {
     for (let i = 0; i < value.length; i += 1) {
-      validateArgs(schemaValue.items, value[i], `${path}[${i}]`);
+      validateValue(schemaValue.items, value[i], `${path}[${i}]`, target);
     }
   }
 };
 
+export const validateArgs = (schemaValue: unknown, value: unknown, path = "$"): void =>
+  validateValue(schemaValue, value, path, "args");
+
+export const validateResult = (schemaValue: unknown, value: unknown, path = "$"): void =>
+  validateValue(schemaValue, value, path, "result");
+
+const SQLSTATE_PATTERN = /^[0-9A-Z]{5}$/;
+
+// Inside plts the class comes from the runtime bootstrap, which keeps the SQLSTATE in a private
+// field that only its constructor sets; plts raises under that field, never under the name.
+class LocalStopgapError extends Error {
+  readonly #sqlstate: string;
+
+  constructor(message: string, sqlstate = "P0001") {
+    if (!SQLSTATE_PATTERN.test(sqlstate)) {
+      throw new TypeError(`stopgap error sqlstate must be 5 characters of 0-9/A-Z, got ${sqlstate}`);
+    }
+    super(message);
+    this.#sqlstate = sqlstate;
+    this.name = "StopgapError";
+  }
+
+  get sqlstate(): string {
+    return this.#sqlstate;
+  }
+}
+
+const hostStopgapError = (
+  globalThis as { __plts_internal_ops?: { StopgapError?: typeof LocalStopgapError } }
+).__plts_internal_ops?.StopgapError;
+
+export const StopgapError: typeof LocalStopgapError = hostStopgapError ?? LocalStopgapError;
+export type StopgapError = LocalStopgapError;
+
+export function assert(condition: unknown, message: string, sqlstate?: string): asserts condition {
+  if (!condition) {
+    throw new StopgapError(message, sqlstate);
+  }
+}
+
+const isWrapperSchemas = (candidate: unknown): candidate is { args?: unknown; result?: unknown } =>
+  isPlainObject(candidate) &&
+  !isSchemaLike(candidate) &&
+  Object.keys(candidate).length > 0 &&
+  Object.keys(candidate).every((key) => key === "args" || key === "result");
+
 const normalizeWrapperArgs = (
   kind: "query" | "mutation",
-  argsSchema: unknown,
+  schemas: unknown,
   handler: unknown
 ) => {
-  if (typeof argsSchema === "function" && handler === undefined) {
-    return { argsSchema: null, handler: argsSchema };
+  if (typeof schemas === "function" && handler === undefined) {
+    return { argsSchema: null, resultSchema: null, handler: schemas };
   }
 
   if (typeof handler !== "function") {
     throw new TypeError(`stopgap.${kind} expects a function handler`);
   }
 
-  return { argsSchema: argsSchema ?? null, handler };
+  if (isWrapperSchemas(schemas)) {
+    return {
+      argsSchema: schemas.args ?? null,
+      resultSchema: schemas.result ?? null,
+      handler,
+    };
+  }
+
+  return { argsSchema: schemas ?? null, resultSchema: null, handler };
+};
+
+const assertSchemaPatterns = (kind: "query" | "mutation", schemaValue: unknown, path: string): void => {
+  if (!isPlainObject(schemaValue) || isSchemaLike(schemaValue)) {
+    return;
+  }
+
+  if (schemaValue.pattern !== undefined) {
+    try {
+      new RegExp(String(schemaValue.pattern));
+    } catch (err) {
+      const text = err instanceof Error ? err.message : String(err);
+      throw new TypeError(`stopgap.${kind} schema has an invalid pattern at ${path}: ${text}`);
+    }
+  }
+
+  if (isPlainObject(schemaValue.properties)) {
+    for (const [key, propertySchema] of Object.entries(schemaValue.properties)) {
+      assertSchemaPatterns(kind, propertySchema, `${path}.${key}`);
+    }
+  }
+  if (schemaValue.items !== undefined) {
+    assertSchemaPatterns(kind, schemaValue.items, `${path}[]`);
+  }
+  if (Array.isArray(schemaValue.anyOf)) {
+    schemaValue.anyOf.forEach((branch) => assertSchemaPatterns(kind, branch, path));
+  }
 };
 
 const wrap = (
@@ -275,17 +399,24 @@ const wrap = (
   handler: unknown
 ) => {
   const normalized = normalizeWrapperArgs(kind, argsSchema, handler);
+  assertSchemaPatterns(kind, normalized.argsSchema, "$");
+  assertSchemaPatterns(kind, normalized.resultSchema, "$");
 
   const wrapped = async (ctx: unknown) => {
     const runtimeCtx = (ctx ?? {}) as { args?: unknown };
     const args = runtimeCtx.args ?? null;
     validateArgs(normalized.argsSchema, args);
-    return await (normalized.handler as (args: unknown, ctx: unknown) => unknown)(args, runtimeCtx);
+    const result = await (normalized.handler as (args: unknown, ctx: unknown) => unknown)(args, runtimeCtx);
+    if (normalized.resultSchema !== null) {
+      validateResult(normalized.resultSchema, result);
+    }
+    return result;
   };
 
   Object.assign(wrapped, {
     __stopgap_kind: kind,
     __stopgap_args_schema: normalized.argsSchema,
+    __stopgap_result_schema: normalized.resultSchema,
   });
   return wrapped;
 };
@@ -300,4 +431,7 @@ export default {
   query,
   mutation,
   validateArgs,
+  validateResult,
+  assert,
+  StopgapError,
 };
@@ -1,8 +1,11 @@
 import {
+  StopgapError,
+  assert,
   mutation as mutationCore,
   query as queryCore,
   v,
   validateArgs as validateArgsCore,
+  validateResult as validateResultCore,
 } from "./embedded.js";
 
 export type JsonPrimitive = string | number | boolean | null;
@@ -16,6 +19,13 @@ export type JsonSchema = {
   items?: JsonSchema;
   enum?: readonly JsonValue[];
   anyOf?: readonly JsonSchema[];
+  minimum?: number;
+  maximum?: number;
+  exclusiveMinimum?: number;
+  exclusiveMaximum?: number;
+  minLength?: number;
+  maxLength?: number;
+  pattern?: string;
 };
 
 type SchemaTypeName<S extends JsonSchema> = S["type"] extends string ? S["type"] : never;
@@ -71,22 +81,76 @@ export type InferArgsSchema<S> = S extends SchemaLike<infer T>
 
 export type DbMode = "ro" | "rw";
 
+export type DbCallOptions = {
+  readOnly?: boolean;
+};
+
 export type DbApi = {
   mode: DbMode;
-  query: (sql: string, params?: JsonValue[]) => Promise<JsonValue[]>;
-  exec: (sql: string, params?: JsonValue[]) => Promise<{ ok: true }>;
+  query: (sql: string, params?: JsonValue[], options?: DbCallOptions) => Promise<JsonValue[]>;
+  queryRow: (
+    sql: string,
+    params?: JsonValue[],
+    options?: DbCallOptions,
+  ) => Promise<JsonValue | null>;
+  queryValue: (
+    sql: string,
+    params?: JsonValue[],
+    options?: DbCallOptions,
+  ) => Promise<JsonValue | null>;
+  queryColumnar: (
+    sql: string,
+    params?: JsonValue[],
+    options?: DbCallOptions,
+  ) => Promise<{ columns: string[]; rows: JsonValue[][] }>;
+  exec: (sql: string, params?: JsonValue[], options?: DbCallOptions) => Promise<{ ok: true }>;
+  batch: (
+    statements: Array<{ sql: string; params?: JsonValue[] }>,
+  ) => Promise<{ ok: true; count: number }>;
+};
+
+export type TriggerContext = {
+  name: string;
+  operation: "INSERT" | "UPDATE" | "DELETE" | "TRUNCATE";
+  when: "BEFORE" | "AFTER" | "INSTEAD OF";
+  level: "ROW" | "STATEMENT";
+  table: string;
+  schema: string;
+  old: Record<string, JsonValue> | null;
+  new: Record<string, JsonValue> | null;
 };
 
 export type StopgapContext<TArgs> = {
   args: TArgs;
   db: DbApi;
-  fn: { oid: number; schema: string; name: string };
+  fn: {
+    oid: number;
+    schema: string;
+    name: string;
+    args: Array<{ name: string | null; type_oid: number; type_name: string }>;
+  };
   now: string;
+  trigger?: TriggerContext;
+  memo: <T>(key: unknown, factory: () => T) => T;
+  log: {
+    info: (message: unknown) => void;
+    warn: (message: unknown) => void;
+  };
+  describe: (schema: string, name: string) => {
+    kind: "query" | "mutation" | null;
+    args_schema: JsonValue | null;
+  };
 };
 
 type StopgapWrapped = ((ctx: unknown) => Promise<unknown>) & {
   __stopgap_kind: "query" | "mutation";
   __stopgap_args_schema: unknown;
+  __stopgap_result_schema: unknown;
+};
+
+export type StopgapSchemas<S, R> = {
+  args?: S;
+  result?: R;
 };
 
 type StopgapHandler<TArgs, TResult> = (args: TArgs, ctx: StopgapContext<TArgs>) => TResult | Promise<TResult>;
@@ -94,8 +158,18 @@ type StopgapHandler<TArgs, TResult> = (args: TArgs, ctx: StopgapContext<TArgs>)
 export const validateArgs = (schema: JsonSchema | SchemaLike | null | undefined, value: unknown, path = "$"): void =>
   validateArgsCore(schema, value, path);
 
-export { v };
+export const validateResult = (
+  schema: JsonSchema | SchemaLike | null | undefined,
+  value: unknown,
+  path = "$"
+): void => validateResultCore(schema, value, path);
+
+export { StopgapError, assert, v };
 
+export function query<S, R>(
+  schemas: StopgapSchemas<S, R>,
+  handler: StopgapHandler<InferArgsSchema<S>, InferArgsSchema<R>>
+): StopgapWrapped;
 export function query<S, TResult>(
   argsSchema: S,
   handler: StopgapHandler<InferArgsSchema<S>, TResult>
@@ -110,6 +184,10 @@ export function query<S, TResult>(
   return queryCore(argsSchemaOrHandler, maybeHandler) as StopgapWrapped;
 }
 
+export function mutation<S, R>(
+  schemas: StopgapSchemas<S, R>,
+  handler: StopgapHandler<InferArgsSchema<S>, InferArgsSchema<R>>
+): StopgapWrapped;
 export function mutation<S, TResult>(
   argsSchema: S,
   handler: StopgapHandler<InferArgsSchema<S>, TResult>
@@ -129,11 +207,17 @@ const runtimeApi: {
   query: typeof query;
   mutation: typeof mutation;
   validateArgs: typeof validateArgs;
+  validateResult: typeof validateResult;
+  assert: typeof assert;
+  StopgapError: typeof StopgapError;
 } = {
   v,
   query,
   mutation,
   validateArgs,
+  validateResult,
+  assert,
+  StopgapError,
 };
 
 export default runtimeApi;
@@ -1,4 +1,12 @@
-import runtime, { mutation, query, v, validateArgs } from "../src/index.js";
+import runtime, {
+  StopgapError,
+  assert,
+  mutation,
+  query,
+  v,
+  validateArgs,
+  validateResult,
+} from "../src/index.js";
 import { describe, expect, it } from "vitest";
 
 const makeCtx = (args: unknown, mode: "ro" | "rw") => ({
@@ -60,6 +68,34 @@ describe("@stopgap/runtime wrappers", () => {
     });
   });
 
+  it("validates handler results against a declared result schema", async () => {
+    const argsSchema = v.object({ id: v.int() });
+    const resultSchema = v.object({ id: v.int(), label: v.string() });
+
+    const wrappedQuery = query({ args: argsSchema, result: resultSchema }, async (args) => ({
+      id: args.id,
+      label: args.id > 0 ? "positive" : (args.id as unknown as string),
+    }));
+
+    expect(wrappedQuery.__stopgap_args_schema).toBe(argsSchema);
+    expect(wrappedQuery.__stopgap_result_schema).toBe(resultSchema);
+
+    await expect(wrappedQuery(makeCtx({ id: 3 }, "ro"))).resolves.toEqual({
+      id: 3,
+      label: "positive",
+    });
+
+    await expect(wrappedQuery(makeCtx({ id: 0 }, "ro"))).rejects.toThrow(
+      "stopgap result validation failed at $.label"
+    );
+
+    const resultOnlyMutation = mutation({ result: { type: "integer" } }, async () => 1.5);
+    expect(resultOnlyMutation.__stopgap_args_schema).toBeNull();
+    await expect(resultOnlyMutation(makeCtx(null, "rw"))).rejects.toThrow(
+      "stopgap result validation failed at $"
+    );
+  });
+
   it("supports schema-less wrappers and exports parity", async () => {
     const schemaLessQuery = query(async (args) => args);
     expect(schemaLessQuery.__stopgap_args_schema).toBeNull();
@@ -70,6 +106,7 @@ describe("@stopgap/runtime wrappers", () => {
     expect(runtime.mutation).toBe(mutation);
     expect(runtime.v).toBe(v);
     expect(runtime.validateArgs).toBe(validateArgs);
+    expect(runtime.validateResult).toBe(validateResult);
   });
 
   it("validates args for v and legacy JSON schema formats", () => {
@@ -80,4 +117,50 @@ describe("@stopgap/runtime wrappers", () => {
     expect(() => validateArgs(v.enum(["a", "b"]), "c")).toThrow("Invalid input");
     expect(() => validateArgs({ enum: ["x", "y"] }, "z")).toThrow("value is not in enum");
   });
+
+  it("enforces numeric bounds in JSON schemas", () => {
+    expect(() => validateArgs({ type: "integer", minimum: 1 }, 1)).not.toThrow();
+    expect(() => validateArgs({ type: "number", maximum: 10, exclusiveMinimum: 0 }, 10)).not.toThrow();
+
+    expect(() => validateArgs({ type: "integer", minimum: 1 }, 0)).toThrow(
+      "stopgap args validation failed at $: expected >= 1, got 0"
+    );
+    expect(() => validateArgs({ maximum: 10 }, 11)).toThrow("expected <= 10, got 11");
+    expect(() => validateArgs({ exclusiveMinimum: 0 }, 0)).toThrow("expected > 0, got 0");
+    expect(() => validateArgs({ exclusiveMaximum: 5 }, 5)).toThrow("expected < 5, got 5");
+  });
+
+  it("enforces string length and pattern constraints", () => {
+    expect(() => validateArgs({ type: "string", minLength: 2, maxLength: 3 }, "abc")).not.toThrow();
+    expect(() => validateArgs({ type: "string", pattern: "^[a-z]+$" }, "abc")).not.toThrow();
+
+    expect(() => validateArgs({ minLength: 2 }, "a")).toThrow(
+      "stopgap args validation failed at $: expected length >= 2, got 1"
+    );
+    expect(() => validateArgs({ maxLength: 3 }, "abcd")).toThrow("expected length <= 3, got 4");
+    expect(() => validateArgs({ pattern: "^[a-z]+$" }, "ABC")).toThrow(
+      "expected to match pattern ^[a-z]+$"
+    );
+    expect(() =>
+      query({ type: "object", properties: { slug: { type: "string", pattern: "[" } } }, async () => null)
+    ).toThrow("stopgap.query schema has an invalid pattern at $.slug");
+  });
+
+  it("throws StopgapError with a sqlstate from failed assertions", () => {
+    expect(() => assert(true, "unreachable")).not.toThrow();
+
+    try {
+      assert(false, "quantity must be positive", "22023");
+      expect.unreachable();
+    } catch (err) {
+      expect(err).toBeInstanceOf(StopgapError);
+      expect((err as StopgapError).sqlstate).toBe("22023");
+      expect((err as StopgapError).message).toBe("quantity must be positive");
+      expect(String(err)).toBe("StopgapError: quantity must be positive");
+    }
+
+    expect(() => assert(0, "defaults")).toThrow(expect.objectContaining({ sqlstate: "P0001" }));
+    expect(() => new StopgapError("bad", "oops")).toThrow(TypeError);
+    expect(runtime.assert).toBe(assert);
+  });
 });
@@ -42,6 +42,7 @@ func HandleRequest(req RequestEnvelope) ResponseEnvelope {
 		result := Transpile(TranspileRequest{
 			SourceTS:     req.SourceTS,
 			SourceMap:    req.SourceMap,
+			Target:       req.Target,
 			Declarations: req.Declarations,
 		})
 		return ResponseEnvelope{
@@ -53,6 +54,7 @@ func HandleRequest(req RequestEnvelope) ResponseEnvelope {
 		result := CompileChecked(TranspileRequest{
 			SourceTS:     req.SourceTS,
 			SourceMap:    req.SourceMap,
+			Target:       req.Target,
 			Declarations: req.Declarations,
 		})
 		return ResponseEnvelope{
@@ -191,8 +193,13 @@ func buildTypecheckProgram(req TypecheckRequest) (*compiler.Program, *ast.Source
 }
 
 func buildTranspileProgram(req TranspileRequest) (*compiler.Program, *ast.SourceFile, error) {
+	target, err := resolveScriptTarget(req.Target)
+	if err != nil {
+		return nil, nil, err
+	}
+
 	compilerOptions := &core.CompilerOptions{
-		Target:           core.ScriptTargetESNext,
+		Target:           target,
 		Module:           core.ModuleKindESNext,
 		ModuleResolution: core.ModuleResolutionKindBundler,
 		IsolatedModules:  core.TSTrue,
@@ -212,7 +219,13 @@ func buildTranspileProgram(req TranspileRequest) (*compiler.Program, *ast.Source
 }
 
 func buildCompileCheckedProgram(req TranspileRequest) (*compiler.Program, *ast.SourceFile, error) {
+	target, err := resolveScriptTarget(req.Target)
+	if err != nil {
+		return nil, nil, err
+	}
+
 	compilerOptions := typecheckCompilerOptions()
+	compilerOptions.Target = target
 	compilerOptions.NoEmit = core.TSFalse
 	compilerOptions.NoEmitOnError = core.TSTrue
 	compilerOptions.OutDir = transpileOutDir
@@ -225,6 +238,34 @@ func buildCompileCheckedProgram(req TranspileRequest) (*compiler.Program, *ast.S
 	return buildProgram(req.SourceTS, req.Declarations, compilerOptions)
 }
 
+func resolveScriptTarget(raw string) (core.ScriptTarget, error) {
+	switch strings.ToLower(strings.TrimSpace(raw)) {
+	case "", "esnext":
+		return core.ScriptTargetESNext, nil
+	case "es2015", "es6":
+		return core.ScriptTargetES2015, nil
+	case "es2016":
+		return core.ScriptTargetES2016, nil
+	case "es2017":
+		return core.ScriptTargetES2017, nil
+	case "es2018":
+		return core.ScriptTargetES2018, nil
+	case "es2019":
+		return core.ScriptTargetES2019, nil
+	case "es2020":
+		return core.ScriptTargetES2020, nil
+	case "es2021":
+		return core.ScriptTargetES2021, nil
+	case "es2022":
+		return core.ScriptTargetES2022, nil
+	default:
+		return core.ScriptTargetNone, fmt.Errorf(
+			"unsupported compiler target %q; expected one of es2015-es2022 or esnext",
+			raw,
+		)
+	}
+}
+
 func buildProgram(
 	sourceTS string,
 	declarations []VirtualDeclaration,
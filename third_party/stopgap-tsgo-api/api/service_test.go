@@ -186,6 +186,52 @@ func TestTranspileSupportsInlineSourceMaps(t *testing.T) {
 	}
 }
 
+func TestTranspileReportsEverySyntaxDiagnostic(t *testing.T) {
+	result := Transpile(TranspileRequest{
+		SourceTS: "export const first = ;\n" +
+			"export const second: number = (1;\n",
+	})
+	lines := map[int]bool{}
+	for _, diagnostic := range result.Diagnostics {
+		if diagnostic.Severity != "error" || diagnostic.Message == "" {
+			t.Fatalf("unexpected diagnostic: %+v", diagnostic)
+		}
+		if diagnostic.Line == nil || diagnostic.Column == nil {
+			t.Fatalf("expected diagnostic position: %+v", diagnostic)
+		}
+		lines[*diagnostic.Line] = true
+	}
+	if !lines[1] || !lines[2] {
+		t.Fatalf("expected diagnostics on lines 1 and 2, got %+v", result.Diagnostics)
+	}
+}
+
+func TestTranspileTargetControlsDownleveling(t *testing.T) {
+	source := "export function fill(o: { v?: number }) { o.v ??= 1; return o; }"
+
+	esnext := Transpile(TranspileRequest{SourceTS: source, Target: "esnext"})
+	if len(esnext.Diagnostics) != 0 {
+		t.Fatalf("unexpected esnext diagnostics: %+v", esnext.Diagnostics)
+	}
+	if !strings.Contains(esnext.CompiledJS, "??=") {
+		t.Fatalf("expected esnext output to keep logical assignment, got %q", esnext.CompiledJS)
+	}
+
+	es2015 := Transpile(TranspileRequest{SourceTS: source, Target: "es2015"})
+	if len(es2015.Diagnostics) != 0 {
+		t.Fatalf("unexpected es2015 diagnostics: %+v", es2015.Diagnostics)
+	}
+	if strings.Contains(es2015.CompiledJS, "??=") || es2015.CompiledJS == esnext.CompiledJS {
+		t.Fatalf("expected es2015 output to downlevel logical assignment, got %q", es2015.CompiledJS)
+	}
+
+	unknown := Transpile(TranspileRequest{SourceTS: source, Target: "es3"})
+	if unknown.CompiledJS != "" || len(unknown.Diagnostics) != 1 ||
+		!strings.Contains(unknown.Diagnostics[0].Message, "unsupported compiler target") {
+		t.Fatalf("expected unsupported target diagnostic, got %+v", unknown)
+	}
+}
+
 func TestCompileCheckedBlocksEmitOnSemanticErrors(t *testing.T) {
 	result := CompileChecked(TranspileRequest{
 		SourceTS: "import { query, v } from '@stopgap/runtime';\n" +
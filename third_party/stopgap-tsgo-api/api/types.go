@@ -31,6 +31,7 @@ type TypecheckResponse struct {
 type TranspileRequest struct {
 	SourceTS     string               `json:"source_ts"`
 	SourceMap    bool                 `json:"source_map,omitempty"`
+	Target       string               `json:"target,omitempty"`
 	Declarations []VirtualDeclaration `json:"declarations,omitempty"`
 }
 
@@ -44,6 +45,7 @@ type RequestEnvelope struct {
 	Operation    string               `json:"operation"`
 	SourceTS     string               `json:"source_ts"`
 	SourceMap    bool                 `json:"source_map,omitempty"`
+	Target       string               `json:"target,omitempty"`
 	Declarations []VirtualDeclaration `json:"declarations,omitempty"`
 }
 